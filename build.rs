@@ -0,0 +1,65 @@
+// Embeds the SQL files of migrations/sqlite and migrations/postgres into the
+// binary, so the server does not depend on the working directory. Generates
+// the SQLITE_MIGRATIONS and POSTGRES_MIGRATIONS tables included by
+// src/config/migrations.rs.
+use std::env;
+use std::fs;
+use std::path::Path;
+
+// Entries of one backend directory: (version, up SQL, down SQL)
+fn migration_table(name: &str, dir: &Path) -> String {
+    let mut names: Vec<String> = fs::read_dir(dir)
+        .unwrap_or_else(|_| panic!("migrations directory {}", dir.display()))
+        .map(|entry| entry.unwrap().file_name().into_string().expect("UTF-8 file name"))
+        .filter(|name| name.ends_with(".sql"))
+        .collect();
+    names.sort();
+
+    let mut entries = String::new();
+    for name in names.iter().filter(|name| !name.ends_with(".down.sql")) {
+        let version = name.trim_end_matches(".sql");
+        let up = dir.join(name);
+        let down = dir.join(format!("{}.down.sql", version));
+
+        let down = if down.exists() {
+            format!("Some(include_str!({:?}))", down.display().to_string())
+        } else {
+            "None".to_string()
+        };
+
+        entries.push_str(&format!(
+            "    ({:?}, include_str!({:?}), {}),\n",
+            version,
+            up.display().to_string(),
+            down
+        ));
+    }
+
+    for name in names.iter().filter(|name| name.ends_with(".down.sql")) {
+        let up = name.replace(".down.sql", ".sql");
+        if !names.contains(&up) {
+            panic!("Down migration {} has no matching {}", name, up);
+        }
+    }
+
+    format!(
+        "pub static {}: &[(&str, &str, Option<&str>)] = &[\n{}];\n",
+        name,
+        entries
+    )
+}
+
+fn main() {
+    println!("cargo:rerun-if-changed=migrations");
+
+    let dir = Path::new(&env::var("CARGO_MANIFEST_DIR").unwrap()).join("migrations");
+
+    let code = format!(
+        "// Generated by build.rs: (version, up SQL, down SQL)\n{}{}",
+        migration_table("SQLITE_MIGRATIONS", &dir.join("sqlite")),
+        migration_table("POSTGRES_MIGRATIONS", &dir.join("postgres"))
+    );
+
+    let out = Path::new(&env::var("OUT_DIR").unwrap()).join("embedded_migrations.rs");
+    fs::write(out, code).expect("write embedded migrations");
+}
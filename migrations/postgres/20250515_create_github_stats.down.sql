@@ -0,0 +1,2 @@
+-- Drop github_stats table
+DROP TABLE IF EXISTS github_stats;
@@ -0,0 +1,15 @@
+-- Create projects table
+CREATE TABLE IF NOT EXISTS projects (
+    id TEXT PRIMARY KEY,
+    title TEXT NOT NULL,
+    description TEXT NOT NULL,
+    technologies TEXT NOT NULL, -- JSON array as text
+    github_url TEXT,
+    live_url TEXT,
+    image_url TEXT, -- Main project image
+    image_urls TEXT, -- JSON array of additional image URLs
+    year INTEGER NOT NULL,
+    highlights TEXT NOT NULL, -- JSON array as text
+    created_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP
+);
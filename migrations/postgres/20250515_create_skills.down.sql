@@ -0,0 +1,2 @@
+-- Drop skills table
+DROP TABLE IF EXISTS skills;
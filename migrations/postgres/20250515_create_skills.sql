@@ -0,0 +1,8 @@
+-- Create skills table
+CREATE TABLE IF NOT EXISTS skills (
+    id TEXT PRIMARY KEY,
+    category TEXT NOT NULL,
+    items TEXT NOT NULL, -- JSON array as text
+    created_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP
+);
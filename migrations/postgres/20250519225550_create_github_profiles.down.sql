@@ -0,0 +1,2 @@
+-- Drop github_profiles table
+DROP TABLE IF EXISTS github_profiles;
@@ -0,0 +1,11 @@
+-- Create refresh_tokens table
+CREATE TABLE IF NOT EXISTS refresh_tokens (
+    id TEXT PRIMARY KEY,
+    user_id TEXT NOT NULL,
+    token_hash TEXT NOT NULL UNIQUE, -- SHA-256 of the token, hex encoded
+    family_id TEXT NOT NULL, -- Shared by every token issued from the same login
+    expires_at BIGINT NOT NULL, -- UTC timestamp
+    revoked_at BIGINT, -- UTC timestamp, NULL while the token is usable
+    replaced_by TEXT, -- ID of the token issued when this one was rotated
+    created_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP
+);
@@ -0,0 +1,6 @@
+-- Create revoked_tokens table (access token denylist)
+CREATE TABLE IF NOT EXISTS revoked_tokens (
+    jti TEXT PRIMARY KEY,
+    expires_at BIGINT NOT NULL, -- UTC timestamp after which the entry can be purged
+    revoked_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP
+);
@@ -0,0 +1,2 @@
+-- Drop user_totp table
+DROP TABLE IF EXISTS user_totp;
@@ -0,0 +1,9 @@
+-- Create user_totp table
+CREATE TABLE IF NOT EXISTS user_totp (
+    user_id TEXT PRIMARY KEY,
+    secret TEXT NOT NULL, -- Base32 encoded shared secret
+    confirmed_at BIGINT, -- UTC timestamp, NULL until the first code is confirmed
+    last_used_step BIGINT, -- Last accepted time step, to reject replayed codes
+    recovery_codes TEXT NOT NULL, -- JSON array of SHA-256 hashes of unused recovery codes
+    created_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP
+);
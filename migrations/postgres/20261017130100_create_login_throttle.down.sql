@@ -0,0 +1,2 @@
+-- Drop login_throttle table
+DROP TABLE IF EXISTS login_throttle;
@@ -0,0 +1,9 @@
+-- Drop the full-text index and the triggers maintaining it
+DROP TRIGGER IF EXISTS posts_search_sync ON posts;
+DROP TRIGGER IF EXISTS projects_search_sync ON projects;
+DROP TRIGGER IF EXISTS experiences_search_sync ON experiences;
+DROP FUNCTION IF EXISTS posts_search_sync();
+DROP FUNCTION IF EXISTS projects_search_sync();
+DROP FUNCTION IF EXISTS experiences_search_sync();
+DROP FUNCTION IF EXISTS search_json_list(TEXT, TEXT);
+DROP TABLE IF EXISTS search_index;
@@ -0,0 +1,87 @@
+-- Full-text index of posts, projects and experiences, the PostgreSQL
+-- counterpart of the SQLite FTS5 table: one row per indexed item, kept in
+-- sync by triggers, searched through a weighted, stemmed tsvector.
+CREATE TABLE IF NOT EXISTS search_index (
+    kind TEXT NOT NULL, -- post, project or experience
+    ref_id TEXT NOT NULL, -- id of the indexed row
+    title TEXT NOT NULL,
+    body TEXT NOT NULL,
+    keywords TEXT NOT NULL,
+    document TSVECTOR GENERATED ALWAYS AS (
+        setweight(to_tsvector('english', title), 'A') ||
+        setweight(to_tsvector('english', keywords), 'B') ||
+        setweight(to_tsvector('english', body), 'C')
+    ) STORED,
+    PRIMARY KEY (kind, ref_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_search_index_document ON search_index USING GIN (document);
+
+-- Elements of a JSON array stored as text, joined with a separator
+CREATE OR REPLACE FUNCTION search_json_list(list TEXT, separator TEXT) RETURNS TEXT AS $$
+    SELECT COALESCE(string_agg(value, separator), '') FROM json_array_elements_text(list::json)
+$$ LANGUAGE sql IMMUTABLE;
+
+CREATE OR REPLACE FUNCTION posts_search_sync() RETURNS TRIGGER AS $$
+BEGIN
+    IF TG_OP <> 'INSERT' THEN
+        DELETE FROM search_index WHERE kind = 'post' AND ref_id = OLD.id;
+    END IF;
+    IF TG_OP <> 'DELETE' THEN
+        INSERT INTO search_index (kind, ref_id, title, body, keywords)
+        VALUES ('post', NEW.id, NEW.title, NEW.excerpt || ' ' || NEW.content, search_json_list(NEW.tags, ', '));
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE FUNCTION projects_search_sync() RETURNS TRIGGER AS $$
+BEGIN
+    IF TG_OP <> 'INSERT' THEN
+        DELETE FROM search_index WHERE kind = 'project' AND ref_id = OLD.id;
+    END IF;
+    IF TG_OP <> 'DELETE' THEN
+        INSERT INTO search_index (kind, ref_id, title, body, keywords)
+        VALUES ('project', NEW.id, NEW.title,
+            NEW.description || ' ' || search_json_list(NEW.highlights, ' '),
+            search_json_list(NEW.technologies, ', '));
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE FUNCTION experiences_search_sync() RETURNS TRIGGER AS $$
+BEGIN
+    IF TG_OP <> 'INSERT' THEN
+        DELETE FROM search_index WHERE kind = 'experience' AND ref_id = OLD.id;
+    END IF;
+    IF TG_OP <> 'DELETE' THEN
+        INSERT INTO search_index (kind, ref_id, title, body, keywords)
+        VALUES ('experience', NEW.id, NEW.position || ' at ' || NEW.company,
+            NEW.description || ' ' || search_json_list(NEW.highlights, ' '),
+            search_json_list(NEW.technologies, ', '));
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+INSERT INTO search_index (kind, ref_id, title, body, keywords)
+SELECT 'post', id, title, excerpt || ' ' || content, search_json_list(tags, ', ') FROM posts;
+
+INSERT INTO search_index (kind, ref_id, title, body, keywords)
+SELECT 'project', id, title, description || ' ' || search_json_list(highlights, ' '), search_json_list(technologies, ', ')
+FROM projects;
+
+INSERT INTO search_index (kind, ref_id, title, body, keywords)
+SELECT 'experience', id, position || ' at ' || company, description || ' ' || search_json_list(highlights, ' '),
+    search_json_list(technologies, ', ')
+FROM experiences;
+
+CREATE TRIGGER posts_search_sync AFTER INSERT OR UPDATE OR DELETE ON posts
+    FOR EACH ROW EXECUTE FUNCTION posts_search_sync();
+
+CREATE TRIGGER projects_search_sync AFTER INSERT OR UPDATE OR DELETE ON projects
+    FOR EACH ROW EXECUTE FUNCTION projects_search_sync();
+
+CREATE TRIGGER experiences_search_sync AFTER INSERT OR UPDATE OR DELETE ON experiences
+    FOR EACH ROW EXECUTE FUNCTION experiences_search_sync();
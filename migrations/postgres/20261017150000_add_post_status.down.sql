@@ -0,0 +1,22 @@
+-- Restore the search trigger indexing every post, then drop the status columns
+CREATE OR REPLACE FUNCTION posts_search_sync() RETURNS TRIGGER AS $$
+BEGIN
+    IF TG_OP <> 'INSERT' THEN
+        DELETE FROM search_index WHERE kind = 'post' AND ref_id = OLD.id;
+    END IF;
+    IF TG_OP <> 'DELETE' THEN
+        INSERT INTO search_index (kind, ref_id, title, body, keywords)
+        VALUES ('post', NEW.id, NEW.title, NEW.excerpt || ' ' || NEW.content, search_json_list(NEW.tags, ', '));
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+INSERT INTO search_index (kind, ref_id, title, body, keywords)
+SELECT 'post', id, title, excerpt || ' ' || content, search_json_list(tags, ', ')
+FROM posts
+WHERE status <> 'published';
+
+DROP INDEX IF EXISTS idx_posts_status_publish_at;
+ALTER TABLE posts DROP COLUMN publish_at;
+ALTER TABLE posts DROP COLUMN status;
@@ -0,0 +1,22 @@
+-- Publication state of posts. Existing posts were public, so they start out
+-- published; only published posts are served publicly and indexed for search.
+ALTER TABLE posts ADD COLUMN status TEXT NOT NULL DEFAULT 'published'; -- draft, scheduled, published or archived
+ALTER TABLE posts ADD COLUMN publish_at BIGINT; -- UTC timestamp the post was or will be published
+
+UPDATE posts SET publish_at = CAST(EXTRACT(EPOCH FROM created_at) AS BIGINT);
+
+CREATE INDEX IF NOT EXISTS idx_posts_status_publish_at ON posts (status, publish_at);
+
+-- Index published posts only
+CREATE OR REPLACE FUNCTION posts_search_sync() RETURNS TRIGGER AS $$
+BEGIN
+    IF TG_OP <> 'INSERT' THEN
+        DELETE FROM search_index WHERE kind = 'post' AND ref_id = OLD.id;
+    END IF;
+    IF TG_OP <> 'DELETE' AND NEW.status = 'published' THEN
+        INSERT INTO search_index (kind, ref_id, title, body, keywords)
+        VALUES ('post', NEW.id, NEW.title, NEW.excerpt || ' ' || NEW.content, search_json_list(NEW.tags, ', '));
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
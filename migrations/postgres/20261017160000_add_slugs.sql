@@ -0,0 +1,18 @@
+-- Permalinks of posts and projects. Rows created before this migration get a
+-- slug from their title when the server starts.
+ALTER TABLE posts ADD COLUMN slug TEXT;
+ALTER TABLE projects ADD COLUMN slug TEXT;
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_posts_slug ON posts (slug);
+CREATE UNIQUE INDEX IF NOT EXISTS idx_projects_slug ON projects (slug);
+
+-- Previous slugs, redirecting to the row that used them
+CREATE TABLE IF NOT EXISTS slug_aliases (
+    target TEXT NOT NULL, -- posts or projects
+    slug TEXT NOT NULL,
+    ref_id TEXT NOT NULL, -- id of the post or project
+    created_at BIGINT NOT NULL, -- UTC timestamp the slug was replaced
+    PRIMARY KEY (target, slug)
+);
+
+CREATE INDEX IF NOT EXISTS idx_slug_aliases_ref_id ON slug_aliases (target, ref_id);
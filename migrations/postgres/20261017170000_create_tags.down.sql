@@ -0,0 +1,14 @@
+-- Drop the tag and technology tables and the triggers maintaining them
+DROP TRIGGER IF EXISTS posts_tags_sync ON posts;
+DROP TRIGGER IF EXISTS projects_technologies_sync ON projects;
+DROP TRIGGER IF EXISTS experiences_technologies_sync ON experiences;
+DROP FUNCTION IF EXISTS posts_tags_sync();
+DROP FUNCTION IF EXISTS projects_technologies_sync();
+DROP FUNCTION IF EXISTS experiences_technologies_sync();
+DROP FUNCTION IF EXISTS json_list_keys(TEXT);
+
+DROP TABLE IF EXISTS experience_technologies;
+DROP TABLE IF EXISTS project_technologies;
+DROP TABLE IF EXISTS technologies;
+DROP TABLE IF EXISTS post_tags;
+DROP TABLE IF EXISTS tags;
@@ -0,0 +1,125 @@
+-- Tags of posts and technologies of projects and experiences, normalised out
+-- of their JSON array columns. The arrays keep the order and spelling shown on
+-- each item; the triggers below keep the join tables in sync with them. Names
+-- match ignoring case through their key, and keep the spelling of first use.
+CREATE TABLE IF NOT EXISTS tags (
+    key TEXT PRIMARY KEY, -- lowercase name
+    name TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS post_tags (
+    post_id TEXT NOT NULL,
+    tag_key TEXT NOT NULL,
+    position BIGINT NOT NULL, -- index in the post's tags, from 0
+    PRIMARY KEY (post_id, tag_key)
+);
+
+CREATE INDEX IF NOT EXISTS idx_post_tags_tag_key ON post_tags (tag_key);
+
+CREATE TABLE IF NOT EXISTS technologies (
+    key TEXT PRIMARY KEY, -- lowercase name
+    name TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS project_technologies (
+    project_id TEXT NOT NULL,
+    technology_key TEXT NOT NULL,
+    position BIGINT NOT NULL,
+    PRIMARY KEY (project_id, technology_key)
+);
+
+CREATE INDEX IF NOT EXISTS idx_project_technologies_technology_key ON project_technologies (technology_key);
+
+CREATE TABLE IF NOT EXISTS experience_technologies (
+    experience_id TEXT NOT NULL,
+    technology_key TEXT NOT NULL,
+    position BIGINT NOT NULL,
+    PRIMARY KEY (experience_id, technology_key)
+);
+
+CREATE INDEX IF NOT EXISTS idx_experience_technologies_technology_key ON experience_technologies (technology_key);
+
+-- Non-blank entries of a JSON array stored as text: key, name and index from 0.
+-- An entry repeated with another case is only listed the first time.
+CREATE OR REPLACE FUNCTION json_list_keys(list TEXT) RETURNS TABLE (key TEXT, name TEXT, list_index BIGINT) AS $$
+    SELECT DISTINCT ON (LOWER(TRIM(entry.value))) LOWER(TRIM(entry.value)), TRIM(entry.value), entry.ordinality - 1
+    FROM json_array_elements_text(list::json) WITH ORDINALITY AS entry
+    WHERE TRIM(entry.value) <> ''
+    ORDER BY LOWER(TRIM(entry.value)), entry.ordinality
+$$ LANGUAGE sql IMMUTABLE;
+
+CREATE OR REPLACE FUNCTION posts_tags_sync() RETURNS TRIGGER AS $$
+BEGIN
+    IF TG_OP <> 'INSERT' THEN
+        DELETE FROM post_tags WHERE post_id = OLD.id;
+    END IF;
+    IF TG_OP <> 'DELETE' THEN
+        INSERT INTO tags (key, name) SELECT entry.key, entry.name FROM json_list_keys(NEW.tags) AS entry
+        ON CONFLICT (key) DO NOTHING;
+        INSERT INTO post_tags (post_id, tag_key, position)
+        SELECT NEW.id, entry.key, entry.list_index FROM json_list_keys(NEW.tags) AS entry;
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE FUNCTION projects_technologies_sync() RETURNS TRIGGER AS $$
+BEGIN
+    IF TG_OP <> 'INSERT' THEN
+        DELETE FROM project_technologies WHERE project_id = OLD.id;
+    END IF;
+    IF TG_OP <> 'DELETE' THEN
+        INSERT INTO technologies (key, name) SELECT entry.key, entry.name FROM json_list_keys(NEW.technologies) AS entry
+        ON CONFLICT (key) DO NOTHING;
+        INSERT INTO project_technologies (project_id, technology_key, position)
+        SELECT NEW.id, entry.key, entry.list_index FROM json_list_keys(NEW.technologies) AS entry;
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE FUNCTION experiences_technologies_sync() RETURNS TRIGGER AS $$
+BEGIN
+    IF TG_OP <> 'INSERT' THEN
+        DELETE FROM experience_technologies WHERE experience_id = OLD.id;
+    END IF;
+    IF TG_OP <> 'DELETE' THEN
+        INSERT INTO technologies (key, name) SELECT entry.key, entry.name FROM json_list_keys(NEW.technologies) AS entry
+        ON CONFLICT (key) DO NOTHING;
+        INSERT INTO experience_technologies (experience_id, technology_key, position)
+        SELECT NEW.id, entry.key, entry.list_index FROM json_list_keys(NEW.technologies) AS entry;
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+-- Backfill from the existing rows
+INSERT INTO tags (key, name)
+SELECT entry.key, entry.name FROM posts, json_list_keys(posts.tags) AS entry
+ON CONFLICT (key) DO NOTHING;
+
+INSERT INTO post_tags (post_id, tag_key, position)
+SELECT posts.id, entry.key, entry.list_index FROM posts, json_list_keys(posts.tags) AS entry;
+
+INSERT INTO technologies (key, name)
+SELECT entry.key, entry.name FROM projects, json_list_keys(projects.technologies) AS entry
+ON CONFLICT (key) DO NOTHING;
+
+INSERT INTO technologies (key, name)
+SELECT entry.key, entry.name FROM experiences, json_list_keys(experiences.technologies) AS entry
+ON CONFLICT (key) DO NOTHING;
+
+INSERT INTO project_technologies (project_id, technology_key, position)
+SELECT projects.id, entry.key, entry.list_index FROM projects, json_list_keys(projects.technologies) AS entry;
+
+INSERT INTO experience_technologies (experience_id, technology_key, position)
+SELECT experiences.id, entry.key, entry.list_index FROM experiences, json_list_keys(experiences.technologies) AS entry;
+
+CREATE TRIGGER posts_tags_sync AFTER INSERT OR UPDATE OF tags OR DELETE ON posts
+    FOR EACH ROW EXECUTE FUNCTION posts_tags_sync();
+
+CREATE TRIGGER projects_technologies_sync AFTER INSERT OR UPDATE OF technologies OR DELETE ON projects
+    FOR EACH ROW EXECUTE FUNCTION projects_technologies_sync();
+
+CREATE TRIGGER experiences_technologies_sync AFTER INSERT OR UPDATE OF technologies OR DELETE ON experiences
+    FOR EACH ROW EXECUTE FUNCTION experiences_technologies_sync();
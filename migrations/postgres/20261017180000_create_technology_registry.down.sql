@@ -0,0 +1,4 @@
+-- Drop the aliases and the registry columns
+DROP TABLE IF EXISTS technology_aliases;
+ALTER TABLE technologies DROP COLUMN icon;
+ALTER TABLE technologies DROP COLUMN category;
@@ -0,0 +1,50 @@
+-- Technology registry: the technologies table holds the canonical name of each
+-- technology, with an optional category and icon, and aliases map other
+-- spellings onto it. Names written to projects, experiences, skills and GitHub
+-- languages are normalised against the registry.
+ALTER TABLE technologies ADD COLUMN category TEXT; -- e.g. "language", "framework", "database"
+ALTER TABLE technologies ADD COLUMN icon TEXT; -- icon identifier for the frontend, e.g. "simple-icons:rust"
+
+CREATE TABLE IF NOT EXISTS technology_aliases (
+    alias_key TEXT PRIMARY KEY, -- lowercase alias, never the key of a technology
+    alias TEXT NOT NULL,
+    technology_key TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_technology_aliases_technology_key ON technology_aliases (technology_key);
+
+-- Common technologies and their usual abbreviations
+INSERT INTO technologies (key, name, category) VALUES
+    ('typescript', 'TypeScript', 'language'),
+    ('javascript', 'JavaScript', 'language'),
+    ('go', 'Go', 'language'),
+    ('python', 'Python', 'language'),
+    ('c#', 'C#', 'language'),
+    ('c++', 'C++', 'language'),
+    ('node.js', 'Node.js', 'runtime'),
+    ('react', 'React', 'framework'),
+    ('vue.js', 'Vue.js', 'framework'),
+    ('postgresql', 'PostgreSQL', 'database'),
+    ('kubernetes', 'Kubernetes', 'infrastructure')
+ON CONFLICT (key) DO UPDATE SET name = excluded.name, category = excluded.category;
+
+INSERT INTO technology_aliases (alias_key, alias, technology_key) VALUES
+    ('ts', 'TS', 'typescript'),
+    ('js', 'JS', 'javascript'),
+    ('golang', 'Golang', 'go'),
+    ('py', 'py', 'python'),
+    ('csharp', 'CSharp', 'c#'),
+    ('cpp', 'cpp', 'c++'),
+    ('node', 'Node', 'node.js'),
+    ('nodejs', 'NodeJS', 'node.js'),
+    ('reactjs', 'ReactJS', 'react'),
+    ('react.js', 'React.js', 'react'),
+    ('vue', 'Vue', 'vue.js'),
+    ('vuejs', 'VueJS', 'vue.js'),
+    ('postgres', 'Postgres', 'postgresql'),
+    ('k8s', 'k8s', 'kubernetes')
+ON CONFLICT (alias_key) DO NOTHING;
+
+-- Spellings that became aliases are no longer technologies of their own; the
+-- content using them is rewritten to the canonical names when the server starts
+DELETE FROM technologies WHERE key IN (SELECT alias_key FROM technology_aliases);
@@ -0,0 +1,8 @@
+-- Turn skill items back into plain names
+UPDATE skills SET items = COALESCE((
+    SELECT json_agg(
+        CASE WHEN json_typeof(entry.value) = 'object' THEN entry.value -> 'name' ELSE entry.value END
+        ORDER BY entry.ordinality
+    )
+    FROM json_array_elements(skills.items::json) WITH ORDINALITY AS entry
+), '[]')::text;
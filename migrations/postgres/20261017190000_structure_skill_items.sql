@@ -0,0 +1,12 @@
+-- Skill items become objects with a proficiency, years of experience and a
+-- display order. Plain names are converted in place, keeping their order.
+UPDATE skills SET items = COALESCE((
+    SELECT json_agg(
+        CASE WHEN json_typeof(entry.value) = 'string'
+            THEN json_build_object('name', entry.value #>> '{}', 'proficiency', NULL, 'years', NULL, 'order', entry.ordinality - 1)
+            ELSE entry.value
+        END
+        ORDER BY entry.ordinality
+    )
+    FROM json_array_elements(skills.items::json) WITH ORDINALITY AS entry
+), '[]')::text;
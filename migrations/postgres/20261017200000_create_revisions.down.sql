@@ -0,0 +1,2 @@
+-- Drop the revision history
+DROP TABLE IF EXISTS revisions;
@@ -0,0 +1,14 @@
+-- Saved versions of posts, projects, experiences and the profile, one per
+-- write through the API
+CREATE TABLE IF NOT EXISTS revisions (
+    id TEXT PRIMARY KEY,
+    target TEXT NOT NULL, -- posts, projects, experiences or profiles
+    ref_id TEXT NOT NULL, -- id of the item
+    number BIGINT NOT NULL, -- position in the item's history, from 1
+    snapshot TEXT NOT NULL, -- the whole item as JSON
+    author_id TEXT, -- NULL for the version found when history started
+    author_name TEXT,
+    restored_from BIGINT, -- number of the revision this one restored
+    created_at BIGINT NOT NULL, -- UTC timestamp
+    UNIQUE (target, ref_id, number)
+);
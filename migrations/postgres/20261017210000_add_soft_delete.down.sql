@@ -0,0 +1,119 @@
+-- Purge the trash, since deleting was permanent before, then restore the
+-- triggers indexing every row and drop the columns
+DELETE FROM revisions WHERE (target = 'posts' AND ref_id IN (SELECT id FROM posts WHERE deleted_at IS NOT NULL))
+    OR (target = 'projects' AND ref_id IN (SELECT id FROM projects WHERE deleted_at IS NOT NULL))
+    OR (target = 'experiences' AND ref_id IN (SELECT id FROM experiences WHERE deleted_at IS NOT NULL));
+DELETE FROM slug_aliases WHERE (target = 'posts' AND ref_id IN (SELECT id FROM posts WHERE deleted_at IS NOT NULL))
+    OR (target = 'projects' AND ref_id IN (SELECT id FROM projects WHERE deleted_at IS NOT NULL));
+DELETE FROM posts WHERE deleted_at IS NOT NULL;
+DELETE FROM projects WHERE deleted_at IS NOT NULL;
+DELETE FROM experiences WHERE deleted_at IS NOT NULL;
+DELETE FROM skills WHERE deleted_at IS NOT NULL;
+DELETE FROM contacts WHERE deleted_at IS NOT NULL;
+
+DROP TRIGGER IF EXISTS posts_tags_sync ON posts;
+DROP TRIGGER IF EXISTS projects_technologies_sync ON projects;
+DROP TRIGGER IF EXISTS experiences_technologies_sync ON experiences;
+
+CREATE TRIGGER posts_tags_sync AFTER INSERT OR UPDATE OF tags OR DELETE ON posts
+    FOR EACH ROW EXECUTE FUNCTION posts_tags_sync();
+
+CREATE TRIGGER projects_technologies_sync AFTER INSERT OR UPDATE OF technologies OR DELETE ON projects
+    FOR EACH ROW EXECUTE FUNCTION projects_technologies_sync();
+
+CREATE TRIGGER experiences_technologies_sync AFTER INSERT OR UPDATE OF technologies OR DELETE ON experiences
+    FOR EACH ROW EXECUTE FUNCTION experiences_technologies_sync();
+
+CREATE OR REPLACE FUNCTION posts_tags_sync() RETURNS TRIGGER AS $$
+BEGIN
+    IF TG_OP <> 'INSERT' THEN
+        DELETE FROM post_tags WHERE post_id = OLD.id;
+    END IF;
+    IF TG_OP <> 'DELETE' THEN
+        INSERT INTO tags (key, name) SELECT entry.key, entry.name FROM json_list_keys(NEW.tags) AS entry
+        ON CONFLICT (key) DO NOTHING;
+        INSERT INTO post_tags (post_id, tag_key, position)
+        SELECT NEW.id, entry.key, entry.list_index FROM json_list_keys(NEW.tags) AS entry;
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE FUNCTION projects_technologies_sync() RETURNS TRIGGER AS $$
+BEGIN
+    IF TG_OP <> 'INSERT' THEN
+        DELETE FROM project_technologies WHERE project_id = OLD.id;
+    END IF;
+    IF TG_OP <> 'DELETE' THEN
+        INSERT INTO technologies (key, name) SELECT entry.key, entry.name FROM json_list_keys(NEW.technologies) AS entry
+        ON CONFLICT (key) DO NOTHING;
+        INSERT INTO project_technologies (project_id, technology_key, position)
+        SELECT NEW.id, entry.key, entry.list_index FROM json_list_keys(NEW.technologies) AS entry;
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE FUNCTION experiences_technologies_sync() RETURNS TRIGGER AS $$
+BEGIN
+    IF TG_OP <> 'INSERT' THEN
+        DELETE FROM experience_technologies WHERE experience_id = OLD.id;
+    END IF;
+    IF TG_OP <> 'DELETE' THEN
+        INSERT INTO technologies (key, name) SELECT entry.key, entry.name FROM json_list_keys(NEW.technologies) AS entry
+        ON CONFLICT (key) DO NOTHING;
+        INSERT INTO experience_technologies (experience_id, technology_key, position)
+        SELECT NEW.id, entry.key, entry.list_index FROM json_list_keys(NEW.technologies) AS entry;
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE FUNCTION posts_search_sync() RETURNS TRIGGER AS $$
+BEGIN
+    IF TG_OP <> 'INSERT' THEN
+        DELETE FROM search_index WHERE kind = 'post' AND ref_id = OLD.id;
+    END IF;
+    IF TG_OP <> 'DELETE' AND NEW.status = 'published' THEN
+        INSERT INTO search_index (kind, ref_id, title, body, keywords)
+        VALUES ('post', NEW.id, NEW.title, NEW.excerpt || ' ' || NEW.content, search_json_list(NEW.tags, ', '));
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE FUNCTION projects_search_sync() RETURNS TRIGGER AS $$
+BEGIN
+    IF TG_OP <> 'INSERT' THEN
+        DELETE FROM search_index WHERE kind = 'project' AND ref_id = OLD.id;
+    END IF;
+    IF TG_OP <> 'DELETE' THEN
+        INSERT INTO search_index (kind, ref_id, title, body, keywords)
+        VALUES ('project', NEW.id, NEW.title,
+            NEW.description || ' ' || search_json_list(NEW.highlights, ' '),
+            search_json_list(NEW.technologies, ', '));
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE FUNCTION experiences_search_sync() RETURNS TRIGGER AS $$
+BEGIN
+    IF TG_OP <> 'INSERT' THEN
+        DELETE FROM search_index WHERE kind = 'experience' AND ref_id = OLD.id;
+    END IF;
+    IF TG_OP <> 'DELETE' THEN
+        INSERT INTO search_index (kind, ref_id, title, body, keywords)
+        VALUES ('experience', NEW.id, NEW.position || ' at ' || NEW.company,
+            NEW.description || ' ' || search_json_list(NEW.highlights, ' '),
+            search_json_list(NEW.technologies, ', '));
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+ALTER TABLE contacts DROP COLUMN deleted_at;
+ALTER TABLE skills DROP COLUMN deleted_at;
+ALTER TABLE experiences DROP COLUMN deleted_at;
+ALTER TABLE projects DROP COLUMN deleted_at;
+ALTER TABLE posts DROP COLUMN deleted_at;
@@ -0,0 +1,109 @@
+-- Deleted posts, projects, experiences, skills and contact messages stay in
+-- the trash until they are restored or purged. Trashed rows are left out of
+-- the tag, technology and search indexes, as if they were gone.
+ALTER TABLE posts ADD COLUMN deleted_at BIGINT; -- UTC timestamp the row was moved to the trash
+ALTER TABLE projects ADD COLUMN deleted_at BIGINT;
+ALTER TABLE experiences ADD COLUMN deleted_at BIGINT;
+ALTER TABLE skills ADD COLUMN deleted_at BIGINT;
+ALTER TABLE contacts ADD COLUMN deleted_at BIGINT;
+
+CREATE OR REPLACE FUNCTION posts_tags_sync() RETURNS TRIGGER AS $$
+BEGIN
+    IF TG_OP <> 'INSERT' THEN
+        DELETE FROM post_tags WHERE post_id = OLD.id;
+    END IF;
+    IF TG_OP <> 'DELETE' AND NEW.deleted_at IS NULL THEN
+        INSERT INTO tags (key, name) SELECT entry.key, entry.name FROM json_list_keys(NEW.tags) AS entry
+        ON CONFLICT (key) DO NOTHING;
+        INSERT INTO post_tags (post_id, tag_key, position)
+        SELECT NEW.id, entry.key, entry.list_index FROM json_list_keys(NEW.tags) AS entry;
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE FUNCTION projects_technologies_sync() RETURNS TRIGGER AS $$
+BEGIN
+    IF TG_OP <> 'INSERT' THEN
+        DELETE FROM project_technologies WHERE project_id = OLD.id;
+    END IF;
+    IF TG_OP <> 'DELETE' AND NEW.deleted_at IS NULL THEN
+        INSERT INTO technologies (key, name) SELECT entry.key, entry.name FROM json_list_keys(NEW.technologies) AS entry
+        ON CONFLICT (key) DO NOTHING;
+        INSERT INTO project_technologies (project_id, technology_key, position)
+        SELECT NEW.id, entry.key, entry.list_index FROM json_list_keys(NEW.technologies) AS entry;
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE FUNCTION experiences_technologies_sync() RETURNS TRIGGER AS $$
+BEGIN
+    IF TG_OP <> 'INSERT' THEN
+        DELETE FROM experience_technologies WHERE experience_id = OLD.id;
+    END IF;
+    IF TG_OP <> 'DELETE' AND NEW.deleted_at IS NULL THEN
+        INSERT INTO technologies (key, name) SELECT entry.key, entry.name FROM json_list_keys(NEW.technologies) AS entry
+        ON CONFLICT (key) DO NOTHING;
+        INSERT INTO experience_technologies (experience_id, technology_key, position)
+        SELECT NEW.id, entry.key, entry.list_index FROM json_list_keys(NEW.technologies) AS entry;
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE FUNCTION posts_search_sync() RETURNS TRIGGER AS $$
+BEGIN
+    IF TG_OP <> 'INSERT' THEN
+        DELETE FROM search_index WHERE kind = 'post' AND ref_id = OLD.id;
+    END IF;
+    IF TG_OP <> 'DELETE' AND NEW.status = 'published' AND NEW.deleted_at IS NULL THEN
+        INSERT INTO search_index (kind, ref_id, title, body, keywords)
+        VALUES ('post', NEW.id, NEW.title, NEW.excerpt || ' ' || NEW.content, search_json_list(NEW.tags, ', '));
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE FUNCTION projects_search_sync() RETURNS TRIGGER AS $$
+BEGIN
+    IF TG_OP <> 'INSERT' THEN
+        DELETE FROM search_index WHERE kind = 'project' AND ref_id = OLD.id;
+    END IF;
+    IF TG_OP <> 'DELETE' AND NEW.deleted_at IS NULL THEN
+        INSERT INTO search_index (kind, ref_id, title, body, keywords)
+        VALUES ('project', NEW.id, NEW.title,
+            NEW.description || ' ' || search_json_list(NEW.highlights, ' '),
+            search_json_list(NEW.technologies, ', '));
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE FUNCTION experiences_search_sync() RETURNS TRIGGER AS $$
+BEGIN
+    IF TG_OP <> 'INSERT' THEN
+        DELETE FROM search_index WHERE kind = 'experience' AND ref_id = OLD.id;
+    END IF;
+    IF TG_OP <> 'DELETE' AND NEW.deleted_at IS NULL THEN
+        INSERT INTO search_index (kind, ref_id, title, body, keywords)
+        VALUES ('experience', NEW.id, NEW.position || ' at ' || NEW.company,
+            NEW.description || ' ' || search_json_list(NEW.highlights, ' '),
+            search_json_list(NEW.technologies, ', '));
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS posts_tags_sync ON posts;
+DROP TRIGGER IF EXISTS projects_technologies_sync ON projects;
+DROP TRIGGER IF EXISTS experiences_technologies_sync ON experiences;
+
+CREATE TRIGGER posts_tags_sync AFTER INSERT OR UPDATE OF tags, deleted_at OR DELETE ON posts
+    FOR EACH ROW EXECUTE FUNCTION posts_tags_sync();
+
+CREATE TRIGGER projects_technologies_sync AFTER INSERT OR UPDATE OF technologies, deleted_at OR DELETE ON projects
+    FOR EACH ROW EXECUTE FUNCTION projects_technologies_sync();
+
+CREATE TRIGGER experiences_technologies_sync AFTER INSERT OR UPDATE OF technologies, deleted_at OR DELETE ON experiences
+    FOR EACH ROW EXECUTE FUNCTION experiences_technologies_sync();
@@ -0,0 +1,6 @@
+-- Drop the versions
+ALTER TABLE profiles DROP COLUMN version;
+ALTER TABLE skills DROP COLUMN version;
+ALTER TABLE experiences DROP COLUMN version;
+ALTER TABLE projects DROP COLUMN version;
+ALTER TABLE posts DROP COLUMN version;
@@ -0,0 +1,2 @@
+-- Drop experiences table
+DROP TABLE IF EXISTS experiences;
@@ -0,0 +1,2 @@
+-- Drop profiles table
+DROP TABLE IF EXISTS profiles;
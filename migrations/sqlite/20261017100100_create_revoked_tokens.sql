@@ -0,0 +1,6 @@
+-- Create revoked_tokens table (access token denylist)
+CREATE TABLE IF NOT EXISTS revoked_tokens (
+    jti TEXT PRIMARY KEY,
+    expires_at INTEGER NOT NULL, -- UTC timestamp after which the entry can be purged
+    revoked_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
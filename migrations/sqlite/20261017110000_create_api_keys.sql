@@ -0,0 +1,13 @@
+-- Create api_keys table
+CREATE TABLE IF NOT EXISTS api_keys (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    prefix TEXT NOT NULL UNIQUE, -- Public part of the key, used for lookup
+    key_hash TEXT NOT NULL, -- SHA-256 of the full key, hex encoded
+    scopes TEXT NOT NULL, -- JSON array as text
+    created_by TEXT NOT NULL, -- User the key acts on behalf of
+    expires_at INTEGER, -- UTC timestamp, NULL for keys that never expire
+    last_used_at INTEGER, -- UTC timestamp
+    revoked_at INTEGER, -- UTC timestamp
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
@@ -0,0 +1,9 @@
+-- Create login_attempts table
+CREATE TABLE IF NOT EXISTS login_attempts (
+    id TEXT PRIMARY KEY,
+    username TEXT NOT NULL, -- Username as submitted, the account may not exist
+    ip_address TEXT,
+    user_agent TEXT,
+    outcome TEXT NOT NULL, -- success, mfa_required, invalid_credentials, invalid_code or locked
+    attempted_at INTEGER NOT NULL -- UTC timestamp
+);
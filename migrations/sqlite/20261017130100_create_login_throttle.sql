@@ -0,0 +1,7 @@
+-- Create login_throttle table
+CREATE TABLE IF NOT EXISTS login_throttle (
+    throttle_key TEXT PRIMARY KEY, -- "user:<username>" or "ip:<address>"
+    failures INTEGER NOT NULL DEFAULT 0, -- Consecutive failed attempts
+    last_failure_at INTEGER NOT NULL, -- UTC timestamp
+    locked_until INTEGER -- UTC timestamp, NULL when not locked
+);
@@ -0,0 +1,11 @@
+-- Drop the full-text index and the triggers maintaining it
+DROP TRIGGER IF EXISTS posts_search_insert;
+DROP TRIGGER IF EXISTS posts_search_update;
+DROP TRIGGER IF EXISTS posts_search_delete;
+DROP TRIGGER IF EXISTS projects_search_insert;
+DROP TRIGGER IF EXISTS projects_search_update;
+DROP TRIGGER IF EXISTS projects_search_delete;
+DROP TRIGGER IF EXISTS experiences_search_insert;
+DROP TRIGGER IF EXISTS experiences_search_update;
+DROP TRIGGER IF EXISTS experiences_search_delete;
+DROP TABLE IF EXISTS search_index;
@@ -0,0 +1,83 @@
+-- Full-text index of posts, projects and experiences. Titles, body text and
+-- keywords (tags and technologies) are indexed with the porter stemmer; the
+-- triggers below keep one row per indexed item in sync with its table.
+CREATE VIRTUAL TABLE IF NOT EXISTS search_index USING fts5(
+    kind UNINDEXED, -- post, project or experience
+    ref_id UNINDEXED, -- id of the indexed row
+    title,
+    body,
+    keywords,
+    tokenize = 'porter unicode61 remove_diacritics 2'
+);
+
+INSERT INTO search_index (kind, ref_id, title, body, keywords)
+SELECT 'post', id, title, excerpt || ' ' || content,
+    (SELECT COALESCE(group_concat(value, ', '), '') FROM json_each(posts.tags))
+FROM posts;
+
+INSERT INTO search_index (kind, ref_id, title, body, keywords)
+SELECT 'project', id, title,
+    description || ' ' || (SELECT COALESCE(group_concat(value, ' '), '') FROM json_each(projects.highlights)),
+    (SELECT COALESCE(group_concat(value, ', '), '') FROM json_each(projects.technologies))
+FROM projects;
+
+INSERT INTO search_index (kind, ref_id, title, body, keywords)
+SELECT 'experience', id, position || ' at ' || company,
+    description || ' ' || (SELECT COALESCE(group_concat(value, ' '), '') FROM json_each(experiences.highlights)),
+    (SELECT COALESCE(group_concat(value, ', '), '') FROM json_each(experiences.technologies))
+FROM experiences;
+
+CREATE TRIGGER IF NOT EXISTS posts_search_insert AFTER INSERT ON posts BEGIN
+    INSERT INTO search_index (kind, ref_id, title, body, keywords)
+    VALUES ('post', new.id, new.title, new.excerpt || ' ' || new.content,
+        (SELECT COALESCE(group_concat(value, ', '), '') FROM json_each(new.tags)));
+END;
+
+CREATE TRIGGER IF NOT EXISTS posts_search_update AFTER UPDATE ON posts BEGIN
+    DELETE FROM search_index WHERE kind = 'post' AND ref_id = old.id;
+    INSERT INTO search_index (kind, ref_id, title, body, keywords)
+    VALUES ('post', new.id, new.title, new.excerpt || ' ' || new.content,
+        (SELECT COALESCE(group_concat(value, ', '), '') FROM json_each(new.tags)));
+END;
+
+CREATE TRIGGER IF NOT EXISTS posts_search_delete AFTER DELETE ON posts BEGIN
+    DELETE FROM search_index WHERE kind = 'post' AND ref_id = old.id;
+END;
+
+CREATE TRIGGER IF NOT EXISTS projects_search_insert AFTER INSERT ON projects BEGIN
+    INSERT INTO search_index (kind, ref_id, title, body, keywords)
+    VALUES ('project', new.id, new.title,
+        new.description || ' ' || (SELECT COALESCE(group_concat(value, ' '), '') FROM json_each(new.highlights)),
+        (SELECT COALESCE(group_concat(value, ', '), '') FROM json_each(new.technologies)));
+END;
+
+CREATE TRIGGER IF NOT EXISTS projects_search_update AFTER UPDATE ON projects BEGIN
+    DELETE FROM search_index WHERE kind = 'project' AND ref_id = old.id;
+    INSERT INTO search_index (kind, ref_id, title, body, keywords)
+    VALUES ('project', new.id, new.title,
+        new.description || ' ' || (SELECT COALESCE(group_concat(value, ' '), '') FROM json_each(new.highlights)),
+        (SELECT COALESCE(group_concat(value, ', '), '') FROM json_each(new.technologies)));
+END;
+
+CREATE TRIGGER IF NOT EXISTS projects_search_delete AFTER DELETE ON projects BEGIN
+    DELETE FROM search_index WHERE kind = 'project' AND ref_id = old.id;
+END;
+
+CREATE TRIGGER IF NOT EXISTS experiences_search_insert AFTER INSERT ON experiences BEGIN
+    INSERT INTO search_index (kind, ref_id, title, body, keywords)
+    VALUES ('experience', new.id, new.position || ' at ' || new.company,
+        new.description || ' ' || (SELECT COALESCE(group_concat(value, ' '), '') FROM json_each(new.highlights)),
+        (SELECT COALESCE(group_concat(value, ', '), '') FROM json_each(new.technologies)));
+END;
+
+CREATE TRIGGER IF NOT EXISTS experiences_search_update AFTER UPDATE ON experiences BEGIN
+    DELETE FROM search_index WHERE kind = 'experience' AND ref_id = old.id;
+    INSERT INTO search_index (kind, ref_id, title, body, keywords)
+    VALUES ('experience', new.id, new.position || ' at ' || new.company,
+        new.description || ' ' || (SELECT COALESCE(group_concat(value, ' '), '') FROM json_each(new.highlights)),
+        (SELECT COALESCE(group_concat(value, ', '), '') FROM json_each(new.technologies)));
+END;
+
+CREATE TRIGGER IF NOT EXISTS experiences_search_delete AFTER DELETE ON experiences BEGIN
+    DELETE FROM search_index WHERE kind = 'experience' AND ref_id = old.id;
+END;
@@ -0,0 +1,26 @@
+-- Restore the search triggers indexing every post, then drop the status columns
+DROP TRIGGER IF EXISTS posts_search_insert;
+DROP TRIGGER IF EXISTS posts_search_update;
+
+CREATE TRIGGER IF NOT EXISTS posts_search_insert AFTER INSERT ON posts BEGIN
+    INSERT INTO search_index (kind, ref_id, title, body, keywords)
+    VALUES ('post', new.id, new.title, new.excerpt || ' ' || new.content,
+        (SELECT COALESCE(group_concat(value, ', '), '') FROM json_each(new.tags)));
+END;
+
+CREATE TRIGGER IF NOT EXISTS posts_search_update AFTER UPDATE ON posts BEGIN
+    DELETE FROM search_index WHERE kind = 'post' AND ref_id = old.id;
+    INSERT INTO search_index (kind, ref_id, title, body, keywords)
+    VALUES ('post', new.id, new.title, new.excerpt || ' ' || new.content,
+        (SELECT COALESCE(group_concat(value, ', '), '') FROM json_each(new.tags)));
+END;
+
+INSERT INTO search_index (kind, ref_id, title, body, keywords)
+SELECT 'post', id, title, excerpt || ' ' || content,
+    (SELECT COALESCE(group_concat(value, ', '), '') FROM json_each(posts.tags))
+FROM posts
+WHERE status <> 'published';
+
+DROP INDEX IF EXISTS idx_posts_status_publish_at;
+ALTER TABLE posts DROP COLUMN publish_at;
+ALTER TABLE posts DROP COLUMN status;
@@ -0,0 +1,26 @@
+-- Publication state of posts. Existing posts were public, so they start out
+-- published; only published posts are served publicly and indexed for search.
+ALTER TABLE posts ADD COLUMN status TEXT NOT NULL DEFAULT 'published'; -- draft, scheduled, published or archived
+ALTER TABLE posts ADD COLUMN publish_at INTEGER; -- UTC timestamp the post was or will be published
+
+UPDATE posts SET publish_at = CAST(strftime('%s', created_at) AS INTEGER);
+
+CREATE INDEX IF NOT EXISTS idx_posts_status_publish_at ON posts (status, publish_at);
+
+-- Index published posts only
+DROP TRIGGER IF EXISTS posts_search_insert;
+DROP TRIGGER IF EXISTS posts_search_update;
+
+CREATE TRIGGER IF NOT EXISTS posts_search_insert AFTER INSERT ON posts WHEN new.status = 'published' BEGIN
+    INSERT INTO search_index (kind, ref_id, title, body, keywords)
+    VALUES ('post', new.id, new.title, new.excerpt || ' ' || new.content,
+        (SELECT COALESCE(group_concat(value, ', '), '') FROM json_each(new.tags)));
+END;
+
+CREATE TRIGGER IF NOT EXISTS posts_search_update AFTER UPDATE ON posts BEGIN
+    DELETE FROM search_index WHERE kind = 'post' AND ref_id = old.id;
+    INSERT INTO search_index (kind, ref_id, title, body, keywords)
+    SELECT 'post', new.id, new.title, new.excerpt || ' ' || new.content,
+        (SELECT COALESCE(group_concat(value, ', '), '') FROM json_each(new.tags))
+    WHERE new.status = 'published';
+END;
@@ -0,0 +1,6 @@
+-- Drop slugs and their aliases
+DROP TABLE IF EXISTS slug_aliases;
+DROP INDEX IF EXISTS idx_projects_slug;
+DROP INDEX IF EXISTS idx_posts_slug;
+ALTER TABLE projects DROP COLUMN slug;
+ALTER TABLE posts DROP COLUMN slug;
@@ -0,0 +1,16 @@
+-- Drop the tag and technology tables and the triggers maintaining them
+DROP TRIGGER IF EXISTS posts_tags_insert;
+DROP TRIGGER IF EXISTS posts_tags_update;
+DROP TRIGGER IF EXISTS posts_tags_delete;
+DROP TRIGGER IF EXISTS projects_technologies_insert;
+DROP TRIGGER IF EXISTS projects_technologies_update;
+DROP TRIGGER IF EXISTS projects_technologies_delete;
+DROP TRIGGER IF EXISTS experiences_technologies_insert;
+DROP TRIGGER IF EXISTS experiences_technologies_update;
+DROP TRIGGER IF EXISTS experiences_technologies_delete;
+
+DROP TABLE IF EXISTS experience_technologies;
+DROP TABLE IF EXISTS project_technologies;
+DROP TABLE IF EXISTS technologies;
+DROP TABLE IF EXISTS post_tags;
+DROP TABLE IF EXISTS tags;
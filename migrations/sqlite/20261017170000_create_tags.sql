@@ -0,0 +1,143 @@
+-- Tags of posts and technologies of projects and experiences, normalised out
+-- of their JSON array columns. The arrays keep the order and spelling shown on
+-- each item; the triggers below keep the join tables in sync with them. Names
+-- match ignoring case through their key, and keep the spelling of first use.
+CREATE TABLE IF NOT EXISTS tags (
+    key TEXT PRIMARY KEY, -- lowercase name
+    name TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS post_tags (
+    post_id TEXT NOT NULL,
+    tag_key TEXT NOT NULL,
+    position INTEGER NOT NULL, -- index in the post's tags, from 0
+    PRIMARY KEY (post_id, tag_key)
+);
+
+CREATE INDEX IF NOT EXISTS idx_post_tags_tag_key ON post_tags (tag_key);
+
+CREATE TABLE IF NOT EXISTS technologies (
+    key TEXT PRIMARY KEY, -- lowercase name
+    name TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS project_technologies (
+    project_id TEXT NOT NULL,
+    technology_key TEXT NOT NULL,
+    position INTEGER NOT NULL,
+    PRIMARY KEY (project_id, technology_key)
+);
+
+CREATE INDEX IF NOT EXISTS idx_project_technologies_technology_key ON project_technologies (technology_key);
+
+CREATE TABLE IF NOT EXISTS experience_technologies (
+    experience_id TEXT NOT NULL,
+    technology_key TEXT NOT NULL,
+    position INTEGER NOT NULL,
+    PRIMARY KEY (experience_id, technology_key)
+);
+
+CREATE INDEX IF NOT EXISTS idx_experience_technologies_technology_key ON experience_technologies (technology_key);
+
+-- Backfill from the existing rows
+INSERT INTO tags (key, name)
+SELECT LOWER(TRIM(tag.value)), TRIM(tag.value) FROM posts, json_each(posts.tags) AS tag
+WHERE TRIM(tag.value) <> ''
+ON CONFLICT (key) DO NOTHING;
+
+INSERT INTO post_tags (post_id, tag_key, position)
+SELECT posts.id, LOWER(TRIM(tag.value)), MIN(tag.key) FROM posts, json_each(posts.tags) AS tag
+WHERE TRIM(tag.value) <> ''
+GROUP BY posts.id, LOWER(TRIM(tag.value));
+
+INSERT INTO technologies (key, name)
+SELECT LOWER(TRIM(technology.value)), TRIM(technology.value) FROM projects, json_each(projects.technologies) AS technology
+WHERE TRIM(technology.value) <> ''
+ON CONFLICT (key) DO NOTHING;
+
+INSERT INTO technologies (key, name)
+SELECT LOWER(TRIM(technology.value)), TRIM(technology.value) FROM experiences, json_each(experiences.technologies) AS technology
+WHERE TRIM(technology.value) <> ''
+ON CONFLICT (key) DO NOTHING;
+
+INSERT INTO project_technologies (project_id, technology_key, position)
+SELECT projects.id, LOWER(TRIM(technology.value)), MIN(technology.key) FROM projects, json_each(projects.technologies) AS technology
+WHERE TRIM(technology.value) <> ''
+GROUP BY projects.id, LOWER(TRIM(technology.value));
+
+INSERT INTO experience_technologies (experience_id, technology_key, position)
+SELECT experiences.id, LOWER(TRIM(technology.value)), MIN(technology.key) FROM experiences, json_each(experiences.technologies) AS technology
+WHERE TRIM(technology.value) <> ''
+GROUP BY experiences.id, LOWER(TRIM(technology.value));
+
+-- Posts
+CREATE TRIGGER IF NOT EXISTS posts_tags_insert AFTER INSERT ON posts BEGIN
+    INSERT INTO tags (key, name)
+    SELECT LOWER(TRIM(value)), TRIM(value) FROM json_each(new.tags) WHERE TRIM(value) <> ''
+    ON CONFLICT (key) DO NOTHING;
+    INSERT INTO post_tags (post_id, tag_key, position)
+    SELECT new.id, LOWER(TRIM(value)), MIN(key) FROM json_each(new.tags) WHERE TRIM(value) <> ''
+    GROUP BY LOWER(TRIM(value));
+END;
+
+CREATE TRIGGER IF NOT EXISTS posts_tags_update AFTER UPDATE OF tags ON posts BEGIN
+    DELETE FROM post_tags WHERE post_id = old.id;
+    INSERT INTO tags (key, name)
+    SELECT LOWER(TRIM(value)), TRIM(value) FROM json_each(new.tags) WHERE TRIM(value) <> ''
+    ON CONFLICT (key) DO NOTHING;
+    INSERT INTO post_tags (post_id, tag_key, position)
+    SELECT new.id, LOWER(TRIM(value)), MIN(key) FROM json_each(new.tags) WHERE TRIM(value) <> ''
+    GROUP BY LOWER(TRIM(value));
+END;
+
+CREATE TRIGGER IF NOT EXISTS posts_tags_delete AFTER DELETE ON posts BEGIN
+    DELETE FROM post_tags WHERE post_id = old.id;
+END;
+
+-- Projects
+CREATE TRIGGER IF NOT EXISTS projects_technologies_insert AFTER INSERT ON projects BEGIN
+    INSERT INTO technologies (key, name)
+    SELECT LOWER(TRIM(value)), TRIM(value) FROM json_each(new.technologies) WHERE TRIM(value) <> ''
+    ON CONFLICT (key) DO NOTHING;
+    INSERT INTO project_technologies (project_id, technology_key, position)
+    SELECT new.id, LOWER(TRIM(value)), MIN(key) FROM json_each(new.technologies) WHERE TRIM(value) <> ''
+    GROUP BY LOWER(TRIM(value));
+END;
+
+CREATE TRIGGER IF NOT EXISTS projects_technologies_update AFTER UPDATE OF technologies ON projects BEGIN
+    DELETE FROM project_technologies WHERE project_id = old.id;
+    INSERT INTO technologies (key, name)
+    SELECT LOWER(TRIM(value)), TRIM(value) FROM json_each(new.technologies) WHERE TRIM(value) <> ''
+    ON CONFLICT (key) DO NOTHING;
+    INSERT INTO project_technologies (project_id, technology_key, position)
+    SELECT new.id, LOWER(TRIM(value)), MIN(key) FROM json_each(new.technologies) WHERE TRIM(value) <> ''
+    GROUP BY LOWER(TRIM(value));
+END;
+
+CREATE TRIGGER IF NOT EXISTS projects_technologies_delete AFTER DELETE ON projects BEGIN
+    DELETE FROM project_technologies WHERE project_id = old.id;
+END;
+
+-- Experiences
+CREATE TRIGGER IF NOT EXISTS experiences_technologies_insert AFTER INSERT ON experiences BEGIN
+    INSERT INTO technologies (key, name)
+    SELECT LOWER(TRIM(value)), TRIM(value) FROM json_each(new.technologies) WHERE TRIM(value) <> ''
+    ON CONFLICT (key) DO NOTHING;
+    INSERT INTO experience_technologies (experience_id, technology_key, position)
+    SELECT new.id, LOWER(TRIM(value)), MIN(key) FROM json_each(new.technologies) WHERE TRIM(value) <> ''
+    GROUP BY LOWER(TRIM(value));
+END;
+
+CREATE TRIGGER IF NOT EXISTS experiences_technologies_update AFTER UPDATE OF technologies ON experiences BEGIN
+    DELETE FROM experience_technologies WHERE experience_id = old.id;
+    INSERT INTO technologies (key, name)
+    SELECT LOWER(TRIM(value)), TRIM(value) FROM json_each(new.technologies) WHERE TRIM(value) <> ''
+    ON CONFLICT (key) DO NOTHING;
+    INSERT INTO experience_technologies (experience_id, technology_key, position)
+    SELECT new.id, LOWER(TRIM(value)), MIN(key) FROM json_each(new.technologies) WHERE TRIM(value) <> ''
+    GROUP BY LOWER(TRIM(value));
+END;
+
+CREATE TRIGGER IF NOT EXISTS experiences_technologies_delete AFTER DELETE ON experiences BEGIN
+    DELETE FROM experience_technologies WHERE experience_id = old.id;
+END;
@@ -0,0 +1,8 @@
+-- Turn skill items back into plain names
+UPDATE skills SET items = (
+    SELECT json_group_array(
+        CASE WHEN entry.type = 'object' THEN json_extract(entry.value, '$.name') ELSE entry.value END
+    )
+    FROM json_each(skills.items) AS entry
+)
+WHERE json_valid(items);
@@ -0,0 +1,12 @@
+-- Skill items become objects with a proficiency, years of experience and a
+-- display order. Plain names are converted in place, keeping their order.
+UPDATE skills SET items = (
+    SELECT json_group_array(
+        CASE WHEN entry.type = 'text'
+            THEN json_object('name', entry.value, 'proficiency', NULL, 'years', NULL, 'order', entry.key)
+            ELSE json(entry.value)
+        END
+    )
+    FROM json_each(skills.items) AS entry
+)
+WHERE json_valid(items);
@@ -0,0 +1,79 @@
+-- Purge the trash, since deleting was permanent before, then restore the
+-- triggers indexing every row and drop the columns
+DELETE FROM revisions WHERE (target = 'posts' AND ref_id IN (SELECT id FROM posts WHERE deleted_at IS NOT NULL))
+    OR (target = 'projects' AND ref_id IN (SELECT id FROM projects WHERE deleted_at IS NOT NULL))
+    OR (target = 'experiences' AND ref_id IN (SELECT id FROM experiences WHERE deleted_at IS NOT NULL));
+DELETE FROM slug_aliases WHERE (target = 'posts' AND ref_id IN (SELECT id FROM posts WHERE deleted_at IS NOT NULL))
+    OR (target = 'projects' AND ref_id IN (SELECT id FROM projects WHERE deleted_at IS NOT NULL));
+DELETE FROM posts WHERE deleted_at IS NOT NULL;
+DELETE FROM projects WHERE deleted_at IS NOT NULL;
+DELETE FROM experiences WHERE deleted_at IS NOT NULL;
+DELETE FROM skills WHERE deleted_at IS NOT NULL;
+DELETE FROM contacts WHERE deleted_at IS NOT NULL;
+
+DROP TRIGGER IF EXISTS posts_tags_update;
+DROP TRIGGER IF EXISTS projects_technologies_update;
+DROP TRIGGER IF EXISTS experiences_technologies_update;
+DROP TRIGGER IF EXISTS posts_search_update;
+DROP TRIGGER IF EXISTS projects_search_update;
+DROP TRIGGER IF EXISTS experiences_search_update;
+
+CREATE TRIGGER IF NOT EXISTS posts_tags_update AFTER UPDATE OF tags ON posts BEGIN
+    DELETE FROM post_tags WHERE post_id = old.id;
+    INSERT INTO tags (key, name)
+    SELECT LOWER(TRIM(value)), TRIM(value) FROM json_each(new.tags) WHERE TRIM(value) <> ''
+    ON CONFLICT (key) DO NOTHING;
+    INSERT INTO post_tags (post_id, tag_key, position)
+    SELECT new.id, LOWER(TRIM(value)), MIN(key) FROM json_each(new.tags) WHERE TRIM(value) <> ''
+    GROUP BY LOWER(TRIM(value));
+END;
+
+CREATE TRIGGER IF NOT EXISTS projects_technologies_update AFTER UPDATE OF technologies ON projects BEGIN
+    DELETE FROM project_technologies WHERE project_id = old.id;
+    INSERT INTO technologies (key, name)
+    SELECT LOWER(TRIM(value)), TRIM(value) FROM json_each(new.technologies) WHERE TRIM(value) <> ''
+    ON CONFLICT (key) DO NOTHING;
+    INSERT INTO project_technologies (project_id, technology_key, position)
+    SELECT new.id, LOWER(TRIM(value)), MIN(key) FROM json_each(new.technologies) WHERE TRIM(value) <> ''
+    GROUP BY LOWER(TRIM(value));
+END;
+
+CREATE TRIGGER IF NOT EXISTS experiences_technologies_update AFTER UPDATE OF technologies ON experiences BEGIN
+    DELETE FROM experience_technologies WHERE experience_id = old.id;
+    INSERT INTO technologies (key, name)
+    SELECT LOWER(TRIM(value)), TRIM(value) FROM json_each(new.technologies) WHERE TRIM(value) <> ''
+    ON CONFLICT (key) DO NOTHING;
+    INSERT INTO experience_technologies (experience_id, technology_key, position)
+    SELECT new.id, LOWER(TRIM(value)), MIN(key) FROM json_each(new.technologies) WHERE TRIM(value) <> ''
+    GROUP BY LOWER(TRIM(value));
+END;
+
+CREATE TRIGGER IF NOT EXISTS posts_search_update AFTER UPDATE ON posts BEGIN
+    DELETE FROM search_index WHERE kind = 'post' AND ref_id = old.id;
+    INSERT INTO search_index (kind, ref_id, title, body, keywords)
+    SELECT 'post', new.id, new.title, new.excerpt || ' ' || new.content,
+        (SELECT COALESCE(group_concat(value, ', '), '') FROM json_each(new.tags))
+    WHERE new.status = 'published';
+END;
+
+CREATE TRIGGER IF NOT EXISTS projects_search_update AFTER UPDATE ON projects BEGIN
+    DELETE FROM search_index WHERE kind = 'project' AND ref_id = old.id;
+    INSERT INTO search_index (kind, ref_id, title, body, keywords)
+    VALUES ('project', new.id, new.title,
+        new.description || ' ' || (SELECT COALESCE(group_concat(value, ' '), '') FROM json_each(new.highlights)),
+        (SELECT COALESCE(group_concat(value, ', '), '') FROM json_each(new.technologies)));
+END;
+
+CREATE TRIGGER IF NOT EXISTS experiences_search_update AFTER UPDATE ON experiences BEGIN
+    DELETE FROM search_index WHERE kind = 'experience' AND ref_id = old.id;
+    INSERT INTO search_index (kind, ref_id, title, body, keywords)
+    VALUES ('experience', new.id, new.position || ' at ' || new.company,
+        new.description || ' ' || (SELECT COALESCE(group_concat(value, ' '), '') FROM json_each(new.highlights)),
+        (SELECT COALESCE(group_concat(value, ', '), '') FROM json_each(new.technologies)));
+END;
+
+ALTER TABLE contacts DROP COLUMN deleted_at;
+ALTER TABLE skills DROP COLUMN deleted_at;
+ALTER TABLE experiences DROP COLUMN deleted_at;
+ALTER TABLE projects DROP COLUMN deleted_at;
+ALTER TABLE posts DROP COLUMN deleted_at;
@@ -0,0 +1,71 @@
+-- Deleted posts, projects, experiences, skills and contact messages stay in
+-- the trash until they are restored or purged. Trashed rows are left out of
+-- the tag, technology and search indexes, as if they were gone.
+ALTER TABLE posts ADD COLUMN deleted_at INTEGER; -- UTC timestamp the row was moved to the trash
+ALTER TABLE projects ADD COLUMN deleted_at INTEGER;
+ALTER TABLE experiences ADD COLUMN deleted_at INTEGER;
+ALTER TABLE skills ADD COLUMN deleted_at INTEGER;
+ALTER TABLE contacts ADD COLUMN deleted_at INTEGER;
+
+DROP TRIGGER IF EXISTS posts_tags_update;
+DROP TRIGGER IF EXISTS projects_technologies_update;
+DROP TRIGGER IF EXISTS experiences_technologies_update;
+DROP TRIGGER IF EXISTS posts_search_update;
+DROP TRIGGER IF EXISTS projects_search_update;
+DROP TRIGGER IF EXISTS experiences_search_update;
+
+CREATE TRIGGER IF NOT EXISTS posts_tags_update AFTER UPDATE OF tags, deleted_at ON posts BEGIN
+    DELETE FROM post_tags WHERE post_id = old.id;
+    INSERT INTO tags (key, name)
+    SELECT LOWER(TRIM(value)), TRIM(value) FROM json_each(new.tags) WHERE TRIM(value) <> '' AND new.deleted_at IS NULL
+    ON CONFLICT (key) DO NOTHING;
+    INSERT INTO post_tags (post_id, tag_key, position)
+    SELECT new.id, LOWER(TRIM(value)), MIN(key) FROM json_each(new.tags) WHERE TRIM(value) <> '' AND new.deleted_at IS NULL
+    GROUP BY LOWER(TRIM(value));
+END;
+
+CREATE TRIGGER IF NOT EXISTS projects_technologies_update AFTER UPDATE OF technologies, deleted_at ON projects BEGIN
+    DELETE FROM project_technologies WHERE project_id = old.id;
+    INSERT INTO technologies (key, name)
+    SELECT LOWER(TRIM(value)), TRIM(value) FROM json_each(new.technologies) WHERE TRIM(value) <> '' AND new.deleted_at IS NULL
+    ON CONFLICT (key) DO NOTHING;
+    INSERT INTO project_technologies (project_id, technology_key, position)
+    SELECT new.id, LOWER(TRIM(value)), MIN(key) FROM json_each(new.technologies) WHERE TRIM(value) <> '' AND new.deleted_at IS NULL
+    GROUP BY LOWER(TRIM(value));
+END;
+
+CREATE TRIGGER IF NOT EXISTS experiences_technologies_update AFTER UPDATE OF technologies, deleted_at ON experiences BEGIN
+    DELETE FROM experience_technologies WHERE experience_id = old.id;
+    INSERT INTO technologies (key, name)
+    SELECT LOWER(TRIM(value)), TRIM(value) FROM json_each(new.technologies) WHERE TRIM(value) <> '' AND new.deleted_at IS NULL
+    ON CONFLICT (key) DO NOTHING;
+    INSERT INTO experience_technologies (experience_id, technology_key, position)
+    SELECT new.id, LOWER(TRIM(value)), MIN(key) FROM json_each(new.technologies) WHERE TRIM(value) <> '' AND new.deleted_at IS NULL
+    GROUP BY LOWER(TRIM(value));
+END;
+
+CREATE TRIGGER IF NOT EXISTS posts_search_update AFTER UPDATE ON posts BEGIN
+    DELETE FROM search_index WHERE kind = 'post' AND ref_id = old.id;
+    INSERT INTO search_index (kind, ref_id, title, body, keywords)
+    SELECT 'post', new.id, new.title, new.excerpt || ' ' || new.content,
+        (SELECT COALESCE(group_concat(value, ', '), '') FROM json_each(new.tags))
+    WHERE new.status = 'published' AND new.deleted_at IS NULL;
+END;
+
+CREATE TRIGGER IF NOT EXISTS projects_search_update AFTER UPDATE ON projects BEGIN
+    DELETE FROM search_index WHERE kind = 'project' AND ref_id = old.id;
+    INSERT INTO search_index (kind, ref_id, title, body, keywords)
+    SELECT 'project', new.id, new.title,
+        new.description || ' ' || (SELECT COALESCE(group_concat(value, ' '), '') FROM json_each(new.highlights)),
+        (SELECT COALESCE(group_concat(value, ', '), '') FROM json_each(new.technologies))
+    WHERE new.deleted_at IS NULL;
+END;
+
+CREATE TRIGGER IF NOT EXISTS experiences_search_update AFTER UPDATE ON experiences BEGIN
+    DELETE FROM search_index WHERE kind = 'experience' AND ref_id = old.id;
+    INSERT INTO search_index (kind, ref_id, title, body, keywords)
+    SELECT 'experience', new.id, new.position || ' at ' || new.company,
+        new.description || ' ' || (SELECT COALESCE(group_concat(value, ' '), '') FROM json_each(new.highlights)),
+        (SELECT COALESCE(group_concat(value, ', '), '') FROM json_each(new.technologies))
+    WHERE new.deleted_at IS NULL;
+END;
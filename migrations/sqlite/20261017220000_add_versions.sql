@@ -0,0 +1,8 @@
+-- Version of each post, project, experience, skill category and profile,
+-- increased by every update. Exposed as the ETag of the item, so that a write
+-- based on an outdated copy can be refused.
+ALTER TABLE posts ADD COLUMN version INTEGER NOT NULL DEFAULT 1;
+ALTER TABLE projects ADD COLUMN version INTEGER NOT NULL DEFAULT 1;
+ALTER TABLE experiences ADD COLUMN version INTEGER NOT NULL DEFAULT 1;
+ALTER TABLE skills ADD COLUMN version INTEGER NOT NULL DEFAULT 1;
+ALTER TABLE profiles ADD COLUMN version INTEGER NOT NULL DEFAULT 1;
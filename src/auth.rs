@@ -1,16 +1,35 @@
 use actix_web::{
-    dev::Payload, error::ErrorUnauthorized, http::header, Error, FromRequest, HttpRequest,
+    dev::Payload,
+    error::{ErrorInternalServerError, ErrorUnauthorized},
+    http::header,
+    web, Error, FromRequest, HttpRequest,
 };
-use futures::future::{ready, Ready};
-use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
-use serde::{Deserialize, Serialize};
+use futures::future::LocalBoxFuture;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use argon2::password_hash::{rand_core::OsRng, PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::Argon2;
+use once_cell::sync::{Lazy, OnceCell};
 use std::env;
 use utoipa::ToSchema;
+use uuid::Uuid;
 
+use crate::auth::keys::KeySet;
+use crate::config::database::DbPool;
 use crate::error::{AppError, AppResult};
+use crate::models::api_key::ApiKey;
+use crate::models::repository::Repository;
+use crate::models::token_repository::TokenRepository;
+use crate::models::user::UserAccount;
+use crate::models::user_repository::UserRepository;
+
+pub mod api_keys;
+pub mod keys;
+pub mod session;
+pub mod throttle;
+pub mod totp;
 
 // JWT Claims structure
-#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct Claims {
     /// Subject (user ID)
     pub sub: String,
@@ -22,10 +41,12 @@ pub struct Claims {
     pub exp: usize,
     /// Issued at (as UTC timestamp)
     pub iat: usize,
+    /// Unique token identifier, used to revoke the token before it expires
+    pub jti: String,
 }
 
 // User structure
-#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 #[schema(example = json!({
    "id": "1",
    "name": "Admin User",
@@ -36,23 +57,55 @@ pub struct User {
     pub id: String,
     /// User's full name
     pub name: String,
-    /// User's role (e.g., "admin", "user")
+    /// User's role ("admin", "editor" or "viewer")
     pub role: String,
 }
 
+// Header carrying an API key, as an alternative to Authorization: Bearer
+pub const API_KEY_HEADER: &str = "X-API-Key";
+
+// Keys used to sign and verify tokens, see AuthMiddleware::init_keys
+static JWT_KEYS: OnceCell<KeySet> = OnceCell::new();
+
 // Authentication middleware
 pub struct AuthMiddleware;
 
 impl AuthMiddleware {
-    // Get JWT secret from environment or use default
-    fn get_secret() -> String {
-        env::var("JWT_SECRET").unwrap_or_else(|_| "default_jwt_secret_for_development".to_string())
+    // Load the signing keys at startup so configuration errors stop the server
+    // instead of failing the first login
+    pub fn init_keys() -> anyhow::Result<()> {
+        let keys = KeySet::from_env()?;
+        log::info!("Loaded JWT keys, signing with '{}' ({:?})", keys.active().kid, keys.active().algorithm);
+        // Ignore a second initialisation; the first key set stays in use
+        let _ = JWT_KEYS.set(keys);
+        Ok(())
+    }
+
+    // Key set in use, loaded from the environment on first use if init_keys
+    // was not called. A bad configuration fails the request instead of the
+    // worker; the server itself refuses to start with one.
+    pub fn keys() -> AppResult<&'static KeySet> {
+        JWT_KEYS.get_or_try_init(KeySet::from_env).map_err(|e| {
+            log::error!("Invalid JWT key configuration: {}", e);
+            AppError::internal_error("Invalid JWT key configuration")
+        })
+    }
+
+    // Lifetime of access tokens, configurable through ACCESS_TOKEN_TTL_MINUTES
+    pub fn access_token_ttl() -> chrono::Duration {
+        let minutes = env::var("ACCESS_TOKEN_TTL_MINUTES")
+            .ok()
+            .and_then(|value| value.parse::<i64>().ok())
+            .unwrap_or(15);
+
+        chrono::Duration::minutes(minutes)
     }
 
     // Generate JWT token
     pub fn generate_token(user: &User) -> AppResult<String> {
-        let expiration = chrono::Utc::now()
-            .checked_add_signed(chrono::Duration::hours(24))
+        let now = chrono::Utc::now();
+        let expiration = now
+            .checked_add_signed(Self::access_token_ttl())
             .expect("valid timestamp")
             .timestamp() as usize;
 
@@ -61,102 +114,344 @@ impl AuthMiddleware {
             name: user.name.clone(),
             role: user.role.clone(),
             exp: expiration,
-            iat: chrono::Utc::now().timestamp() as usize,
+            iat: now.timestamp() as usize,
+            jti: Uuid::new_v4().to_string(),
         };
 
-        let token = encode(
-            &Header::default(),
-            &claims,
-            &EncodingKey::from_secret(Self::get_secret().as_bytes()),
-        )
-        .map_err(|e| AppError::internal_error(format!("Failed to generate token: {}", e)))?;
+        Self::sign(&claims)
+    }
 
-        Ok(token)
+    // Sign any claims with the active key, setting its kid in the header
+    pub fn sign<T: Serialize>(claims: &T) -> AppResult<String> {
+        Self::keys()?.sign(claims)
     }
 
     // Validate JWT token
     pub fn validate_token(token: &str) -> AppResult<Claims> {
-        let token_data = decode::<Claims>(
-            token,
-            &DecodingKey::from_secret(Self::get_secret().as_bytes()),
-            &Validation::default(),
-        )
-        .map_err(|e| AppError::unauthorized(format!("Invalid token: {}", e)))?;
+        Self::verify(token)
+    }
 
-        Ok(token_data.claims)
+    // Verify a token signed by any key of the set, see KeySet::verify
+    pub fn verify<T: DeserializeOwned>(token: &str) -> AppResult<T> {
+        Self::keys()?.verify(token)
     }
 }
 
-// Extractor for authenticated user
-pub struct AuthenticatedUser(pub User);
+// How a request was authenticated
+#[derive(Debug, Clone)]
+pub enum Credential {
+    /// Bearer JWT; the claims are kept so that handlers such as logout can revoke it
+    AccessToken(Claims),
+    /// Long-lived API key, limited to its scopes
+    ApiKey(ApiKey),
+}
+
+// Extractor for authenticated user. Accepts a Bearer access token, or an API
+// key in the X-API-Key header or as a Bearer token.
+pub struct AuthenticatedUser(pub User, pub Credential);
 
 impl FromRequest for AuthenticatedUser {
     type Error = Error;
-    type Future = Ready<Result<Self, Self::Error>>;
+    type Future = LocalBoxFuture<'static, Result<Self, Self::Error>>;
 
     fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
-        // Get authorization header
-        let auth_header = req.headers().get(header::AUTHORIZATION);
+        let token = Self::token_from_headers(req);
+        let db = req.app_data::<web::Data<DbPool>>().cloned();
 
-        let auth_header = match auth_header {
-            Some(header) => header,
-            None => return ready(Err(ErrorUnauthorized("No authorization header"))),
-        };
+        Box::pin(async move {
+            let token = token?;
+            let db = db.ok_or_else(|| ErrorInternalServerError("Database not configured"))?;
+
+            if api_keys::is_api_key(&token) {
+                let (user, key) = api_keys::authenticate_api_key(db.get_ref(), &token)
+                    .await
+                    .map_err(|e| match e {
+                        AppError::UnauthorizedError(message) => ErrorUnauthorized(message),
+                        e => {
+                            log::error!("Failed to check API key: {}", e);
+                            ErrorInternalServerError("Failed to check API key")
+                        }
+                    })?;
+
+                return Ok(AuthenticatedUser(user, Credential::ApiKey(key)));
+            }
+
+            // Validate token
+            let claims = AuthMiddleware::validate_token(&token)
+                .map_err(|_| ErrorUnauthorized("Invalid token"))?;
+
+            // Reject tokens that were revoked through logout
+            let revoked = TokenRepository::new(db.get_ref().clone())
+                .is_access_token_revoked(&claims.jti)
+                .await
+                .map_err(|e| {
+                    log::error!("Failed to check token revocation: {}", e);
+                    ErrorInternalServerError("Failed to check token")
+                })?;
+
+            if revoked {
+                return Err(ErrorUnauthorized("Token has been revoked"));
+            }
+
+            // Create user from claims
+            let user = User {
+                id: claims.sub.clone(),
+                name: claims.name.clone(),
+                role: claims.role.clone(),
+            };
+
+            Ok(AuthenticatedUser(user, Credential::AccessToken(claims)))
+        })
+    }
+}
+
+impl AuthenticatedUser {
+    fn token_from_headers(req: &HttpRequest) -> Result<String, Error> {
+        if let Some(api_key) = req.headers().get(API_KEY_HEADER) {
+            return api_key
+                .to_str()
+                .map(str::to_string)
+                .map_err(|_| ErrorUnauthorized("Invalid API key header"));
+        }
+
+        // Get authorization header
+        let auth_header = req
+            .headers()
+            .get(header::AUTHORIZATION)
+            .ok_or_else(|| ErrorUnauthorized("No authorization header"))?;
 
         // Parse Bearer token
-        let auth_str = match auth_header.to_str() {
-            Ok(str) => str,
-            Err(_) => return ready(Err(ErrorUnauthorized("Invalid authorization header"))),
-        };
+        let auth_str = auth_header
+            .to_str()
+            .map_err(|_| ErrorUnauthorized("Invalid authorization header"))?;
 
-        if !auth_str.starts_with("Bearer ") {
-            return ready(Err(ErrorUnauthorized("Invalid authorization scheme")));
+        auth_str
+            .strip_prefix("Bearer ")
+            .map(str::to_string)
+            .ok_or_else(|| ErrorUnauthorized("Invalid authorization scheme"))
+    }
+
+    // Claims of the access token used for this request. Session endpoints
+    // (logout, password change) are not available to API keys.
+    pub fn access_token(&self) -> AppResult<&Claims> {
+        match &self.1 {
+            Credential::AccessToken(claims) => Ok(claims),
+            Credential::ApiKey(_) => Err(AppError::forbidden("This endpoint requires a user session, not an API key")),
         }
+    }
+}
 
-        let token = &auth_str[7..]; // Remove "Bearer " prefix
+// Roles a user account can hold
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    /// Full access, including user management
+    Admin,
+    /// Can edit all portfolio content and read messages
+    Editor,
+    /// Read-only access to the admin area
+    Viewer,
+}
 
-        // Validate token
-        let claims = match AuthMiddleware::validate_token(token) {
-            Ok(claims) => claims,
-            Err(_) => return ready(Err(ErrorUnauthorized("Invalid token"))),
-        };
+impl Role {
+    pub const ALL: [Role; 3] = [Role::Admin, Role::Editor, Role::Viewer];
 
-        // Create user from claims
-        let user = User {
-            id: claims.sub,
-            name: claims.name,
-            role: claims.role,
-        };
+    pub fn parse(role: &str) -> Option<Role> {
+        Role::ALL.into_iter().find(|r| r.as_str() == role)
+    }
 
-        ready(Ok(AuthenticatedUser(user)))
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::Admin => "admin",
+            Role::Editor => "editor",
+            Role::Viewer => "viewer",
+        }
     }
+
+    pub fn has_permission(&self, permission: Permission) -> bool {
+        match self {
+            Role::Admin => true,
+            Role::Editor => !matches!(
+                permission,
+                Permission::UsersManage | Permission::ApiKeysManage | Permission::AuditRead | Permission::PortfolioImport
+            ),
+            Role::Viewer => matches!(permission, Permission::DashboardRead | Permission::MessagesRead),
+        }
+    }
+}
+
+// Individual actions that can be granted to a role
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Permission {
+    DashboardRead,
+    ProjectsWrite,
+    ExperiencesWrite,
+    SkillsWrite,
+    TechnologiesManage,
+    PostsWrite,
+    ProfileWrite,
+    GithubWrite,
+    GithubRefresh,
+    MessagesRead,
+    MessagesDelete,
+    TrashManage,
+    UsersManage,
+    ApiKeysManage,
+    AuditRead,
+    PortfolioExport,
+    PortfolioImport,
+}
+
+impl Permission {
+    pub const ALL: [Permission; 17] = [
+        Permission::DashboardRead,
+        Permission::ProjectsWrite,
+        Permission::ExperiencesWrite,
+        Permission::SkillsWrite,
+        Permission::TechnologiesManage,
+        Permission::PostsWrite,
+        Permission::ProfileWrite,
+        Permission::GithubWrite,
+        Permission::GithubRefresh,
+        Permission::MessagesRead,
+        Permission::MessagesDelete,
+        Permission::TrashManage,
+        Permission::UsersManage,
+        Permission::ApiKeysManage,
+        Permission::AuditRead,
+        Permission::PortfolioExport,
+        Permission::PortfolioImport,
+    ];
+
+    pub fn parse(permission: &str) -> Option<Permission> {
+        Permission::ALL.into_iter().find(|p| p.as_str() == permission)
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Permission::DashboardRead => "dashboard:read",
+            Permission::ProjectsWrite => "projects:write",
+            Permission::ExperiencesWrite => "experiences:write",
+            Permission::SkillsWrite => "skills:write",
+            Permission::TechnologiesManage => "technologies:manage",
+            Permission::PostsWrite => "posts:write",
+            Permission::ProfileWrite => "profile:write",
+            Permission::GithubWrite => "github:write",
+            Permission::GithubRefresh => "github:refresh",
+            Permission::MessagesRead => "messages:read",
+            Permission::MessagesDelete => "messages:delete",
+            Permission::TrashManage => "trash:manage",
+            Permission::UsersManage => "users:manage",
+            Permission::ApiKeysManage => "api_keys:manage",
+            Permission::AuditRead => "audit:read",
+            Permission::PortfolioExport => "portfolio:export",
+            Permission::PortfolioImport => "portfolio:import",
+        }
+    }
+}
+
+impl AuthenticatedUser {
+    // Guard used at the top of protected handlers; fails with 403 when the
+    // user's role does not grant the permission. Unknown roles grant nothing.
+    // API keys are further limited to their scopes.
+    pub fn require(&self, permission: Permission) -> AppResult<()> {
+        let allowed = Role::parse(&self.0.role)
+            .is_some_and(|role| role.has_permission(permission));
+
+        if !allowed {
+            return Err(AppError::forbidden(format!(
+                "Role '{}' is not allowed to perform '{}'",
+                self.0.role,
+                permission.as_str()
+            )));
+        }
+
+        if let Credential::ApiKey(key) = &self.1 {
+            if !key.scopes.iter().any(|scope| scope == permission.as_str()) {
+                return Err(AppError::forbidden(format!(
+                    "API key '{}' does not have scope '{}'",
+                    key.prefix,
+                    permission.as_str()
+                )));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+// Hash a password with Argon2id, returning a PHC string suitable for storage
+pub fn hash_password(password: &str) -> AppResult<String> {
+    let salt = SaltString::generate(&mut OsRng);
+
+    Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+        .map_err(|e| AppError::internal_error(format!("Failed to hash password: {}", e)))
 }
 
-// Role-based authorization middleware
-pub fn require_admin() -> impl Fn(AuthenticatedUser) -> Result<AuthenticatedUser, Error> {
-    move |user: AuthenticatedUser| {
-        if user.0.role == "admin" {
-            Ok(user)
-        } else {
-            Err(ErrorUnauthorized("Admin access required"))
+// Check a password against a stored PHC hash
+pub fn verify_password(password: &str, password_hash: &str) -> bool {
+    match PasswordHash::new(password_hash) {
+        Ok(parsed) => Argon2::default()
+            .verify_password(password.as_bytes(), &parsed)
+            .is_ok(),
+        Err(_) => false,
+    }
+}
+
+// Hash checked when the username does not exist, so that unknown users take
+// as long to reject as a wrong password and cannot be enumerated by timing
+static DUMMY_PASSWORD_HASH: Lazy<String> = Lazy::new(|| {
+    hash_password("dummy-password-for-timing").expect("hashing a constant password")
+});
+
+// Authenticate a user against the accounts stored in the database
+pub async fn authenticate_user(pool: &DbPool, username: &str, password: &str) -> AppResult<Option<User>> {
+    let repo = UserRepository::new(pool.clone());
+
+    let account = repo.find_by_username(username).await
+        .map_err(|e| AppError::internal_error(format!("Failed to fetch user: {}", e)))?;
+
+    match account {
+        Some(account) if verify_password(password, &account.password_hash) => Ok(Some(User {
+            id: account.id,
+            name: account.name,
+            role: account.role,
+        })),
+        Some(_) => Ok(None),
+        None => {
+            verify_password(password, &DUMMY_PASSWORD_HASH);
+            Ok(None)
         }
     }
 }
 
-// Mock function to authenticate user (in a real app, this would check against a database)
-pub async fn authenticate_user(username: &str, password: &str) -> AppResult<Option<User>> {
-    // In a real application, you would check credentials against a database
-    // For now, we'll use environment variables for credentials
-    let admin_username = env::var("ADMIN_USERNAME").unwrap_or_else(|_| "admin".to_string());
-    let admin_password = env::var("ADMIN_PASSWORD").unwrap_or_else(|_| "admin".to_string());
-
-    if username == admin_username && password == admin_password {
-        Ok(Some(User {
-            id: "1".to_string(),
-            name: "Kaue Fontes".to_string(),
-            role: "admin".to_string(),
-        }))
-    } else {
-        Ok(None)
+// Create the first admin account from ADMIN_USERNAME/ADMIN_PASSWORD when the
+// users table is empty. Existing accounts are never touched.
+pub async fn ensure_admin_user(pool: &DbPool) -> AppResult<()> {
+    let repo = UserRepository::new(pool.clone());
+
+    let count = repo.count().await
+        .map_err(|e| AppError::internal_error(format!("Failed to count users: {}", e)))?;
+
+    if count > 0 {
+        return Ok(());
     }
+
+    let (username, password) = match (env::var("ADMIN_USERNAME"), env::var("ADMIN_PASSWORD")) {
+        (Ok(username), Ok(password)) => (username, password),
+        _ => {
+            log::warn!("No user accounts exist and ADMIN_USERNAME/ADMIN_PASSWORD are not set; nobody can log in");
+            return Ok(());
+        }
+    };
+
+    let name = env::var("ADMIN_NAME").unwrap_or_else(|_| username.clone());
+    let account = UserAccount::new(username, name, hash_password(&password)?, "admin".to_string());
+
+    repo.create(account).await
+        .map_err(|e| AppError::internal_error(format!("Failed to create admin user: {}", e)))?;
+
+    log::info!("Created initial admin account from ADMIN_USERNAME");
+    Ok(())
 }
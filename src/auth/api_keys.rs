@@ -0,0 +1,124 @@
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use log::warn;
+
+use crate::auth::session::hash_token;
+use crate::auth::{Permission, Role, User};
+use crate::config::database::DbPool;
+use crate::error::{AppError, AppResult};
+use crate::models::api_key::ApiKey;
+use crate::models::api_key_repository::ApiKeyRepository;
+use crate::models::repository::Repository;
+use crate::models::user_repository::UserRepository;
+
+// Every key starts with this marker, so keys are recognisable in logs and
+// secret scanners and can be told apart from JWTs in the Authorization header
+pub const API_KEY_MARKER: &str = "rqk_";
+
+// Length of the public prefix: the marker plus 8 hex characters
+const PREFIX_LEN: usize = API_KEY_MARKER.len() + 8;
+
+// A freshly generated key. The plain value is shown to the admin once.
+pub struct GeneratedApiKey {
+    pub key: String,
+    pub prefix: String,
+    pub key_hash: String,
+}
+
+// Build a key of the form rqk_<8 hex prefix>_<base64url secret>
+pub fn generate_api_key() -> GeneratedApiKey {
+    let mut id = [0u8; 4];
+    OsRng.fill_bytes(&mut id);
+    let mut secret = [0u8; 32];
+    OsRng.fill_bytes(&mut secret);
+
+    let prefix = format!("{}{}", API_KEY_MARKER, id.iter().map(|b| format!("{:02x}", b)).collect::<String>());
+    let key = format!("{}_{}", prefix, URL_SAFE_NO_PAD.encode(secret));
+
+    GeneratedApiKey {
+        key_hash: hash_token(&key),
+        prefix,
+        key,
+    }
+}
+
+pub fn is_api_key(token: &str) -> bool {
+    token.starts_with(API_KEY_MARKER)
+}
+
+// Public prefix of a presented key, None if the key is malformed
+fn key_prefix(key: &str) -> Option<&str> {
+    let prefix = key.get(..PREFIX_LEN)?;
+    key[PREFIX_LEN..].starts_with('_').then_some(prefix)
+}
+
+// Parse and check requested scopes. A key may only carry permissions its
+// creator's role grants, and never the permission to manage keys itself.
+pub fn parse_scopes(scopes: &[String], role: &str) -> AppResult<Vec<Permission>> {
+    if scopes.is_empty() {
+        return Err(AppError::validation_error("An API key needs at least one scope"));
+    }
+
+    let role = Role::parse(role)
+        .ok_or_else(|| AppError::forbidden(format!("Role '{}' cannot create API keys", role)))?;
+
+    scopes.iter().map(|scope| {
+        let permission = Permission::parse(scope).ok_or_else(|| {
+            let known: Vec<&str> = Permission::ALL.iter().map(|p| p.as_str()).collect();
+            AppError::validation_error(format!("Unknown scope '{}', expected one of: {}", scope, known.join(", ")))
+        })?;
+
+        if permission == Permission::ApiKeysManage {
+            return Err(AppError::validation_error("API keys cannot be granted 'api_keys:manage'"));
+        }
+
+        if !role.has_permission(permission) {
+            return Err(AppError::forbidden(format!(
+                "Role '{}' cannot grant scope '{}'",
+                role.as_str(),
+                scope
+            )));
+        }
+
+        Ok(permission)
+    }).collect()
+}
+
+// Resolve a presented key to the account it acts for. The account is loaded
+// on every request so that role changes and deletions apply immediately.
+pub async fn authenticate_api_key(pool: &DbPool, key: &str) -> AppResult<(User, ApiKey)> {
+    let prefix = key_prefix(key).ok_or_else(|| AppError::unauthorized("Invalid API key"))?;
+    let repo = ApiKeyRepository::new(pool.clone());
+
+    let record = repo.find_by_prefix(prefix).await
+        .map_err(|e| AppError::internal_error(format!("Failed to fetch API key: {}", e)))?
+        .filter(|record| record.key_hash == hash_token(key))
+        .ok_or_else(|| AppError::unauthorized("Invalid API key"))?;
+
+    let now = chrono::Utc::now().timestamp();
+
+    if record.revoked_at.is_some() {
+        warn!("Revoked API key {} was presented", record.prefix);
+        return Err(AppError::unauthorized("API key has been revoked"));
+    }
+
+    if record.expires_at.is_some_and(|expires_at| expires_at <= now) {
+        return Err(AppError::unauthorized("API key has expired"));
+    }
+
+    let account = UserRepository::new(pool.clone())
+        .find_by_id(&record.created_by).await
+        .map_err(|e| AppError::internal_error(format!("Failed to fetch user: {}", e)))?
+        .ok_or_else(|| AppError::unauthorized("API key owner no longer exists"))?;
+
+    repo.touch_last_used(&record.id, now).await
+        .map_err(|e| AppError::internal_error(format!("Failed to update API key: {}", e)))?;
+
+    let user = User {
+        id: account.id,
+        name: account.name,
+        role: account.role,
+    };
+
+    Ok((user, record))
+}
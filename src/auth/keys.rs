@@ -0,0 +1,497 @@
+use anyhow::{anyhow, bail, Context, Result};
+use base64::{engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD}, Engine};
+use jsonwebtoken::{decode, decode_header, encode, Algorithm, DecodingKey, EncodingKey, Header, Validation};
+use pkcs1::RsaPublicKey;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{json, Value};
+use spki::{der::Decode, ObjectIdentifier, SubjectPublicKeyInfoRef};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::error::{AppError, AppResult};
+
+// Fallback used when neither JWT_KEYS_FILE nor JWT_SECRET is set
+const DEVELOPMENT_SECRET: &str = "default_jwt_secret_for_development";
+
+// Secrets that ship in the repository (.env.example, docker-compose.yml) and
+// must never protect a real deployment
+const PLACEHOLDER_SECRETS: [&str; 2] = [
+    DEVELOPMENT_SECRET,
+    "your_secret_key_for_jwt_tokens_change_in_production",
+];
+
+// kid given to the key built from JWT_SECRET
+const SECRET_KID: &str = "default";
+
+// One key of the set. Verify-only keys (retired asymmetric keys) have no
+// encoding half and can validate old tokens but not sign new ones.
+pub struct JwtKey {
+    pub kid: String,
+    pub algorithm: Algorithm,
+    encoding: Option<EncodingKey>,
+    decoding: DecodingKey,
+    // Public JWK for asymmetric keys; HMAC secrets are never published
+    jwk: Option<Value>,
+    // Secret known from the repository, only acceptable in development
+    is_placeholder: bool,
+}
+
+impl JwtKey {
+    pub fn encoding_key(&self) -> Option<&EncodingKey> {
+        self.encoding.as_ref()
+    }
+
+    pub fn decoding_key(&self) -> &DecodingKey {
+        &self.decoding
+    }
+}
+
+// All keys accepted for validation plus the one used for signing
+pub struct KeySet {
+    keys: Vec<JwtKey>,
+    active_kid: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct KeyFile {
+    /// kid of the key used to sign new tokens
+    active: String,
+    keys: Vec<KeyFileEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+struct KeyFileEntry {
+    kid: String,
+    alg: String,
+    /// HS256 only
+    secret: Option<String>,
+    /// RS256/EdDSA, PEM; omit to keep a retired key for validation only
+    private_key_file: Option<PathBuf>,
+    /// RS256/EdDSA, PEM
+    public_key_file: Option<PathBuf>,
+}
+
+// Whether the server runs in development mode. APP_ENV wins; without it
+// debug builds count as development and release builds as production.
+pub fn is_development() -> bool {
+    match env::var("APP_ENV") {
+        Ok(value) => value.eq_ignore_ascii_case("development"),
+        Err(_) => cfg!(debug_assertions),
+    }
+}
+
+impl KeySet {
+    // Load keys from JWT_KEYS_FILE, falling back to a single HS256 key from
+    // JWT_SECRET. Refuses placeholder secrets outside development.
+    pub fn from_env() -> Result<KeySet> {
+        let keys = match env::var("JWT_KEYS_FILE") {
+            Ok(path) => Self::from_file(Path::new(&path))?,
+            Err(_) => {
+                let secret = env::var("JWT_SECRET").unwrap_or_else(|_| DEVELOPMENT_SECRET.to_string());
+                Self::from_secret(&secret)
+            }
+        };
+
+        keys.check_secrets(is_development())?;
+        Ok(keys)
+    }
+
+    pub fn from_secret(secret: &str) -> KeySet {
+        KeySet {
+            keys: vec![hmac_key(SECRET_KID, secret)],
+            active_kid: SECRET_KID.to_string(),
+        }
+    }
+
+    pub fn from_file(path: &Path) -> Result<KeySet> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read JWT key file {}", path.display()))?;
+        let file: KeyFile = serde_json::from_str(&content)
+            .with_context(|| format!("Invalid JWT key file {}", path.display()))?;
+        let base_dir = path.parent().unwrap_or_else(|| Path::new("."));
+
+        let keys = file.keys
+            .iter()
+            .map(|entry| load_entry(entry, base_dir))
+            .collect::<Result<Vec<_>>>()?;
+
+        let key_set = KeySet { keys, active_kid: file.active };
+        key_set.validate()?;
+        Ok(key_set)
+    }
+
+    fn validate(&self) -> Result<()> {
+        for (i, key) in self.keys.iter().enumerate() {
+            if self.keys[..i].iter().any(|other| other.kid == key.kid) {
+                bail!("Duplicate JWT key id '{}'", key.kid);
+            }
+        }
+
+        let active = self.find(&self.active_kid)
+            .ok_or_else(|| anyhow!("Active JWT key '{}' is not in the key set", self.active_kid))?;
+
+        if active.encoding.is_none() {
+            bail!("Active JWT key '{}' has no private key", self.active_kid);
+        }
+
+        Ok(())
+    }
+
+    fn check_secrets(&self, development: bool) -> Result<()> {
+        if development {
+            return Ok(());
+        }
+
+        if self.keys.iter().any(|key| key.is_placeholder) {
+            bail!("Refusing to start with the default JWT secret outside development; set JWT_SECRET or JWT_KEYS_FILE");
+        }
+
+        Ok(())
+    }
+
+    pub fn active(&self) -> &JwtKey {
+        self.find(&self.active_kid).expect("active key checked on load")
+    }
+
+    pub fn find(&self, kid: &str) -> Option<&JwtKey> {
+        self.keys.iter().find(|key| key.kid == kid)
+    }
+
+    // Keys that may have signed a token without a kid header. Only HMAC
+    // keys are tried, as tokens issued before key rotation were all HS256.
+    pub fn legacy_keys(&self) -> impl Iterator<Item = &JwtKey> {
+        self.keys.iter().filter(|key| key.algorithm == Algorithm::HS256)
+    }
+
+    // JSON Web Key Set with the public half of every asymmetric key
+    pub fn jwks(&self) -> Value {
+        let keys: Vec<&Value> = self.keys.iter().filter_map(|key| key.jwk.as_ref()).collect();
+        json!({ "keys": keys })
+    }
+
+    // Sign any claims with the active key, setting its kid in the header
+    pub fn sign<T: Serialize>(&self, claims: &T) -> AppResult<String> {
+        let key = self.active();
+        let encoding_key = key.encoding_key()
+            .ok_or_else(|| AppError::internal_error("Active JWT key cannot sign"))?;
+
+        let mut header = Header::new(key.algorithm);
+        header.kid = Some(key.kid.clone());
+
+        encode(&header, claims, encoding_key)
+            .map_err(|e| AppError::internal_error(format!("Failed to generate token: {}", e)))
+    }
+
+    // Verify a token against the key named by its kid header. Tokens without
+    // a kid predate key rotation and are checked against the HMAC keys.
+    pub fn verify<T: DeserializeOwned>(&self, token: &str) -> AppResult<T> {
+        let header = decode_header(token)
+            .map_err(|e| AppError::unauthorized(format!("Invalid token: {}", e)))?;
+
+        let candidates: Vec<&JwtKey> = match &header.kid {
+            Some(kid) => self.find(kid).into_iter().collect(),
+            None => self.legacy_keys().collect(),
+        };
+
+        let mut last_error = AppError::unauthorized("Invalid token: unknown signing key");
+        for key in candidates {
+            // Pinning the algorithm to the key prevents algorithm confusion
+            match decode::<T>(token, key.decoding_key(), &Validation::new(key.algorithm)) {
+                Ok(token_data) => return Ok(token_data.claims),
+                Err(e) => last_error = AppError::unauthorized(format!("Invalid token: {}", e)),
+            }
+        }
+
+        Err(last_error)
+    }
+}
+
+fn hmac_key(kid: &str, secret: &str) -> JwtKey {
+    JwtKey {
+        kid: kid.to_string(),
+        algorithm: Algorithm::HS256,
+        encoding: Some(EncodingKey::from_secret(secret.as_bytes())),
+        decoding: DecodingKey::from_secret(secret.as_bytes()),
+        jwk: None,
+        is_placeholder: PLACEHOLDER_SECRETS.contains(&secret),
+    }
+}
+
+fn load_entry(entry: &KeyFileEntry, base_dir: &Path) -> Result<JwtKey> {
+    let read = |file: &PathBuf| -> Result<Vec<u8>> {
+        let path = base_dir.join(file);
+        fs::read(&path).with_context(|| format!("Failed to read key file {}", path.display()))
+    };
+
+    match entry.alg.as_str() {
+        "HS256" => {
+            let secret = entry.secret.as_deref()
+                .ok_or_else(|| anyhow!("HS256 key '{}' needs a secret", entry.kid))?;
+            Ok(hmac_key(&entry.kid, secret))
+        }
+        "RS256" | "EdDSA" => {
+            let public_pem = entry.public_key_file.as_ref()
+                .ok_or_else(|| anyhow!("{} key '{}' needs public_key_file", entry.alg, entry.kid))
+                .and_then(read)?;
+            let private_pem = entry.private_key_file.as_ref().map(read).transpose()?;
+
+            let (algorithm, decoding, encoding, jwk) = if entry.alg == "RS256" {
+                let encoding = private_pem
+                    .map(|pem| EncodingKey::from_rsa_pem(&pem))
+                    .transpose()?;
+                (Algorithm::RS256, DecodingKey::from_rsa_pem(&public_pem)?, encoding, rsa_jwk(&entry.kid, &public_pem)?)
+            } else {
+                let encoding = private_pem
+                    .map(|pem| EncodingKey::from_ed_pem(&pem))
+                    .transpose()?;
+                (Algorithm::EdDSA, DecodingKey::from_ed_pem(&public_pem)?, encoding, ed25519_jwk(&entry.kid, &public_pem)?)
+            };
+
+            Ok(JwtKey {
+                kid: entry.kid.clone(),
+                algorithm,
+                encoding,
+                decoding,
+                jwk: Some(jwk),
+                is_placeholder: false,
+            })
+        }
+        other => bail!("Unsupported JWT algorithm '{}' for key '{}'", other, entry.kid),
+    }
+}
+
+// PEM label (such as "PUBLIC KEY") and DER contents of a PEM document
+fn pem_der(pem: &[u8]) -> Result<(String, Vec<u8>)> {
+    let pem = std::str::from_utf8(pem)?;
+    let label = pem.lines()
+        .find_map(|line| line.strip_prefix("-----BEGIN ")?.strip_suffix("-----"))
+        .ok_or_else(|| anyhow!("Not a PEM document"))?;
+    let body: String = pem.lines().filter(|line| !line.starts_with("-----")).collect();
+
+    Ok((label.to_string(), STANDARD.decode(body.trim())?))
+}
+
+// Object identifiers of RSA (rsaEncryption) and Ed25519 keys
+const RSA_ENCRYPTION_OID: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113549.1.1.1");
+const ED25519_OID: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.101.112");
+
+// Key held by a SubjectPublicKeyInfo ("PUBLIC KEY") for an algorithm
+fn spki_key(der: &[u8], algorithm: ObjectIdentifier) -> Option<&[u8]> {
+    let spki = SubjectPublicKeyInfoRef::from_der(der).ok()?;
+    if spki.algorithm.oid != algorithm {
+        return None;
+    }
+    spki.subject_public_key.as_bytes()
+}
+
+// Public JWK of an RSA key, given as SubjectPublicKeyInfo ("PUBLIC KEY") or
+// PKCS#1 ("RSA PUBLIC KEY")
+fn rsa_jwk(kid: &str, public_pem: &[u8]) -> Result<Value> {
+    let (label, der) = pem_der(public_pem)?;
+    let key = match label.as_str() {
+        "PUBLIC KEY" => spki_key(&der, RSA_ENCRYPTION_OID),
+        "RSA PUBLIC KEY" => Some(der.as_slice()),
+        _ => None,
+    };
+    let (n, e) = key
+        .and_then(|key| RsaPublicKey::from_der(key).ok())
+        .map(|key| (key.modulus.as_bytes(), key.public_exponent.as_bytes()))
+        .filter(|(n, e)| !n.is_empty() && !e.is_empty())
+        .ok_or_else(|| anyhow!("Invalid RSA public key for '{}'", kid))?;
+
+    Ok(json!({
+        "kty": "RSA",
+        "use": "sig",
+        "alg": "RS256",
+        "kid": kid,
+        "n": URL_SAFE_NO_PAD.encode(n),
+        "e": URL_SAFE_NO_PAD.encode(e),
+    }))
+}
+
+fn ed25519_jwk(kid: &str, public_pem: &[u8]) -> Result<Value> {
+    let (_, der) = pem_der(public_pem)?;
+
+    let x = spki_key(&der, ED25519_OID)
+        .filter(|x| x.len() == 32)
+        .ok_or_else(|| anyhow!("Invalid Ed25519 public key for '{}'", kid))?;
+
+    Ok(json!({
+        "kty": "OKP",
+        "crv": "Ed25519",
+        "use": "sig",
+        "alg": "EdDSA",
+        "kid": kid,
+        "x": URL_SAFE_NO_PAD.encode(x),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Public values of the fixture keys, as computed by openssl
+    const RSA_N: &str = "wiDOnVn1givd1RaSawwH34-DGYoirUVIAy-FywbUfk3dgGfL5WGCMYxFSsW0dJgDH4edn8OQiGfx66dB0OCi9osFGDYdeyoYKQfhLpozxixA7sBG5-GPVTLu4neIIDLJzVrMeXp4ayzo-QYGGnRmxGo7eUXBPkOmUbK5a42hCejzLxnC2_AgS6ECSWNm9gOK3FpXn6Gnw5_6yPacE7d3Y8CYBDMo0KsK6RD9qWmGKEbfIuDosz56OUbz59Q6aOQ8VNgJ9YxbGJg3wtrKB68mlNhewQabxBl0GG-AFY-vMO00iYpqiNBBZqG_x4rAh5Jw2X_V69V8oBcm3BWX1Fk2Dw";
+    const ED25519_X: &str = "j5rCYRdp1X3tAYeFVzm5EEt6DWF5H_UVLKMYDYZ8OKU";
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct TestClaims {
+        sub: String,
+        exp: usize,
+    }
+
+    fn claims() -> TestClaims {
+        TestClaims {
+            sub: "user-1".to_string(),
+            exp: (chrono::Utc::now().timestamp() + 600) as usize,
+        }
+    }
+
+    fn fixture(name: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/jwt").join(name)
+    }
+
+    // A retired HMAC key, the active RSA key and a verify-only Ed25519 key
+    fn key_file() -> Value {
+        json!({
+            "active": "rsa-2026",
+            "keys": [
+                { "kid": "hmac-2024", "alg": "HS256", "secret": "a retired but real secret" },
+                { "kid": "rsa-2026", "alg": "RS256", "private_key_file": fixture("rsa-private.pem"), "public_key_file": fixture("rsa-public.pem") },
+                { "kid": "ed-2025", "alg": "EdDSA", "public_key_file": fixture("ed25519-public.pem") }
+            ]
+        })
+    }
+
+    // Write a key file to a directory of its own and load it
+    fn load(file: &Value) -> Result<KeySet> {
+        let dir = env::temp_dir().join(format!("jwt-keys-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("keys.json");
+        fs::write(&path, file.to_string()).unwrap();
+
+        let keys = KeySet::from_file(&path);
+        fs::remove_dir_all(&dir).unwrap();
+        keys
+    }
+
+    fn sign_with(encoding_key: &EncodingKey, algorithm: Algorithm, kid: Option<&str>) -> String {
+        let mut header = Header::new(algorithm);
+        header.kid = kid.map(str::to_string);
+        encode(&header, &claims(), encoding_key).unwrap()
+    }
+
+    #[test]
+    fn loads_key_files() {
+        let keys = load(&key_file()).unwrap();
+
+        assert_eq!((keys.active().kid.as_str(), keys.active().algorithm), ("rsa-2026", Algorithm::RS256));
+        assert!(keys.find("ed-2025").unwrap().encoding_key().is_none());
+        let legacy: Vec<&str> = keys.legacy_keys().map(|key| key.kid.as_str()).collect();
+        assert_eq!(legacy, ["hmac-2024"]);
+    }
+
+    #[test]
+    fn rejects_invalid_key_files() {
+        let edits: [(&str, Value); 8] = [
+            ("/active", json!("missing")),
+            ("/active", json!("ed-2025")),
+            ("/keys/2/kid", json!("rsa-2026")),
+            ("/keys/0/alg", json!("ES256")),
+            ("/keys/0/secret", Value::Null),
+            ("/keys/1/public_key_file", Value::Null),
+            ("/keys/1/public_key_file", json!(fixture("missing.pem"))),
+            ("/keys/1/public_key_file", json!(fixture("ed25519-public.pem"))),
+        ];
+
+        for (pointer, value) in edits {
+            let mut file = key_file();
+            *file.pointer_mut(pointer).unwrap() = value.clone();
+            assert!(load(&file).is_err(), "{} = {}", pointer, value);
+        }
+    }
+
+    #[test]
+    fn verifies_tokens_with_the_key_named_by_their_kid() {
+        let keys = load(&key_file()).unwrap();
+        let hmac = keys.find("hmac-2024").unwrap().encoding_key().unwrap();
+        let ed25519 = EncodingKey::from_ed_pem(&fs::read(fixture("ed25519-private.pem")).unwrap()).unwrap();
+
+        // New tokens carry the kid of the active key
+        let token = keys.sign(&claims()).unwrap();
+        assert_eq!(decode_header(&token).unwrap().kid.as_deref(), Some("rsa-2026"));
+        assert_eq!(keys.verify::<TestClaims>(&token).unwrap().sub, "user-1");
+
+        // Retired keys still verify, and HMAC keys also verify tokens without a kid
+        for token in [
+            sign_with(hmac, Algorithm::HS256, Some("hmac-2024")),
+            sign_with(hmac, Algorithm::HS256, None),
+            sign_with(&ed25519, Algorithm::EdDSA, Some("ed-2025")),
+        ] {
+            assert!(keys.verify::<TestClaims>(&token).is_ok());
+        }
+
+        // A token is only checked against the key its kid names, with that key's algorithm
+        let public_pem = fs::read(fixture("rsa-public.pem")).unwrap();
+        for token in [
+            sign_with(hmac, Algorithm::HS256, Some("rsa-2026")),
+            sign_with(hmac, Algorithm::HS256, Some("unknown")),
+            sign_with(&ed25519, Algorithm::EdDSA, None),
+            sign_with(&EncodingKey::from_secret(&public_pem), Algorithm::HS256, Some("rsa-2026")),
+            sign_with(&EncodingKey::from_secret(b"another secret"), Algorithm::HS256, None),
+        ] {
+            assert!(keys.verify::<TestClaims>(&token).is_err());
+        }
+    }
+
+    #[test]
+    fn publishes_only_public_keys() {
+        let keys = load(&key_file()).unwrap();
+
+        assert_eq!(keys.jwks(), json!({ "keys": [
+            { "kty": "RSA", "use": "sig", "alg": "RS256", "kid": "rsa-2026", "n": RSA_N, "e": "AQAB" },
+            { "kty": "OKP", "crv": "Ed25519", "use": "sig", "alg": "EdDSA", "kid": "ed-2025", "x": ED25519_X }
+        ] }));
+        assert_eq!(KeySet::from_secret("a real secret").jwks(), json!({ "keys": [] }));
+    }
+
+    #[test]
+    fn reads_rsa_keys_in_both_pem_formats() {
+        let spki = rsa_jwk("k", &fs::read(fixture("rsa-public.pem")).unwrap()).unwrap();
+        let pkcs1 = rsa_jwk("k", &fs::read(fixture("rsa-public-pkcs1.pem")).unwrap()).unwrap();
+        assert_eq!(spki, pkcs1);
+
+        assert!(rsa_jwk("k", &fs::read(fixture("rsa-private.pem")).unwrap()).is_err());
+        assert!(rsa_jwk("k", b"not a key").is_err());
+
+        // The key's long-form length must match the input exactly, and the
+        // outer element must be a sequence holding the right algorithm
+        let (_, der) = pem_der(&fs::read(fixture("rsa-public.pem")).unwrap()).unwrap();
+        assert_eq!(&der[..2], [0x30, 0x82]);
+        assert!(spki_key(&der, RSA_ENCRYPTION_OID).is_some());
+        assert!(spki_key(&der[..der.len() - 1], RSA_ENCRYPTION_OID).is_none());
+        assert!(spki_key(&[&der[..], &[0]].concat(), RSA_ENCRYPTION_OID).is_none());
+        let mut longer = der.clone();
+        longer[3] += 1;
+        assert!(spki_key(&longer, RSA_ENCRYPTION_OID).is_none());
+        let mut mistagged = der.clone();
+        mistagged[0] = 0x31;
+        assert!(spki_key(&mistagged, RSA_ENCRYPTION_OID).is_none());
+        assert!(spki_key(&der, ED25519_OID).is_none());
+    }
+
+    #[test]
+    fn refuses_placeholder_secrets_outside_development() {
+        for secret in PLACEHOLDER_SECRETS {
+            let keys = KeySet::from_secret(secret);
+            assert!(keys.check_secrets(false).is_err());
+            assert!(keys.check_secrets(true).is_ok());
+        }
+        assert!(KeySet::from_secret("a real secret").check_secrets(false).is_ok());
+
+        let mut file = key_file();
+        file["keys"][0]["secret"] = json!(DEVELOPMENT_SECRET);
+        assert!(load(&file).unwrap().check_secrets(false).is_err());
+    }
+}
@@ -0,0 +1,176 @@
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use log::{info, warn};
+use sha2::{Digest, Sha256};
+use std::env;
+
+use crate::auth::{AuthMiddleware, Claims, User};
+use crate::config::database::DbPool;
+use crate::error::{AppError, AppResult};
+use crate::models::repository::Repository;
+use crate::models::token::RefreshToken;
+use crate::models::token_repository::TokenRepository;
+use crate::models::user_repository::UserRepository;
+
+// Tokens handed to a client after login or refresh
+#[derive(Debug)]
+pub struct Session {
+    /// Short-lived JWT access token
+    pub access_token: String,
+    /// Opaque single-use refresh token
+    pub refresh_token: String,
+    /// Access token lifetime in seconds
+    pub expires_in: i64,
+}
+
+// Lifetime of refresh tokens, configurable through REFRESH_TOKEN_TTL_DAYS
+pub fn refresh_token_ttl() -> chrono::Duration {
+    let days = env::var("REFRESH_TOKEN_TTL_DAYS")
+        .ok()
+        .and_then(|value| value.parse::<i64>().ok())
+        .unwrap_or(30);
+
+    chrono::Duration::days(days)
+}
+
+// Hex encoded SHA-256, the only form in which refresh tokens are stored
+pub fn hash_token(token: &str) -> String {
+    format!("{:x}", Sha256::digest(token.as_bytes()))
+}
+
+fn generate_refresh_token() -> String {
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+fn now() -> i64 {
+    chrono::Utc::now().timestamp()
+}
+
+fn db_error(context: &str, e: anyhow::Error) -> AppError {
+    AppError::internal_error(format!("{}: {}", context, e))
+}
+
+async fn issue_in_family(repo: &TokenRepository, user: &User, token_id: String, family_id: String) -> AppResult<Session> {
+    let access_token = AuthMiddleware::generate_token(user)?;
+    let refresh_token = generate_refresh_token();
+
+    let mut record = RefreshToken::new(
+        user.id.clone(),
+        hash_token(&refresh_token),
+        family_id,
+        now() + refresh_token_ttl().num_seconds(),
+    );
+    record.id = token_id;
+
+    repo.create_refresh_token(record).await
+        .map_err(|e| db_error("Failed to store refresh token", e))?;
+
+    Ok(Session {
+        access_token,
+        refresh_token,
+        expires_in: AuthMiddleware::access_token_ttl().num_seconds(),
+    })
+}
+
+// Start a new session (a new refresh token family) for a freshly authenticated user
+pub async fn issue_session(pool: &DbPool, user: &User) -> AppResult<Session> {
+    let repo = TokenRepository::new(pool.clone());
+    let family_id = crate::models::repository::generate_id();
+
+    issue_in_family(&repo, user, crate::models::repository::generate_id(), family_id).await
+}
+
+// Exchange a refresh token for a new access/refresh token pair. Each refresh
+// token can be used once; presenting one that was already used revokes the
+// whole family, since it means the token was stolen or replayed.
+pub async fn refresh_session(pool: &DbPool, refresh_token: &str) -> AppResult<(User, Session)> {
+    let repo = TokenRepository::new(pool.clone());
+    let now = now();
+
+    let record = repo.find_refresh_token_by_hash(&hash_token(refresh_token)).await
+        .map_err(|e| db_error("Failed to fetch refresh token", e))?
+        .ok_or_else(|| AppError::unauthorized("Invalid refresh token"))?;
+
+    if record.revoked_at.is_some() {
+        warn!("Reuse of rotated refresh token detected for user {}; revoking its family", record.user_id);
+        repo.revoke_refresh_token_family(&record.family_id, now).await
+            .map_err(|e| db_error("Failed to revoke token family", e))?;
+        return Err(AppError::unauthorized("Refresh token has been revoked"));
+    }
+
+    if record.expires_at <= now {
+        return Err(AppError::unauthorized("Refresh token has expired"));
+    }
+
+    // Reload the account so role changes and deletions take effect on refresh
+    let account = UserRepository::new(pool.clone())
+        .find_by_id(&record.user_id).await
+        .map_err(|e| db_error("Failed to fetch user", e))?;
+
+    let account = match account {
+        Some(account) => account,
+        None => {
+            repo.revoke_refresh_token_family(&record.family_id, now).await
+                .map_err(|e| db_error("Failed to revoke token family", e))?;
+            return Err(AppError::unauthorized("User no longer exists"));
+        }
+    };
+
+    let next_id = crate::models::repository::generate_id();
+    let rotated = repo.rotate_refresh_token(&record.id, &next_id, now).await
+        .map_err(|e| db_error("Failed to rotate refresh token", e))?;
+
+    if !rotated {
+        // Another request rotated this token between our read and write
+        repo.revoke_refresh_token_family(&record.family_id, now).await
+            .map_err(|e| db_error("Failed to revoke token family", e))?;
+        return Err(AppError::unauthorized("Refresh token has been revoked"));
+    }
+
+    let user = User {
+        id: account.id,
+        name: account.name,
+        role: account.role,
+    };
+
+    let session = issue_in_family(&repo, &user, next_id, record.family_id).await?;
+    Ok((user, session))
+}
+
+// End a session: deny the presented access token until it expires and, when
+// given, revoke the refresh token family it belongs to
+pub async fn revoke_session(pool: &DbPool, claims: &Claims, refresh_token: Option<&str>) -> AppResult<()> {
+    let repo = TokenRepository::new(pool.clone());
+    let now = now();
+
+    repo.revoke_access_token(&claims.jti, claims.exp as i64).await
+        .map_err(|e| db_error("Failed to revoke access token", e))?;
+
+    if let Some(refresh_token) = refresh_token {
+        let record = repo.find_refresh_token_by_hash(&hash_token(refresh_token)).await
+            .map_err(|e| db_error("Failed to fetch refresh token", e))?;
+
+        // Only allow revoking tokens that belong to the caller
+        if let Some(record) = record.filter(|r| r.user_id == claims.sub) {
+            repo.revoke_refresh_token_family(&record.family_id, now).await
+                .map_err(|e| db_error("Failed to revoke token family", e))?;
+        }
+    }
+
+    repo.purge_expired(now).await
+        .map_err(|e| db_error("Failed to purge expired tokens", e))?;
+
+    info!("Session ended for user {}", claims.name);
+    Ok(())
+}
+
+// Revoke every refresh token of a user, e.g. after a password change
+pub async fn revoke_user_sessions(pool: &DbPool, user_id: &str) -> AppResult<()> {
+    TokenRepository::new(pool.clone())
+        .revoke_refresh_tokens_for_user(user_id, now()).await
+        .map_err(|e| db_error("Failed to revoke refresh tokens", e))?;
+
+    Ok(())
+}
@@ -0,0 +1,208 @@
+use actix_web::{http::header, HttpRequest};
+use log::warn;
+use std::env;
+
+use crate::config::database::DbPool;
+use crate::error::{AppError, AppResult};
+use crate::models::login_attempt::LoginAttempt;
+use crate::models::login_attempt_repository::LoginAttemptRepository;
+
+// Outcomes recorded in the login audit table
+pub const OUTCOME_SUCCESS: &str = "success";
+pub const OUTCOME_MFA_REQUIRED: &str = "mfa_required";
+pub const OUTCOME_INVALID_CREDENTIALS: &str = "invalid_credentials";
+pub const OUTCOME_INVALID_CODE: &str = "invalid_code";
+pub const OUTCOME_LOCKED: &str = "locked";
+
+// Lockout settings, read from the environment
+#[derive(Debug, Clone, Copy)]
+pub struct ThrottleConfig {
+    /// Failures of one username before it is locked (LOGIN_MAX_FAILURES)
+    pub max_failures: i64,
+    /// Failures from one address before it is locked (LOGIN_MAX_FAILURES_PER_IP)
+    pub max_failures_per_ip: i64,
+    /// First lockout, doubled on each further failure (LOGIN_LOCKOUT_SECONDS)
+    pub lockout_seconds: i64,
+    /// Upper bound of a lockout; counters also reset after this much quiet (LOGIN_MAX_LOCKOUT_SECONDS)
+    pub max_lockout_seconds: i64,
+}
+
+fn env_i64(name: &str, default: i64) -> i64 {
+    env::var(name)
+        .ok()
+        .and_then(|value| value.parse::<i64>().ok())
+        .filter(|value| *value > 0)
+        .unwrap_or(default)
+}
+
+impl ThrottleConfig {
+    pub fn from_env() -> Self {
+        Self {
+            max_failures: env_i64("LOGIN_MAX_FAILURES", 5),
+            max_failures_per_ip: env_i64("LOGIN_MAX_FAILURES_PER_IP", 20),
+            lockout_seconds: env_i64("LOGIN_LOCKOUT_SECONDS", 30),
+            max_lockout_seconds: env_i64("LOGIN_MAX_LOCKOUT_SECONDS", 3600),
+        }
+    }
+
+    // Lockout after `failures` consecutive failures, doubling from
+    // lockout_seconds once the threshold is reached
+    pub fn lockout_after(&self, failures: i64, threshold: i64) -> Option<i64> {
+        if failures < threshold {
+            return None;
+        }
+
+        let doublings = (failures - threshold).min(32) as u32;
+        Some(self.lockout_seconds.saturating_mul(1i64 << doublings).min(self.max_lockout_seconds))
+    }
+}
+
+// Who is trying to log in, as far as the server can tell
+pub struct LoginContext {
+    pub username: String,
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+}
+
+impl LoginContext {
+    // Client address from the socket, or from X-Forwarded-For/Forwarded when
+    // TRUST_PROXY_HEADERS is set (only safe behind a reverse proxy that sets them)
+    pub fn from_request(req: &HttpRequest, username: &str) -> Self {
+        let trust_proxy = env::var("TRUST_PROXY_HEADERS").is_ok_and(|value| value == "true");
+
+        let ip_address = if trust_proxy {
+            req.connection_info().realip_remote_addr().map(str::to_string)
+        } else {
+            req.peer_addr().map(|addr| addr.ip().to_string())
+        };
+
+        let user_agent = req.headers()
+            .get(header::USER_AGENT)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string);
+
+        Self {
+            username: username.to_string(),
+            ip_address,
+            user_agent,
+        }
+    }
+
+    // Counters that apply to this attempt, with their failure thresholds
+    fn throttle_keys(&self, config: &ThrottleConfig) -> Vec<(String, i64)> {
+        let mut keys = vec![(format!("user:{}", self.username), config.max_failures)];
+        if let Some(ip) = &self.ip_address {
+            keys.push((format!("ip:{}", ip), config.max_failures_per_ip));
+        }
+        keys
+    }
+}
+
+fn db_error(context: &str, e: anyhow::Error) -> AppError {
+    AppError::internal_error(format!("{}: {}", context, e))
+}
+
+async fn insert(repo: &LoginAttemptRepository, ctx: &LoginContext, outcome: &str, now: i64) -> AppResult<()> {
+    let attempt = LoginAttempt::new(
+        ctx.username.clone(),
+        ctx.ip_address.clone(),
+        ctx.user_agent.clone(),
+        outcome.to_string(),
+        now,
+    );
+
+    repo.record(attempt).await
+        .map_err(|e| db_error("Failed to record login attempt", e))?;
+
+    Ok(())
+}
+
+// Refuse the attempt with 429 while the username or the address is locked.
+// Runs before the password is checked, so a locked account cannot be probed.
+pub async fn check_lockout(pool: &DbPool, ctx: &LoginContext, now: i64) -> AppResult<()> {
+    let repo = LoginAttemptRepository::new(pool.clone());
+    let config = ThrottleConfig::from_env();
+    let mut retry_after = 0;
+
+    for (key, _) in ctx.throttle_keys(&config) {
+        let throttle = repo.find_throttle(&key).await
+            .map_err(|e| db_error("Failed to check login throttle", e))?;
+
+        if let Some(locked_until) = throttle.and_then(|t| t.locked_until) {
+            retry_after = retry_after.max(locked_until - now);
+        }
+    }
+
+    if retry_after > 0 {
+        insert(&repo, ctx, OUTCOME_LOCKED, now).await?;
+        return Err(AppError::too_many_requests(
+            "Too many failed login attempts, try again later",
+            retry_after,
+        ));
+    }
+
+    Ok(())
+}
+
+// Record a failed attempt and extend the lockout of its username and address
+pub async fn record_failure(pool: &DbPool, ctx: &LoginContext, outcome: &str, now: i64) -> AppResult<()> {
+    let repo = LoginAttemptRepository::new(pool.clone());
+    let config = ThrottleConfig::from_env();
+
+    insert(&repo, ctx, outcome, now).await?;
+
+    for (key, threshold) in ctx.throttle_keys(&config) {
+        // Failures older than the longest lockout are forgotten
+        let failures = repo.add_failure(&key, now, now - config.max_lockout_seconds).await
+            .map_err(|e| db_error("Failed to update login throttle", e))?;
+
+        if let Some(seconds) = config.lockout_after(failures, threshold) {
+            warn!("Locking login for {} for {}s after {} failures", key, seconds, failures);
+            repo.lock_throttle(&key, now + seconds).await
+                .map_err(|e| db_error("Failed to update login throttle", e))?;
+        }
+    }
+
+    Ok(())
+}
+
+// Record an attempt in the audit table without touching the counters, e.g.
+// a correct password that still needs a second factor
+pub async fn record_attempt(pool: &DbPool, ctx: &LoginContext, outcome: &str, now: i64) -> AppResult<()> {
+    insert(&LoginAttemptRepository::new(pool.clone()), ctx, outcome, now).await
+}
+
+// Record a completed login and reset the username's counter. The address
+// counter is left alone, so one valid account cannot unlock a guessing client.
+pub async fn record_success(pool: &DbPool, ctx: &LoginContext, now: i64) -> AppResult<()> {
+    let repo = LoginAttemptRepository::new(pool.clone());
+
+    insert(&repo, ctx, OUTCOME_SUCCESS, now).await?;
+
+    repo.clear_throttle(&format!("user:{}", ctx.username)).await
+        .map_err(|e| db_error("Failed to reset login throttle", e))?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lockout_doubles_up_to_the_maximum() {
+        let config = ThrottleConfig {
+            max_failures: 5,
+            max_failures_per_ip: 20,
+            lockout_seconds: 30,
+            max_lockout_seconds: 3600,
+        };
+
+        assert_eq!(config.lockout_after(4, 5), None);
+        assert_eq!(config.lockout_after(5, 5), Some(30));
+        assert_eq!(config.lockout_after(6, 5), Some(60));
+        assert_eq!(config.lockout_after(8, 5), Some(240));
+        assert_eq!(config.lockout_after(12, 5), Some(3600));
+        assert_eq!(config.lockout_after(500, 5), Some(3600));
+    }
+}
@@ -0,0 +1,217 @@
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use base32::Alphabet;
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha1::Sha1;
+use std::env;
+use uuid::Uuid;
+
+use crate::auth::session::hash_token;
+use crate::auth::AuthMiddleware;
+use crate::error::{AppError, AppResult};
+
+// RFC 6238 parameters understood by every authenticator app
+const STEP_SECONDS: u64 = 30;
+const DIGITS: u32 = 6;
+
+// Accept codes from one step before and after the current one for clock drift
+const ALLOWED_DRIFT_STEPS: u64 = 1;
+
+const SECRET_BYTES: usize = 20;
+const RECOVERY_CODE_COUNT: usize = 10;
+
+// Purpose claim that keeps challenge tokens apart from access tokens
+const CHALLENGE_PURPOSE: &str = "mfa";
+
+// Lifetime of the interim token between password and code
+const CHALLENGE_TTL_SECONDS: i64 = 300;
+
+const BASE32: Alphabet = Alphabet::RFC4648 { padding: false };
+
+// Claims of the interim token returned by login when a second factor is
+// needed. It lacks the name and role of access token claims, so it can never
+// be accepted as an access token.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ChallengeClaims {
+    /// Subject (user ID)
+    pub sub: String,
+    /// Always "mfa"
+    pub purpose: String,
+    /// Expiration time (as UTC timestamp)
+    pub exp: usize,
+    /// Issued at (as UTC timestamp)
+    pub iat: usize,
+    /// Unique token identifier, revoked once the challenge is completed
+    pub jti: String,
+}
+
+// New random shared secret, base32 encoded as authenticator apps expect
+pub fn generate_secret() -> String {
+    let mut bytes = [0u8; SECRET_BYTES];
+    OsRng.fill_bytes(&mut bytes);
+    base32::encode(BASE32, &bytes)
+}
+
+// otpauth:// URI to show as a QR code in the authenticator app
+pub fn provisioning_uri(secret: &str, account: &str) -> String {
+    let issuer = env::var("TOTP_ISSUER").unwrap_or_else(|_| "Retro Quewui".to_string());
+
+    format!(
+        "otpauth://totp/{}:{}?secret={}&issuer={}&algorithm=SHA1&digits={}&period={}",
+        urlencode(&issuer),
+        urlencode(account),
+        secret,
+        urlencode(&issuer),
+        DIGITS,
+        STEP_SECONDS
+    )
+}
+
+fn urlencode(value: &str) -> String {
+    value.bytes().map(|b| match b {
+        b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => (b as char).to_string(),
+        _ => format!("%{:02X}", b),
+    }).collect()
+}
+
+// HOTP value (RFC 4226) of a raw key for one counter value
+fn hotp(key: &[u8], counter: u64) -> u32 {
+    let mut mac = Hmac::<Sha1>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(&counter.to_be_bytes());
+    let digest = mac.finalize().into_bytes();
+
+    let offset = (digest[digest.len() - 1] & 0x0f) as usize;
+    let value = u32::from_be_bytes([
+        digest[offset] & 0x7f,
+        digest[offset + 1],
+        digest[offset + 2],
+        digest[offset + 3],
+    ]);
+
+    value % 10u32.pow(DIGITS)
+}
+
+// Code for a secret at a unix time, zero padded to six digits
+pub fn code_at(secret: &str, unix_time: u64) -> Option<String> {
+    let key = base32::decode(BASE32, secret)?;
+    Some(format!("{:0width$}", hotp(&key, unix_time / STEP_SECONDS), width = DIGITS as usize))
+}
+
+// Check a code at a unix time. Returns the matching time step, which the
+// caller must record so the code cannot be used twice.
+pub fn verify_code(secret: &str, code: &str, unix_time: u64) -> Option<i64> {
+    let code = code.trim();
+    if code.len() != DIGITS as usize || !code.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+
+    let key = base32::decode(BASE32, secret)?;
+    let current = unix_time / STEP_SECONDS;
+
+    (current.saturating_sub(ALLOWED_DRIFT_STEPS)..=current + ALLOWED_DRIFT_STEPS)
+        .find(|&step| format!("{:0width$}", hotp(&key, step), width = DIGITS as usize) == code)
+        .map(|step| step as i64)
+}
+
+// Fresh one-time recovery codes: the plain codes for the user and their hashes for storage
+pub fn generate_recovery_codes() -> (Vec<String>, Vec<String>) {
+    let codes: Vec<String> = (0..RECOVERY_CODE_COUNT)
+        .map(|_| {
+            let mut bytes = [0u8; 5];
+            OsRng.fill_bytes(&mut bytes);
+            let hex: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+            format!("{}-{}", &hex[..5], &hex[5..])
+        })
+        .collect();
+
+    let hashes = codes.iter().map(|code| hash_recovery_code(code)).collect();
+    (codes, hashes)
+}
+
+// Recovery codes are compared case-insensitively and with or without the dash
+pub fn hash_recovery_code(code: &str) -> String {
+    let normalized: String = code.trim().to_ascii_lowercase().chars().filter(|c| *c != '-').collect();
+    hash_token(&normalized)
+}
+
+// Sign the interim token handed out after a correct password
+pub fn issue_challenge(user_id: &str, now: i64) -> AppResult<(String, i64)> {
+    let claims = ChallengeClaims {
+        sub: user_id.to_string(),
+        purpose: CHALLENGE_PURPOSE.to_string(),
+        exp: (now + CHALLENGE_TTL_SECONDS) as usize,
+        iat: now as usize,
+        jti: Uuid::new_v4().to_string(),
+    };
+
+    Ok((AuthMiddleware::sign(&claims)?, CHALLENGE_TTL_SECONDS))
+}
+
+pub fn verify_challenge(token: &str) -> AppResult<ChallengeClaims> {
+    let claims: ChallengeClaims = AuthMiddleware::verify(token)
+        .map_err(|_| AppError::unauthorized("Invalid or expired challenge token"))?;
+
+    if claims.purpose != CHALLENGE_PURPOSE {
+        return Err(AppError::unauthorized("Invalid or expired challenge token"));
+    }
+
+    Ok(claims)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Secret of the RFC 6238 SHA-1 test vectors ("12345678901234567890")
+    fn rfc_secret() -> String {
+        base32::encode(BASE32, b"12345678901234567890")
+    }
+
+    #[test]
+    fn matches_rfc_6238_vectors() {
+        let secret = rfc_secret();
+
+        // The RFC lists 8 digit codes; 6 digit codes are their last six digits
+        assert_eq!(code_at(&secret, 59).as_deref(), Some("287082"));
+        assert_eq!(code_at(&secret, 1111111109).as_deref(), Some("081804"));
+        assert_eq!(code_at(&secret, 1111111111).as_deref(), Some("050471"));
+        assert_eq!(code_at(&secret, 1234567890).as_deref(), Some("005924"));
+        assert_eq!(code_at(&secret, 2000000000).as_deref(), Some("279037"));
+    }
+
+    #[test]
+    fn accepts_one_step_of_drift() {
+        let secret = rfc_secret();
+        let step = (1111111111 / STEP_SECONDS) as i64;
+
+        assert_eq!(verify_code(&secret, "050471", 1111111111), Some(step));
+        assert_eq!(verify_code(&secret, "050471", 1111111111 + 30), Some(step));
+        assert_eq!(verify_code(&secret, "050471", 1111111111 - 30), Some(step));
+        assert_eq!(verify_code(&secret, "050471", 1111111111 + 90), None);
+    }
+
+    #[test]
+    fn rejects_malformed_codes() {
+        let secret = rfc_secret();
+
+        assert_eq!(verify_code(&secret, "", 59), None);
+        assert_eq!(verify_code(&secret, "28708", 59), None);
+        assert_eq!(verify_code(&secret, "28708x", 59), None);
+        assert_eq!(verify_code("not base32!", "287082", 59), None);
+    }
+
+    #[test]
+    fn recovery_codes_are_normalized() {
+        let (codes, hashes) = generate_recovery_codes();
+
+        assert_eq!(codes.len(), RECOVERY_CODE_COUNT);
+        assert_eq!(hash_recovery_code(&codes[0].to_uppercase()), hashes[0]);
+        assert_eq!(hash_recovery_code(&codes[0].replace('-', "")), hashes[0]);
+    }
+
+    #[test]
+    fn provisioning_uri_escapes_account() {
+        let uri = provisioning_uri("JBSWY3DPEHPK3PXP", "jane doe");
+        assert!(uri.contains(":jane%20doe?secret=JBSWY3DPEHPK3PXP&"));
+    }
+}
@@ -0,0 +1,151 @@
+use std::env;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use actix_web::body::MessageBody;
+use actix_web::http::{header::{self, HttpDate}, StatusCode};
+use actix_web::{HttpRequest, HttpResponse};
+use log::{error, info};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use crate::config::database::DbPool;
+use crate::error::{AppError, AppResult};
+use crate::models::repository::{find_last_modified, Versioned};
+
+// Conditional requests. Items with a version send it as their ETag, and
+// writes must name it in If-Match. Public reads send an ETag and
+// Last-Modified, and answer 304 Not Modified when the client's copy is current.
+
+/// Strong entity tag of an item version, e.g. "3"
+pub fn version_etag(version: i64) -> String {
+    format!("\"{}\"", version)
+}
+
+/// Current version of an item, or 404 Not Found; `item` names it in messages,
+/// e.g. "Project with ID 1". Read before the item itself, so that the ETag
+/// sent is never newer than the copy it goes with.
+pub async fn item_version<T>(repo: &impl Versioned<T>, id: &str, item: &str) -> AppResult<i64> {
+    repo.find_version(id).await
+        .map_err(|e| {
+            error!("Failed to fetch the version of {}: {}", item, e);
+            AppError::internal_error(format!("Failed to fetch the version of {}: {}", item, e))
+        })?
+        .ok_or_else(|| {
+            info!("{} not found", item);
+            AppError::not_found(format!("{} not found", item))
+        })
+}
+
+/// The error for a write based on an outdated copy of an item
+pub fn changed_since_read() -> AppError {
+    AppError::precondition_failed("The item changed since it was read; fetch it again and retry")
+}
+
+/// Check the If-Match header of a write against the item's current version,
+/// returning the version to write at: 428 Precondition Required without the
+/// header, 412 Precondition Failed unless it lists the current ETag or `*`
+pub fn if_match(req: &HttpRequest, current: i64) -> AppResult<i64> {
+    let Some(value) = req.headers().get(header::IF_MATCH) else {
+        return Err(AppError::precondition_required("Send the item's ETag in an If-Match header"));
+    };
+
+    let etag = version_etag(current);
+    let matches = value.to_str().is_ok_and(|tags| {
+        tags.split(',').map(str::trim).any(|tag| tag == etag || tag == "*")
+    });
+
+    if !matches {
+        return Err(changed_since_read());
+    }
+
+    Ok(current)
+}
+
+/// 200 OK with an item as JSON and the ETag of its version
+pub fn versioned_json<T: Serialize>(version: i64, item: &T) -> HttpResponse {
+    HttpResponse::Ok()
+        .insert_header((header::ETAG, version_etag(version)))
+        .json(item)
+}
+
+/// Cache-Control of public reads unless configured otherwise: shared caches
+/// may keep a copy for a minute, and revalidate it with the ETag after that
+pub const PUBLIC_CACHE_CONTROL: &str = "public, max-age=60";
+
+/// Cache-Control sent with a group of public reads: CACHE_CONTROL_<GROUP>
+/// (e.g. CACHE_CONTROL_POSTS), else CACHE_CONTROL, else the group's default
+pub fn cache_control(group: &str, default: &str) -> String {
+    env::var(format!("CACHE_CONTROL_{}", group.to_uppercase()))
+        .or_else(|_| env::var("CACHE_CONTROL"))
+        .unwrap_or_else(|_| default.to_string())
+}
+
+/// Unix time of the last write to the tables a public read is built from.
+/// Read before the content, so that Last-Modified is never newer than the
+/// copy it goes with.
+pub async fn last_modified(db: &DbPool, tables: &[&str]) -> AppResult<Option<i64>> {
+    find_last_modified(db, tables).await.map_err(|e| {
+        error!("Failed to fetch the last modification of {}: {}", tables.join(", "), e);
+        AppError::internal_error(format!("Failed to fetch the last modification: {}", e))
+    })
+}
+
+/// Whether the client already has this copy: If-None-Match against the ETag
+/// when sent, else If-Modified-Since against the last modification
+pub fn not_modified(req: &HttpRequest, etag: &str, last_modified: Option<SystemTime>) -> bool {
+    if let Some(if_none_match) = req.headers().get(header::IF_NONE_MATCH) {
+        return if_none_match.to_str().is_ok_and(|tags| {
+            tags.split(',')
+                .map(|tag| tag.trim().trim_start_matches("W/"))
+                .any(|tag| tag == etag || tag == "*")
+        });
+    }
+
+    let Some(last_modified) = last_modified else {
+        return false;
+    };
+
+    req.headers()
+        .get(header::IF_MODIFIED_SINCE)
+        .and_then(|since| since.to_str().ok()?.parse::<HttpDate>().ok())
+        .is_some_and(|since| last_modified <= SystemTime::from(since))
+}
+
+/// Make a successful public read conditional. The response keeps the ETag of
+/// its version if it has one, or gets a strong ETag of its body, along with
+/// Last-Modified when known and a Cache-Control policy; it becomes 304 Not
+/// Modified, without a body, when the client's copy is current.
+pub fn cacheable(req: &HttpRequest, response: HttpResponse, last_modified: Option<i64>, cache_control: &str) -> HttpResponse {
+    if response.status() != StatusCode::OK {
+        return response;
+    }
+
+    let (head, body) = response.into_parts();
+    let body = match body.try_into_bytes() {
+        Ok(body) => body,
+        Err(body) => return head.set_body(body),
+    };
+
+    let etag = head.headers().get(header::ETAG)
+        .and_then(|etag| etag.to_str().ok())
+        .map(str::to_string)
+        .unwrap_or_else(|| format!("\"{:x}\"", Sha256::digest(&body)));
+    let last_modified = last_modified.map(|seconds| UNIX_EPOCH + Duration::from_secs(seconds.max(0) as u64));
+    let unchanged = not_modified(req, &etag, last_modified);
+
+    let mut response = if unchanged { HttpResponse::NotModified() } else { HttpResponse::Ok() };
+    for (name, value) in head.headers() {
+        response.append_header((name.clone(), value.clone()));
+    }
+    response
+        .insert_header((header::ETAG, etag))
+        .insert_header((header::CACHE_CONTROL, cache_control));
+    if let Some(last_modified) = last_modified {
+        response.insert_header((header::LAST_MODIFIED, HttpDate::from(last_modified)));
+    }
+
+    if unchanged {
+        return response.finish();
+    }
+    response.body(body)
+}
@@ -1,63 +1,93 @@
-use sqlx::{migrate::MigrateDatabase, sqlite::SqlitePoolOptions, Pool, Sqlite};
-use std::{env, path::Path};
-use anyhow::Result;
-use std::fs;
+use sqlx::{
+    any::{install_default_drivers, AnyPoolOptions},
+    migrate::MigrateDatabase,
+    Any, AnyPool,
+};
+use std::env;
+use anyhow::{bail, Result};
 
-pub type DbPool = Pool<Sqlite>;
+use crate::config::migrations::run_migrations;
+
+/// Connection pool for either backend. The backend is chosen at runtime from
+/// the DATABASE_URL scheme, so queries must use SQL understood by both:
+/// `$1` style placeholders and `ON CONFLICT` upserts.
+pub type DbPool = AnyPool;
+
+/// Database engine behind a pool
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DbBackend {
+    Sqlite,
+    Postgres,
+}
+
+impl DbBackend {
+    /// Backend of a database URL, from its scheme
+    pub fn from_url(url: &str) -> Result<Self> {
+        match url.split_once(':').map(|(scheme, _)| scheme) {
+            Some("sqlite") => Ok(Self::Sqlite),
+            Some("postgres" | "postgresql") => Ok(Self::Postgres),
+            _ => bail!("Unsupported DATABASE_URL {}; use sqlite: or postgres://", url),
+        }
+    }
+
+    /// Backend of an open pool
+    pub fn of(pool: &DbPool) -> Self {
+        match pool.connect_options().database_url.scheme() {
+            "postgres" | "postgresql" => Self::Postgres,
+            _ => Self::Sqlite,
+        }
+    }
+
+    /// SQL expression reading a TIMESTAMP column as UTC unix seconds, since
+    /// the Any driver cannot decode timestamps
+    pub fn unix_seconds(&self, column: &str) -> String {
+        match self {
+            Self::Sqlite => format!("CAST(strftime('%s', {}) AS INTEGER)", column),
+            Self::Postgres => format!("CAST(EXTRACT(EPOCH FROM {}) AS BIGINT)", column),
+        }
+    }
+}
+
+/// Open a pool for a database URL, creating the database if needed, without migrating
+pub async fn connect_url(database_url: &str, max_connections: u32) -> Result<DbPool> {
+    install_default_drivers();
+    DbBackend::from_url(database_url)?;
 
-/// Initialize the SQLite database connection pool
-pub async fn init_db() -> Result<DbPool> {
-    // Get database URL from environment or use default
-    let database_url = env::var("DATABASE_URL")
-        .unwrap_or_else(|_| "sqlite:./data.db".to_string());
-    
     // Create database if it doesn't exist
-    if !Sqlite::database_exists(&database_url).await.unwrap_or(false) {
+    if !Any::database_exists(database_url).await.unwrap_or(false) {
         log::info!("Creating database {}", database_url);
-        Sqlite::create_database(&database_url).await?;
+        Any::create_database(database_url).await?;
     }
-    
+
     // Create connection pool
-    let pool = SqlitePoolOptions::new()
-        .max_connections(10)
-        .connect(&database_url)
+    let pool = AnyPoolOptions::new()
+        .max_connections(max_connections)
+        .connect(database_url)
         .await?;
-    
-    // Run migrations manually
+
+    Ok(pool)
+}
+
+/// Connect to the database, creating it if needed, without migrating
+pub async fn connect_db() -> Result<DbPool> {
+    // Get database URL from environment or use default
+    let database_url = env::var("DATABASE_URL")
+        .unwrap_or_else(|_| "sqlite:./data.db".to_string());
+
+    let pool = connect_url(&database_url, 10).await?;
+    log::info!("Connected to {:?} database", DbBackend::of(&pool));
+
+    Ok(pool)
+}
+
+/// Initialize the database connection pool
+pub async fn init_db() -> Result<DbPool> {
+    let pool = connect_db().await?;
+
+    // Apply pending migrations
     log::info!("Running database migrations");
     run_migrations(&pool).await?;
-    
+
     log::info!("Database initialized successfully");
     Ok(pool)
 }
-
-/// Run migrations manually by executing SQL files
-async fn run_migrations(pool: &Pool<Sqlite>) -> Result<()> {
-    let migrations_dir = Path::new("migrations");
-    
-    if !migrations_dir.exists() {
-        return Err(anyhow::anyhow!("Migrations directory not found"));
-    }
-    
-    // Get all SQL files in the migrations directory
-    let mut migration_files = Vec::new();
-    for entry in fs::read_dir(migrations_dir)? {
-        let entry = entry?;
-        let path = entry.path();
-        if path.extension().map_or(false, |ext| ext == "sql") {
-            migration_files.push(path);
-        }
-    }
-    
-    // Sort migration files by name
-    migration_files.sort();
-    
-    // Execute each migration file
-    for path in migration_files {
-        let sql = fs::read_to_string(&path)?;
-        log::info!("Executing migration: {:?}", path.file_name().unwrap());
-        sqlx::query(&sql).execute(pool).await?;
-    }
-    
-    Ok(())
-}
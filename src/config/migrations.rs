@@ -0,0 +1,245 @@
+use anyhow::{bail, Context, Result};
+use sha2::{Digest, Sha256};
+use sqlx::{Executor, FromRow};
+use std::collections::HashMap;
+
+use crate::config::database::{DbBackend, DbPool};
+
+// SQL of migrations/sqlite and migrations/postgres, embedded at compile time by build.rs
+include!(concat!(env!("OUT_DIR"), "/embedded_migrations.rs"));
+
+// Table recording applied migrations
+const CREATE_MIGRATIONS_TABLE: &str = "
+    CREATE TABLE IF NOT EXISTS _migrations (
+        version TEXT PRIMARY KEY,
+        description TEXT NOT NULL,
+        checksum TEXT NOT NULL,
+        applied_at BIGINT NOT NULL
+    )
+";
+
+/// A migration file. Migrations are identified and ordered by their file
+/// name without the `.sql` extension, e.g. `20261017090000_create_users`.
+/// The optional `<version>.down.sql` file reverts it.
+#[derive(Debug, Clone)]
+pub struct Migration {
+    pub version: String,
+    pub description: String,
+    pub sql: String,
+    /// SQL reverting the migration, used by rollbacks
+    pub down_sql: Option<String>,
+    /// Hex encoded SHA-256 of the file contents
+    pub checksum: String,
+}
+
+impl Migration {
+    pub fn new(version: &str, sql: String) -> Self {
+        let description = version
+            .split_once('_')
+            .map_or(version, |(_, rest)| rest)
+            .replace('_', " ");
+
+        Self {
+            version: version.to_string(),
+            description,
+            checksum: format!("{:x}", Sha256::digest(sql.as_bytes())),
+            sql,
+            down_sql: None,
+        }
+    }
+
+    pub fn with_down(mut self, down_sql: String) -> Self {
+        self.down_sql = Some(down_sql);
+        self
+    }
+}
+
+#[derive(Debug, FromRow)]
+struct AppliedMigration {
+    version: String,
+    checksum: String,
+    applied_at: i64,
+}
+
+/// Migrations of a backend compiled into the binary, sorted by version. Both
+/// backends have the same versions, written in their own SQL dialect.
+pub fn embedded_migrations(backend: DbBackend) -> Vec<Migration> {
+    let files = match backend {
+        DbBackend::Sqlite => SQLITE_MIGRATIONS,
+        DbBackend::Postgres => POSTGRES_MIGRATIONS,
+    };
+
+    files
+        .iter()
+        .map(|(version, sql, down_sql)| {
+            let migration = Migration::new(version, sql.to_string());
+            match down_sql {
+                Some(down_sql) => migration.with_down(down_sql.to_string()),
+                None => migration,
+            }
+        })
+        .collect()
+}
+
+/// Apply the embedded migrations that have not been applied yet
+pub async fn run_migrations(pool: &DbPool) -> Result<()> {
+    apply_migrations(pool, &embedded_migrations(DbBackend::of(pool))).await?;
+    Ok(())
+}
+
+async fn applied_migrations(pool: &DbPool) -> Result<Vec<AppliedMigration>> {
+    pool.execute(CREATE_MIGRATIONS_TABLE).await?;
+
+    let applied = sqlx::query_as::<_, AppliedMigration>(
+        "SELECT version, checksum, applied_at FROM _migrations ORDER BY version",
+    )
+        .fetch_all(pool)
+        .await?;
+
+    Ok(applied)
+}
+
+/// Apply pending migrations in order, each in its own transaction, and return
+/// the versions that were applied. Fails without applying anything if an
+/// already applied migration has been modified since.
+pub async fn apply_migrations(pool: &DbPool, migrations: &[Migration]) -> Result<Vec<String>> {
+    let applied: HashMap<String, String> = applied_migrations(pool).await?
+        .into_iter()
+        .map(|row| (row.version, row.checksum))
+        .collect();
+
+    for migration in migrations {
+        if let Some(checksum) = applied.get(&migration.version) {
+            if *checksum != migration.checksum {
+                bail!(
+                    "Migration {} was modified after it was applied (checksum {} in database, {} in this build); add a new migration instead",
+                    migration.version,
+                    checksum,
+                    migration.checksum
+                );
+            }
+        }
+    }
+
+    for version in applied.keys() {
+        if !migrations.iter().any(|m| &m.version == version) {
+            log::warn!("Applied migration {} has no migration file", version);
+        }
+    }
+
+    let mut newly_applied = Vec::new();
+    for migration in migrations.iter().filter(|m| !applied.contains_key(&m.version)) {
+        log::info!("Applying migration {}", migration.version);
+
+        let mut tx = pool.begin().await?;
+
+        // Executing the raw string runs every statement in the file
+        tx.execute(migration.sql.as_str()).await
+            .with_context(|| format!("Migration {} failed", migration.version))?;
+
+        sqlx::query("INSERT INTO _migrations (version, description, checksum, applied_at) VALUES ($1, $2, $3, $4)")
+            .bind(&migration.version)
+            .bind(&migration.description)
+            .bind(&migration.checksum)
+            .bind(chrono::Utc::now().timestamp())
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        newly_applied.push(migration.version.clone());
+    }
+
+    if newly_applied.is_empty() {
+        log::info!("Database schema is up to date");
+    }
+
+    Ok(newly_applied)
+}
+
+/// Revert applied migrations newer than `target`, newest first, and return
+/// the reverted versions. `target` must be a known version, or "0" to revert
+/// everything. Fails before reverting anything if one of them has no down
+/// migration.
+pub async fn rollback_migrations(pool: &DbPool, migrations: &[Migration], target: &str) -> Result<Vec<String>> {
+    if target != "0" && !migrations.iter().any(|m| m.version == target) {
+        bail!("Unknown migration version {}", target);
+    }
+
+    let mut to_revert = Vec::new();
+    for applied in applied_migrations(pool).await?.iter().rev() {
+        if applied.version.as_str() <= target {
+            continue;
+        }
+
+        let migration = migrations.iter()
+            .find(|m| m.version == applied.version)
+            .with_context(|| format!("Applied migration {} has no migration file", applied.version))?;
+
+        let down_sql = migration.down_sql.as_deref()
+            .with_context(|| format!("Migration {} has no down migration", migration.version))?;
+
+        to_revert.push((migration.version.clone(), down_sql));
+    }
+
+    let mut reverted = Vec::new();
+    for (version, down_sql) in to_revert {
+        log::info!("Reverting migration {}", version);
+
+        let mut tx = pool.begin().await?;
+
+        tx.execute(down_sql).await
+            .with_context(|| format!("Down migration {} failed", version))?;
+
+        sqlx::query("DELETE FROM _migrations WHERE version = $1")
+            .bind(&version)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        reverted.push(version);
+    }
+
+    Ok(reverted)
+}
+
+/// Command line entry point: `migrate [up]`, `migrate status` or
+/// `migrate rollback <version>`
+pub async fn run_command(pool: &DbPool, args: &[String]) -> Result<()> {
+    let migrations = embedded_migrations(DbBackend::of(pool));
+
+    match args.iter().map(String::as_str).collect::<Vec<_>>().as_slice() {
+        [] | ["up"] => {
+            let applied = apply_migrations(pool, &migrations).await?;
+            println!("Applied {} migration(s)", applied.len());
+            for version in applied {
+                println!("  {}", version);
+            }
+        }
+        ["status"] => {
+            let applied: HashMap<String, i64> = applied_migrations(pool).await?
+                .into_iter()
+                .map(|row| (row.version, row.applied_at))
+                .collect();
+
+            for migration in &migrations {
+                let state = match applied.get(&migration.version) {
+                    Some(applied_at) => chrono::DateTime::from_timestamp(*applied_at, 0)
+                        .map_or_else(|| "applied".to_string(), |at| format!("applied {}", at.format("%Y-%m-%d %H:%M:%S UTC"))),
+                    None => "pending".to_string(),
+                };
+                let down = if migration.down_sql.is_some() { "" } else { " (no down migration)" };
+                println!("{}  {}{}", migration.version, state, down);
+            }
+        }
+        ["rollback", target] => {
+            let reverted = rollback_migrations(pool, &migrations, target).await?;
+            println!("Reverted {} migration(s)", reverted.len());
+            for version in reverted {
+                println!("  {}", version);
+            }
+        }
+        _ => bail!("Usage: retro-quewui-backend migrate [up | status | rollback <version|0>]"),
+    }
+
+    Ok(())
+}
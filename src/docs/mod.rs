@@ -11,33 +11,80 @@ use utoipa_swagger_ui::SwaggerUi;
 
         // Auth endpoints
         crate::routes::auth::login,
+        crate::routes::auth::login_totp,
+        crate::routes::auth::refresh,
+        crate::routes::auth::logout,
+        crate::routes::auth::jwks,
+        crate::routes::auth::change_password,
+        crate::routes::totp::enroll_totp,
+        crate::routes::totp::confirm_totp,
+        crate::routes::totp::disable_totp,
+
+        // User management endpoints
+        crate::routes::users::get_all_users,
+        crate::routes::users::create_user,
+        crate::routes::users::update_user,
+        crate::routes::users::delete_user,
+        crate::routes::users::reset_user_totp,
+
+        // Admin endpoints
+        crate::routes::admin::get_login_attempts,
+        crate::routes::trash::get_trash,
+        crate::routes::trash::restore_trash_item,
+        crate::routes::trash::purge_trash_item,
+        crate::routes::trash::empty_trash,
+        crate::routes::bundle::export_bundle,
+        crate::routes::bundle::import_bundle,
+
+        // API key endpoints
+        crate::routes::api_keys::get_all_api_keys,
+        crate::routes::api_keys::create_api_key,
+        crate::routes::api_keys::revoke_api_key,
 
         // Project endpoints
         crate::routes::projects::get_all_projects,
         crate::routes::projects::get_project_by_id,
+        crate::routes::projects::get_project_by_slug,
         crate::routes::projects::create_project,
         crate::routes::projects::update_project,
+        crate::routes::projects::patch_project,
         crate::routes::projects::delete_project,
+        crate::routes::projects::get_project_revisions,
+        crate::routes::projects::get_project_revision,
+        crate::routes::projects::diff_project_revisions,
+        crate::routes::projects::restore_project_revision,
 
         // Experience endpoints
         crate::routes::experiences::get_all_experiences,
         crate::routes::experiences::get_experience_by_id,
         crate::routes::experiences::create_experience,
         crate::routes::experiences::update_experience,
+        crate::routes::experiences::patch_experience,
         crate::routes::experiences::delete_experience,
+        crate::routes::experiences::get_experience_revisions,
+        crate::routes::experiences::get_experience_revision,
+        crate::routes::experiences::diff_experience_revisions,
+        crate::routes::experiences::restore_experience_revision,
 
         // Skills endpoint
         crate::routes::skills::get_all_skills,
         crate::routes::skills::create_skill,
         crate::routes::skills::update_skill,
+        crate::routes::skills::patch_skill,
         crate::routes::skills::delete_skill,
 
         // Posts endpoints
         crate::routes::posts::get_all_posts,
         crate::routes::posts::get_post_by_id,
+        crate::routes::posts::get_post_by_slug,
         crate::routes::posts::create_post,
         crate::routes::posts::update_post,
+        crate::routes::posts::patch_post,
         crate::routes::posts::delete_post,
+        crate::routes::posts::get_post_revisions,
+        crate::routes::posts::get_post_revision,
+        crate::routes::posts::diff_post_revisions,
+        crate::routes::posts::restore_post_revision,
 
         // GitHub stats endpoint
         crate::routes::github_stats::get_github_stats,
@@ -47,12 +94,42 @@ use utoipa_swagger_ui::SwaggerUi;
         // Profile endpoint
         crate::routes::profile::get_profile,
         crate::routes::profile::update_profile,
+        crate::routes::profile::patch_profile,
+        crate::routes::profile::get_profile_revisions,
+        crate::routes::profile::get_profile_revision,
+        crate::routes::profile::diff_profile_revisions,
+        crate::routes::profile::restore_profile_revision,
 
         // Contact endpoints
         crate::routes::contact::submit_contact_form,
         crate::routes::contact::get_all_messages,
         crate::routes::contact::get_message_by_id,
-        crate::routes::contact::delete_message
+        crate::routes::contact::delete_message,
+
+        // Feed endpoints
+        crate::routes::feeds::rss_feed,
+        crate::routes::feeds::atom_feed,
+        crate::routes::feeds::json_feed,
+        crate::routes::feeds::tag_rss_feed,
+        crate::routes::feeds::tag_atom_feed,
+        crate::routes::feeds::tag_json_feed,
+
+        // Tag endpoints
+        crate::routes::tags::get_all_tags,
+        crate::routes::tags::get_tag,
+        crate::routes::tags::get_all_technologies,
+        crate::routes::tags::get_technology,
+
+        // Technology registry endpoints
+        crate::routes::technologies::get_registry,
+        crate::routes::technologies::get_registered_technology,
+        crate::routes::technologies::create_technology,
+        crate::routes::technologies::update_technology,
+        crate::routes::technologies::merge_technology,
+        crate::routes::technologies::delete_technology,
+
+        // Search endpoint
+        crate::routes::search::search
     ),
     components(
         schemas(
@@ -60,7 +137,25 @@ use utoipa_swagger_ui::SwaggerUi;
             crate::models::project::Project,
             crate::models::experience::Experience,
             crate::models::skill::Skill,
+            crate::models::skill::SkillItem,
+            crate::models::skill::Proficiency,
+            crate::models::skill::EnrichedSkill,
+            crate::models::skill::EnrichedSkillItem,
+            crate::models::skill::ProjectReference,
+            crate::models::skill::ExperienceReference,
+            crate::models::revision::Revision,
+            crate::models::revision::RevisionSummary,
+            crate::models::revision::RevisionChange,
+            crate::models::revision::RevisionDiff,
             crate::models::post::Post,
+            crate::models::post::PostStatus,
+            crate::models::post::RenderedPost,
+            crate::models::post::TocEntry,
+            crate::models::tag::Tag,
+            crate::models::tag::TagDetail,
+            crate::models::tag::Technology,
+            crate::models::tag::TechnologyDetail,
+            crate::models::technology::TechnologyEntry,
             crate::models::github_stats::GithubStats,
             crate::models::github_stats::TopLanguage,
             crate::models::github_stats::RecentActivity,
@@ -70,6 +165,12 @@ use utoipa_swagger_ui::SwaggerUi;
             crate::models::profile::Language,
             crate::models::contact::ContactMessage,
             crate::models::contact::ContactResponse,
+            crate::models::search::SearchHit,
+            crate::models::bundle::Bundle,
+            crate::models::bundle::ImportMode,
+            crate::models::bundle::ImportAction,
+            crate::models::bundle::ImportChange,
+            crate::models::bundle::ImportReport,
 
             // Request bodies
             crate::routes::projects::CreateProjectRequest,
@@ -78,6 +179,9 @@ use utoipa_swagger_ui::SwaggerUi;
             crate::routes::experiences::UpdateExperienceRequest,
             crate::routes::skills::CreateSkillRequest,
             crate::routes::skills::UpdateSkillRequest,
+            crate::routes::technologies::CreateTechnologyRequest,
+            crate::routes::technologies::UpdateTechnologyRequest,
+            crate::routes::technologies::MergeTechnologyRequest,
             crate::routes::posts::CreatePostRequest,
             crate::routes::posts::UpdatePostRequest,
             crate::routes::github_stats::UpdateGithubStatsRequest,
@@ -88,6 +192,24 @@ use utoipa_swagger_ui::SwaggerUi;
             crate::auth::Claims,
             crate::routes::auth::LoginRequest,
             crate::routes::auth::LoginResponse,
+            crate::routes::auth::MfaChallengeResponse,
+            crate::routes::auth::TotpLoginRequest,
+            crate::routes::auth::RefreshRequest,
+            crate::routes::auth::LogoutRequest,
+            crate::routes::auth::ChangePasswordRequest,
+            crate::routes::totp::TotpEnrollResponse,
+            crate::routes::totp::TotpConfirmRequest,
+            crate::routes::totp::TotpConfirmResponse,
+            crate::routes::totp::TotpDisableRequest,
+            crate::routes::users::UserResponse,
+            crate::routes::users::CreateUserRequest,
+            crate::routes::users::UpdateUserRequest,
+            crate::models::api_key::ApiKey,
+            crate::models::login_attempt::LoginAttempt,
+            crate::models::trash::TrashItem,
+            crate::models::trash::PurgeResult,
+            crate::routes::api_keys::CreateApiKeyRequest,
+            crate::routes::api_keys::CreateApiKeyResponse,
 
             // Health
             crate::routes::health::HealthResponse,
@@ -100,13 +222,19 @@ use utoipa_swagger_ui::SwaggerUi;
         (name = "health", description = "Health check endpoints"),
         (name = "auth", description = "Authentication endpoints"),
         (name = "admin", description = "Admin dashboard endpoints"),
+        (name = "users", description = "User account management endpoints"),
+        (name = "api-keys", description = "API key management endpoints"),
         (name = "projects", description = "Project management endpoints"),
         (name = "experiences", description = "Professional experience endpoints"),
         (name = "skills", description = "Skills and technologies endpoints"),
         (name = "posts", description = "Blog post endpoints"),
+        (name = "feeds", description = "RSS, Atom and JSON feeds of blog posts"),
+        (name = "tags", description = "Post tag and technology index endpoints"),
+        (name = "technologies", description = "Technology registry management endpoints"),
         (name = "github-stats", description = "GitHub statistics endpoints"),
         (name = "profile", description = "User profile endpoints"),
-        (name = "contact", description = "Contact form endpoints")
+        (name = "contact", description = "Contact form endpoints"),
+        (name = "search", description = "Full-text search endpoints")
     ),
     info(
         title = "Retro Quewui Backend API",
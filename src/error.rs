@@ -1,4 +1,4 @@
-use actix_web::{error::Error as ActixError, http::StatusCode, HttpResponse, ResponseError};
+use actix_web::{error::Error as ActixError, http::{header, StatusCode}, HttpResponse, ResponseError};
 use serde::Serialize;
 use thiserror::Error;
 use utoipa::ToSchema;
@@ -25,6 +25,24 @@ pub enum AppError {
     
     #[error("Bad request: {0}")]
     BadRequestError(String),
+    
+    #[error("Conflict: {0}")]
+    ConflictError(String),
+    
+    #[error("Precondition failed: {0}")]
+    PreconditionFailedError(String),
+    
+    #[error("Precondition required: {0}")]
+    PreconditionRequiredError(String),
+    
+    /// Body in a format the route does not take; for PATCH routes, the
+    /// second field lists the patch formats they do, sent in Accept-Patch
+    #[error("Unsupported media type: {0}")]
+    UnsupportedMediaTypeError(String, Option<&'static str>),
+    
+    /// Rate limited; the second field is the number of seconds to wait
+    #[error("Too many requests: {0}")]
+    TooManyRequestsError(String, i64),
 }
 
 #[derive(Serialize, ToSchema)]
@@ -47,7 +65,17 @@ impl ResponseError for AppError {
             error_code: Some(status_code.as_str().to_string()),
         };
         
-        HttpResponse::build(status_code).json(error_response)
+        let mut response = HttpResponse::build(status_code);
+        
+        if let AppError::TooManyRequestsError(_, retry_after) = self {
+            response.insert_header((header::RETRY_AFTER, retry_after.max(&1).to_string()));
+        }
+        
+        if let AppError::UnsupportedMediaTypeError(_, Some(accept_patch)) = self {
+            response.insert_header(("Accept-Patch", *accept_patch));
+        }
+        
+        response.json(error_response)
     }
     
     fn status_code(&self) -> StatusCode {
@@ -59,6 +87,11 @@ impl ResponseError for AppError {
             AppError::UnauthorizedError(_) => StatusCode::UNAUTHORIZED,
             AppError::ForbiddenError(_) => StatusCode::FORBIDDEN,
             AppError::BadRequestError(_) => StatusCode::BAD_REQUEST,
+            AppError::ConflictError(_) => StatusCode::CONFLICT,
+            AppError::PreconditionFailedError(_) => StatusCode::PRECONDITION_FAILED,
+            AppError::PreconditionRequiredError(_) => StatusCode::PRECONDITION_REQUIRED,
+            AppError::UnsupportedMediaTypeError(..) => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            AppError::TooManyRequestsError(..) => StatusCode::TOO_MANY_REQUESTS,
         }
     }
 }
@@ -88,6 +121,26 @@ impl AppError {
     pub fn bad_request(message: impl Into<String>) -> Self {
         AppError::BadRequestError(message.into())
     }
+    
+    pub fn conflict(message: impl Into<String>) -> Self {
+        AppError::ConflictError(message.into())
+    }
+    
+    pub fn precondition_failed(message: impl Into<String>) -> Self {
+        AppError::PreconditionFailedError(message.into())
+    }
+    
+    pub fn precondition_required(message: impl Into<String>) -> Self {
+        AppError::PreconditionRequiredError(message.into())
+    }
+    
+    pub fn unsupported_media_type(message: impl Into<String>, accept_patch: Option<&'static str>) -> Self {
+        AppError::UnsupportedMediaTypeError(message.into(), accept_patch)
+    }
+    
+    pub fn too_many_requests(message: impl Into<String>, retry_after: i64) -> Self {
+        AppError::TooManyRequestsError(message.into(), retry_after)
+    }
 }
 
 // Type alias for Result with AppError
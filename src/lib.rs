@@ -1,9 +1,15 @@
 // Import and re-export the modules we need to expose
 pub mod auth;
+pub mod conditional;
 pub mod config;
 pub mod docs;
 pub mod error;
 pub mod models;
+pub mod pagination;
+pub mod patch;
 pub mod routes;
 pub mod services;
 pub mod validation;
+
+#[cfg(test)]
+mod tests;
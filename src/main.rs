@@ -6,6 +6,21 @@ use std::env;
 use retro_quewui_backend::services::github_service::GitHubService;
 use retro_quewui_backend::models::github_profile_repository::GitHubProfileRepository;
 
+// Run a migration command against DATABASE_URL without starting the server
+async fn migrate(args: &[String]) -> std::io::Result<()> {
+    let db_pool = retro_quewui_backend::config::database::connect_db().await
+        .map_err(|e| {
+            log::error!("Failed to connect to database: {}", e);
+            std::io::Error::other("Database connection failed")
+        })?;
+    
+    retro_quewui_backend::config::migrations::run_command(&db_pool, args).await
+        .map_err(|e| {
+            log::error!("Migration command failed: {:#}", e);
+            std::io::Error::other("Migration command failed")
+        })
+}
+
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
     // Load .env file
@@ -14,6 +29,18 @@ async fn main() -> std::io::Result<()> {
     // Initialize logger
     env_logger::init_from_env(env_logger::Env::new().default_filter_or("info"));
     
+    // `retro-quewui-backend migrate ...` manages the schema and exits
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.first().is_some_and(|arg| arg == "migrate") {
+        return migrate(&args[1..]).await;
+    }
+    
+    // Load JWT signing keys; refuses the default secret outside development
+    if let Err(e) = retro_quewui_backend::auth::AuthMiddleware::init_keys() {
+        log::error!("Failed to load JWT keys: {}", e);
+        return Err(std::io::Error::other("Invalid JWT key configuration"));
+    }
+    
     // Initialize database
     let db_pool = match retro_quewui_backend::config::database::init_db().await {
         Ok(pool) => {
@@ -22,10 +49,45 @@ async fn main() -> std::io::Result<()> {
         },
         Err(e) => {
             log::error!("Failed to connect to database: {}", e);
-            return Err(std::io::Error::new(std::io::ErrorKind::Other, "Database connection failed"));
+            return Err(std::io::Error::other("Database connection failed"));
         }
     };
     
+    // Create the initial admin account if no users exist yet
+    if let Err(e) = retro_quewui_backend::auth::ensure_admin_user(&db_pool).await {
+        log::error!("Failed to create initial admin user: {}", e);
+        return Err(std::io::Error::other("Admin user bootstrap failed"));
+    }
+    
+    // Give posts and projects created before slugs existed a permalink
+    match retro_quewui_backend::models::slug_repository::SlugRepository::new(db_pool.clone()).backfill().await {
+        Ok(0) => {}
+        Ok(assigned) => log::info!("Assigned slugs to {} existing posts and projects", assigned),
+        Err(e) => {
+            log::error!("Failed to assign slugs: {}", e);
+            return Err(std::io::Error::other("Slug backfill failed"));
+        }
+    }
+    
+    // Rewrite technology names stored before the registry knew them
+    match retro_quewui_backend::models::technology_repository::TechnologyRepository::new(db_pool.clone()).apply().await {
+        Ok(0) => {}
+        Ok(changed) => log::info!("Normalised technology names in {} rows", changed),
+        Err(e) => {
+            log::error!("Failed to normalise technology names: {}", e);
+            return Err(std::io::Error::other("Technology normalisation failed"));
+        }
+    }
+    
+    // Publish scheduled posts as they come due
+    actix_web::rt::spawn(retro_quewui_backend::services::post_scheduler::run(db_pool.clone()));
+    
+    // Purge deleted items kept past their retention period
+    actix_web::rt::spawn(retro_quewui_backend::services::trash_purger::run(db_pool.clone()));
+    
+    // Prune the login audit log and failure counters that have run out
+    actix_web::rt::spawn(retro_quewui_backend::services::login_pruner::run(db_pool.clone()));
+    
     // Get host and port from environment or use defaults
     let host = env::var("HOST").unwrap_or_else(|_| "0.0.0.0".to_string());
     let port = env::var("PORT").unwrap_or_else(|_| "8080".to_string())
@@ -56,8 +118,9 @@ async fn main() -> std::io::Result<()> {
         // Configure CORS
         let cors = Cors::default()
             .allowed_origin(&frontend_url)
-            .allowed_methods(vec!["GET", "POST", "PUT", "DELETE"])
-            .allowed_headers(vec![http::header::AUTHORIZATION, http::header::ACCEPT, http::header::CONTENT_TYPE])
+            .allowed_methods(vec!["GET", "POST", "PUT", "PATCH", "DELETE"])
+            .allowed_headers(vec![http::header::AUTHORIZATION, http::header::ACCEPT, http::header::CONTENT_TYPE, http::header::IF_MATCH, http::header::IF_NONE_MATCH, http::header::IF_MODIFIED_SINCE, http::header::HeaderName::from_static("x-api-key")])
+            .expose_headers(vec![http::header::LINK, http::header::ETAG, http::header::HeaderName::from_static("x-total-count")])
             .max_age(3600);
         
         // Create app data with database pool
@@ -73,15 +136,24 @@ async fn main() -> std::io::Result<()> {
             // API Routes
             .configure(retro_quewui_backend::routes::health::config)
             .configure(retro_quewui_backend::routes::auth::config)
+            .configure(retro_quewui_backend::routes::totp::config)
             .configure(retro_quewui_backend::routes::admin::config)
+            .configure(retro_quewui_backend::routes::trash::config)
+            .configure(retro_quewui_backend::routes::bundle::config)
+            .configure(retro_quewui_backend::routes::users::config)
+            .configure(retro_quewui_backend::routes::api_keys::config)
             .configure(retro_quewui_backend::routes::projects::config)
             .configure(retro_quewui_backend::routes::experiences::config)
             .configure(retro_quewui_backend::routes::skills::config)
             .configure(retro_quewui_backend::routes::posts::config)
+            .configure(retro_quewui_backend::routes::feeds::config)
+            .configure(retro_quewui_backend::routes::tags::config)
+            .configure(retro_quewui_backend::routes::technologies::config)
             .configure(retro_quewui_backend::routes::github_stats::config)
             .configure(retro_quewui_backend::routes::github_profile::configure)
             .configure(retro_quewui_backend::routes::contact::config)
             .configure(retro_quewui_backend::routes::profile::config)
+            .configure(retro_quewui_backend::routes::search::config)
     })
     .bind((host, port))?
     .run()
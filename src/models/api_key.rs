@@ -0,0 +1,63 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// A long-lived API key for automation, as stored in the `api_keys` table.
+///
+/// The secret part of the key is only returned once, at creation; afterwards
+/// only its SHA-256 hash and public prefix are known.
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+#[schema(example = json!({
+    "id": "8d0f7a8e-3c1b-4f0e-9a51-1f7c5d1e2b3a",
+    "name": "CI publisher",
+    "prefix": "rqk_3f9a1c2b",
+    "scopes": ["posts:write", "projects:write"],
+    "created_by": "3f1c9b1e-0d5a-4c57-9a0e-5b1f0f2b7c11",
+    "expires_at": 1798761600,
+    "last_used_at": null,
+    "revoked_at": null
+}))]
+pub struct ApiKey {
+    /// Unique identifier for the key
+    pub id: String,
+    /// Human-readable label
+    pub name: String,
+    /// Public beginning of the key, shown to identify it
+    pub prefix: String,
+    /// Hex encoded SHA-256 of the full key
+    #[serde(skip_serializing)]
+    pub key_hash: String,
+    /// Permissions granted to the key (e.g., "posts:write")
+    pub scopes: Vec<String>,
+    /// User the key acts on behalf of
+    pub created_by: String,
+    /// Expiration time (as UTC timestamp), null if the key never expires
+    pub expires_at: Option<i64>,
+    /// Last time the key authenticated a request (as UTC timestamp)
+    pub last_used_at: Option<i64>,
+    /// When the key was revoked (as UTC timestamp)
+    pub revoked_at: Option<i64>,
+}
+
+impl ApiKey {
+    pub fn new(
+        name: String,
+        prefix: String,
+        key_hash: String,
+        scopes: Vec<String>,
+        created_by: String,
+        expires_at: Option<i64>,
+    ) -> Self {
+        Self {
+            id: Uuid::new_v4().to_string(),
+            name,
+            prefix,
+            key_hash,
+            scopes,
+            created_by,
+            expires_at,
+            last_used_at: None,
+            revoked_at: None,
+        }
+    }
+}
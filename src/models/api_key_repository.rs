@@ -0,0 +1,124 @@
+use anyhow::Result;
+use sqlx::FromRow;
+use serde::{Serialize, Deserialize};
+
+use crate::config::database::DbPool;
+use crate::models::api_key::ApiKey;
+use crate::models::repository::{vec_to_json, json_to_vec, fetch_page, Filter, ListQuery, ListSpec, Page, SortDirection};
+
+// Keys are revoked rather than edited or deleted, so this does not implement
+// the generic Repository trait
+pub struct ApiKeyRepository {
+    pool: DbPool,
+}
+
+#[derive(Debug, FromRow, Serialize, Deserialize)]
+struct ApiKeyRow {
+    id: String,
+    name: String,
+    prefix: String,
+    key_hash: String,
+    scopes: String,
+    created_by: String,
+    expires_at: Option<i64>,
+    last_used_at: Option<i64>,
+    revoked_at: Option<i64>,
+}
+
+impl From<ApiKeyRow> for ApiKey {
+    fn from(row: ApiKeyRow) -> Self {
+        ApiKey {
+            id: row.id,
+            name: row.name,
+            prefix: row.prefix,
+            key_hash: row.key_hash,
+            scopes: json_to_vec(&row.scopes).unwrap_or_default(),
+            created_by: row.created_by,
+            expires_at: row.expires_at,
+            last_used_at: row.last_used_at,
+            revoked_at: row.revoked_at,
+        }
+    }
+}
+
+impl ApiKeyRepository {
+    pub const LIST: ListSpec = ListSpec {
+        table: "api_keys",
+        columns: "id, name, prefix, key_hash, scopes, created_by, expires_at, last_used_at, revoked_at",
+        sort_fields: &[("created_at", "created_at"), ("name", "name"), ("last_used_at", "last_used_at")],
+        default_sort: ("created_at", SortDirection::Desc),
+        filters: &[("created_by", Filter::Equals("created_by"))],
+    };
+
+    pub fn new(pool: DbPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn find_page(&self, query: &ListQuery) -> Result<Page<ApiKey>> {
+        let page = fetch_page::<ApiKeyRow>(&self.pool, query).await?;
+        Ok(page.map(ApiKey::from))
+    }
+
+    pub async fn find_by_prefix(&self, prefix: &str) -> Result<Option<ApiKey>> {
+        let query = "
+            SELECT id, name, prefix, key_hash, scopes, created_by, expires_at, last_used_at, revoked_at
+            FROM api_keys
+            WHERE prefix = $1
+        ";
+
+        let row = sqlx::query_as::<_, ApiKeyRow>(query)
+            .bind(prefix)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(row.map(ApiKey::from))
+    }
+
+    pub async fn create(&self, item: ApiKey) -> Result<ApiKey> {
+        let scopes = vec_to_json(&item.scopes)?;
+
+        let query = "
+            INSERT INTO api_keys (id, name, prefix, key_hash, scopes, created_by, expires_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+        ";
+
+        sqlx::query(query)
+            .bind(&item.id)
+            .bind(&item.name)
+            .bind(&item.prefix)
+            .bind(&item.key_hash)
+            .bind(scopes)
+            .bind(&item.created_by)
+            .bind(item.expires_at)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(item)
+    }
+
+    pub async fn touch_last_used(&self, id: &str, now: i64) -> Result<()> {
+        sqlx::query("UPDATE api_keys SET last_used_at = $1 WHERE id = $2")
+            .bind(now)
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn revoke(&self, id: &str, now: i64) -> Result<bool> {
+        let query = "
+            UPDATE api_keys
+            SET revoked_at = $1
+            WHERE id = $2 AND revoked_at IS NULL
+        ";
+
+        let result = sqlx::query(query)
+            .bind(now)
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
@@ -0,0 +1,98 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::models::experience::Experience;
+use crate::models::github_stats::GithubStats;
+use crate::models::post::Post;
+use crate::models::profile::Profile;
+use crate::models::project::Project;
+use crate::models::revision::RevisionChange;
+use crate::models::skill::Skill;
+
+/// Version of the bundle format written by exports; imports only take
+/// bundles of this version
+pub const BUNDLE_FORMAT_VERSION: u32 = 1;
+
+/// The whole portfolio in one document, to copy content from one site to
+/// another. Deleted items are left out.
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+#[serde(deny_unknown_fields)]
+#[schema(example = json!({
+    "format_version": 1,
+    "exported_at": 1792137600,
+    "profile": { "bio": ["Software developer based in Brazil."], "social_links": [], "education": [], "languages": [] },
+    "projects": [],
+    "experiences": [],
+    "skills": [{ "category": "Languages", "items": [{ "name": "Rust", "proficiency": "advanced", "years": 4, "order": 0 }] }],
+    "posts": [],
+    "github_stats": null
+}))]
+pub struct Bundle {
+    pub format_version: u32,
+    /// When the bundle was exported (as UTC timestamp)
+    #[serde(default)]
+    pub exported_at: Option<i64>,
+    pub profile: Option<Profile>,
+    pub projects: Vec<Project>,
+    pub experiences: Vec<Experience>,
+    /// Skill categories, matched by name on import
+    pub skills: Vec<Skill>,
+    pub posts: Vec<Post>,
+    pub github_stats: Option<GithubStats>,
+}
+
+/// How an import treats the items already there
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportMode {
+    /// Items missing from the bundle are deleted, so that the portfolio
+    /// becomes the bundle
+    Replace,
+    /// Items missing from the bundle are kept
+    Merge,
+}
+
+/// What an import does to one item
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportAction {
+    Create,
+    Update,
+    Delete,
+}
+
+/// One item created, changed or deleted by an import
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+#[schema(example = json!({
+    "kind": "projects",
+    "id": "8b0a6b55-3c52-4d47-9a8e-0f1f3c2b5a10",
+    "title": "Retro Portfolio",
+    "action": "update",
+    "changes": [{ "path": "/year", "from": 2024, "to": 2025 }]
+}))]
+pub struct ImportChange {
+    /// profile, projects, experiences, skills, posts or github_stats
+    pub kind: String,
+    /// Id of the item; the category of a skill category
+    pub id: String,
+    /// Title of the item: position and company of an experience, username of
+    /// the GitHub stats
+    pub title: String,
+    pub action: ImportAction,
+    /// Values changed by an update
+    pub changes: Vec<RevisionChange>,
+}
+
+/// The changes made by an import, or that it would make on a dry run
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct ImportReport {
+    pub mode: ImportMode,
+    /// Whether the changes were only computed and not saved
+    pub dry_run: bool,
+    pub created: usize,
+    pub updated: usize,
+    pub deleted: usize,
+    /// Items of the bundle that are already as they are there
+    pub unchanged: usize,
+    pub changes: Vec<ImportChange>,
+}
@@ -0,0 +1,539 @@
+use std::collections::{HashMap, HashSet};
+
+use anyhow::Result;
+use serde::Serialize;
+use sqlx::AnyConnection;
+
+use crate::auth::User;
+use crate::config::database::DbPool;
+use crate::error::{AppError, AppResult};
+use crate::models::bundle::{Bundle, ImportAction, ImportChange, ImportMode, ImportReport, BUNDLE_FORMAT_VERSION};
+use crate::models::experience::Experience;
+use crate::models::experience_repository::ExperienceRepository;
+use crate::models::github_stats_repository::GithubStatsRepository;
+use crate::models::post::{resolve_status, Post};
+use crate::models::post_repository::PostRepository;
+use crate::models::profile_repository::ProfileRepository;
+use crate::models::project::Project;
+use crate::models::project_repository::ProjectRepository;
+use crate::models::revision::{diff_snapshots, RevisionChange};
+use crate::models::revision_repository::RevisionRepository;
+use crate::models::skill::Skill;
+use crate::models::skill_repository::SkillRepository;
+use crate::models::slug::{is_valid_slug, MAX_SLUG_LENGTH};
+use crate::models::slug_repository::SlugRepository;
+use crate::models::technology::{normalize_languages, normalize_skill_items, normalize_technologies};
+use crate::models::technology_repository::canonical_names;
+use crate::models::trash_repository::TrashRepository;
+
+// Export and import of the whole portfolio. Items are read and written
+// through the repository of their kind, on one connection: an import runs in
+// a single transaction, rolled back on a dry run or at the first error, so
+// that a bundle is either imported whole or not at all.
+pub struct BundleRepository {
+    pool: DbPool,
+}
+
+type Canonical = HashMap<String, String>;
+
+fn db_error(e: impl std::fmt::Display) -> AppError {
+    AppError::internal_error(format!("Failed to import the bundle: {}", e))
+}
+
+fn diff<T: Serialize>(before: &T, after: &T) -> Result<Vec<RevisionChange>> {
+    Ok(diff_snapshots(&serde_json::to_value(before)?, &serde_json::to_value(after)?))
+}
+
+// Posts, projects and experiences: kept by id, and moved to the trash when
+// deleted
+#[allow(async_fn_in_trait)]
+trait TrashableItem: Serialize + Sized {
+    /// Key of the kind in the bundle; also its table
+    const KIND: &'static str;
+
+    fn id(&self) -> &str;
+    fn title(&self) -> String;
+    /// Slug, for kinds that have one
+    fn slug(&self) -> Option<&str> {
+        None
+    }
+    fn set_slug(&mut self, _slug: String) {}
+    /// Put the item as the repository saves it, so that it compares equal to
+    /// the saved copy when nothing changed
+    fn normalize(&mut self, _canonical: &Canonical) {}
+    /// Check the item as a write through its routes would, putting its fields
+    /// as those save them
+    fn check(&mut self, _now: i64) -> AppResult<()> {
+        Ok(())
+    }
+
+    async fn fetch_all(conn: &mut AnyConnection) -> Result<Vec<Self>>;
+    async fn insert(conn: &mut AnyConnection, item: Self) -> Result<Self>;
+    async fn write(conn: &mut AnyConnection, id: &str, item: Self) -> Result<Self>;
+    async fn trash(conn: &mut AnyConnection, id: &str) -> Result<bool>;
+}
+
+impl TrashableItem for Project {
+    const KIND: &'static str = "projects";
+
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn title(&self) -> String {
+        self.title.clone()
+    }
+
+    fn slug(&self) -> Option<&str> {
+        Some(&self.slug)
+    }
+
+    fn set_slug(&mut self, slug: String) {
+        self.slug = slug;
+    }
+
+    fn normalize(&mut self, canonical: &Canonical) {
+        self.technologies = normalize_technologies(&self.technologies, canonical);
+    }
+
+    async fn fetch_all(conn: &mut AnyConnection) -> Result<Vec<Self>> {
+        ProjectRepository::fetch_all(conn).await
+    }
+
+    async fn insert(conn: &mut AnyConnection, item: Self) -> Result<Self> {
+        ProjectRepository::insert(conn, item).await
+    }
+
+    async fn write(conn: &mut AnyConnection, id: &str, item: Self) -> Result<Self> {
+        Ok(ProjectRepository::write(conn, id, item, None).await?.0)
+    }
+
+    async fn trash(conn: &mut AnyConnection, id: &str) -> Result<bool> {
+        ProjectRepository::trash(conn, id, None).await
+    }
+}
+
+impl TrashableItem for Experience {
+    const KIND: &'static str = "experiences";
+
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn title(&self) -> String {
+        format!("{} at {}", self.position, self.company)
+    }
+
+    fn normalize(&mut self, canonical: &Canonical) {
+        self.technologies = normalize_technologies(&self.technologies, canonical);
+    }
+
+    async fn fetch_all(conn: &mut AnyConnection) -> Result<Vec<Self>> {
+        ExperienceRepository::fetch_all(conn).await
+    }
+
+    async fn insert(conn: &mut AnyConnection, item: Self) -> Result<Self> {
+        ExperienceRepository::insert(conn, item).await
+    }
+
+    async fn write(conn: &mut AnyConnection, id: &str, item: Self) -> Result<Self> {
+        Ok(ExperienceRepository::write(conn, id, item, None).await?.0)
+    }
+
+    async fn trash(conn: &mut AnyConnection, id: &str) -> Result<bool> {
+        ExperienceRepository::trash(conn, id, None).await
+    }
+}
+
+impl TrashableItem for Post {
+    const KIND: &'static str = "posts";
+
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn title(&self) -> String {
+        self.title.clone()
+    }
+
+    fn slug(&self) -> Option<&str> {
+        Some(&self.slug)
+    }
+
+    fn set_slug(&mut self, slug: String) {
+        self.slug = slug;
+    }
+
+    fn check(&mut self, now: i64) -> AppResult<()> {
+        (self.status, self.publish_at) = resolve_status(self.status, self.publish_at, now).map_err(|e| match e {
+            AppError::BadRequestError(message) => AppError::bad_request(format!("posts {}: {}", self.id, message)),
+            e => e,
+        })?;
+        Ok(())
+    }
+
+    async fn fetch_all(conn: &mut AnyConnection) -> Result<Vec<Self>> {
+        PostRepository::fetch_all(conn).await
+    }
+
+    async fn insert(conn: &mut AnyConnection, item: Self) -> Result<Self> {
+        PostRepository::insert(conn, item).await
+    }
+
+    async fn write(conn: &mut AnyConnection, id: &str, item: Self) -> Result<Self> {
+        Ok(PostRepository::write(conn, id, item, None).await?.0)
+    }
+
+    async fn trash(conn: &mut AnyConnection, id: &str) -> Result<bool> {
+        PostRepository::trash(conn, id, None).await
+    }
+}
+
+// Reject bundles that cannot be imported whole, before touching the database,
+// and put their items as the routes would save them
+fn check_bundle(bundle: &mut Bundle) -> AppResult<()> {
+    if bundle.format_version != BUNDLE_FORMAT_VERSION {
+        return Err(AppError::bad_request(format!(
+            "Unsupported bundle format version {}; this server reads version {}",
+            bundle.format_version, BUNDLE_FORMAT_VERSION
+        )));
+    }
+
+    let now = chrono::Utc::now().timestamp();
+    check_items(&mut bundle.projects, now)?;
+    check_items(&mut bundle.experiences, now)?;
+    check_items(&mut bundle.posts, now)?;
+
+    let mut categories = HashSet::new();
+    for skill in &bundle.skills {
+        if !categories.insert(skill.category.as_str()) {
+            return Err(AppError::bad_request(format!("Skill category '{}' appears twice", skill.category)));
+        }
+    }
+
+    Ok(())
+}
+
+fn check_items<T: TrashableItem>(items: &mut [T], now: i64) -> AppResult<()> {
+    let mut ids = HashSet::new();
+    for item in items.iter() {
+        if item.id().trim().is_empty() {
+            return Err(AppError::bad_request(format!("An item of {} has no id", T::KIND)));
+        }
+        if !ids.insert(item.id()) {
+            return Err(AppError::bad_request(format!("{} {} appears twice", T::KIND, item.id())));
+        }
+        if let Some(slug) = item.slug().filter(|slug| !slug.is_empty() && !is_valid_slug(slug)) {
+            return Err(AppError::bad_request(format!(
+                "Invalid slug '{}' for {} {}: use lowercase letters, digits and single dashes, at most {} characters",
+                slug, T::KIND, item.id(), MAX_SLUG_LENGTH
+            )));
+        }
+    }
+    for item in items {
+        item.check(now)?;
+    }
+    Ok(())
+}
+
+impl ImportReport {
+    fn record(&mut self, kind: &str, id: &str, title: String, action: ImportAction, changes: Vec<RevisionChange>) {
+        match action {
+            ImportAction::Create => self.created += 1,
+            ImportAction::Update => self.updated += 1,
+            ImportAction::Delete => self.deleted += 1,
+        }
+
+        self.changes.push(ImportChange {
+            kind: kind.to_string(),
+            id: id.to_string(),
+            title,
+            action,
+            changes,
+        });
+    }
+}
+
+// Slugs the bundle gives its items, explicitly or by keeping the current
+// one, by slug. They are checked against the rows outside the bundle only, so
+// that items of the bundle may swap slugs, and taken from the items of the
+// bundle that give them up, which are written later.
+async fn claim_slugs<T: TrashableItem>(
+    conn: &mut AnyConnection,
+    items: &[T],
+    current: &HashMap<String, T>,
+) -> AppResult<HashMap<String, String>> {
+    let ids: HashSet<&str> = items.iter().map(|item| item.id()).collect();
+    let mut claimed = HashMap::new();
+    for item in items {
+        let slug = match item.slug() {
+            Some("") => current.get(item.id()).and_then(|before| before.slug()).unwrap_or_default(),
+            Some(slug) => slug,
+            None => continue,
+        };
+        if slug.is_empty() {
+            continue;
+        }
+        if claimed.insert(slug.to_string(), item.id().to_string()).is_some() {
+            return Err(AppError::conflict(format!("Slug '{}' is given to more than one item of {}", slug, T::KIND)));
+        }
+    }
+
+    for (slug, id) in &claimed {
+        let holders = SlugRepository::holders(conn, T::KIND, slug).await.map_err(db_error)?;
+        if holders.iter().any(|holder| holder != id && !ids.contains(holder.as_str())) {
+            return Err(AppError::conflict(format!("Slug '{}' of {} {} is already in use", slug, T::KIND, id)));
+        }
+        SlugRepository::release(conn, T::KIND, slug, id).await.map_err(db_error)?;
+    }
+
+    Ok(claimed)
+}
+
+async fn import_items<T: TrashableItem>(
+    conn: &mut AnyConnection,
+    mut items: Vec<T>,
+    canonical: &Canonical,
+    author: &User,
+    report: &mut ImportReport,
+) -> AppResult<()> {
+    let current = T::fetch_all(conn).await.map_err(db_error)?;
+    let trashed = TrashRepository::trashed_ids(conn, T::KIND).await.map_err(db_error)?;
+
+    if report.mode == ImportMode::Replace {
+        let kept: HashSet<&str> = items.iter().map(|item| item.id()).collect();
+        for item in current.iter().filter(|item| !kept.contains(item.id())) {
+            T::trash(conn, item.id()).await.map_err(db_error)?;
+            report.record(T::KIND, item.id(), item.title(), ImportAction::Delete, Vec::new());
+        }
+    }
+
+    let current: HashMap<String, T> = current.into_iter().map(|item| (item.id().to_string(), item)).collect();
+    let claimed = claim_slugs(conn, &items, &current).await?;
+    // Items getting a slug from their title come last, so that it cannot be
+    // one the bundle gives to an item not saved yet
+    items.sort_by_key(|item| {
+        item.slug() == Some("") && current.get(item.id()).and_then(|before| before.slug()).unwrap_or_default().is_empty()
+    });
+
+    for mut item in items {
+        item.normalize(canonical);
+        let id = item.id().to_string();
+        let before = current.get(&id);
+
+        // Without a slug, an item keeps its current one or gets one from its title
+        let old_slug = before.and_then(|before| before.slug()).unwrap_or_default().to_string();
+        if let Some(slug) = item.slug().map(str::to_string) {
+            let slug = if !slug.is_empty() {
+                slug
+            } else if !old_slug.is_empty() {
+                old_slug.clone()
+            } else {
+                SlugRepository::free_slug(conn, T::KIND, &item.title(), &id).await.map_err(db_error)?
+            };
+            item.set_slug(slug);
+        }
+
+        let saved = match before {
+            Some(before) => {
+                let changes = diff(before, &item).map_err(db_error)?;
+                if changes.is_empty() {
+                    report.unchanged += 1;
+                    continue;
+                }
+
+                let saved = T::write(conn, &id, item).await.map_err(db_error)?;
+                report.record(T::KIND, &id, saved.title(), ImportAction::Update, changes);
+                saved
+            }
+            None => {
+                // An item deleted here comes back from the trash
+                let saved = if trashed.contains(&id) {
+                    TrashRepository::untrash(conn, T::KIND, &id).await.map_err(db_error)?;
+                    T::write(conn, &id, item).await.map_err(db_error)?
+                } else {
+                    T::insert(conn, item).await.map_err(db_error)?
+                };
+                report.record(T::KIND, &id, saved.title(), ImportAction::Create, Vec::new());
+                saved
+            }
+        };
+
+        // A slug given up to another item of the bundle is not kept as an alias
+        if let Some(slug) = saved.slug() {
+            let old_slug = if claimed.get(&old_slug).is_some_and(|owner| *owner != id) { "" } else { &old_slug };
+            SlugRepository::write_change(conn, T::KIND, &id, old_slug, slug).await.map_err(db_error)?;
+        }
+        RevisionRepository::append(conn, T::KIND, &id, before, &saved, author, None).await.map_err(db_error)?;
+    }
+
+    Ok(())
+}
+
+async fn import_skills(
+    conn: &mut AnyConnection,
+    skills: Vec<Skill>,
+    canonical: &Canonical,
+    report: &mut ImportReport,
+) -> AppResult<()> {
+    let current = SkillRepository::fetch_all(conn).await.map_err(db_error)?;
+
+    if report.mode == ImportMode::Replace {
+        let kept: HashSet<&str> = skills.iter().map(|skill| skill.category.as_str()).collect();
+        for (id, skill) in current.iter().filter(|(_, skill)| !kept.contains(skill.category.as_str())) {
+            SkillRepository::trash(conn, id, None).await.map_err(db_error)?;
+            report.record("skills", &skill.category, skill.category.clone(), ImportAction::Delete, Vec::new());
+        }
+    }
+
+    for mut skill in skills {
+        skill.items = normalize_skill_items(&skill.items, canonical);
+        skill.sort_items();
+        let category = skill.category.clone();
+
+        match current.iter().find(|(_, before)| before.category == category) {
+            Some((id, before)) => {
+                let changes = diff(before, &skill).map_err(db_error)?;
+                if changes.is_empty() {
+                    report.unchanged += 1;
+                    continue;
+                }
+
+                SkillRepository::write(conn, id, skill, None).await.map_err(db_error)?;
+                report.record("skills", &category, category.clone(), ImportAction::Update, changes);
+            }
+            None => {
+                SkillRepository::insert(conn, skill).await.map_err(db_error)?;
+                report.record("skills", &category, category.clone(), ImportAction::Create, Vec::new());
+            }
+        }
+    }
+
+    Ok(())
+}
+
+async fn import_profile(conn: &mut AnyConnection, bundle: &mut Bundle, author: &User, report: &mut ImportReport) -> AppResult<()> {
+    let current = ProfileRepository::fetch_first(conn).await.map_err(db_error)?;
+
+    match (bundle.profile.take(), current) {
+        (Some(profile), Some((id, before))) => {
+            let changes = diff(&before, &profile).map_err(db_error)?;
+            if changes.is_empty() {
+                report.unchanged += 1;
+                return Ok(());
+            }
+
+            let (saved, _) = ProfileRepository::write(conn, &id, profile, None).await.map_err(db_error)?;
+            RevisionRepository::append(conn, "profiles", &id, Some(&before), &saved, author, None).await.map_err(db_error)?;
+            report.record("profile", &id, "Profile".to_string(), ImportAction::Update, changes);
+        }
+        (Some(profile), None) => {
+            let saved = ProfileRepository::insert(conn, profile).await.map_err(db_error)?;
+            let (id, _) = ProfileRepository::fetch_first(conn).await
+                .map_err(db_error)?
+                .ok_or_else(|| db_error("profile missing after create"))?;
+            RevisionRepository::append(conn, "profiles", &id, None, &saved, author, None).await.map_err(db_error)?;
+            report.record("profile", &id, "Profile".to_string(), ImportAction::Create, Vec::new());
+        }
+        (None, Some((id, _))) if report.mode == ImportMode::Replace => {
+            ProfileRepository::remove(conn, &id, None).await.map_err(db_error)?;
+            report.record("profile", &id, "Profile".to_string(), ImportAction::Delete, Vec::new());
+        }
+        (None, _) => {}
+    }
+
+    Ok(())
+}
+
+async fn import_github_stats(conn: &mut AnyConnection, bundle: &mut Bundle, canonical: &Canonical, report: &mut ImportReport) -> AppResult<()> {
+    let current = GithubStatsRepository::fetch_first(conn).await.map_err(db_error)?;
+
+    match (bundle.github_stats.take(), current) {
+        (Some(mut stats), Some((id, before))) => {
+            stats.top_languages = normalize_languages(&stats.top_languages, canonical);
+            let changes = diff(&before, &stats).map_err(db_error)?;
+            if changes.is_empty() {
+                report.unchanged += 1;
+                return Ok(());
+            }
+
+            let saved = GithubStatsRepository::write(conn, &id, stats).await.map_err(db_error)?;
+            report.record("github_stats", &id, saved.username, ImportAction::Update, changes);
+        }
+        (Some(stats), None) => {
+            let saved = GithubStatsRepository::insert(conn, stats).await.map_err(db_error)?;
+            let (id, _) = GithubStatsRepository::fetch_first(conn).await
+                .map_err(db_error)?
+                .ok_or_else(|| db_error("GitHub stats missing after create"))?;
+            report.record("github_stats", &id, saved.username, ImportAction::Create, Vec::new());
+        }
+        (None, Some((id, before))) if report.mode == ImportMode::Replace => {
+            GithubStatsRepository::remove(conn, &id).await.map_err(db_error)?;
+            report.record("github_stats", &id, before.username, ImportAction::Delete, Vec::new());
+        }
+        (None, _) => {}
+    }
+
+    Ok(())
+}
+
+impl BundleRepository {
+    pub fn new(pool: DbPool) -> Self {
+        Self { pool }
+    }
+
+    /// Everything not in the trash, read in one transaction so that the
+    /// bundle is consistent
+    pub async fn export(&self) -> Result<Bundle> {
+        let mut tx = self.pool.begin().await?;
+
+        let bundle = Bundle {
+            format_version: BUNDLE_FORMAT_VERSION,
+            exported_at: Some(chrono::Utc::now().timestamp()),
+            profile: ProfileRepository::fetch_first(&mut tx).await?.map(|(_, profile)| profile),
+            projects: ProjectRepository::fetch_all(&mut tx).await?,
+            experiences: ExperienceRepository::fetch_all(&mut tx).await?,
+            skills: SkillRepository::fetch_all(&mut tx).await?.into_iter().map(|(_, skill)| skill).collect(),
+            posts: PostRepository::fetch_all(&mut tx).await?,
+            github_stats: GithubStatsRepository::fetch_first(&mut tx).await?.map(|(_, stats)| stats),
+        };
+
+        tx.commit().await?;
+        Ok(bundle)
+    }
+
+    /// Load a bundle, recording each write in the revision history with the
+    /// user as author. On a dry run the changes are computed the same way,
+    /// then rolled back.
+    pub async fn import(&self, mut bundle: Bundle, mode: ImportMode, dry_run: bool, author: &User) -> AppResult<ImportReport> {
+        check_bundle(&mut bundle)?;
+
+        let mut report = ImportReport {
+            mode,
+            dry_run,
+            created: 0,
+            updated: 0,
+            deleted: 0,
+            unchanged: 0,
+            changes: Vec::new(),
+        };
+
+        let mut tx = self.pool.begin().await.map_err(db_error)?;
+        let canonical = canonical_names(&mut tx).await.map_err(db_error)?;
+
+        import_profile(&mut tx, &mut bundle, author, &mut report).await?;
+        import_items(&mut tx, std::mem::take(&mut bundle.projects), &canonical, author, &mut report).await?;
+        import_items(&mut tx, std::mem::take(&mut bundle.experiences), &canonical, author, &mut report).await?;
+        import_skills(&mut tx, std::mem::take(&mut bundle.skills), &canonical, &mut report).await?;
+        import_items(&mut tx, std::mem::take(&mut bundle.posts), &canonical, author, &mut report).await?;
+        import_github_stats(&mut tx, &mut bundle, &canonical, &mut report).await?;
+
+        if dry_run {
+            tx.rollback().await.map_err(db_error)?;
+        } else {
+            tx.commit().await.map_err(db_error)?;
+        }
+
+        Ok(report)
+    }
+}
@@ -2,10 +2,12 @@
 use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
 use uuid::Uuid;
-use chrono::{DateTime, Utc};
-use sqlx::{Pool, Sqlite};
+use chrono::{DateTime, NaiveDateTime, SecondsFormat, Utc};
+use sqlx::FromRow;
 
+use crate::config::database::DbPool;
 use crate::error::{AppResult, AppError};
+use crate::models::repository::{fetch_page, Filter, ListQuery, ListSpec, Page, SortDirection};
 
 #[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
 pub struct ContactMessage {
@@ -58,79 +60,88 @@ impl ContactResponse {
 }
 
 pub struct ContactRepository {
-    db: Pool<Sqlite>,
+    db: DbPool,
+}
+
+#[derive(Debug, FromRow)]
+struct ContactRow {
+    id: String,
+    name: String,
+    email: String,
+    message: String,
+    created_at: String,
+}
+
+// Read as text, since the SQLite column is declared TIMESTAMP. Written as
+// RFC 3339; rows inserted with the SQLite column default hold "YYYY-MM-DD HH:MM:SS"
+fn parse_created_at(value: &str) -> DateTime<Utc> {
+    DateTime::parse_from_rfc3339(value)
+        .map(|at| at.with_timezone(&Utc))
+        .or_else(|_| NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S%.f").map(|at| at.and_utc()))
+        .unwrap_or_default()
+}
+
+impl From<ContactRow> for ContactMessage {
+    fn from(row: ContactRow) -> Self {
+        Self {
+            id: row.id,
+            name: row.name,
+            email: row.email,
+            message: row.message,
+            created_at: parse_created_at(&row.created_at),
+        }
+    }
 }
 
 impl ContactRepository {
-    pub fn new(db: Pool<Sqlite>) -> Self {
+    pub const LIST: ListSpec = ListSpec {
+        table: "(SELECT * FROM contacts WHERE deleted_at IS NULL) AS contacts",
+        columns: "id, name, email, message, CAST(created_at AS TEXT) AS created_at",
+        sort_fields: &[("created_at", "created_at"), ("name", "name"), ("email", "email")],
+        default_sort: ("created_at", SortDirection::Desc),
+        filters: &[("email", Filter::Equals("email"))],
+    };
+
+    pub fn new(db: DbPool) -> Self {
         Self { db }
     }
 
     pub async fn create(&self, contact: ContactMessage) -> AppResult<ContactMessage> {
-        let result = sqlx::query!(
+        let result = sqlx::query_as::<_, ContactRow>(
             r#"
             INSERT INTO contacts (id, name, email, message, created_at)
             VALUES ($1, $2, $3, $4, $5)
-            RETURNING id, name, email, message, created_at
+            RETURNING id, name, email, message, CAST(created_at AS TEXT) AS created_at
             "#,
-            contact.id,
-            contact.name,
-            contact.email,
-            contact.message,
-            contact.created_at,
         )
+        .bind(&contact.id)
+        .bind(&contact.name)
+        .bind(&contact.email)
+        .bind(&contact.message)
+        .bind(contact.created_at.to_rfc3339_opts(SecondsFormat::Micros, true))
         .fetch_one(&self.db)
         .await
-        .map_err(|e| AppError::DatabaseError(e))?;
-
-        Ok(ContactMessage {
-            id: result.id.expect("ID should not be null"),
-            name: result.name,
-            email: result.email,
-            message: result.message,
-            created_at: DateTime::from_naive_utc_and_offset(
-                result.created_at.expect("Created at should not be null"), 
-                Utc
-            ),
-        })
+        .map_err(AppError::DatabaseError)?;
+
+        Ok(result.into())
     }
 
-    pub async fn get_all(&self) -> AppResult<Vec<ContactMessage>> {
-        let results = sqlx::query!(
-            r#"
-            SELECT id, name, email, message, created_at
-            FROM contacts
-            ORDER BY created_at DESC
-            "#
-        )
-        .fetch_all(&self.db)
-        .await
-        .map_err(|e| AppError::DatabaseError(e))?;
-
-        Ok(results
-            .into_iter()
-            .map(|row| ContactMessage {
-                id: row.id.expect("ID should not be null"),
-                name: row.name,
-                email: row.email,
-                message: row.message,
-                created_at: DateTime::from_naive_utc_and_offset(
-                    row.created_at.expect("Created at should not be null"), 
-                    Utc
-                ),
-            })
-            .collect())
+    pub async fn get_page(&self, query: &ListQuery) -> AppResult<Page<ContactMessage>> {
+        let page = fetch_page::<ContactRow>(&self.db, query).await
+            .map_err(|e| AppError::internal_error(format!("Failed to fetch messages: {}", e)))?;
+
+        Ok(page.map(ContactMessage::from))
     }
 
     pub async fn get_by_id(&self, id: &str) -> AppResult<ContactMessage> {
-        let result = sqlx::query!(
+        let result = sqlx::query_as::<_, ContactRow>(
             r#"
-            SELECT id, name, email, message, created_at
+            SELECT id, name, email, message, CAST(created_at AS TEXT) AS created_at
             FROM contacts
-            WHERE id = $1
+            WHERE id = $1 AND deleted_at IS NULL
             "#,
-            id
         )
+        .bind(id)
         .fetch_one(&self.db)
         .await
         .map_err(|e| match e {
@@ -138,34 +149,26 @@ impl ContactRepository {
             _ => AppError::DatabaseError(e),
         })?;
 
-        Ok(ContactMessage {
-            id: result.id.expect("ID should not be null"),
-            name: result.name,
-            email: result.email,
-            message: result.message,
-            created_at: DateTime::from_naive_utc_and_offset(
-                result.created_at.expect("Created at should not be null"), 
-                Utc
-            ),
-        })
+        Ok(result.into())
     }
     
     pub async fn delete(&self, id: &str) -> AppResult<()> {
-        let result = sqlx::query!(
+        let result = sqlx::query(
             r#"
-            DELETE FROM contacts
-            WHERE id = $1
-            RETURNING id
+            UPDATE contacts
+            SET deleted_at = $1
+            WHERE id = $2 AND deleted_at IS NULL
             "#,
-            id
         )
-        .fetch_optional(&self.db)
+        .bind(Utc::now().timestamp())
+        .bind(id)
+        .execute(&self.db)
         .await
-        .map_err(|e| AppError::DatabaseError(e))?;
+        .map_err(AppError::DatabaseError)?;
         
-        match result {
-            Some(_) => Ok(()),
-            None => Err(AppError::NotFoundError(format!("Contact not found with id: {}", id))),
+        match result.rows_affected() {
+            0 => Err(AppError::NotFoundError(format!("Contact not found with id: {}", id))),
+            _ => Ok(()),
         }
     }
 }
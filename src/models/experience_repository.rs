@@ -1,12 +1,15 @@
 use anyhow::Result;
-use sqlx::{Pool, Sqlite, FromRow};
+use sqlx::{AnyConnection, FromRow};
 use serde::{Serialize, Deserialize};
 
+use crate::config::database::DbPool;
 use crate::models::experience::Experience;
-use crate::models::repository::{Repository, vec_to_json, json_to_vec};
+use crate::models::repository::{Repository, Versioned, vec_to_json, json_to_vec, fetch_page, Filter, ListQuery, ListSpec, Page, SortDirection};
+use crate::models::technology::normalize_technologies;
+use crate::models::technology_repository::canonical_names;
 
 pub struct ExperienceRepository {
-    pool: Pool<Sqlite>,
+    pool: DbPool,
 }
 
 #[derive(Debug, FromRow, Serialize, Deserialize)]
@@ -21,86 +24,127 @@ struct ExperienceRow {
     highlights: String,
 }
 
+impl From<ExperienceRow> for Experience {
+    fn from(row: ExperienceRow) -> Self {
+        let technologies: Vec<String> = json_to_vec(&row.technologies).unwrap_or_default();
+        let highlights: Vec<String> = json_to_vec(&row.highlights).unwrap_or_default();
+
+        Experience {
+            id: row.id,
+            company: row.company,
+            position: row.position,
+            start_date: row.start_date,
+            end_date: row.end_date,
+            description: row.description,
+            technologies,
+            highlights,
+        }
+    }
+}
+
 impl Repository<Experience> for ExperienceRepository {
-    fn new(pool: Pool<Sqlite>) -> Self {
+    const LIST: ListSpec = ListSpec {
+        table: "(SELECT * FROM experiences WHERE deleted_at IS NULL) AS experiences",
+        columns: "id, company, position, start_date, end_date, description, technologies, highlights",
+        sort_fields: &[("start_date", "start_date"), ("company", "company")],
+        default_sort: ("start_date", SortDirection::Desc),
+        filters: &[
+            ("technology", Filter::Technology("experience_technologies", "experience_id")),
+            ("year", Filter::Year("start_date")),
+        ],
+    };
+
+    fn new(pool: DbPool) -> Self {
         Self { pool }
     }
 
     async fn find_all(&self) -> Result<Vec<Experience>> {
+        Self::fetch_all(&mut *self.pool.acquire().await?).await
+    }
+
+    async fn find_page(&self, query: &ListQuery) -> Result<Page<Experience>> {
+        let page = fetch_page::<ExperienceRow>(&self.pool, query).await?;
+        Ok(page.map(Experience::from))
+    }
+
+    async fn find_by_id(&self, id: &str) -> Result<Option<Experience>> {
         let query = "
             SELECT id, company, position, start_date, end_date, description, technologies, highlights
             FROM experiences
-            ORDER BY start_date DESC
+            WHERE id = $1 AND deleted_at IS NULL
         ";
         
-        let rows = sqlx::query_as::<_, ExperienceRow>(query)
-            .fetch_all(&self.pool)
+        let row = sqlx::query_as::<_, ExperienceRow>(query)
+            .bind(id)
+            .fetch_optional(&self.pool)
             .await?;
 
-        let experiences = rows
-            .into_iter()
-            .map(|row| {
-                let technologies: Vec<String> = json_to_vec(&row.technologies).unwrap_or_default();
-                let highlights: Vec<String> = json_to_vec(&row.highlights).unwrap_or_default();
+        Ok(row.map(Experience::from))
+    }
+
+    async fn create(&self, item: Experience) -> Result<Experience> {
+        Self::insert(&mut *self.pool.acquire().await?, item).await
+    }
 
-                Experience {
-                    id: row.id,
-                    company: row.company,
-                    position: row.position,
-                    start_date: row.start_date,
-                    end_date: row.end_date,
-                    description: row.description,
-                    technologies,
-                    highlights,
-                }
-            })
-            .collect();
+    async fn update(&self, id: &str, item: Experience) -> Result<Experience> {
+        Ok(Self::write(&mut *self.pool.acquire().await?, id, item, None).await?.0)
+    }
 
-        Ok(experiences)
+    async fn delete(&self, id: &str) -> Result<bool> {
+        Self::trash(&mut *self.pool.acquire().await?, id, None).await
     }
+}
 
-    async fn find_by_id(&self, id: &str) -> Result<Option<Experience>> {
+impl Versioned<Experience> for ExperienceRepository {
+    async fn find_version(&self, id: &str) -> Result<Option<i64>> {
+        let version = sqlx::query_scalar("SELECT version FROM experiences WHERE id = $1 AND deleted_at IS NULL")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(version)
+    }
+
+    async fn update_at(&self, id: &str, item: Experience, version: i64) -> Result<Option<Experience>> {
+        let (item, saved) = Self::write(&mut *self.pool.acquire().await?, id, item, Some(version)).await?;
+        Ok(saved.then_some(item))
+    }
+
+    async fn delete_at(&self, id: &str, version: i64) -> Result<bool> {
+        Self::trash(&mut *self.pool.acquire().await?, id, Some(version)).await
+    }
+}
+
+impl ExperienceRepository {
+    // Reads and writes on a connection, which the methods above take from the
+    // pool, and an import runs within one transaction
+
+    /// Every experience not in the trash, most recent first
+    pub async fn fetch_all(conn: &mut AnyConnection) -> Result<Vec<Experience>> {
         let query = "
             SELECT id, company, position, start_date, end_date, description, technologies, highlights
             FROM experiences
-            WHERE id = ?
+            WHERE deleted_at IS NULL
+            ORDER BY start_date DESC
         ";
         
-        let row = sqlx::query_as::<_, ExperienceRow>(query)
-            .bind(id)
-            .fetch_optional(&self.pool)
+        let rows = sqlx::query_as::<_, ExperienceRow>(query)
+            .fetch_all(&mut *conn)
             .await?;
 
-        let experience = match row {
-            Some(row) => {
-                let technologies: Vec<String> = json_to_vec(&row.technologies).unwrap_or_default();
-                let highlights: Vec<String> = json_to_vec(&row.highlights).unwrap_or_default();
-
-                Some(Experience {
-                    id: row.id,
-                    company: row.company,
-                    position: row.position,
-                    start_date: row.start_date,
-                    end_date: row.end_date,
-                    description: row.description,
-                    technologies,
-                    highlights,
-                })
-            }
-            None => None,
-        };
-
-        Ok(experience)
+        Ok(rows.into_iter().map(Experience::from).collect())
     }
 
-    async fn create(&self, item: Experience) -> Result<Experience> {
+    /// Save a new experience under its own id
+    pub async fn insert(conn: &mut AnyConnection, mut item: Experience) -> Result<Experience> {
+        item.technologies = normalize_technologies(&item.technologies, &canonical_names(conn).await?);
         let id = item.id.clone();
         let technologies = vec_to_json(&item.technologies)?;
         let highlights = vec_to_json(&item.highlights)?;
 
         let query = "
             INSERT INTO experiences (id, company, position, start_date, end_date, description, technologies, highlights)
-            VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
         ";
         
         sqlx::query(query)
@@ -112,23 +156,27 @@ impl Repository<Experience> for ExperienceRepository {
             .bind(&item.description)
             .bind(technologies)
             .bind(highlights)
-            .execute(&self.pool)
+            .execute(&mut *conn)
             .await?;
 
         Ok(item)
     }
 
-    async fn update(&self, id: &str, item: Experience) -> Result<Experience> {
+    /// Write an item over a row, at a version when given, returning the item
+    /// as saved and whether the row was written
+    pub async fn write(conn: &mut AnyConnection, id: &str, mut item: Experience, version: Option<i64>) -> Result<(Experience, bool)> {
+        item.technologies = normalize_technologies(&item.technologies, &canonical_names(conn).await?);
         let technologies = vec_to_json(&item.technologies)?;
         let highlights = vec_to_json(&item.highlights)?;
 
         let query = "
             UPDATE experiences
-            SET company = ?, position = ?, start_date = ?, end_date = ?, description = ?, technologies = ?, highlights = ?, updated_at = CURRENT_TIMESTAMP
-            WHERE id = ?
+            SET company = $1, position = $2, start_date = $3, end_date = $4, description = $5, technologies = $6, highlights = $7, updated_at = CURRENT_TIMESTAMP,
+                version = version + 1
+            WHERE id = $8 AND deleted_at IS NULL AND version = COALESCE($9, version)
         ";
         
-        sqlx::query(query)
+        let result = sqlx::query(query)
             .bind(&item.company)
             .bind(&item.position)
             .bind(&item.start_date)
@@ -137,23 +185,45 @@ impl Repository<Experience> for ExperienceRepository {
             .bind(technologies)
             .bind(highlights)
             .bind(id)
-            .execute(&self.pool)
+            .bind(version)
+            .execute(&mut *conn)
             .await?;
 
-        Ok(item)
+        Ok((item, result.rows_affected() > 0))
     }
 
-    async fn delete(&self, id: &str) -> Result<bool> {
+    /// Move a row to the trash, at a version when given
+    pub async fn trash(conn: &mut AnyConnection, id: &str, version: Option<i64>) -> Result<bool> {
         let query = "
-            DELETE FROM experiences
-            WHERE id = ?
+            UPDATE experiences
+            SET deleted_at = $1
+            WHERE id = $2 AND deleted_at IS NULL AND version = COALESCE($3, version)
         ";
         
         let result = sqlx::query(query)
+            .bind(chrono::Utc::now().timestamp())
             .bind(id)
-            .execute(&self.pool)
+            .bind(version)
+            .execute(&mut *conn)
             .await?;
 
         Ok(result.rows_affected() > 0)
     }
+
+    /// Experiences using a technology, ignoring case, most recent first
+    pub async fn find_by_technology(&self, technology: &str) -> Result<Vec<Experience>> {
+        let query = "
+            SELECT id, company, position, start_date, end_date, description, technologies, highlights
+            FROM experiences
+            WHERE deleted_at IS NULL AND id IN (SELECT experience_id FROM experience_technologies WHERE technology_key = LOWER(TRIM($1)))
+            ORDER BY start_date DESC, id
+        ";
+
+        let rows = sqlx::query_as::<_, ExperienceRow>(query)
+            .bind(technology)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(rows.into_iter().map(Experience::from).collect())
+    }
 }
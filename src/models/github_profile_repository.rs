@@ -1,13 +1,14 @@
 use anyhow::Result;
-use sqlx::{Pool, Sqlite, FromRow};
+use sqlx::FromRow;
 use serde::{Serialize, Deserialize};
 use chrono::{DateTime, Utc};
 
+use crate::config::database::DbPool;
 use crate::models::github_profile::{GitHubProfile, GitHubOrganization, GitHubRepository, GitHubActivityItem};
 use crate::services::github_service::GitHubService;
 
 pub struct GitHubProfileRepository {
-    pool: Pool<Sqlite>,
+    pool: DbPool,
     github_service: GitHubService,
 }
 
@@ -19,7 +20,7 @@ struct GitHubProfileRow {
 }
 
 impl GitHubProfileRepository {
-    pub fn new(pool: Pool<Sqlite>, github_service: GitHubService) -> Self {
+    pub fn new(pool: DbPool, github_service: GitHubService) -> Self {
         Self { pool, github_service }
     }
     
@@ -139,7 +140,7 @@ impl GitHubProfileRepository {
         // First try to update any existing profile
         let update_query = "
             UPDATE github_profiles
-            SET data = ?, last_updated = ?, updated_at = CURRENT_TIMESTAMP
+            SET data = $1, last_updated = $2, updated_at = CURRENT_TIMESTAMP
             WHERE id = (SELECT id FROM github_profiles ORDER BY last_updated DESC LIMIT 1)
         ";
         
@@ -153,7 +154,7 @@ impl GitHubProfileRepository {
         if result.rows_affected() == 0 {
             let insert_query = "
                 INSERT INTO github_profiles (id, data, last_updated)
-                VALUES (?, ?, ?)
+                VALUES ($1, $2, $3)
             ";
             
             sqlx::query(insert_query)
@@ -1,12 +1,15 @@
 use anyhow::Result;
-use sqlx::{Pool, Sqlite, FromRow};
+use sqlx::{AnyConnection, FromRow};
 use serde::{Serialize, Deserialize};
 
+use crate::config::database::DbPool;
 use crate::models::github_stats::{GithubStats, TopLanguage, RecentActivity};
-use crate::models::repository::Repository;
+use crate::models::repository::{Repository, fetch_page, ListQuery, ListSpec, Page, SortDirection};
+use crate::models::technology::normalize_languages;
+use crate::models::technology_repository::canonical_names;
 
 pub struct GithubStatsRepository {
-    pool: Pool<Sqlite>,
+    pool: DbPool,
 }
 
 #[derive(Debug, FromRow, Serialize, Deserialize)]
@@ -20,8 +23,32 @@ struct GithubStatsRow {
     recent_activity: String,
 }
 
+impl From<GithubStatsRow> for GithubStats {
+    fn from(row: GithubStatsRow) -> Self {
+        let top_languages: Vec<TopLanguage> = serde_json::from_str(&row.top_languages).unwrap_or_default();
+        let recent_activity: Vec<RecentActivity> = serde_json::from_str(&row.recent_activity).unwrap_or_default();
+
+        GithubStats {
+            username: row.username,
+            repo_count: row.repo_count,
+            followers: row.followers,
+            contributions: row.contributions,
+            top_languages,
+            recent_activity,
+        }
+    }
+}
+
 impl Repository<GithubStats> for GithubStatsRepository {
-    fn new(pool: Pool<Sqlite>) -> Self {
+    const LIST: ListSpec = ListSpec {
+        table: "github_stats",
+        columns: "id, username, repo_count, followers, contributions, top_languages, recent_activity",
+        sort_fields: &[("username", "username")],
+        default_sort: ("username", SortDirection::Asc),
+        filters: &[],
+    };
+
+    fn new(pool: DbPool) -> Self {
         Self { pool }
     }
 
@@ -38,31 +65,19 @@ impl Repository<GithubStats> for GithubStatsRepository {
             .fetch_all(&self.pool)
             .await?;
 
-        let stats = rows
-            .into_iter()
-            .map(|row| {
-                let top_languages: Vec<TopLanguage> = serde_json::from_str(&row.top_languages).unwrap_or_default();
-                let recent_activity: Vec<RecentActivity> = serde_json::from_str(&row.recent_activity).unwrap_or_default();
-
-                GithubStats {
-                    username: row.username,
-                    repo_count: row.repo_count,
-                    followers: row.followers,
-                    contributions: row.contributions,
-                    top_languages,
-                    recent_activity,
-                }
-            })
-            .collect();
-
-        Ok(stats)
+        Ok(rows.into_iter().map(GithubStats::from).collect())
+    }
+
+    async fn find_page(&self, query: &ListQuery) -> Result<Page<GithubStats>> {
+        let page = fetch_page::<GithubStatsRow>(&self.pool, query).await?;
+        Ok(page.map(GithubStats::from))
     }
 
     async fn find_by_id(&self, id: &str) -> Result<Option<GithubStats>> {
         let query = "
             SELECT id, username, repo_count, followers, contributions, top_languages, recent_activity
             FROM github_stats
-            WHERE id = ?
+            WHERE id = $1
         ";
         
         let row = sqlx::query_as::<_, GithubStatsRow>(query)
@@ -70,34 +85,51 @@ impl Repository<GithubStats> for GithubStatsRepository {
             .fetch_optional(&self.pool)
             .await?;
 
-        let stats = match row {
-            Some(row) => {
-                let top_languages: Vec<TopLanguage> = serde_json::from_str(&row.top_languages).unwrap_or_default();
-                let recent_activity: Vec<RecentActivity> = serde_json::from_str(&row.recent_activity).unwrap_or_default();
-
-                Some(GithubStats {
-                    username: row.username,
-                    repo_count: row.repo_count,
-                    followers: row.followers,
-                    contributions: row.contributions,
-                    top_languages,
-                    recent_activity,
-                })
-            }
-            None => None,
-        };
-
-        Ok(stats)
+        Ok(row.map(GithubStats::from))
     }
 
     async fn create(&self, item: GithubStats) -> Result<GithubStats> {
+        Self::insert(&mut *self.pool.acquire().await?, item).await
+    }
+
+    async fn update(&self, id: &str, item: GithubStats) -> Result<GithubStats> {
+        Self::write(&mut *self.pool.acquire().await?, id, item).await
+    }
+
+    async fn delete(&self, id: &str) -> Result<bool> {
+        Self::remove(&mut *self.pool.acquire().await?, id).await
+    }
+}
+
+impl GithubStatsRepository {
+    // Reads and writes on a connection, which the methods above take from the
+    // pool, and an import runs within one transaction
+
+    /// The stats with their id, if there are any
+    pub async fn fetch_first(conn: &mut AnyConnection) -> Result<Option<(String, GithubStats)>> {
+        let query = "
+            SELECT id, username, repo_count, followers, contributions, top_languages, recent_activity
+            FROM github_stats
+            LIMIT 1
+        ";
+
+        let row = sqlx::query_as::<_, GithubStatsRow>(query)
+            .fetch_optional(&mut *conn)
+            .await?;
+
+        Ok(row.map(|row| (row.id.clone(), GithubStats::from(row))))
+    }
+
+    /// Save new stats
+    pub async fn insert(conn: &mut AnyConnection, mut item: GithubStats) -> Result<GithubStats> {
+        item.top_languages = normalize_languages(&item.top_languages, &canonical_names(conn).await?);
         let id = crate::models::repository::generate_id();
         let top_languages = serde_json::to_string(&item.top_languages)?;
         let recent_activity = serde_json::to_string(&item.recent_activity)?;
 
         let query = "
             INSERT INTO github_stats (id, username, repo_count, followers, contributions, top_languages, recent_activity)
-            VALUES (?, ?, ?, ?, ?, ?, ?)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
         ";
         
         sqlx::query(query)
@@ -108,21 +140,23 @@ impl Repository<GithubStats> for GithubStatsRepository {
             .bind(item.contributions)
             .bind(top_languages)
             .bind(recent_activity)
-            .execute(&self.pool)
+            .execute(&mut *conn)
             .await?;
 
         Ok(item)
     }
 
-    async fn update(&self, id: &str, item: GithubStats) -> Result<GithubStats> {
+    /// Write stats over a row
+    pub async fn write(conn: &mut AnyConnection, id: &str, mut item: GithubStats) -> Result<GithubStats> {
+        item.top_languages = normalize_languages(&item.top_languages, &canonical_names(conn).await?);
         let top_languages = serde_json::to_string(&item.top_languages)?;
         let recent_activity = serde_json::to_string(&item.recent_activity)?;
 
         let query = "
             UPDATE github_stats
-            SET username = ?, repo_count = ?, followers = ?, contributions = ?, 
-                top_languages = ?, recent_activity = ?, updated_at = CURRENT_TIMESTAMP
-            WHERE id = ?
+            SET username = $1, repo_count = $2, followers = $3, contributions = $4, 
+                top_languages = $5, recent_activity = $6, updated_at = CURRENT_TIMESTAMP
+            WHERE id = $7
         ";
         
         sqlx::query(query)
@@ -133,21 +167,22 @@ impl Repository<GithubStats> for GithubStatsRepository {
             .bind(top_languages)
             .bind(recent_activity)
             .bind(id)
-            .execute(&self.pool)
+            .execute(&mut *conn)
             .await?;
 
         Ok(item)
     }
 
-    async fn delete(&self, id: &str) -> Result<bool> {
+    /// Delete a row, returning false if there is none
+    pub async fn remove(conn: &mut AnyConnection, id: &str) -> Result<bool> {
         let query = "
             DELETE FROM github_stats
-            WHERE id = ?
+            WHERE id = $1
         ";
         
         let result = sqlx::query(query)
             .bind(id)
-            .execute(&self.pool)
+            .execute(&mut *conn)
             .await?;
 
         Ok(result.rows_affected() > 0)
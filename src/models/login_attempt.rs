@@ -0,0 +1,60 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// One login attempt, as recorded in the `login_attempts` audit table.
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+#[schema(example = json!({
+    "id": "0b7c2c55-8f0e-4c57-a3a4-9d1c0a6e3f21",
+    "username": "admin",
+    "ip_address": "203.0.113.7",
+    "user_agent": "curl/8.4.0",
+    "outcome": "invalid_credentials",
+    "attempted_at": 1792206897
+}))]
+pub struct LoginAttempt {
+    /// Unique identifier for the attempt
+    pub id: String,
+    /// Username as submitted; the account may not exist
+    pub username: String,
+    /// Client address
+    pub ip_address: Option<String>,
+    /// Client User-Agent header
+    pub user_agent: Option<String>,
+    /// "success", "mfa_required", "invalid_credentials", "invalid_code" or "locked"
+    pub outcome: String,
+    /// Time of the attempt (as UTC timestamp)
+    pub attempted_at: i64,
+}
+
+impl LoginAttempt {
+    pub fn new(
+        username: String,
+        ip_address: Option<String>,
+        user_agent: Option<String>,
+        outcome: String,
+        attempted_at: i64,
+    ) -> Self {
+        Self {
+            id: Uuid::new_v4().to_string(),
+            username,
+            ip_address,
+            user_agent,
+            outcome,
+            attempted_at,
+        }
+    }
+}
+
+/// Failure counter of one username or client address, from the `login_throttle` table.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct LoginThrottle {
+    /// "user:<username>" or "ip:<address>"
+    pub throttle_key: String,
+    /// Consecutive failed attempts
+    pub failures: i64,
+    /// Time of the latest failure (as UTC timestamp)
+    pub last_failure_at: i64,
+    /// End of the current lockout (as UTC timestamp)
+    pub locked_until: Option<i64>,
+}
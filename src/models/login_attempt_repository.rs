@@ -0,0 +1,190 @@
+use anyhow::Result;
+use sqlx::FromRow;
+use serde::{Serialize, Deserialize};
+
+use crate::config::database::DbPool;
+use crate::models::login_attempt::{LoginAttempt, LoginThrottle};
+use crate::models::repository::{fetch_page, Filter, ListQuery, ListSpec, Page, SortDirection};
+
+// Stores the login audit trail and the failure counters used for lockouts.
+// Audit entries are append-only, so this does not implement the generic
+// Repository trait.
+pub struct LoginAttemptRepository {
+    pool: DbPool,
+}
+
+#[derive(Debug, FromRow, Serialize, Deserialize)]
+struct LoginAttemptRow {
+    id: String,
+    username: String,
+    ip_address: Option<String>,
+    user_agent: Option<String>,
+    outcome: String,
+    attempted_at: i64,
+}
+
+impl From<LoginAttemptRow> for LoginAttempt {
+    fn from(row: LoginAttemptRow) -> Self {
+        LoginAttempt {
+            id: row.id,
+            username: row.username,
+            ip_address: row.ip_address,
+            user_agent: row.user_agent,
+            outcome: row.outcome,
+            attempted_at: row.attempted_at,
+        }
+    }
+}
+
+#[derive(Debug, FromRow, Serialize, Deserialize)]
+struct LoginThrottleRow {
+    throttle_key: String,
+    failures: i64,
+    last_failure_at: i64,
+    locked_until: Option<i64>,
+}
+
+impl From<LoginThrottleRow> for LoginThrottle {
+    fn from(row: LoginThrottleRow) -> Self {
+        LoginThrottle {
+            throttle_key: row.throttle_key,
+            failures: row.failures,
+            last_failure_at: row.last_failure_at,
+            locked_until: row.locked_until,
+        }
+    }
+}
+
+impl LoginAttemptRepository {
+    pub const LIST: ListSpec = ListSpec {
+        table: "login_attempts",
+        columns: "id, username, ip_address, user_agent, outcome, attempted_at",
+        sort_fields: &[("attempted_at", "attempted_at"), ("username", "username")],
+        default_sort: ("attempted_at", SortDirection::Desc),
+        filters: &[
+            ("username", Filter::Equals("username")),
+            ("ip_address", Filter::Equals("ip_address")),
+            ("outcome", Filter::Equals("outcome")),
+        ],
+    };
+
+    pub fn new(pool: DbPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn record(&self, attempt: LoginAttempt) -> Result<LoginAttempt> {
+        let query = "
+            INSERT INTO login_attempts (id, username, ip_address, user_agent, outcome, attempted_at)
+            VALUES ($1, $2, $3, $4, $5, $6)
+        ";
+
+        sqlx::query(query)
+            .bind(&attempt.id)
+            .bind(&attempt.username)
+            .bind(&attempt.ip_address)
+            .bind(&attempt.user_agent)
+            .bind(&attempt.outcome)
+            .bind(attempt.attempted_at)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(attempt)
+    }
+
+    pub async fn find_page(&self, query: &ListQuery) -> Result<Page<LoginAttempt>> {
+        let page = fetch_page::<LoginAttemptRow>(&self.pool, query).await?;
+        Ok(page.map(LoginAttempt::from))
+    }
+
+    pub async fn find_throttle(&self, throttle_key: &str) -> Result<Option<LoginThrottle>> {
+        let query = "
+            SELECT throttle_key, failures, last_failure_at, locked_until
+            FROM login_throttle
+            WHERE throttle_key = $1
+        ";
+
+        let row = sqlx::query_as::<_, LoginThrottleRow>(query)
+            .bind(throttle_key)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(row.map(LoginThrottle::from))
+    }
+
+    // Count one more failure in a single statement, so that concurrent
+    // attempts cannot read the same count and overwrite each other. Failures
+    // up to `forget_before` are forgotten, and their lock with them. Returns
+    // the new count.
+    pub async fn add_failure(&self, throttle_key: &str, now: i64, forget_before: i64) -> Result<i64> {
+        let query = "
+            INSERT INTO login_throttle (throttle_key, failures, last_failure_at, locked_until)
+            VALUES ($1, 1, $2, NULL)
+            ON CONFLICT (throttle_key) DO UPDATE SET
+                failures = CASE WHEN login_throttle.last_failure_at > $3 THEN login_throttle.failures + 1 ELSE 1 END,
+                locked_until = CASE WHEN login_throttle.last_failure_at > $3 THEN login_throttle.locked_until END,
+                last_failure_at = excluded.last_failure_at
+            RETURNING failures
+        ";
+
+        let (failures,) = sqlx::query_as::<_, (i64,)>(query)
+            .bind(throttle_key)
+            .bind(now)
+            .bind(forget_before)
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(failures)
+    }
+
+    // Lock a throttle key until `locked_until`, keeping any longer lock set
+    // by a concurrent failure
+    pub async fn lock_throttle(&self, throttle_key: &str, locked_until: i64) -> Result<()> {
+        let query = "
+            UPDATE login_throttle
+            SET locked_until = $2
+            WHERE throttle_key = $1 AND (locked_until IS NULL OR locked_until < $2)
+        ";
+
+        sqlx::query(query)
+            .bind(throttle_key)
+            .bind(locked_until)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn clear_throttle(&self, throttle_key: &str) -> Result<()> {
+        sqlx::query("DELETE FROM login_throttle WHERE throttle_key = $1")
+            .bind(throttle_key)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    // Delete the attempts made before `attempted_before`, when the audit log
+    // has a retention, and the counters whose failures are forgotten and
+    // whose lock is over. Returns how many attempts were deleted.
+    pub async fn prune(&self, attempted_before: Option<i64>, forget_before: i64, now: i64) -> Result<u64> {
+        let mut tx = self.pool.begin().await?;
+
+        let deleted = match attempted_before {
+            Some(before) => sqlx::query("DELETE FROM login_attempts WHERE attempted_at < $1")
+                .bind(before)
+                .execute(&mut *tx)
+                .await?
+                .rows_affected(),
+            None => 0,
+        };
+
+        sqlx::query("DELETE FROM login_throttle WHERE last_failure_at <= $1 AND (locked_until IS NULL OR locked_until <= $2)")
+            .bind(forget_before)
+            .bind(now)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(deleted)
+    }
+}
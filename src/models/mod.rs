@@ -14,3 +14,27 @@ pub mod profile;
 pub mod profile_repository;
 pub mod github_profile;
 pub mod github_profile_repository;
+pub mod user;
+pub mod user_repository;
+pub mod token;
+pub mod token_repository;
+pub mod api_key;
+pub mod api_key_repository;
+pub mod totp;
+pub mod totp_repository;
+pub mod login_attempt;
+pub mod login_attempt_repository;
+pub mod search;
+pub mod search_repository;
+pub mod slug;
+pub mod slug_repository;
+pub mod tag;
+pub mod tag_repository;
+pub mod technology;
+pub mod technology_repository;
+pub mod revision;
+pub mod revision_repository;
+pub mod trash;
+pub mod trash_repository;
+pub mod bundle;
+pub mod bundle_repository;
@@ -2,22 +2,138 @@ use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 use utoipa::ToSchema;
 
+use crate::error::{AppError, AppResult};
+use crate::services::markdown::RenderedMarkdown;
+
 #[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
 #[schema(example = json!({
     "id": "post-1",
+    "slug": "the-future-of-rust-in-web-development",
     "title": "The Future of Rust in Web Development",
     "date": "2025-04-10",
     "tags": ["Rust", "Web Development", "Backend"],
     "excerpt": "Exploring how Rust is changing the landscape of web development with its performance and safety guarantees.",
-    "content": "# The Future of Rust in Web Development\n\nAs web applications become more complex..."
+    "content": "# The Future of Rust in Web Development\n\nAs web applications become more complex...",
+    "status": "published",
+    "publish_at": 1744279200
 }))]
 pub struct Post {
     pub id: String,
+    /// Unique permalink, generated from the title unless chosen by an editor
+    pub slug: String,
     pub title: String,
     pub date: String,
     pub tags: Vec<String>,
     pub excerpt: String,
     pub content: String,
+    /// Publication state; only published posts are public
+    pub status: PostStatus,
+    /// When the post was or will be published (as UTC timestamp)
+    pub publish_at: Option<i64>,
+}
+
+/// A post with its content rendered to HTML, returned with `format=html`.
+/// The Markdown source is left out.
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+#[schema(example = json!({
+    "id": "post-1",
+    "slug": "the-future-of-rust-in-web-development",
+    "title": "The Future of Rust in Web Development",
+    "date": "2025-04-10",
+    "tags": ["Rust", "Web Development", "Backend"],
+    "excerpt": "Exploring how Rust is changing the landscape of web development with its performance and safety guarantees.",
+    "content_html": "<h1 id=\"the-future-of-rust-in-web-development\">The Future of Rust in Web Development</h1>\n<p>As web applications become more complex...</p>\n",
+    "toc": [{"level": 1, "id": "the-future-of-rust-in-web-development", "text": "The Future of Rust in Web Development"}],
+    "word_count": 1240,
+    "reading_time_minutes": 7,
+    "status": "published",
+    "publish_at": 1744279200
+}))]
+pub struct RenderedPost {
+    pub id: String,
+    pub slug: String,
+    pub title: String,
+    pub date: String,
+    pub tags: Vec<String>,
+    pub excerpt: String,
+    /// Sanitised HTML of the content, with highlighted code blocks
+    pub content_html: String,
+    /// Headings of the content, in order
+    pub toc: Vec<TocEntry>,
+    /// Words in the content, code blocks excluded
+    pub word_count: usize,
+    /// Estimated reading time, at 200 words per minute
+    pub reading_time_minutes: usize,
+    pub status: PostStatus,
+    pub publish_at: Option<i64>,
+}
+
+/// A heading of a post, linked to with `#id`
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, ToSchema)]
+pub struct TocEntry {
+    /// Heading level, from 1 to 6
+    pub level: u8,
+    /// Anchor of the heading in `content_html`
+    pub id: String,
+    pub text: String,
+}
+
+/// Publication state of a post. Scheduled posts are published automatically
+/// once their `publish_at` time has passed.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum PostStatus {
+    Draft,
+    Scheduled,
+    Published,
+    Archived,
+}
+
+impl PostStatus {
+    pub const ALL: [PostStatus; 4] = [
+        PostStatus::Draft,
+        PostStatus::Scheduled,
+        PostStatus::Published,
+        PostStatus::Archived,
+    ];
+
+    pub fn parse(status: &str) -> Option<PostStatus> {
+        PostStatus::ALL.into_iter().find(|s| s.as_str() == status)
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PostStatus::Draft => "draft",
+            PostStatus::Scheduled => "scheduled",
+            PostStatus::Published => "published",
+            PostStatus::Archived => "archived",
+        }
+    }
+}
+
+/// Check a status and publication time, publishing at once a post scheduled
+/// in the past and recording when a published post went out
+pub fn resolve_status(status: PostStatus, publish_at: Option<i64>, now: i64) -> AppResult<(PostStatus, Option<i64>)> {
+    match status {
+        PostStatus::Scheduled => {
+            let publish_at = publish_at
+                .ok_or_else(|| AppError::bad_request("Scheduled posts need a publish_at time"))?;
+
+            if publish_at <= now {
+                Ok((PostStatus::Published, Some(publish_at)))
+            } else {
+                Ok((PostStatus::Scheduled, Some(publish_at)))
+            }
+        }
+        PostStatus::Published => match publish_at {
+            Some(publish_at) if publish_at > now => Err(AppError::bad_request(
+                "publish_at is in the future; use status scheduled to publish later",
+            )),
+            Some(publish_at) => Ok((PostStatus::Published, Some(publish_at))),
+            None => Ok((PostStatus::Published, Some(now))),
+        },
+        PostStatus::Draft | PostStatus::Archived => Ok((status, publish_at)),
+    }
 }
 
 impl Post {
@@ -30,11 +146,33 @@ impl Post {
     ) -> Self {
         Self {
             id: Uuid::new_v4().to_string(),
+            slug: String::new(),
             title,
             date,
             tags,
             excerpt,
             content,
+            status: PostStatus::Draft,
+            publish_at: None,
+        }
+    }
+}
+
+impl RenderedPost {
+    pub fn new(post: Post, rendered: &RenderedMarkdown) -> Self {
+        Self {
+            id: post.id,
+            slug: post.slug,
+            title: post.title,
+            date: post.date,
+            tags: post.tags,
+            excerpt: post.excerpt,
+            content_html: rendered.html.clone(),
+            toc: rendered.toc.clone(),
+            word_count: rendered.word_count,
+            reading_time_minutes: rendered.reading_time_minutes,
+            status: post.status,
+            publish_at: post.publish_at,
         }
     }
 }
@@ -1,97 +1,150 @@
+use std::collections::HashMap;
+
 use anyhow::Result;
-use sqlx::{Pool, Sqlite, FromRow};
+use sqlx::{AnyConnection, FromRow};
 use serde::{Serialize, Deserialize};
 
-use crate::models::post::Post;
-use crate::models::repository::{Repository, vec_to_json, json_to_vec};
+use crate::config::database::{DbBackend, DbPool};
+use crate::models::post::{Post, PostStatus};
+use crate::models::repository::{Repository, Versioned, vec_to_json, json_to_vec, slug_value, fetch_page, Filter, ListQuery, ListSpec, Page, SortDirection};
 
 pub struct PostRepository {
-    pool: Pool<Sqlite>,
+    pool: DbPool,
 }
 
 #[derive(Debug, FromRow, Serialize, Deserialize)]
 struct PostRow {
     id: String,
+    slug: Option<String>,
     title: String,
     date: String,
     tags: String,
     excerpt: String,
     content: String,
+    status: String,
+    publish_at: Option<i64>,
+}
+
+impl From<PostRow> for Post {
+    fn from(row: PostRow) -> Self {
+        let tags: Vec<String> = json_to_vec(&row.tags).unwrap_or_default();
+
+        Post {
+            id: row.id,
+            slug: row.slug.unwrap_or_default(),
+            title: row.title,
+            date: row.date,
+            tags,
+            excerpt: row.excerpt,
+            content: row.content,
+            status: PostStatus::parse(&row.status).unwrap_or(PostStatus::Draft),
+            publish_at: row.publish_at,
+        }
+    }
 }
 
 impl Repository<Post> for PostRepository {
-    fn new(pool: Pool<Sqlite>) -> Self {
+    const LIST: ListSpec = ListSpec {
+        table: "(SELECT * FROM posts WHERE deleted_at IS NULL) AS posts",
+        columns: "id, slug, title, date, tags, excerpt, content, status, publish_at",
+        sort_fields: &[("date", "date"), ("title", "title"), ("publish_at", "publish_at")],
+        default_sort: ("date", SortDirection::Desc),
+        filters: &[
+            ("tag", Filter::Linked("post_tags", "post_id", "tag_key")),
+            ("year", Filter::Year("date")),
+            ("status", Filter::Equals("status")),
+        ],
+    };
+
+    fn new(pool: DbPool) -> Self {
         Self { pool }
     }
 
     async fn find_all(&self) -> Result<Vec<Post>> {
+        Self::fetch_all(&mut *self.pool.acquire().await?).await
+    }
+
+    async fn find_page(&self, query: &ListQuery) -> Result<Page<Post>> {
+        let page = fetch_page::<PostRow>(&self.pool, query).await?;
+        Ok(page.map(Post::from))
+    }
+
+    async fn find_by_id(&self, id: &str) -> Result<Option<Post>> {
         let query = "
-            SELECT id, title, date, tags, excerpt, content
+            SELECT id, slug, title, date, tags, excerpt, content, status, publish_at
             FROM posts
-            ORDER BY date DESC
+            WHERE id = $1 AND deleted_at IS NULL
         ";
         
-        let rows = sqlx::query_as::<_, PostRow>(query)
-            .fetch_all(&self.pool)
+        let row = sqlx::query_as::<_, PostRow>(query)
+            .bind(id)
+            .fetch_optional(&self.pool)
             .await?;
 
-        let posts = rows
-            .into_iter()
-            .map(|row| {
-                let tags: Vec<String> = json_to_vec(&row.tags).unwrap_or_default();
+        Ok(row.map(Post::from))
+    }
 
-                Post {
-                    id: row.id,
-                    title: row.title,
-                    date: row.date,
-                    tags,
-                    excerpt: row.excerpt,
-                    content: row.content,
-                }
-            })
-            .collect();
+    async fn create(&self, item: Post) -> Result<Post> {
+        Self::insert(&mut *self.pool.acquire().await?, item).await
+    }
 
-        Ok(posts)
+    async fn update(&self, id: &str, item: Post) -> Result<Post> {
+        Ok(Self::write(&mut *self.pool.acquire().await?, id, item, None).await?.0)
     }
 
-    async fn find_by_id(&self, id: &str) -> Result<Option<Post>> {
-        let query = "
-            SELECT id, title, date, tags, excerpt, content
-            FROM posts
-            WHERE id = ?
-        ";
-        
-        let row = sqlx::query_as::<_, PostRow>(query)
+    async fn delete(&self, id: &str) -> Result<bool> {
+        Self::trash(&mut *self.pool.acquire().await?, id, None).await
+    }
+}
+
+impl Versioned<Post> for PostRepository {
+    async fn find_version(&self, id: &str) -> Result<Option<i64>> {
+        let version = sqlx::query_scalar("SELECT version FROM posts WHERE id = $1 AND deleted_at IS NULL")
             .bind(id)
             .fetch_optional(&self.pool)
             .await?;
 
-        let post = match row {
-            Some(row) => {
-                let tags: Vec<String> = json_to_vec(&row.tags).unwrap_or_default();
+        Ok(version)
+    }
 
-                Some(Post {
-                    id: row.id,
-                    title: row.title,
-                    date: row.date,
-                    tags,
-                    excerpt: row.excerpt,
-                    content: row.content,
-                })
-            }
-            None => None,
-        };
+    async fn update_at(&self, id: &str, item: Post, version: i64) -> Result<Option<Post>> {
+        let (item, saved) = Self::write(&mut *self.pool.acquire().await?, id, item, Some(version)).await?;
+        Ok(saved.then_some(item))
+    }
 
-        Ok(post)
+    async fn delete_at(&self, id: &str, version: i64) -> Result<bool> {
+        Self::trash(&mut *self.pool.acquire().await?, id, Some(version)).await
     }
+}
 
-    async fn create(&self, item: Post) -> Result<Post> {
+impl PostRepository {
+    // Reads and writes on a connection, which the methods above take from the
+    // pool, and an import runs within one transaction
+
+    /// Every post not in the trash, newest first
+    pub async fn fetch_all(conn: &mut AnyConnection) -> Result<Vec<Post>> {
+        let query = "
+            SELECT id, slug, title, date, tags, excerpt, content, status, publish_at
+            FROM posts
+            WHERE deleted_at IS NULL
+            ORDER BY date DESC
+        ";
+        
+        let rows = sqlx::query_as::<_, PostRow>(query)
+            .fetch_all(&mut *conn)
+            .await?;
+
+        Ok(rows.into_iter().map(Post::from).collect())
+    }
+
+    /// Save a new post under its own id
+    pub async fn insert(conn: &mut AnyConnection, item: Post) -> Result<Post> {
         let id = item.id.clone();
         let tags = vec_to_json(&item.tags)?;
 
         let query = "
-            INSERT INTO posts (id, title, date, tags, excerpt, content)
-            VALUES (?, ?, ?, ?, ?, ?)
+            INSERT INTO posts (id, title, date, tags, excerpt, content, status, publish_at, slug)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
         ";
         
         sqlx::query(query)
@@ -101,45 +154,124 @@ impl Repository<Post> for PostRepository {
             .bind(tags)
             .bind(&item.excerpt)
             .bind(&item.content)
-            .execute(&self.pool)
+            .bind(item.status.as_str())
+            .bind(item.publish_at)
+            .bind(slug_value(&item.slug))
+            .execute(&mut *conn)
             .await?;
 
         Ok(item)
     }
 
-    async fn update(&self, id: &str, item: Post) -> Result<Post> {
+    /// Write an item over a row, at a version when given, returning the item
+    /// as saved and whether the row was written
+    pub async fn write(conn: &mut AnyConnection, id: &str, item: Post, version: Option<i64>) -> Result<(Post, bool)> {
         let tags = vec_to_json(&item.tags)?;
 
         let query = "
             UPDATE posts
-            SET title = ?, date = ?, tags = ?, excerpt = ?, content = ?, updated_at = CURRENT_TIMESTAMP
-            WHERE id = ?
+            SET title = $1, date = $2, tags = $3, excerpt = $4, content = $5, status = $6, publish_at = $7,
+                slug = $8, updated_at = CURRENT_TIMESTAMP,
+                version = version + 1
+            WHERE id = $9 AND deleted_at IS NULL AND version = COALESCE($10, version)
         ";
         
-        sqlx::query(query)
+        let result = sqlx::query(query)
             .bind(&item.title)
             .bind(&item.date)
             .bind(tags)
             .bind(&item.excerpt)
             .bind(&item.content)
+            .bind(item.status.as_str())
+            .bind(item.publish_at)
+            .bind(slug_value(&item.slug))
             .bind(id)
-            .execute(&self.pool)
+            .bind(version)
+            .execute(&mut *conn)
             .await?;
 
-        Ok(item)
+        Ok((item, result.rows_affected() > 0))
     }
 
-    async fn delete(&self, id: &str) -> Result<bool> {
+    /// Move a row to the trash, at a version when given
+    pub async fn trash(conn: &mut AnyConnection, id: &str, version: Option<i64>) -> Result<bool> {
         let query = "
-            DELETE FROM posts
-            WHERE id = ?
+            UPDATE posts
+            SET deleted_at = $1
+            WHERE id = $2 AND deleted_at IS NULL AND version = COALESCE($3, version)
         ";
         
         let result = sqlx::query(query)
+            .bind(chrono::Utc::now().timestamp())
             .bind(id)
-            .execute(&self.pool)
+            .bind(version)
+            .execute(&mut *conn)
             .await?;
 
         Ok(result.rows_affected() > 0)
     }
+
+    pub async fn find_by_slug(&self, slug: &str) -> Result<Option<Post>> {
+        let query = "
+            SELECT id, slug, title, date, tags, excerpt, content, status, publish_at
+            FROM posts
+            WHERE slug = $1 AND deleted_at IS NULL
+        ";
+
+        let row = sqlx::query_as::<_, PostRow>(query)
+            .bind(slug)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(row.map(Post::from))
+    }
+
+    /// Posts with a tag, ignoring case, newest first
+    pub async fn find_by_tag(&self, tag: &str) -> Result<Vec<Post>> {
+        let query = "
+            SELECT id, slug, title, date, tags, excerpt, content, status, publish_at
+            FROM posts
+            WHERE deleted_at IS NULL AND id IN (SELECT post_id FROM post_tags WHERE tag_key = LOWER(TRIM($1)))
+            ORDER BY date DESC, id
+        ";
+
+        let rows = sqlx::query_as::<_, PostRow>(query)
+            .bind(tag)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(rows.into_iter().map(Post::from).collect())
+    }
+
+    /// When each post was last modified, as UTC timestamps by post id
+    pub async fn find_updated_at(&self) -> Result<HashMap<String, i64>> {
+        let query = format!(
+            "SELECT id, {} FROM posts WHERE updated_at IS NOT NULL AND deleted_at IS NULL",
+            DbBackend::of(&self.pool).unix_seconds("updated_at")
+        );
+
+        let rows = sqlx::query_as::<_, (String, i64)>(&query)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(rows.into_iter().collect())
+    }
+
+    /// Publish scheduled posts whose publication time has come, returning how many
+    pub async fn publish_due(&self, now: i64) -> Result<u64> {
+        let query = "
+            UPDATE posts
+            SET status = $1, updated_at = CURRENT_TIMESTAMP, version = version + 1
+            WHERE status = $2 AND publish_at <= $3 AND deleted_at IS NULL
+        ";
+
+        let result = sqlx::query(query)
+            .bind(PostStatus::Published.as_str())
+            .bind(PostStatus::Scheduled.as_str())
+            .bind(now)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected())
+    }
 }
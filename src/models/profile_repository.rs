@@ -1,12 +1,13 @@
 use anyhow::Result;
-use sqlx::{Pool, Sqlite, FromRow};
+use sqlx::{AnyConnection, FromRow};
 use serde::{Serialize, Deserialize};
 
+use crate::config::database::DbPool;
 use crate::models::profile::{Profile, SocialLink, Education, Language};
-use crate::models::repository::{Repository, vec_to_json, json_to_vec};
+use crate::models::repository::{Repository, Versioned, vec_to_json, json_to_vec, fetch_page, ListQuery, ListSpec, Page, SortDirection};
 
 pub struct ProfileRepository {
-    pool: Pool<Sqlite>,
+    pool: DbPool,
 }
 
 #[derive(Debug, FromRow, Serialize, Deserialize)]
@@ -18,8 +19,32 @@ struct ProfileRow {
     languages: String,
 }
 
+impl From<ProfileRow> for Profile {
+    fn from(row: ProfileRow) -> Self {
+        let bio: Vec<String> = json_to_vec(&row.bio).unwrap_or_default();
+        let social_links: Vec<SocialLink> = serde_json::from_str(&row.social_links).unwrap_or_default();
+        let education: Vec<Education> = serde_json::from_str(&row.education).unwrap_or_default();
+        let languages: Vec<Language> = serde_json::from_str(&row.languages).unwrap_or_default();
+
+        Profile {
+            bio,
+            social_links,
+            education,
+            languages,
+        }
+    }
+}
+
 impl Repository<Profile> for ProfileRepository {
-    fn new(pool: Pool<Sqlite>) -> Self {
+    const LIST: ListSpec = ListSpec {
+        table: "profiles",
+        columns: "id, bio, social_links, education, languages",
+        sort_fields: &[],
+        default_sort: ("id", SortDirection::Asc),
+        filters: &[],
+    };
+
+    fn new(pool: DbPool) -> Self {
         Self { pool }
     }
 
@@ -36,31 +61,19 @@ impl Repository<Profile> for ProfileRepository {
             .fetch_all(&self.pool)
             .await?;
 
-        let profiles = rows
-            .into_iter()
-            .map(|row| {
-                let bio: Vec<String> = json_to_vec(&row.bio).unwrap_or_default();
-                let social_links: Vec<SocialLink> = serde_json::from_str(&row.social_links).unwrap_or_default();
-                let education: Vec<Education> = serde_json::from_str(&row.education).unwrap_or_default();
-                let languages: Vec<Language> = serde_json::from_str(&row.languages).unwrap_or_default();
-
-                Profile {
-                    bio,
-                    social_links,
-                    education,
-                    languages,
-                }
-            })
-            .collect();
-
-        Ok(profiles)
+        Ok(rows.into_iter().map(Profile::from).collect())
+    }
+
+    async fn find_page(&self, query: &ListQuery) -> Result<Page<Profile>> {
+        let page = fetch_page::<ProfileRow>(&self.pool, query).await?;
+        Ok(page.map(Profile::from))
     }
 
     async fn find_by_id(&self, id: &str) -> Result<Option<Profile>> {
         let query = "
             SELECT id, bio, social_links, education, languages
             FROM profiles
-            WHERE id = ?
+            WHERE id = $1
         ";
         
         let row = sqlx::query_as::<_, ProfileRow>(query)
@@ -68,27 +81,43 @@ impl Repository<Profile> for ProfileRepository {
             .fetch_optional(&self.pool)
             .await?;
 
-        let profile = match row {
-            Some(row) => {
-                let bio: Vec<String> = json_to_vec(&row.bio).unwrap_or_default();
-                let social_links: Vec<SocialLink> = serde_json::from_str(&row.social_links).unwrap_or_default();
-                let education: Vec<Education> = serde_json::from_str(&row.education).unwrap_or_default();
-                let languages: Vec<Language> = serde_json::from_str(&row.languages).unwrap_or_default();
-
-                Some(Profile {
-                    bio,
-                    social_links,
-                    education,
-                    languages,
-                })
-            }
-            None => None,
-        };
-
-        Ok(profile)
+        Ok(row.map(Profile::from))
     }
 
     async fn create(&self, item: Profile) -> Result<Profile> {
+        Self::insert(&mut *self.pool.acquire().await?, item).await
+    }
+
+    async fn update(&self, id: &str, item: Profile) -> Result<Profile> {
+        Ok(Self::write(&mut *self.pool.acquire().await?, id, item, None).await?.0)
+    }
+
+    async fn delete(&self, id: &str) -> Result<bool> {
+        Self::remove(&mut *self.pool.acquire().await?, id, None).await
+    }
+}
+
+impl ProfileRepository {
+    // Reads and writes on a connection, which the methods above take from the
+    // pool, and an import runs within one transaction
+
+    /// The profile with its id, if there is one
+    pub async fn fetch_first(conn: &mut AnyConnection) -> Result<Option<(String, Profile)>> {
+        let query = "
+            SELECT id, bio, social_links, education, languages
+            FROM profiles
+            LIMIT 1
+        ";
+
+        let row = sqlx::query_as::<_, ProfileRow>(query)
+            .fetch_optional(&mut *conn)
+            .await?;
+
+        Ok(row.map(|row| (row.id.clone(), Profile::from(row))))
+    }
+
+    /// Save a new profile
+    pub async fn insert(conn: &mut AnyConnection, item: Profile) -> Result<Profile> {
         let id = crate::models::repository::generate_id();
         let bio = vec_to_json(&item.bio)?;
         let social_links = serde_json::to_string(&item.social_links)?;
@@ -97,7 +126,7 @@ impl Repository<Profile> for ProfileRepository {
 
         let query = "
             INSERT INTO profiles (id, bio, social_links, education, languages)
-            VALUES (?, ?, ?, ?, ?)
+            VALUES ($1, $2, $3, $4, $5)
         ";
         
         sqlx::query(query)
@@ -106,13 +135,15 @@ impl Repository<Profile> for ProfileRepository {
             .bind(social_links)
             .bind(education)
             .bind(languages)
-            .execute(&self.pool)
+            .execute(&mut *conn)
             .await?;
 
         Ok(item)
     }
 
-    async fn update(&self, id: &str, item: Profile) -> Result<Profile> {
+    /// Write an item over a row, at a version when given, returning the item
+    /// as saved and whether the row was written
+    pub async fn write(conn: &mut AnyConnection, id: &str, item: Profile, version: Option<i64>) -> Result<(Profile, bool)> {
         log::info!("Updating profile with ID: {}", id);
         
         let bio = vec_to_json(&item.bio)?;
@@ -127,8 +158,9 @@ impl Repository<Profile> for ProfileRepository {
 
         let query = "
             UPDATE profiles
-            SET bio = ?, social_links = ?, education = ?, languages = ?, updated_at = CURRENT_TIMESTAMP
-            WHERE id = ?
+            SET bio = $1, social_links = $2, education = $3, languages = $4, updated_at = CURRENT_TIMESTAMP,
+                version = version + 1
+            WHERE id = $5 AND version = COALESCE($6, version)
         ";
         
         let result = sqlx::query(query)
@@ -137,25 +169,48 @@ impl Repository<Profile> for ProfileRepository {
             .bind(education)
             .bind(languages)
             .bind(id)
-            .execute(&self.pool)
+            .bind(version)
+            .execute(&mut *conn)
             .await?;
             
         log::info!("Update affected {} rows", result.rows_affected());
 
-        Ok(item)
+        Ok((item, result.rows_affected() > 0))
     }
 
-    async fn delete(&self, id: &str) -> Result<bool> {
+    /// Delete a row, at a version when given
+    pub async fn remove(conn: &mut AnyConnection, id: &str, version: Option<i64>) -> Result<bool> {
         let query = "
             DELETE FROM profiles
-            WHERE id = ?
+            WHERE id = $1 AND version = COALESCE($2, version)
         ";
         
         let result = sqlx::query(query)
             .bind(id)
-            .execute(&self.pool)
+            .bind(version)
+            .execute(&mut *conn)
             .await?;
 
         Ok(result.rows_affected() > 0)
     }
 }
+
+impl Versioned<Profile> for ProfileRepository {
+    async fn find_version(&self, id: &str) -> Result<Option<i64>> {
+        let version = sqlx::query_scalar("SELECT version FROM profiles WHERE id = $1")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(version)
+    }
+
+    async fn update_at(&self, id: &str, item: Profile, version: i64) -> Result<Option<Profile>> {
+        let (item, saved) = Self::write(&mut *self.pool.acquire().await?, id, item, Some(version)).await?;
+        Ok(saved.then_some(item))
+    }
+
+    async fn delete_at(&self, id: &str, version: i64) -> Result<bool> {
+        Self::remove(&mut *self.pool.acquire().await?, id, Some(version)).await
+    }
+}
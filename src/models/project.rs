@@ -5,6 +5,7 @@ use utoipa::ToSchema;
 #[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
 #[schema(example = json!({
     "id": "project-1",
+    "slug": "automotive-dashboard",
     "title": "Automotive Dashboard",
     "description": "Real-time dashboard for vehicle diagnostics and monitoring",
     "technologies": ["React", "TypeScript", "WebSockets", "D3.js"],
@@ -18,6 +19,8 @@ use utoipa::ToSchema;
 pub struct Project {
     /// Unique identifier for the project
     pub id: String,
+    /// Unique permalink, generated from the title unless chosen by an editor
+    pub slug: String,
     /// Project title
     pub title: String,
     /// Detailed description of the project
@@ -39,6 +42,7 @@ pub struct Project {
 }
 
 impl Project {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         title: String,
         description: String,
@@ -52,6 +56,7 @@ impl Project {
     ) -> Self {
         Self {
             id: Uuid::new_v4().to_string(),
+            slug: String::new(),
             title,
             description,
             technologies,
@@ -1,17 +1,21 @@
 use anyhow::Result;
-use sqlx::{Pool, Sqlite, FromRow};
+use sqlx::{AnyConnection, FromRow};
 use serde::{Serialize, Deserialize};
 
+use crate::config::database::DbPool;
 use crate::models::project::Project;
-use crate::models::repository::{Repository, vec_to_json, json_to_vec};
+use crate::models::repository::{Repository, Versioned, vec_to_json, json_to_vec, slug_value, fetch_page, Filter, ListQuery, ListSpec, Page, SortDirection};
+use crate::models::technology::normalize_technologies;
+use crate::models::technology_repository::canonical_names;
 
 pub struct ProjectRepository {
-    pool: Pool<Sqlite>,
+    pool: DbPool,
 }
 
 #[derive(Debug, FromRow, Serialize, Deserialize)]
 struct ProjectRow {
     id: String,
+    slug: Option<String>,
     title: String,
     description: String,
     technologies: String,
@@ -23,54 +27,60 @@ struct ProjectRow {
     highlights: String,
 }
 
+impl From<ProjectRow> for Project {
+    fn from(row: ProjectRow) -> Self {
+        let technologies: Vec<String> = json_to_vec(&row.technologies).unwrap_or_default();
+        let highlights: Vec<String> = json_to_vec(&row.highlights).unwrap_or_default();
+        let image_urls: Option<Vec<String>> = row.image_urls
+            .as_ref()
+            .and_then(|urls| json_to_vec(urls).ok());
+
+        Project {
+            id: row.id,
+            slug: row.slug.unwrap_or_default(),
+            title: row.title,
+            description: row.description,
+            technologies,
+            github_url: row.github_url,
+            live_url: row.live_url,
+            image_url: row.image_url,
+            image_urls,
+            year: row.year,
+            highlights,
+        }
+    }
+}
+
 impl Repository<Project> for ProjectRepository {
-    fn new(pool: Pool<Sqlite>) -> Self {
+    const LIST: ListSpec = ListSpec {
+        table: "(SELECT * FROM projects WHERE deleted_at IS NULL) AS projects",
+        columns: "id, slug, title, description, technologies, github_url, live_url, image_url, image_urls, year, highlights",
+        sort_fields: &[("year", "year"), ("title", "title")],
+        default_sort: ("year", SortDirection::Desc),
+        filters: &[
+            ("technology", Filter::Technology("project_technologies", "project_id")),
+            ("year", Filter::IntEquals("year")),
+        ],
+    };
+
+    fn new(pool: DbPool) -> Self {
         Self { pool }
     }
 
     async fn find_all(&self) -> Result<Vec<Project>> {
-        let query = "
-            SELECT id, title, description, technologies, github_url, live_url, image_url, image_urls, year, highlights
-            FROM projects
-            ORDER BY year DESC
-        ";
-        
-        let rows = sqlx::query_as::<_, ProjectRow>(query)
-            .fetch_all(&self.pool)
-            .await?;
+        Self::fetch_all(&mut *self.pool.acquire().await?).await
+    }
 
-        let projects = rows
-            .into_iter()
-            .map(|row| {
-                let technologies: Vec<String> = json_to_vec(&row.technologies).unwrap_or_default();
-                let highlights: Vec<String> = json_to_vec(&row.highlights).unwrap_or_default();
-                let image_urls: Option<Vec<String>> = row.image_urls
-                    .as_ref()
-                    .and_then(|urls| json_to_vec(urls).ok());
-
-                Project {
-                    id: row.id,
-                    title: row.title,
-                    description: row.description,
-                    technologies,
-                    github_url: row.github_url,
-                    live_url: row.live_url,
-                    image_url: row.image_url,
-                    image_urls,
-                    year: row.year,
-                    highlights,
-                }
-            })
-            .collect();
-
-        Ok(projects)
+    async fn find_page(&self, query: &ListQuery) -> Result<Page<Project>> {
+        let page = fetch_page::<ProjectRow>(&self.pool, query).await?;
+        Ok(page.map(Project::from))
     }
 
     async fn find_by_id(&self, id: &str) -> Result<Option<Project>> {
         let query = "
-            SELECT id, title, description, technologies, github_url, live_url, image_url, image_urls, year, highlights
+            SELECT id, slug, title, description, technologies, github_url, live_url, image_url, image_urls, year, highlights
             FROM projects
-            WHERE id = ?
+            WHERE id = $1 AND deleted_at IS NULL
         ";
         
         let row = sqlx::query_as::<_, ProjectRow>(query)
@@ -78,34 +88,65 @@ impl Repository<Project> for ProjectRepository {
             .fetch_optional(&self.pool)
             .await?;
 
-        let project = match row {
-            Some(row) => {
-                let technologies: Vec<String> = json_to_vec(&row.technologies).unwrap_or_default();
-                let highlights: Vec<String> = json_to_vec(&row.highlights).unwrap_or_default();
-                let image_urls: Option<Vec<String>> = row.image_urls
-                    .as_ref()
-                    .and_then(|urls| json_to_vec(urls).ok());
-
-                Some(Project {
-                    id: row.id,
-                    title: row.title,
-                    description: row.description,
-                    technologies,
-                    github_url: row.github_url,
-                    live_url: row.live_url,
-                    image_url: row.image_url,
-                    image_urls,
-                    year: row.year,
-                    highlights,
-                })
-            }
-            None => None,
-        };
-
-        Ok(project)
+        Ok(row.map(Project::from))
     }
 
     async fn create(&self, item: Project) -> Result<Project> {
+        Self::insert(&mut *self.pool.acquire().await?, item).await
+    }
+
+    async fn update(&self, id: &str, item: Project) -> Result<Project> {
+        Ok(Self::write(&mut *self.pool.acquire().await?, id, item, None).await?.0)
+    }
+
+    async fn delete(&self, id: &str) -> Result<bool> {
+        Self::trash(&mut *self.pool.acquire().await?, id, None).await
+    }
+}
+
+impl Versioned<Project> for ProjectRepository {
+    async fn find_version(&self, id: &str) -> Result<Option<i64>> {
+        let version = sqlx::query_scalar("SELECT version FROM projects WHERE id = $1 AND deleted_at IS NULL")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(version)
+    }
+
+    async fn update_at(&self, id: &str, item: Project, version: i64) -> Result<Option<Project>> {
+        let (item, saved) = Self::write(&mut *self.pool.acquire().await?, id, item, Some(version)).await?;
+        Ok(saved.then_some(item))
+    }
+
+    async fn delete_at(&self, id: &str, version: i64) -> Result<bool> {
+        Self::trash(&mut *self.pool.acquire().await?, id, Some(version)).await
+    }
+}
+
+impl ProjectRepository {
+    // Reads and writes on a connection, which the methods above take from the
+    // pool, and an import runs within one transaction
+
+    /// Every project not in the trash, newest first
+    pub async fn fetch_all(conn: &mut AnyConnection) -> Result<Vec<Project>> {
+        let query = "
+            SELECT id, slug, title, description, technologies, github_url, live_url, image_url, image_urls, year, highlights
+            FROM projects
+            WHERE deleted_at IS NULL
+            ORDER BY year DESC
+        ";
+        
+        let rows = sqlx::query_as::<_, ProjectRow>(query)
+            .fetch_all(&mut *conn)
+            .await?;
+
+        Ok(rows.into_iter().map(Project::from).collect())
+    }
+
+    /// Save a new project under its own id
+    pub async fn insert(conn: &mut AnyConnection, mut item: Project) -> Result<Project> {
+        item.technologies = normalize_technologies(&item.technologies, &canonical_names(conn).await?);
         let id = item.id.clone();
         let technologies = vec_to_json(&item.technologies)?;
         let highlights = vec_to_json(&item.highlights)?;
@@ -115,8 +156,8 @@ impl Repository<Project> for ProjectRepository {
         };
 
         let query = "
-            INSERT INTO projects (id, title, description, technologies, github_url, live_url, image_url, image_urls, year, highlights)
-            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            INSERT INTO projects (id, title, description, technologies, github_url, live_url, image_url, image_urls, year, highlights, slug)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
         ";
         
         sqlx::query(query)
@@ -130,13 +171,17 @@ impl Repository<Project> for ProjectRepository {
             .bind(image_urls)
             .bind(item.year)
             .bind(highlights)
-            .execute(&self.pool)
+            .bind(slug_value(&item.slug))
+            .execute(&mut *conn)
             .await?;
 
         Ok(item)
     }
 
-    async fn update(&self, id: &str, item: Project) -> Result<Project> {
+    /// Write an item over a row, at a version when given, returning the item
+    /// as saved and whether the row was written
+    pub async fn write(conn: &mut AnyConnection, id: &str, mut item: Project, version: Option<i64>) -> Result<(Project, bool)> {
+        item.technologies = normalize_technologies(&item.technologies, &canonical_names(conn).await?);
         let technologies = vec_to_json(&item.technologies)?;
         let highlights = vec_to_json(&item.highlights)?;
         let image_urls = match &item.image_urls {
@@ -146,11 +191,12 @@ impl Repository<Project> for ProjectRepository {
 
         let query = "
             UPDATE projects
-            SET title = ?, description = ?, technologies = ?, github_url = ?, live_url = ?, image_url = ?, image_urls = ?, year = ?, highlights = ?, updated_at = CURRENT_TIMESTAMP
-            WHERE id = ?
+            SET title = $1, description = $2, technologies = $3, github_url = $4, live_url = $5, image_url = $6, image_urls = $7, year = $8, highlights = $9, slug = $10, updated_at = CURRENT_TIMESTAMP,
+                version = version + 1
+            WHERE id = $11 AND deleted_at IS NULL AND version = COALESCE($12, version)
         ";
         
-        sqlx::query(query)
+        let result = sqlx::query(query)
             .bind(&item.title)
             .bind(&item.description)
             .bind(technologies)
@@ -160,26 +206,64 @@ impl Repository<Project> for ProjectRepository {
             .bind(image_urls)
             .bind(item.year)
             .bind(highlights)
+            .bind(slug_value(&item.slug))
             .bind(id)
-            .execute(&self.pool)
+            .bind(version)
+            .execute(&mut *conn)
             .await?;
 
-        Ok(item)
+        Ok((item, result.rows_affected() > 0))
     }
 
-    async fn delete(&self, id: &str) -> Result<bool> {
+    /// Move a row to the trash, at a version when given
+    pub async fn trash(conn: &mut AnyConnection, id: &str, version: Option<i64>) -> Result<bool> {
         let query = "
-            DELETE FROM projects
-            WHERE id = ?
+            UPDATE projects
+            SET deleted_at = $1
+            WHERE id = $2 AND deleted_at IS NULL AND version = COALESCE($3, version)
         ";
         
         let result = sqlx::query(query)
+            .bind(chrono::Utc::now().timestamp())
             .bind(id)
-            .execute(&self.pool)
+            .bind(version)
+            .execute(&mut *conn)
             .await?;
 
         Ok(result.rows_affected() > 0)
     }
+
+    pub async fn find_by_slug(&self, slug: &str) -> Result<Option<Project>> {
+        let query = "
+            SELECT id, slug, title, description, technologies, github_url, live_url, image_url, image_urls, year, highlights
+            FROM projects
+            WHERE slug = $1 AND deleted_at IS NULL
+        ";
+
+        let row = sqlx::query_as::<_, ProjectRow>(query)
+            .bind(slug)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(row.map(Project::from))
+    }
+
+    /// Projects using a technology, ignoring case, newest first
+    pub async fn find_by_technology(&self, technology: &str) -> Result<Vec<Project>> {
+        let query = "
+            SELECT id, slug, title, description, technologies, github_url, live_url, image_url, image_urls, year, highlights
+            FROM projects
+            WHERE deleted_at IS NULL AND id IN (SELECT project_id FROM project_technologies WHERE technology_key = LOWER(TRIM($1)))
+            ORDER BY year DESC, id
+        ";
+
+        let rows = sqlx::query_as::<_, ProjectRow>(query)
+            .bind(technology)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(rows.into_iter().map(Project::from).collect())
+    }
 }
 
 // No seeding functions - using real database data instead
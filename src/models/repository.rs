@@ -1,18 +1,42 @@
-use sqlx::{Pool, Sqlite};
-use anyhow::Result;
+use crate::config::database::{DbBackend, DbPool};
+use crate::error::{AppError, AppResult};
+use crate::models::trash::TRASH_KINDS;
+use anyhow::{Context, Result};
 use serde_json;
+use sqlx::{any::AnyRow, FromRow};
 use uuid::Uuid;
 
 // Generic repository trait for database operations
+#[allow(async_fn_in_trait)]
 pub trait Repository<T> {
-    fn new(pool: Pool<Sqlite>) -> Self;
+    /// Sort fields and filters accepted by find_page
+    const LIST: ListSpec;
+
+    fn new(pool: DbPool) -> Self;
     async fn find_all(&self) -> Result<Vec<T>>;
+    /// One page of rows, filtered and sorted as requested
+    async fn find_page(&self, query: &ListQuery) -> Result<Page<T>>;
     async fn find_by_id(&self, id: &str) -> Result<Option<T>>;
     async fn create(&self, item: T) -> Result<T>;
     async fn update(&self, id: &str, item: T) -> Result<T>;
     async fn delete(&self, id: &str) -> Result<bool>;
 }
 
+// Optimistic concurrency for tables with a `version` column, which every
+// update increases. Writes made at a version only apply if the row is still
+// at that version, so that one editor cannot silently overwrite another.
+#[allow(async_fn_in_trait)]
+pub trait Versioned<T>: Repository<T> {
+    /// Current version of a row; None if there is no such row
+    async fn find_version(&self, id: &str) -> Result<Option<i64>>;
+    /// Update a row still at `version`, returning the item as saved, or None
+    /// if the row changed in the meantime
+    async fn update_at(&self, id: &str, item: T, version: i64) -> Result<Option<T>>;
+    /// Delete a row still at `version`, returning false if it changed in the
+    /// meantime or is gone
+    async fn delete_at(&self, id: &str, version: i64) -> Result<bool>;
+}
+
 // Helper function to convert Vec<String> to JSON string
 pub fn vec_to_json<T: serde::Serialize>(vec: &[T]) -> Result<String> {
     Ok(serde_json::to_string(vec)?)
@@ -23,7 +47,312 @@ pub fn json_to_vec<T: serde::de::DeserializeOwned>(json: &str) -> Result<Vec<T>>
     Ok(serde_json::from_str(json)?)
 }
 
+// Slugs are stored as NULL until assigned, so that the unique index allows
+// several rows without one
+pub fn slug_value(slug: &str) -> Option<&str> {
+    Some(slug).filter(|slug| !slug.is_empty())
+}
+
 // Generate a new UUID
 pub fn generate_id() -> String {
     Uuid::new_v4().to_string()
 }
+
+/// Unix time of the latest write to any of the tables, moving rows to the
+/// trash included; None while they are empty
+pub async fn find_last_modified(pool: &DbPool, tables: &[&str]) -> Result<Option<i64>> {
+    let updated_at = DbBackend::of(pool).unix_seconds("updated_at");
+    let mut times = Vec::new();
+    for table in tables {
+        times.push(format!("SELECT MAX({}) AS t FROM {}", updated_at, table));
+        if TRASH_KINDS.contains(table) {
+            times.push(format!("SELECT MAX(deleted_at) AS t FROM {}", table));
+        }
+    }
+
+    let last_modified = sqlx::query_scalar::<_, Option<i64>>(&format!(
+        "SELECT MAX(t) FROM ({}) AS times",
+        times.join(" UNION ALL ")
+    ))
+        .fetch_one(pool)
+        .await?;
+
+    Ok(last_modified)
+}
+
+pub const DEFAULT_PER_PAGE: i64 = 20;
+pub const MAX_PER_PAGE: i64 = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortDirection {
+    Asc,
+    Desc,
+}
+
+impl SortDirection {
+    fn sql(self) -> &'static str {
+        match self {
+            SortDirection::Asc => "ASC",
+            SortDirection::Desc => "DESC",
+        }
+    }
+}
+
+// How a filter parameter restricts the rows of a list
+#[derive(Debug, Clone, Copy)]
+pub enum Filter {
+    /// Text column equal to the value
+    Equals(&'static str),
+    /// Integer column equal to the value
+    IntEquals(&'static str),
+    /// Rows linked to the tag named by the value, ignoring case, through a
+    /// join table: (join table, row id column, key column)
+    Linked(&'static str, &'static str, &'static str),
+    /// Rows linked to the technology named by the value or one of its
+    /// aliases, ignoring case: (join table, row id column)
+    Technology(&'static str, &'static str),
+    /// Date column ("YYYY-MM-DD") within the year given as value
+    Year(&'static str),
+}
+
+// How list queries map onto a table
+#[derive(Debug)]
+pub struct ListSpec {
+    pub table: &'static str,
+    /// Selected columns, as in a SELECT clause
+    pub columns: &'static str,
+    /// Sortable fields: (query parameter value, column)
+    pub sort_fields: &'static [(&'static str, &'static str)],
+    /// Column and direction used when no sort is requested
+    pub default_sort: (&'static str, SortDirection),
+    /// Filters: (query parameter name, filter)
+    pub filters: &'static [(&'static str, Filter)],
+}
+
+impl ListSpec {
+    fn filter(&self, name: &str) -> Option<Filter> {
+        self.filters.iter().find(|(n, _)| *n == name).map(|(_, filter)| *filter)
+    }
+}
+
+/// Page number and size with their defaults, rejecting out of range values
+pub fn page_bounds(page: Option<i64>, per_page: Option<i64>) -> AppResult<(i64, i64)> {
+    let page = page.unwrap_or(1);
+    if page < 1 {
+        return Err(AppError::bad_request("page must be 1 or greater"));
+    }
+
+    let per_page = per_page.unwrap_or(DEFAULT_PER_PAGE);
+    if !(1..=MAX_PER_PAGE).contains(&per_page) {
+        return Err(AppError::bad_request(format!("per_page must be between 1 and {}", MAX_PER_PAGE)));
+    }
+
+    Ok((page, per_page))
+}
+
+// A validated list request: page, sort order and filters
+#[derive(Debug, Clone)]
+pub struct ListQuery {
+    spec: &'static ListSpec,
+    /// Page number, starting at 1
+    pub page: i64,
+    pub per_page: i64,
+    /// Column and direction; None for the spec's default order
+    pub sort: Option<(&'static str, SortDirection)>,
+    pub filters: Vec<(&'static str, String)>,
+}
+
+impl ListQuery {
+    /// Check the page bounds and the sort field ("field" or "-field" for
+    /// descending order) against a spec
+    pub fn new(spec: &'static ListSpec, page: Option<i64>, per_page: Option<i64>, sort: Option<&str>) -> AppResult<Self> {
+        let (page, per_page) = page_bounds(page, per_page)?;
+
+        let sort = match sort.filter(|s| !s.is_empty()) {
+            Some(sort) => {
+                let (field, direction) = match sort.strip_prefix('-') {
+                    Some(field) => (field, SortDirection::Desc),
+                    None => (sort, SortDirection::Asc),
+                };
+
+                let column = spec.sort_fields.iter()
+                    .find(|(name, _)| *name == field)
+                    .map(|(_, column)| *column)
+                    .ok_or_else(|| {
+                        let allowed: Vec<&str> = spec.sort_fields.iter().map(|(name, _)| *name).collect();
+                        AppError::bad_request(format!("Cannot sort by '{}'; use one of: {}", field, allowed.join(", ")))
+                    })?;
+
+                Some((column, direction))
+            }
+            None => None,
+        };
+
+        Ok(Self {
+            spec,
+            page,
+            per_page,
+            sort,
+            filters: Vec::new(),
+        })
+    }
+
+    /// Add a filter declared by the spec; None leaves the list unfiltered
+    pub fn filter(mut self, name: &'static str, value: Option<String>) -> AppResult<Self> {
+        let Some(value) = value else {
+            return Ok(self);
+        };
+
+        let filter = self.spec.filter(name)
+            .ok_or_else(|| AppError::internal_error(format!("{} cannot be filtered by {}", self.spec.table, name)))?;
+
+        let valid = match filter {
+            Filter::Equals(_) | Filter::Linked(..) | Filter::Technology(..) => !value.trim().is_empty(),
+            Filter::IntEquals(_) => value.parse::<i64>().is_ok(),
+            Filter::Year(_) => value.len() == 4 && value.bytes().all(|b| b.is_ascii_digit()),
+        };
+
+        if !valid {
+            return Err(AppError::bad_request(format!("Invalid value '{}' for {}", value, name)));
+        }
+
+        self.filters.push((name, value));
+        Ok(self)
+    }
+
+    pub fn offset(&self) -> i64 {
+        (self.page - 1) * self.per_page
+    }
+}
+
+// One page of a list and the number of rows across all pages
+#[derive(Debug, Clone)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub total: i64,
+    pub page: i64,
+    pub per_page: i64,
+}
+
+impl<T> Page<T> {
+    pub fn map<U>(self, f: impl FnMut(T) -> U) -> Page<U> {
+        Page {
+            items: self.items.into_iter().map(f).collect(),
+            total: self.total,
+            page: self.page,
+            per_page: self.per_page,
+        }
+    }
+
+    /// Number of the last page, at least 1
+    pub fn last_page(&self) -> i64 {
+        ((self.total + self.per_page - 1) / self.per_page).max(1)
+    }
+}
+
+enum FilterValue {
+    Text(String),
+    Int(i64),
+}
+
+/// Fetch one page of the rows described by the query's spec. The SQL is
+/// built from the spec's column names only; filter values are always bound.
+pub async fn fetch_page<R>(pool: &DbPool, query: &ListQuery) -> Result<Page<R>>
+where
+    R: for<'r> FromRow<'r, AnyRow> + Send + Unpin,
+{
+    let spec = query.spec;
+    let mut conditions = Vec::new();
+    let mut values = Vec::new();
+
+    for (name, value) in &query.filters {
+        let filter = spec.filter(name)
+            .with_context(|| format!("{} cannot be filtered by {}", spec.table, name))?;
+        let n = values.len() + 1;
+
+        match filter {
+            Filter::Equals(column) => {
+                conditions.push(format!("{} = ${}", column, n));
+                values.push(FilterValue::Text(value.clone()));
+            }
+            Filter::IntEquals(column) => {
+                conditions.push(format!("{} = ${}", column, n));
+                values.push(FilterValue::Int(value.parse()?));
+            }
+            Filter::Linked(join_table, id_column, key_column) => {
+                // Keys are lowercased by the database, as when they are stored
+                conditions.push(format!(
+                    "id IN (SELECT {} FROM {} WHERE {} = LOWER(TRIM(${})))",
+                    id_column, join_table, key_column, n
+                ));
+                values.push(FilterValue::Text(value.clone()));
+            }
+            Filter::Technology(join_table, id_column) => {
+                conditions.push(format!(
+                    "id IN (SELECT {0} FROM {1} WHERE technology_key IN (
+                        SELECT LOWER(TRIM(${2}))
+                        UNION SELECT technology_key FROM technology_aliases WHERE alias_key = LOWER(TRIM(${2}))
+                    ))",
+                    id_column, join_table, n
+                ));
+                values.push(FilterValue::Text(value.clone()));
+            }
+            Filter::Year(column) => {
+                conditions.push(format!("{} LIKE ${}", column, n));
+                values.push(FilterValue::Text(format!("{}-%", value)));
+            }
+        }
+    }
+
+    let where_clause = if conditions.is_empty() {
+        String::new()
+    } else {
+        format!(" WHERE {}", conditions.join(" AND "))
+    };
+
+    let (sort_column, direction) = query.sort.unwrap_or(spec.default_sort);
+
+    // id breaks ties, so rows do not move between pages
+    let select = format!(
+        "SELECT {} FROM {}{} ORDER BY {} {}, id {} LIMIT ${} OFFSET ${}",
+        spec.columns,
+        spec.table,
+        where_clause,
+        sort_column,
+        direction.sql(),
+        direction.sql(),
+        values.len() + 1,
+        values.len() + 2
+    );
+    let count = format!("SELECT COUNT(*) FROM {}{}", spec.table, where_clause);
+
+    let mut rows = sqlx::query_as::<_, R>(&select);
+    let mut total = sqlx::query_scalar::<_, i64>(&count);
+    for value in &values {
+        match value {
+            FilterValue::Text(text) => {
+                rows = rows.bind(text);
+                total = total.bind(text);
+            }
+            FilterValue::Int(int) => {
+                rows = rows.bind(*int);
+                total = total.bind(*int);
+            }
+        }
+    }
+
+    let items = rows
+        .bind(query.per_page)
+        .bind(query.offset())
+        .fetch_all(pool)
+        .await?;
+
+    let total = total.fetch_one(pool).await?;
+
+    Ok(Page {
+        items,
+        total,
+        page: query.page,
+        per_page: query.per_page,
+    })
+}
@@ -0,0 +1,100 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use utoipa::ToSchema;
+
+// Kinds of items with a revision history; also the `target` of their revisions
+pub const REVISION_TARGETS: &[&str] = &["posts", "projects", "experiences", "profiles"];
+
+/// A saved version of a post, project, experience or the profile
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+#[schema(example = json!({
+    "number": 3,
+    "author_id": "8b0a6b55-3c52-4d47-9a8e-0f1f3c2b5a10",
+    "author_name": "Admin",
+    "restored_from": null,
+    "created_at": 1792137600,
+    "snapshot": { "bio": ["Software developer based in Brazil."], "social_links": [], "education": [], "languages": [] }
+}))]
+pub struct Revision {
+    /// Position in the item's history, from 1 for the oldest
+    pub number: i64,
+    /// User who saved this version; none for the version found when history
+    /// started being kept
+    pub author_id: Option<String>,
+    pub author_name: Option<String>,
+    /// Revision this version was restored from, if it was a restore
+    pub restored_from: Option<i64>,
+    /// When the version was saved (as UTC timestamp)
+    pub created_at: i64,
+    /// The whole item as it was saved
+    #[schema(value_type = Object)]
+    pub snapshot: Value,
+}
+
+/// A revision without its snapshot, as listed in an item's history
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct RevisionSummary {
+    pub number: i64,
+    pub author_id: Option<String>,
+    pub author_name: Option<String>,
+    pub restored_from: Option<i64>,
+    pub created_at: i64,
+}
+
+/// One value that differs between two revisions
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, ToSchema)]
+#[schema(example = json!({ "path": "/bio/0", "from": "Developer.", "to": "Software developer based in Brazil." }))]
+pub struct RevisionChange {
+    /// JSON Pointer to the value within the snapshot
+    pub path: String,
+    /// Value in the older revision; absent if it was added
+    #[schema(value_type = Option<Object>)]
+    pub from: Option<Value>,
+    /// Value in the newer revision; absent if it was removed
+    #[schema(value_type = Option<Object>)]
+    pub to: Option<Value>,
+}
+
+/// The changes from one revision to another
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct RevisionDiff {
+    pub from: i64,
+    pub to: i64,
+    pub changes: Vec<RevisionChange>,
+}
+
+fn escape_pointer(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
+
+fn diff_at(path: &str, from: Option<&Value>, to: Option<&Value>, changes: &mut Vec<RevisionChange>) {
+    match (from, to) {
+        (Some(Value::Object(a)), Some(Value::Object(b))) => {
+            for (key, value) in a {
+                diff_at(&format!("{}/{}", path, escape_pointer(key)), Some(value), b.get(key), changes);
+            }
+            for (key, value) in b.iter().filter(|(key, _)| !a.contains_key(*key)) {
+                diff_at(&format!("{}/{}", path, escape_pointer(key)), None, Some(value), changes);
+            }
+        }
+        (Some(Value::Array(a)), Some(Value::Array(b))) => {
+            for i in 0..a.len().max(b.len()) {
+                diff_at(&format!("{}/{}", path, i), a.get(i), b.get(i), changes);
+            }
+        }
+        (from, to) if from != to => changes.push(RevisionChange {
+            path: path.to_string(),
+            from: from.cloned(),
+            to: to.cloned(),
+        }),
+        _ => {}
+    }
+}
+
+/// Values that differ between two snapshots, compared field by field and
+/// array element by array element
+pub fn diff_snapshots(from: &Value, to: &Value) -> Vec<RevisionChange> {
+    let mut changes = Vec::new();
+    diff_at("", Some(from), Some(to), &mut changes);
+    changes
+}
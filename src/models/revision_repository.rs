@@ -0,0 +1,187 @@
+use anyhow::{bail, Result};
+use serde::Serialize;
+use sqlx::{AnyConnection, FromRow};
+
+use crate::auth::User;
+use crate::config::database::DbPool;
+use crate::models::repository::{fetch_page, generate_id, Filter, ListQuery, ListSpec, Page, SortDirection};
+use crate::models::revision::{Revision, RevisionSummary, REVISION_TARGETS};
+
+// Saved versions of posts, projects, experiences and the profile. Every write
+// through the API records the item as written, with its author.
+pub struct RevisionRepository {
+    pool: DbPool,
+}
+
+#[derive(Debug, FromRow)]
+struct RevisionRow {
+    number: i64,
+    author_id: Option<String>,
+    author_name: Option<String>,
+    restored_from: Option<i64>,
+    created_at: i64,
+    snapshot: String,
+}
+
+#[derive(Debug, FromRow)]
+struct RevisionSummaryRow {
+    number: i64,
+    author_id: Option<String>,
+    author_name: Option<String>,
+    restored_from: Option<i64>,
+    created_at: i64,
+}
+
+impl From<RevisionSummaryRow> for RevisionSummary {
+    fn from(row: RevisionSummaryRow) -> Self {
+        RevisionSummary {
+            number: row.number,
+            author_id: row.author_id,
+            author_name: row.author_name,
+            restored_from: row.restored_from,
+            created_at: row.created_at,
+        }
+    }
+}
+
+fn check_target(target: &str) -> Result<()> {
+    if !REVISION_TARGETS.contains(&target) {
+        bail!("{} has no revisions", target);
+    }
+    Ok(())
+}
+
+impl RevisionRepository {
+    pub const LIST: ListSpec = ListSpec {
+        table: "revisions",
+        columns: "id, number, author_id, author_name, restored_from, created_at",
+        sort_fields: &[("number", "number"), ("created_at", "created_at")],
+        default_sort: ("number", SortDirection::Desc),
+        filters: &[("target", Filter::Equals("target")), ("ref_id", Filter::Equals("ref_id"))],
+    };
+
+    pub fn new(pool: DbPool) -> Self {
+        Self { pool }
+    }
+
+    /// Save an item as written by a user, returning the revision number, or
+    /// None when it is the same as the latest revision. `before` is the item
+    /// as it was before the write: when the item has no history yet, it is
+    /// saved first, without an author, so that the write can be undone.
+    pub async fn record<T: Serialize>(
+        &self,
+        target: &'static str,
+        ref_id: &str,
+        before: Option<&T>,
+        after: &T,
+        author: &User,
+        restored_from: Option<i64>,
+    ) -> Result<Option<i64>> {
+        let mut tx = self.pool.begin().await?;
+        let number = Self::append(&mut tx, target, ref_id, before, after, author, restored_from).await?;
+        tx.commit().await?;
+        Ok(number)
+    }
+
+    /// `record` on a connection, for writes made within a transaction. Made
+    /// after the item is written in the same transaction, the row lock of the
+    /// item keeps concurrent writes from taking the same number.
+    pub async fn append<T: Serialize>(
+        conn: &mut AnyConnection,
+        target: &'static str,
+        ref_id: &str,
+        before: Option<&T>,
+        after: &T,
+        author: &User,
+        restored_from: Option<i64>,
+    ) -> Result<Option<i64>> {
+        check_target(target)?;
+        let snapshot = serde_json::to_string(after)?;
+        let now = chrono::Utc::now().timestamp();
+
+        let latest = sqlx::query_as::<_, (i64, String)>(
+            "SELECT number, snapshot FROM revisions WHERE target = $1 AND ref_id = $2 ORDER BY number DESC LIMIT 1",
+        )
+            .bind(target)
+            .bind(ref_id)
+            .fetch_optional(&mut *conn)
+            .await?;
+
+        let mut number = match (latest, before) {
+            (Some((_, latest)), _) if latest == snapshot => return Ok(None),
+            (Some((number, _)), _) => number,
+            (None, Some(before)) => {
+                sqlx::query(
+                    "INSERT INTO revisions (id, target, ref_id, number, snapshot, created_at)
+                    VALUES ($1, $2, $3, 1, $4, $5)",
+                )
+                    .bind(generate_id())
+                    .bind(target)
+                    .bind(ref_id)
+                    .bind(serde_json::to_string(before)?)
+                    .bind(now)
+                    .execute(&mut *conn)
+                    .await?;
+                1
+            }
+            (None, None) => 0,
+        };
+        number += 1;
+
+        sqlx::query(
+            "INSERT INTO revisions (id, target, ref_id, number, snapshot, author_id, author_name, restored_from, created_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)",
+        )
+            .bind(generate_id())
+            .bind(target)
+            .bind(ref_id)
+            .bind(number)
+            .bind(&snapshot)
+            .bind(&author.id)
+            .bind(&author.name)
+            .bind(restored_from)
+            .bind(now)
+            .execute(&mut *conn)
+            .await?;
+
+        Ok(Some(number))
+    }
+
+    /// One page of an item's revisions, newest first by default
+    pub async fn find_page(&self, target: &'static str, ref_id: &str, query: ListQuery) -> Result<Page<RevisionSummary>> {
+        check_target(target)?;
+        let query = query
+            .filter("target", Some(target.to_string()))?
+            .filter("ref_id", Some(ref_id.to_string()))?;
+
+        let page = fetch_page::<RevisionSummaryRow>(&self.pool, &query).await?;
+        Ok(page.map(RevisionSummary::from))
+    }
+
+    pub async fn find(&self, target: &'static str, ref_id: &str, number: i64) -> Result<Option<Revision>> {
+        check_target(target)?;
+
+        let row = sqlx::query_as::<_, RevisionRow>(
+            "SELECT number, author_id, author_name, restored_from, created_at, snapshot
+            FROM revisions
+            WHERE target = $1 AND ref_id = $2 AND number = $3",
+        )
+            .bind(target)
+            .bind(ref_id)
+            .bind(number)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        row.map(|row| {
+            Ok(Revision {
+                number: row.number,
+                author_id: row.author_id,
+                author_name: row.author_name,
+                restored_from: row.restored_from,
+                created_at: row.created_at,
+                snapshot: serde_json::from_str(&row.snapshot)?,
+            })
+        })
+            .transpose()
+    }
+}
@@ -0,0 +1,76 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+// Content types in the search index
+pub const SEARCH_KINDS: &[&str] = &["post", "project", "experience"];
+
+/// A post, project or experience matching a search, with the matched words
+/// wrapped in `<mark>` tags. Title and snippet are HTML-escaped.
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+#[schema(example = json!({
+    "kind": "post",
+    "id": "post-1",
+    "title": "The Future of <mark>Rust</mark> in Web Development",
+    "snippet": "Exploring how <mark>Rust</mark> is changing the landscape of web development…",
+    "score": 4.72
+}))]
+pub struct SearchHit {
+    /// "post", "project" or "experience"
+    pub kind: String,
+    /// Id of the post, project or experience
+    pub id: String,
+    pub title: String,
+    /// Excerpt of the text around the matches
+    pub snippet: String,
+    /// Relevance, higher is better; only comparable within one search
+    pub score: f64,
+}
+
+/// One word of a search. A trailing `*` in the query makes it a prefix.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchTerm {
+    pub word: String,
+    pub prefix: bool,
+}
+
+/// Words of a search query, all of which must match. Anything but letters and
+/// digits separates words, so user input never reaches the full-text query
+/// syntax of either backend.
+pub fn parse_search_terms(q: &str) -> Vec<SearchTerm> {
+    let mut terms = Vec::new();
+    let mut word = String::new();
+
+    for c in q.chars().chain(std::iter::once(' ')) {
+        if c.is_alphanumeric() {
+            word.extend(c.to_lowercase());
+            continue;
+        }
+
+        if !word.is_empty() {
+            terms.push(SearchTerm {
+                word: std::mem::take(&mut word),
+                prefix: c == '*',
+            });
+        }
+    }
+
+    terms
+}
+
+/// FTS5 query: each word quoted, prefixes followed by `*`
+pub fn fts5_query(terms: &[SearchTerm]) -> String {
+    terms
+        .iter()
+        .map(|term| format!("\"{}\"{}", term.word, if term.prefix { "*" } else { "" }))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// PostgreSQL tsquery for to_tsquery: words joined with `&`, prefixes marked `:*`
+pub fn tsquery(terms: &[SearchTerm]) -> String {
+    terms
+        .iter()
+        .map(|term| format!("{}{}", term.word, if term.prefix { ":*" } else { "" }))
+        .collect::<Vec<_>>()
+        .join(" & ")
+}
@@ -0,0 +1,115 @@
+use anyhow::Result;
+use sqlx::FromRow;
+
+use crate::config::database::{DbBackend, DbPool};
+use crate::models::repository::Page;
+use crate::models::search::{fts5_query, tsquery, SearchHit, SearchTerm};
+
+// Markers the database puts around matched words. They are replaced by
+// <mark> tags once the rest of the text has been HTML-escaped.
+const MATCH_START: &str = "\u{2}";
+const MATCH_END: &str = "\u{3}";
+
+// Ranked full-text search over the search_index table, which triggers keep in
+// sync with posts, projects and experiences. SQLite uses FTS5 with bm25
+// ranking; PostgreSQL a weighted tsvector with ts_rank_cd.
+pub struct SearchRepository {
+    pool: DbPool,
+}
+
+#[derive(Debug, FromRow)]
+struct SearchRow {
+    kind: String,
+    ref_id: String,
+    title: String,
+    snippet: String,
+    score: f64,
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+fn highlight(text: &str) -> String {
+    escape_html(text)
+        .replace(MATCH_START, "<mark>")
+        .replace(MATCH_END, "</mark>")
+}
+
+impl From<SearchRow> for SearchHit {
+    fn from(row: SearchRow) -> Self {
+        SearchHit {
+            kind: row.kind,
+            id: row.ref_id,
+            title: highlight(&row.title),
+            snippet: highlight(row.snippet.trim()),
+            score: row.score,
+        }
+    }
+}
+
+impl SearchRepository {
+    pub fn new(pool: DbPool) -> Self {
+        Self { pool }
+    }
+
+    /// Items matching every term, best first, optionally of one kind only
+    pub async fn search(&self, terms: &[SearchTerm], kind: Option<&str>, page: i64, per_page: i64) -> Result<Page<SearchHit>> {
+        let (select, count, query) = match DbBackend::of(&self.pool) {
+            DbBackend::Sqlite => (
+                // bm25 weights: kind and ref_id are not indexed, titles count most
+                "SELECT kind, ref_id, highlight(search_index, 2, $3, $4) AS title,
+                    snippet(search_index, 3, $3, $4, '…', 24) AS snippet,
+                    -bm25(search_index, 0.0, 0.0, 10.0, 1.0, 5.0) AS score
+                FROM search_index
+                WHERE search_index MATCH $1 AND ($2 = '' OR kind = $2)
+                ORDER BY score DESC, kind, ref_id
+                LIMIT $5 OFFSET $6",
+                "SELECT COUNT(*) FROM search_index WHERE search_index MATCH $1 AND ($2 = '' OR kind = $2)",
+                fts5_query(terms),
+            ),
+            DbBackend::Postgres => (
+                "SELECT kind, ref_id,
+                    ts_headline('english', title, query, 'HighlightAll=true, StartSel=' || $3 || ', StopSel=' || $4) AS title,
+                    ts_headline('english', body, query, 'MaxWords=24, MinWords=12, MaxFragments=2, FragmentDelimiter=\" … \", StartSel=' || $3 || ', StopSel=' || $4) AS snippet,
+                    CAST(ts_rank_cd(document, query) AS DOUBLE PRECISION) AS score
+                FROM search_index, to_tsquery('english', $1) AS query
+                WHERE document @@ query AND ($2 = '' OR kind = $2)
+                ORDER BY score DESC, kind, ref_id
+                LIMIT $5 OFFSET $6",
+                "SELECT COUNT(*) FROM search_index WHERE document @@ to_tsquery('english', $1) AND ($2 = '' OR kind = $2)",
+                tsquery(terms),
+            ),
+        };
+
+        // An empty kind matches every kind
+        let kind = kind.unwrap_or_default();
+
+        let rows = sqlx::query_as::<_, SearchRow>(select)
+            .bind(&query)
+            .bind(kind)
+            .bind(MATCH_START)
+            .bind(MATCH_END)
+            .bind(per_page)
+            .bind((page - 1) * per_page)
+            .fetch_all(&self.pool)
+            .await?;
+
+        let total = sqlx::query_scalar::<_, i64>(count)
+            .bind(&query)
+            .bind(kind)
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(Page {
+            items: rows.into_iter().map(SearchHit::from).collect(),
+            total,
+            page,
+            per_page,
+        })
+    }
+}
@@ -1,10 +1,144 @@
+use std::collections::HashMap;
+
 use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
 
+use crate::models::technology::technology_key;
+
 #[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+#[schema(example = json!({
+    "category": "Languages",
+    "items": [
+        { "name": "Rust", "proficiency": "advanced", "years": 4, "order": 0 },
+        { "name": "TypeScript", "proficiency": "expert", "years": 7, "order": 1 }
+    ]
+}))]
 pub struct Skill {
     pub category: String,
-    pub items: Vec<String>,
+    /// Skills of the category in display order
+    pub items: Vec<SkillItem>,
+}
+
+/// How well a skill is mastered
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Proficiency {
+    Beginner,
+    Intermediate,
+    Advanced,
+    Expert,
+}
+
+/// One skill of a category. Plain strings are accepted in its place, as a
+/// skill with only a name.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, ToSchema)]
+#[serde(from = "SkillItemInput")]
+pub struct SkillItem {
+    /// Technology name, normalised against the technology registry
+    pub name: String,
+    pub proficiency: Option<Proficiency>,
+    /// Years of experience with the skill
+    pub years: Option<u32>,
+    /// Display position within the category, lowest first; skills with the
+    /// same order keep the order they were given in
+    pub order: i32,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum SkillItemInput {
+    Name(String),
+    Item {
+        name: String,
+        proficiency: Option<Proficiency>,
+        years: Option<u32>,
+        #[serde(default)]
+        order: i32,
+    },
+}
+
+impl From<SkillItemInput> for SkillItem {
+    fn from(input: SkillItemInput) -> Self {
+        match input {
+            SkillItemInput::Name(name) => SkillItem::new(name),
+            SkillItemInput::Item { name, proficiency, years, order } => SkillItem { name, proficiency, years, order },
+        }
+    }
+}
+
+impl SkillItem {
+    pub fn new(name: String) -> Self {
+        Self { name, proficiency: None, years: None, order: 0 }
+    }
+}
+
+impl Skill {
+    /// Put the items in display order
+    pub fn sort_items(&mut self) {
+        self.items.sort_by_key(|item| item.order);
+    }
+}
+
+/// A project whose technologies mention a skill
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct ProjectReference {
+    pub id: String,
+    pub slug: String,
+    pub title: String,
+    pub year: i32,
+}
+
+/// An experience whose technologies mention a skill
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct ExperienceReference {
+    pub id: String,
+    pub company: String,
+    pub position: String,
+    pub start_date: String,
+    pub end_date: Option<String>,
+}
+
+/// Projects and experiences by the key of each technology they use, newest first
+#[derive(Debug, Default, Clone)]
+pub struct SkillEvidence {
+    pub projects: HashMap<String, Vec<ProjectReference>>,
+    pub experiences: HashMap<String, Vec<ExperienceReference>>,
+}
+
+/// A skill with the projects and experiences that use it
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct EnrichedSkillItem {
+    #[serde(flatten)]
+    pub item: SkillItem,
+    /// Newest first
+    pub projects: Vec<ProjectReference>,
+    /// Most recent first
+    pub experiences: Vec<ExperienceReference>,
+}
+
+/// A skill category with the evidence for each of its skills
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct EnrichedSkill {
+    pub category: String,
+    pub items: Vec<EnrichedSkillItem>,
+}
+
+impl EnrichedSkill {
+    pub fn new(skill: Skill, evidence: &SkillEvidence) -> Self {
+        let items = skill.items
+            .into_iter()
+            .map(|item| {
+                let key = technology_key(&item.name);
+                EnrichedSkillItem {
+                    projects: evidence.projects.get(&key).cloned().unwrap_or_default(),
+                    experiences: evidence.experiences.get(&key).cloned().unwrap_or_default(),
+                    item,
+                }
+            })
+            .collect();
+
+        Self { category: skill.category, items }
+    }
 }
 
 // No more mock data - using database instead
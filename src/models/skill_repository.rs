@@ -1,12 +1,15 @@
 use anyhow::Result;
-use sqlx::{Pool, Sqlite, FromRow};
+use sqlx::{AnyConnection, FromRow};
 use serde::{Serialize, Deserialize};
 
-use crate::models::skill::Skill;
-use crate::models::repository::{Repository, vec_to_json, json_to_vec};
+use crate::config::database::DbPool;
+use crate::models::skill::{ExperienceReference, ProjectReference, Skill, SkillEvidence, SkillItem};
+use crate::models::repository::{Repository, Versioned, vec_to_json, json_to_vec, fetch_page, ListQuery, ListSpec, Page, SortDirection};
+use crate::models::technology::{normalize_skill_items, technology_key};
+use crate::models::technology_repository::canonical_names;
 
 pub struct SkillRepository {
-    pool: Pool<Sqlite>,
+    pool: DbPool,
 }
 
 #[derive(Debug, FromRow, Serialize, Deserialize)]
@@ -16,113 +19,249 @@ struct SkillRow {
     items: String,
 }
 
-impl Repository<Skill> for SkillRepository {
-    fn new(pool: Pool<Sqlite>) -> Self {
-        Self { pool }
-    }
+impl From<SkillRow> for Skill {
+    fn from(row: SkillRow) -> Self {
+        let items: Vec<SkillItem> = json_to_vec(&row.items).unwrap_or_default();
 
-    async fn find_all(&self) -> Result<Vec<Skill>> {
-        let query = "
-            SELECT id, category, items
-            FROM skills
-            ORDER BY category ASC
-        ";
-        
-        let rows = sqlx::query_as::<_, SkillRow>(query)
-            .fetch_all(&self.pool)
-            .await?;
+        let mut skill = Skill {
+            category: row.category,
+            items,
+        };
+        skill.sort_items();
+        skill
+    }
+}
 
-        let skills = rows
-            .into_iter()
-            .map(|row| {
-                let items: Vec<String> = json_to_vec(&row.items).unwrap_or_default();
+#[derive(Debug, FromRow)]
+struct ProjectEvidenceRow {
+    technology: String,
+    id: String,
+    slug: Option<String>,
+    title: String,
+    year: i32,
+}
 
-                Skill {
-                    category: row.category,
-                    items,
-                }
-            })
-            .collect();
+#[derive(Debug, FromRow)]
+struct ExperienceEvidenceRow {
+    technology: String,
+    id: String,
+    company: String,
+    position: String,
+    start_date: String,
+    end_date: Option<String>,
+}
 
-        Ok(skills)
-    }
+impl SkillRepository {
+    // Reads and writes on a connection, which the methods above take from the
+    // pool, and an import runs within one transaction
 
-    async fn find_by_id(&self, id: &str) -> Result<Option<Skill>> {
+    /// Every skill category not in the trash with its id, by name
+    pub async fn fetch_all(conn: &mut AnyConnection) -> Result<Vec<(String, Skill)>> {
         let query = "
             SELECT id, category, items
             FROM skills
-            WHERE id = ?
+            WHERE deleted_at IS NULL
+            ORDER BY category ASC
         ";
         
-        let row = sqlx::query_as::<_, SkillRow>(query)
-            .bind(id)
-            .fetch_optional(&self.pool)
+        let rows = sqlx::query_as::<_, SkillRow>(query)
+            .fetch_all(&mut *conn)
             .await?;
 
-        let skill = match row {
-            Some(row) => {
-                let items: Vec<String> = json_to_vec(&row.items).unwrap_or_default();
-
-                Some(Skill {
-                    category: row.category,
-                    items,
-                })
-            }
-            None => None,
-        };
-
-        Ok(skill)
+        Ok(rows.into_iter().map(|row| (row.id.clone(), Skill::from(row))).collect())
     }
 
-    async fn create(&self, item: Skill) -> Result<Skill> {
+    /// Save a new skill category
+    pub async fn insert(conn: &mut AnyConnection, mut item: Skill) -> Result<Skill> {
+        item.items = normalize_skill_items(&item.items, &canonical_names(conn).await?);
+        item.sort_items();
         let id = crate::models::repository::generate_id();
         let items = vec_to_json(&item.items)?;
 
         let query = "
             INSERT INTO skills (id, category, items)
-            VALUES (?, ?, ?)
+            VALUES ($1, $2, $3)
         ";
         
         sqlx::query(query)
             .bind(&id)
             .bind(&item.category)
             .bind(items)
-            .execute(&self.pool)
+            .execute(&mut *conn)
             .await?;
 
         Ok(item)
     }
 
-    async fn update(&self, id: &str, item: Skill) -> Result<Skill> {
+    /// Write an item over a row, at a version when given, returning the item
+    /// as saved and whether the row was written
+    pub async fn write(conn: &mut AnyConnection, id: &str, mut item: Skill, version: Option<i64>) -> Result<(Skill, bool)> {
+        item.items = normalize_skill_items(&item.items, &canonical_names(conn).await?);
+        item.sort_items();
         let items = vec_to_json(&item.items)?;
 
         let query = "
             UPDATE skills
-            SET category = ?, items = ?, updated_at = CURRENT_TIMESTAMP
-            WHERE id = ?
+            SET category = $1, items = $2, updated_at = CURRENT_TIMESTAMP,
+                version = version + 1
+            WHERE id = $3 AND deleted_at IS NULL AND version = COALESCE($4, version)
         ";
         
-        sqlx::query(query)
+        let result = sqlx::query(query)
             .bind(&item.category)
             .bind(items)
             .bind(id)
-            .execute(&self.pool)
+            .bind(version)
+            .execute(&mut *conn)
             .await?;
 
-        Ok(item)
+        Ok((item, result.rows_affected() > 0))
     }
 
-    async fn delete(&self, id: &str) -> Result<bool> {
+    /// Move a row to the trash, at a version when given
+    pub async fn trash(conn: &mut AnyConnection, id: &str, version: Option<i64>) -> Result<bool> {
         let query = "
-            DELETE FROM skills
-            WHERE id = ?
+            UPDATE skills
+            SET deleted_at = $1
+            WHERE id = $2 AND deleted_at IS NULL AND version = COALESCE($3, version)
         ";
         
         let result = sqlx::query(query)
+            .bind(chrono::Utc::now().timestamp())
             .bind(id)
-            .execute(&self.pool)
+            .bind(version)
+            .execute(&mut *conn)
             .await?;
 
         Ok(result.rows_affected() > 0)
     }
+
+    /// Id of the skill category with a name, if it is not deleted
+    pub async fn find_id_by_category(&self, category: &str) -> Result<Option<String>> {
+        let id = sqlx::query_scalar("SELECT id FROM skills WHERE category = $1 AND deleted_at IS NULL ORDER BY id LIMIT 1")
+            .bind(category)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(id)
+    }
+
+    /// Projects and experiences by the technologies they use, matched through
+    /// the technology registry
+    pub async fn find_evidence(&self) -> Result<SkillEvidence> {
+        let mut evidence = SkillEvidence::default();
+
+        let projects = sqlx::query_as::<_, ProjectEvidenceRow>(
+            "SELECT technologies.name AS technology, projects.id, projects.slug, projects.title, projects.year
+            FROM project_technologies
+            JOIN technologies ON technologies.key = project_technologies.technology_key
+            JOIN projects ON projects.id = project_technologies.project_id
+            ORDER BY projects.year DESC, projects.id",
+        )
+            .fetch_all(&self.pool)
+            .await?;
+
+        for row in projects {
+            evidence.projects.entry(technology_key(&row.technology)).or_default().push(ProjectReference {
+                id: row.id,
+                slug: row.slug.unwrap_or_default(),
+                title: row.title,
+                year: row.year,
+            });
+        }
+
+        let experiences = sqlx::query_as::<_, ExperienceEvidenceRow>(
+            "SELECT technologies.name AS technology, experiences.id, experiences.company, experiences.position,
+                experiences.start_date, experiences.end_date
+            FROM experience_technologies
+            JOIN technologies ON technologies.key = experience_technologies.technology_key
+            JOIN experiences ON experiences.id = experience_technologies.experience_id
+            ORDER BY experiences.start_date DESC, experiences.id",
+        )
+            .fetch_all(&self.pool)
+            .await?;
+
+        for row in experiences {
+            evidence.experiences.entry(technology_key(&row.technology)).or_default().push(ExperienceReference {
+                id: row.id,
+                company: row.company,
+                position: row.position,
+                start_date: row.start_date,
+                end_date: row.end_date,
+            });
+        }
+
+        Ok(evidence)
+    }
+}
+
+impl Repository<Skill> for SkillRepository {
+    const LIST: ListSpec = ListSpec {
+        table: "(SELECT * FROM skills WHERE deleted_at IS NULL) AS skills",
+        columns: "id, category, items",
+        sort_fields: &[("category", "category")],
+        default_sort: ("category", SortDirection::Asc),
+        filters: &[],
+    };
+
+    fn new(pool: DbPool) -> Self {
+        Self { pool }
+    }
+
+    async fn find_all(&self) -> Result<Vec<Skill>> {
+        let skills = Self::fetch_all(&mut *self.pool.acquire().await?).await?;
+        Ok(skills.into_iter().map(|(_, skill)| skill).collect())
+    }
+
+    async fn find_page(&self, query: &ListQuery) -> Result<Page<Skill>> {
+        let page = fetch_page::<SkillRow>(&self.pool, query).await?;
+        Ok(page.map(Skill::from))
+    }
+
+    async fn find_by_id(&self, id: &str) -> Result<Option<Skill>> {
+        let query = "
+            SELECT id, category, items
+            FROM skills
+            WHERE id = $1 AND deleted_at IS NULL
+        ";
+        
+        let row = sqlx::query_as::<_, SkillRow>(query)
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(row.map(Skill::from))
+    }
+
+    async fn create(&self, item: Skill) -> Result<Skill> {
+        Self::insert(&mut *self.pool.acquire().await?, item).await
+    }
+
+    async fn update(&self, id: &str, item: Skill) -> Result<Skill> {
+        Ok(Self::write(&mut *self.pool.acquire().await?, id, item, None).await?.0)
+    }
+
+    async fn delete(&self, id: &str) -> Result<bool> {
+        Self::trash(&mut *self.pool.acquire().await?, id, None).await
+    }
+}
+
+impl Versioned<Skill> for SkillRepository {
+    async fn find_version(&self, id: &str) -> Result<Option<i64>> {
+        let version = sqlx::query_scalar("SELECT version FROM skills WHERE id = $1 AND deleted_at IS NULL")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(version)
+    }
+
+    async fn update_at(&self, id: &str, item: Skill, version: i64) -> Result<Option<Skill>> {
+        let (item, saved) = Self::write(&mut *self.pool.acquire().await?, id, item, Some(version)).await?;
+        Ok(saved.then_some(item))
+    }
+
+    async fn delete_at(&self, id: &str, version: i64) -> Result<bool> {
+        Self::trash(&mut *self.pool.acquire().await?, id, Some(version)).await
+    }
 }
@@ -0,0 +1,63 @@
+use unicode_normalization::{char::is_combining_mark, UnicodeNormalization};
+
+// Longest slug, in bytes
+pub const MAX_SLUG_LENGTH: usize = 80;
+
+// Latin letters that do not decompose into an ASCII letter and accents
+fn transliterate(c: char) -> Option<&'static str> {
+    Some(match c {
+        'ß' => "ss",
+        'æ' | 'Æ' => "ae",
+        'œ' | 'Œ' => "oe",
+        'ø' | 'Ø' => "o",
+        'đ' | 'Đ' | 'ð' | 'Ð' => "d",
+        'ł' | 'Ł' => "l",
+        'þ' | 'Þ' => "th",
+        'ı' => "i",
+        _ => return None,
+    })
+}
+
+/// URL-friendly version of a title: lowercase ASCII words separated by
+/// dashes, with accents removed, e.g. "Crème Brûlée & Ça" becomes
+/// "creme-brulee-ca". Empty when the title has no Latin letters or digits.
+pub fn slugify(title: &str) -> String {
+    let mut slug = String::new();
+    let mut separate = false;
+
+    for c in title.nfkd().filter(|c| !is_combining_mark(*c)) {
+        let ascii = match transliterate(c) {
+            Some(ascii) => ascii.to_string(),
+            None if c.is_ascii_alphanumeric() => c.to_ascii_lowercase().to_string(),
+            None => {
+                separate = true;
+                continue;
+            }
+        };
+
+        if separate && !slug.is_empty() {
+            slug.push('-');
+        }
+        separate = false;
+        slug.push_str(&ascii);
+    }
+
+    if slug.len() > MAX_SLUG_LENGTH {
+        // Cut at the last word boundary that fits
+        slug.truncate(MAX_SLUG_LENGTH);
+        if let Some(dash) = slug.rfind('-') {
+            slug.truncate(dash);
+        }
+    }
+
+    slug
+}
+
+/// Whether a slug chosen by an editor has the form slugify produces
+pub fn is_valid_slug(slug: &str) -> bool {
+    !slug.is_empty()
+        && slug.len() <= MAX_SLUG_LENGTH
+        && slug.split('-').all(|word| {
+            !word.is_empty() && word.bytes().all(|b| b.is_ascii_lowercase() || b.is_ascii_digit())
+        })
+}
@@ -0,0 +1,242 @@
+use anyhow::{bail, Result};
+use sqlx::{AnyConnection, FromRow};
+
+use crate::config::database::DbPool;
+use crate::error::{AppError, AppResult};
+use crate::models::slug::{is_valid_slug, slugify, MAX_SLUG_LENGTH};
+
+// Tables whose rows have a slug; also the `target` of their aliases
+pub const SLUG_TABLES: &[&str] = &["posts", "projects"];
+
+// Unique slugs of posts and projects, and the aliases left behind when a
+// slug changes so that old permalinks can redirect to the current one.
+pub struct SlugRepository {
+    pool: DbPool,
+}
+
+#[derive(Debug, FromRow)]
+struct UnsluggedRow {
+    id: String,
+    title: String,
+}
+
+fn check_table(table: &str) -> Result<()> {
+    if !SLUG_TABLES.contains(&table) {
+        bail!("{} has no slugs", table);
+    }
+    Ok(())
+}
+
+/// Error of a failed insert or update of a row with a slug. `choose` checks
+/// the slug before the write's transaction, so another write may take it in
+/// between; the unique violation is then a 409 Conflict, as a slug found
+/// taken would be, and any other error a 500 saying `context`.
+pub fn slug_write_error(e: anyhow::Error, context: &str) -> AppError {
+    let unique_violation = e.downcast_ref::<sqlx::Error>()
+        .and_then(|e| e.as_database_error())
+        .is_some_and(|e| e.is_unique_violation());
+
+    if unique_violation {
+        AppError::conflict("The slug was taken by another write; choose another one or try again")
+    } else {
+        AppError::internal_error(format!("{}: {}", context, e))
+    }
+}
+
+impl SlugRepository {
+    pub fn new(pool: DbPool) -> Self {
+        Self { pool }
+    }
+
+    /// Whether a slug is used by another row of the table, as its slug or as an alias
+    pub async fn is_taken(&self, table: &'static str, slug: &str, id: &str) -> Result<bool> {
+        Self::taken(&mut *self.pool.acquire().await?, table, slug, id).await
+    }
+
+    /// A free slug for a row, from its title, adding -2, -3... if needed.
+    /// Titles without Latin letters or digits get a slug from the id.
+    pub async fn unique_slug(&self, table: &'static str, title: &str, id: &str) -> Result<String> {
+        Self::free_slug(&mut *self.pool.acquire().await?, table, title, id).await
+    }
+
+    // Reads and writes on a connection, which the methods above and below
+    // take from the pool, and an import runs within one transaction
+
+    /// `is_taken` on a connection
+    pub async fn taken(conn: &mut AnyConnection, table: &'static str, slug: &str, id: &str) -> Result<bool> {
+        check_table(table)?;
+
+        // The table name comes from SLUG_TABLES, never from the request
+        let query = format!(
+            "SELECT COUNT(*) FROM (
+                SELECT id FROM {} WHERE slug = $1 AND id <> $2
+                UNION ALL
+                SELECT ref_id FROM slug_aliases WHERE target = $3 AND slug = $1 AND ref_id <> $2
+            ) AS taken",
+            table
+        );
+
+        let count: i64 = sqlx::query_scalar(&query)
+            .bind(slug)
+            .bind(id)
+            .bind(table)
+            .fetch_one(&mut *conn)
+            .await?;
+
+        Ok(count > 0)
+    }
+
+    /// Ids of the rows of the table using a slug, as their slug or as an alias
+    pub async fn holders(conn: &mut AnyConnection, table: &'static str, slug: &str) -> Result<Vec<String>> {
+        check_table(table)?;
+
+        let query = format!(
+            "SELECT id FROM {} WHERE slug = $1
+            UNION
+            SELECT ref_id FROM slug_aliases WHERE target = $2 AND slug = $1",
+            table
+        );
+
+        let ids = sqlx::query_scalar(&query)
+            .bind(slug)
+            .bind(table)
+            .fetch_all(&mut *conn)
+            .await?;
+
+        Ok(ids)
+    }
+
+    /// Take a slug from the other row using it as its slug, if any, so that it
+    /// can be given to the row `id`; the other row is left without a slug
+    pub async fn release(conn: &mut AnyConnection, table: &'static str, slug: &str, id: &str) -> Result<()> {
+        check_table(table)?;
+
+        sqlx::query(&format!("UPDATE {} SET slug = NULL WHERE slug = $1 AND id <> $2", table))
+            .bind(slug)
+            .bind(id)
+            .execute(&mut *conn)
+            .await?;
+
+        Ok(())
+    }
+
+    /// `unique_slug` on a connection
+    pub async fn free_slug(conn: &mut AnyConnection, table: &'static str, title: &str, id: &str) -> Result<String> {
+        let mut base = slugify(title);
+        if base.is_empty() {
+            let prefix: String = id.chars().filter(char::is_ascii_alphanumeric).take(8).collect();
+            base = format!("{}-{}", table.trim_end_matches('s'), prefix.to_ascii_lowercase());
+        }
+
+        let mut slug = base.clone();
+        let mut n = 1;
+        while Self::taken(conn, table, &slug, id).await? {
+            n += 1;
+            slug = format!("{}-{}", base, n);
+        }
+
+        Ok(slug)
+    }
+
+    /// Slug of a row being created or updated: the slug requested by the
+    /// editor if it is well-formed and free, else the current slug, else one
+    /// generated from the title
+    pub async fn choose(
+        &self,
+        table: &'static str,
+        requested: Option<&str>,
+        current: &str,
+        title: &str,
+        id: &str,
+    ) -> AppResult<String> {
+        let db_error = |e: anyhow::Error| AppError::internal_error(format!("Failed to check slug: {}", e));
+
+        match requested {
+            Some(slug) if slug == current => Ok(current.to_string()),
+            Some(slug) => {
+                if !is_valid_slug(slug) {
+                    return Err(AppError::bad_request(format!(
+                        "Invalid slug '{}': use lowercase letters, digits and single dashes, at most {} characters",
+                        slug,
+                        MAX_SLUG_LENGTH
+                    )));
+                }
+
+                if self.is_taken(table, slug, id).await.map_err(db_error)? {
+                    return Err(AppError::conflict(format!("Slug '{}' is already in use", slug)));
+                }
+
+                Ok(slug.to_string())
+            }
+            None if !current.is_empty() => Ok(current.to_string()),
+            None => self.unique_slug(table, title, id).await.map_err(db_error),
+        }
+    }
+
+    /// Id of the row an old slug pointed to
+    pub async fn find_alias(&self, table: &'static str, slug: &str) -> Result<Option<String>> {
+        let ref_id = sqlx::query_scalar("SELECT ref_id FROM slug_aliases WHERE target = $1 AND slug = $2")
+            .bind(table)
+            .bind(slug)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(ref_id)
+    }
+
+    /// Record that a row's slug changed, within the transaction that writes
+    /// the row: the old slug becomes an alias, and an alias equal to the new
+    /// slug is no longer needed
+    pub async fn write_change(conn: &mut AnyConnection, table: &'static str, id: &str, old_slug: &str, new_slug: &str) -> Result<()> {
+        check_table(table)?;
+        if old_slug == new_slug {
+            return Ok(());
+        }
+
+        sqlx::query("DELETE FROM slug_aliases WHERE target = $1 AND slug = $2")
+            .bind(table)
+            .bind(new_slug)
+            .execute(&mut *conn)
+            .await?;
+
+        if !old_slug.is_empty() {
+            sqlx::query("INSERT INTO slug_aliases (target, slug, ref_id, created_at) VALUES ($1, $2, $3, $4)")
+                .bind(table)
+                .bind(old_slug)
+                .bind(id)
+                .bind(chrono::Utc::now().timestamp())
+                .execute(&mut *conn)
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Give a slug to the rows created before slugs existed, returning how many
+    pub async fn backfill(&self) -> Result<u64> {
+        let mut assigned = 0;
+
+        for &table in SLUG_TABLES {
+            let rows = sqlx::query_as::<_, UnsluggedRow>(&format!(
+                "SELECT id, title FROM {} WHERE slug IS NULL ORDER BY created_at, id",
+                table
+            ))
+                .fetch_all(&self.pool)
+                .await?;
+
+            for row in rows {
+                let slug = self.unique_slug(table, &row.title, &row.id).await?;
+
+                sqlx::query(&format!("UPDATE {} SET slug = $1, updated_at = CURRENT_TIMESTAMP, version = version + 1 WHERE id = $2", table))
+                    .bind(&slug)
+                    .bind(&row.id)
+                    .execute(&self.pool)
+                    .await?;
+
+                assigned += 1;
+            }
+        }
+
+        Ok(assigned)
+    }
+}
@@ -0,0 +1,60 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::models::experience::Experience;
+use crate::models::post::Post;
+use crate::models::project::Project;
+
+/// A post tag and how many published posts use it
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, ToSchema)]
+#[schema(example = json!({
+    "name": "Rust",
+    "post_count": 4
+}))]
+pub struct Tag {
+    /// Spelling of the tag's first use; tags match ignoring case
+    pub name: String,
+    pub post_count: i64,
+}
+
+/// A technology and how many projects and experiences use it
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, ToSchema)]
+#[schema(example = json!({
+    "name": "TypeScript",
+    "category": "language",
+    "icon": "simple-icons:typescript",
+    "project_count": 3,
+    "experience_count": 2,
+    "usage_count": 5
+}))]
+pub struct Technology {
+    /// Canonical name from the technology registry
+    pub name: String,
+    /// Kind of technology, e.g. "language", "framework" or "database"
+    pub category: Option<String>,
+    /// Icon identifier for the frontend
+    pub icon: Option<String>,
+    pub project_count: i64,
+    pub experience_count: i64,
+    /// Projects and experiences together
+    pub usage_count: i64,
+}
+
+/// A tag with the published posts using it, newest first
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct TagDetail {
+    #[serde(flatten)]
+    pub tag: Tag,
+    pub posts: Vec<Post>,
+}
+
+/// A technology with the projects and experiences using it
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct TechnologyDetail {
+    #[serde(flatten)]
+    pub technology: Technology,
+    /// Newest first
+    pub projects: Vec<Project>,
+    /// Most recent first
+    pub experiences: Vec<Experience>,
+}
@@ -0,0 +1,140 @@
+use anyhow::Result;
+use sqlx::FromRow;
+
+use crate::config::database::DbPool;
+use crate::models::repository::{fetch_page, Filter, ListQuery, ListSpec, Page, SortDirection};
+use crate::models::tag::{Tag, Technology};
+
+// Usage counts of tags and technologies, read from the join tables that
+// triggers keep in sync with the JSON array columns. Only tags of published
+// posts are counted, and unused names are left out; the technology registry
+// itself is managed through TechnologyRepository.
+pub struct TagRepository {
+    pool: DbPool,
+}
+
+#[derive(Debug, FromRow)]
+struct TagRow {
+    name: String,
+    post_count: i64,
+}
+
+#[derive(Debug, FromRow)]
+struct TechnologyRow {
+    name: String,
+    category: Option<String>,
+    icon: Option<String>,
+    project_count: i64,
+    experience_count: i64,
+    usage_count: i64,
+}
+
+impl From<TagRow> for Tag {
+    fn from(row: TagRow) -> Self {
+        Tag {
+            name: row.name,
+            post_count: row.post_count,
+        }
+    }
+}
+
+impl From<TechnologyRow> for Technology {
+    fn from(row: TechnologyRow) -> Self {
+        Technology {
+            name: row.name,
+            category: row.category,
+            icon: row.icon,
+            project_count: row.project_count,
+            experience_count: row.experience_count,
+            usage_count: row.usage_count,
+        }
+    }
+}
+
+impl TagRepository {
+    // The lists are derived tables, with the key as id so that fetch_page can
+    // break ties with it
+    pub const TAG_LIST: ListSpec = ListSpec {
+        table: "(
+            SELECT tags.key AS id, tags.name AS name, COUNT(*) AS post_count
+            FROM tags
+            JOIN post_tags ON post_tags.tag_key = tags.key
+            JOIN posts ON posts.id = post_tags.post_id
+            WHERE posts.status = 'published'
+            GROUP BY tags.key, tags.name
+        ) AS tag_counts",
+        columns: "name, post_count",
+        sort_fields: &[("name", "id"), ("count", "post_count")],
+        default_sort: ("id", SortDirection::Asc),
+        filters: &[],
+    };
+
+    pub const TECHNOLOGY_LIST: ListSpec = ListSpec {
+        table: "(
+            SELECT id, name, category, icon, project_count, experience_count, project_count + experience_count AS usage_count
+            FROM (
+                SELECT technologies.key AS id, technologies.name AS name, technologies.category AS category, technologies.icon AS icon,
+                    (SELECT COUNT(*) FROM project_technologies WHERE technology_key = technologies.key) AS project_count,
+                    (SELECT COUNT(*) FROM experience_technologies WHERE technology_key = technologies.key) AS experience_count
+                FROM technologies
+            ) AS counts
+            WHERE project_count + experience_count > 0
+        ) AS technology_counts",
+        columns: "name, category, icon, project_count, experience_count, usage_count",
+        sort_fields: &[
+            ("name", "id"),
+            ("count", "usage_count"),
+            ("project_count", "project_count"),
+            ("experience_count", "experience_count"),
+        ],
+        default_sort: ("id", SortDirection::Asc),
+        filters: &[("category", Filter::Equals("category"))],
+    };
+
+    pub fn new(pool: DbPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn find_tags(&self, query: &ListQuery) -> Result<Page<Tag>> {
+        let page = fetch_page::<TagRow>(&self.pool, query).await?;
+        Ok(page.map(Tag::from))
+    }
+
+    /// A tag by name, ignoring case; None if no published post uses it
+    pub async fn find_tag(&self, name: &str) -> Result<Option<Tag>> {
+        let query = format!(
+            "SELECT {} FROM {} WHERE id = LOWER(TRIM($1))",
+            Self::TAG_LIST.columns,
+            Self::TAG_LIST.table
+        );
+
+        let row = sqlx::query_as::<_, TagRow>(&query)
+            .bind(name)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(row.map(Tag::from))
+    }
+
+    pub async fn find_technologies(&self, query: &ListQuery) -> Result<Page<Technology>> {
+        let page = fetch_page::<TechnologyRow>(&self.pool, query).await?;
+        Ok(page.map(Technology::from))
+    }
+
+    /// A technology by name or alias, ignoring case; None if nothing uses it
+    pub async fn find_technology(&self, name: &str) -> Result<Option<Technology>> {
+        let query = format!(
+            "SELECT {} FROM {} WHERE id = LOWER(TRIM($1))
+                OR id IN (SELECT technology_key FROM technology_aliases WHERE alias_key = LOWER(TRIM($1)))",
+            Self::TECHNOLOGY_LIST.columns,
+            Self::TECHNOLOGY_LIST.table
+        );
+
+        let row = sqlx::query_as::<_, TechnologyRow>(&query)
+            .bind(name)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(row.map(Technology::from))
+    }
+}
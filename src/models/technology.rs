@@ -0,0 +1,85 @@
+use std::collections::{HashMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::models::github_stats::TopLanguage;
+use crate::models::skill::SkillItem;
+
+/// A technology in the registry, with the other spellings that mean it
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, ToSchema)]
+#[schema(example = json!({
+    "name": "TypeScript",
+    "category": "language",
+    "icon": "simple-icons:typescript",
+    "aliases": ["TS"]
+}))]
+pub struct TechnologyEntry {
+    /// Canonical spelling, used wherever the technology is written
+    pub name: String,
+    /// Kind of technology, e.g. "language", "framework" or "database"
+    pub category: Option<String>,
+    /// Icon identifier for the frontend
+    pub icon: Option<String>,
+    /// Names normalised to this one, matched ignoring case
+    pub aliases: Vec<String>,
+}
+
+/// Key of a technology name or alias: trimmed and lowercased
+pub fn technology_key(name: &str) -> String {
+    name.trim().to_lowercase()
+}
+
+/// Names with registered technologies and aliases replaced by their canonical
+/// spelling, in their original order. Blank names and repeats of an earlier
+/// name are dropped; unknown names are kept, trimmed.
+///
+/// `canonical` maps the key of every technology and alias to the canonical name.
+pub fn normalize_technologies(names: &[String], canonical: &HashMap<String, String>) -> Vec<String> {
+    let mut seen = HashSet::new();
+
+    names
+        .iter()
+        .filter(|name| !name.trim().is_empty())
+        .map(|name| {
+            canonical
+                .get(&technology_key(name))
+                .cloned()
+                .unwrap_or_else(|| name.trim().to_string())
+        })
+        .filter(|name| seen.insert(technology_key(name)))
+        .collect()
+}
+
+/// GitHub languages with their names normalised; languages that turn out to
+/// be the same add up their percentages
+pub fn normalize_languages(languages: &[TopLanguage], canonical: &HashMap<String, String>) -> Vec<TopLanguage> {
+    let mut normalized: Vec<TopLanguage> = Vec::new();
+
+    for language in languages {
+        let Some(name) = normalize_technologies(std::slice::from_ref(&language.name), canonical).pop() else {
+            continue;
+        };
+
+        match normalized.iter_mut().find(|l| technology_key(&l.name) == technology_key(&name)) {
+            Some(existing) => existing.percentage += language.percentage,
+            None => normalized.push(TopLanguage { name, percentage: language.percentage }),
+        }
+    }
+
+    normalized
+}
+
+/// Skills with their names normalised, keeping the first of skills that turn
+/// out to be the same
+pub fn normalize_skill_items(items: &[SkillItem], canonical: &HashMap<String, String>) -> Vec<SkillItem> {
+    let mut seen = HashSet::new();
+
+    items
+        .iter()
+        .filter_map(|item| {
+            let name = normalize_technologies(std::slice::from_ref(&item.name), canonical).pop()?;
+            seen.insert(technology_key(&name)).then(|| SkillItem { name, ..item.clone() })
+        })
+        .collect()
+}
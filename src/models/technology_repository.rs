@@ -0,0 +1,408 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+use sqlx::{AnyConnection, FromRow};
+
+use crate::config::database::DbPool;
+use crate::error::{AppError, AppResult};
+use crate::models::github_stats::TopLanguage;
+use crate::models::repository::{fetch_page, json_to_vec, vec_to_json, Filter, ListQuery, ListSpec, Page, SortDirection};
+use crate::models::skill::SkillItem;
+use crate::models::technology::{normalize_languages, normalize_skill_items, normalize_technologies, technology_key, TechnologyEntry};
+
+// Longest technology name, alias, category or icon accepted
+const MAX_NAME_LENGTH: usize = 100;
+
+// JSON arrays of technology names: (table, column)
+const TECHNOLOGY_COLUMNS: &[(&str, &str)] = &[
+    ("projects", "technologies"),
+    ("experiences", "technologies"),
+];
+
+// The technology registry: canonical names with their category, icon and
+// aliases. Writes of technology names go through `normalize`, and changes to
+// the registry rewrite the stored names so that they stay canonical.
+pub struct TechnologyRepository {
+    pool: DbPool,
+}
+
+#[derive(Debug, FromRow)]
+struct EntryRow {
+    id: String,
+    name: String,
+    category: Option<String>,
+    icon: Option<String>,
+}
+
+impl EntryRow {
+    fn into_entry(self, aliases: &mut HashMap<String, Vec<String>>) -> TechnologyEntry {
+        TechnologyEntry {
+            aliases: aliases.remove(&self.id).unwrap_or_default(),
+            name: self.name,
+            category: self.category,
+            icon: self.icon,
+        }
+    }
+}
+
+fn db_error(e: impl std::fmt::Display) -> AppError {
+    AppError::internal_error(format!("Failed to update technology registry: {}", e))
+}
+
+fn clean_label(value: Option<String>, field: &str) -> AppResult<Option<String>> {
+    let value = value.map(|v| v.trim().to_string()).filter(|v| !v.is_empty());
+    if value.as_ref().is_some_and(|v| v.chars().count() > MAX_NAME_LENGTH) {
+        return Err(AppError::bad_request(format!("{} must be at most {} characters", field, MAX_NAME_LENGTH)));
+    }
+    Ok(value)
+}
+
+// Trim the entry, dropping blank and repeated aliases and aliases of its own name
+fn clean_entry(entry: TechnologyEntry) -> AppResult<TechnologyEntry> {
+    let name = clean_label(Some(entry.name), "Technology name")?
+        .ok_or_else(|| AppError::bad_request("Technology name must not be blank"))?;
+
+    let mut keys = vec![technology_key(&name)];
+    let mut aliases = Vec::new();
+    for alias in entry.aliases {
+        let Some(alias) = clean_label(Some(alias), "Alias")? else {
+            continue;
+        };
+        if !keys.contains(&technology_key(&alias)) {
+            keys.push(technology_key(&alias));
+            aliases.push(alias);
+        }
+    }
+
+    Ok(TechnologyEntry {
+        name,
+        category: clean_label(entry.category, "Category")?,
+        icon: clean_label(entry.icon, "Icon")?,
+        aliases,
+    })
+}
+
+/// Canonical name of every technology and alias, by key, for normalising
+/// names as they are written
+pub async fn canonical_names(conn: &mut AnyConnection) -> Result<HashMap<String, String>> {
+    let rows = sqlx::query_as::<_, (String, String)>(
+        "SELECT name, name FROM technologies
+        UNION ALL
+        SELECT technology_aliases.alias, technologies.name
+        FROM technology_aliases
+        JOIN technologies ON technologies.key = technology_aliases.technology_key",
+    )
+        .fetch_all(&mut *conn)
+        .await?;
+
+    Ok(rows.into_iter().map(|(spelling, name)| (technology_key(&spelling), name)).collect())
+}
+
+// Aliases of every technology, by technology key
+async fn aliases_by_key(conn: &mut AnyConnection) -> Result<HashMap<String, Vec<String>>> {
+    let rows = sqlx::query_as::<_, (String, String)>(
+        "SELECT technology_key, alias FROM technology_aliases ORDER BY alias_key",
+    )
+        .fetch_all(&mut *conn)
+        .await?;
+
+    let mut aliases: HashMap<String, Vec<String>> = HashMap::new();
+    for (key, alias) in rows {
+        aliases.entry(key).or_default().push(alias);
+    }
+    Ok(aliases)
+}
+
+// Rewrite stored technology names to their canonical spelling, returning how
+// many rows changed.
+async fn rewrite_names(conn: &mut AnyConnection) -> Result<u64> {
+    let canonical = canonical_names(conn).await?;
+    let mut changed = 0;
+
+    for (table, column) in TECHNOLOGY_COLUMNS {
+        let rows = sqlx::query_as::<_, (String, String)>(&format!("SELECT id, {} FROM {}", column, table))
+            .fetch_all(&mut *conn)
+            .await?;
+
+        for (id, names) in rows {
+            let names: Vec<String> = json_to_vec(&names).unwrap_or_default();
+            let normalized = normalize_technologies(&names, &canonical);
+            if normalized != names {
+                sqlx::query(&format!("UPDATE {} SET {} = $1, updated_at = CURRENT_TIMESTAMP, version = version + 1 WHERE id = $2", table, column))
+                    .bind(vec_to_json(&normalized)?)
+                    .bind(&id)
+                    .execute(&mut *conn)
+                    .await?;
+                changed += 1;
+            }
+        }
+    }
+
+    let rows = sqlx::query_as::<_, (String, String)>("SELECT id, items FROM skills")
+        .fetch_all(&mut *conn)
+        .await?;
+
+    for (id, items) in rows {
+        let items: Vec<SkillItem> = json_to_vec(&items).unwrap_or_default();
+        let normalized = normalize_skill_items(&items, &canonical);
+        if normalized != items {
+            sqlx::query("UPDATE skills SET items = $1, updated_at = CURRENT_TIMESTAMP, version = version + 1 WHERE id = $2")
+                .bind(vec_to_json(&normalized)?)
+                .bind(&id)
+                .execute(&mut *conn)
+                .await?;
+            changed += 1;
+        }
+    }
+
+    let rows = sqlx::query_as::<_, (String, String)>("SELECT id, top_languages FROM github_stats")
+        .fetch_all(&mut *conn)
+        .await?;
+
+    for (id, languages) in rows {
+        let languages: Vec<TopLanguage> = json_to_vec(&languages).unwrap_or_default();
+        let normalized = normalize_languages(&languages, &canonical);
+        if normalized != languages {
+            sqlx::query("UPDATE github_stats SET top_languages = $1, updated_at = CURRENT_TIMESTAMP WHERE id = $2")
+                .bind(vec_to_json(&normalized)?)
+                .bind(&id)
+                .execute(&mut *conn)
+                .await?;
+            changed += 1;
+        }
+    }
+
+    Ok(changed)
+}
+
+async fn insert_aliases(conn: &mut AnyConnection, aliases: &[String], name: &str) -> Result<()> {
+    for alias in aliases {
+        sqlx::query(
+            "INSERT INTO technology_aliases (alias_key, alias, technology_key)
+            VALUES (LOWER(TRIM($1)), $2, LOWER(TRIM($3)))",
+        )
+            .bind(alias)
+            .bind(alias)
+            .bind(name)
+            .execute(&mut *conn)
+            .await?;
+    }
+    Ok(())
+}
+
+impl TechnologyRepository {
+    pub const LIST: ListSpec = ListSpec {
+        table: "(SELECT key AS id, name, category, icon FROM technologies) AS registry",
+        columns: "id, name, category, icon",
+        sort_fields: &[("name", "id"), ("category", "category")],
+        default_sort: ("id", SortDirection::Asc),
+        filters: &[("category", Filter::Equals("category"))],
+    };
+
+    pub fn new(pool: DbPool) -> Self {
+        Self { pool }
+    }
+
+    /// Rewrite every stored technology name to its canonical spelling,
+    /// returning how many rows changed
+    pub async fn apply(&self) -> Result<u64> {
+        let mut tx = self.pool.begin().await?;
+        let changed = rewrite_names(&mut tx).await?;
+        tx.commit().await?;
+        Ok(changed)
+    }
+
+    pub async fn find_page(&self, query: &ListQuery) -> Result<Page<TechnologyEntry>> {
+        let page = fetch_page::<EntryRow>(&self.pool, query).await?;
+        let mut conn = self.pool.acquire().await?;
+        let mut aliases = aliases_by_key(&mut conn).await?;
+        Ok(page.map(|row| row.into_entry(&mut aliases)))
+    }
+
+    async fn find_row(&self, name: &str) -> Result<Option<EntryRow>> {
+        let row = sqlx::query_as::<_, EntryRow>(
+            "SELECT key AS id, name, category, icon FROM technologies
+            WHERE key = LOWER(TRIM($1))
+                OR key IN (SELECT technology_key FROM technology_aliases WHERE alias_key = LOWER(TRIM($1)))",
+        )
+            .bind(name)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(row)
+    }
+
+    /// A technology by its name or one of its aliases, ignoring case
+    pub async fn find(&self, name: &str) -> Result<Option<TechnologyEntry>> {
+        let Some(row) = self.find_row(name).await? else {
+            return Ok(None);
+        };
+
+        let mut conn = self.pool.acquire().await?;
+        let mut aliases = aliases_by_key(&mut conn).await?;
+        Ok(Some(row.into_entry(&mut aliases)))
+    }
+
+    async fn existing(&self, name: &str) -> AppResult<EntryRow> {
+        self.find_row(name).await
+            .map_err(db_error)?
+            .ok_or_else(|| AppError::not_found(format!("Technology '{}' not found", name)))
+    }
+
+    // Fail with 409 when the name or an alias of the entry belongs to another
+    // technology than `own_key`
+    async fn check_free(&self, entry: &TechnologyEntry, own_key: Option<&str>) -> AppResult<()> {
+        let name = entry.name.as_str();
+        for (index, spelling) in std::iter::once(name).chain(entry.aliases.iter().map(String::as_str)).enumerate() {
+            let Some(owner) = self.find_row(spelling).await.map_err(db_error)? else {
+                continue;
+            };
+            if Some(owner.id.as_str()) == own_key {
+                continue;
+            }
+
+            let is_alias = technology_key(spelling) != owner.id;
+            return Err(AppError::conflict(match (index, is_alias) {
+                (0, false) => format!("Technology '{}' already exists", owner.name),
+                (_, true) => format!("'{}' is already an alias of '{}'", spelling, owner.name),
+                (_, false) => format!("'{}' is a technology of its own; merge it into '{}' instead", owner.name, name),
+            }));
+        }
+        Ok(())
+    }
+
+    /// Register a technology with its aliases, rewriting names that use them
+    pub async fn create(&self, entry: TechnologyEntry) -> AppResult<TechnologyEntry> {
+        let entry = clean_entry(entry)?;
+        self.check_free(&entry, None).await?;
+
+        let mut tx = self.pool.begin().await.map_err(db_error)?;
+        sqlx::query("INSERT INTO technologies (key, name, category, icon) VALUES (LOWER(TRIM($1)), $2, $3, $4)")
+            .bind(&entry.name)
+            .bind(&entry.name)
+            .bind(entry.category.as_deref())
+            .bind(entry.icon.as_deref())
+            .execute(&mut *tx)
+            .await
+            .map_err(db_error)?;
+        insert_aliases(&mut tx, &entry.aliases, &entry.name).await.map_err(db_error)?;
+        rewrite_names(&mut tx).await.map_err(db_error)?;
+        tx.commit().await.map_err(db_error)?;
+
+        self.find(&entry.name).await
+            .map_err(db_error)?
+            .ok_or_else(|| db_error("technology missing after create"))
+    }
+
+    /// Replace a technology's name, category, icon and aliases. A new name
+    /// keeps the previous one as an alias.
+    pub async fn update(&self, name: &str, entry: TechnologyEntry) -> AppResult<TechnologyEntry> {
+        let current = self.existing(name).await?;
+        let mut entry = clean_entry(entry)?;
+        self.check_free(&entry, Some(&current.id)).await?;
+
+        let renamed = technology_key(&entry.name) != current.id;
+        if renamed && !entry.aliases.iter().any(|alias| technology_key(alias) == current.id) {
+            entry.aliases.push(current.name.clone());
+        }
+
+        let mut tx = self.pool.begin().await.map_err(db_error)?;
+        sqlx::query("DELETE FROM technology_aliases WHERE technology_key = $1")
+            .bind(&current.id)
+            .execute(&mut *tx)
+            .await
+            .map_err(db_error)?;
+        sqlx::query("DELETE FROM technologies WHERE key = $1")
+            .bind(&current.id)
+            .execute(&mut *tx)
+            .await
+            .map_err(db_error)?;
+        sqlx::query("INSERT INTO technologies (key, name, category, icon) VALUES (LOWER(TRIM($1)), $2, $3, $4)")
+            .bind(&entry.name)
+            .bind(&entry.name)
+            .bind(entry.category.as_deref())
+            .bind(entry.icon.as_deref())
+            .execute(&mut *tx)
+            .await
+            .map_err(db_error)?;
+        insert_aliases(&mut tx, &entry.aliases, &entry.name).await.map_err(db_error)?;
+        rewrite_names(&mut tx).await.map_err(db_error)?;
+        tx.commit().await.map_err(db_error)?;
+
+        self.find(&entry.name).await
+            .map_err(db_error)?
+            .ok_or_else(|| db_error("technology missing after update"))
+    }
+
+    /// Merge a technology into another: its name and aliases become aliases of
+    /// the target, which takes its category and icon if it has none, and
+    /// every use of it is rewritten to the target's name
+    pub async fn merge(&self, source: &str, target: &str) -> AppResult<TechnologyEntry> {
+        let source = self.existing(source).await?;
+        let target = self.existing(target).await?;
+        if source.id == target.id {
+            return Err(AppError::bad_request(format!("Cannot merge '{}' into itself", source.name)));
+        }
+
+        let mut tx = self.pool.begin().await.map_err(db_error)?;
+        sqlx::query("UPDATE technology_aliases SET technology_key = $1 WHERE technology_key = $2")
+            .bind(&target.id)
+            .bind(&source.id)
+            .execute(&mut *tx)
+            .await
+            .map_err(db_error)?;
+        sqlx::query("UPDATE technologies SET category = COALESCE(category, $1), icon = COALESCE(icon, $2) WHERE key = $3")
+            .bind(source.category.as_deref())
+            .bind(source.icon.as_deref())
+            .bind(&target.id)
+            .execute(&mut *tx)
+            .await
+            .map_err(db_error)?;
+        sqlx::query("DELETE FROM technologies WHERE key = $1")
+            .bind(&source.id)
+            .execute(&mut *tx)
+            .await
+            .map_err(db_error)?;
+        insert_aliases(&mut tx, std::slice::from_ref(&source.name), &target.name).await.map_err(db_error)?;
+        rewrite_names(&mut tx).await.map_err(db_error)?;
+        tx.commit().await.map_err(db_error)?;
+
+        self.find(&target.name).await
+            .map_err(db_error)?
+            .ok_or_else(|| db_error("technology missing after merge"))
+    }
+
+    /// Remove an unused technology and its aliases
+    pub async fn delete(&self, name: &str) -> AppResult<()> {
+        let current = self.existing(name).await?;
+
+        let uses: i64 = sqlx::query_scalar(
+            "SELECT (SELECT COUNT(*) FROM project_technologies WHERE technology_key = $1)
+                + (SELECT COUNT(*) FROM experience_technologies WHERE technology_key = $1)",
+        )
+            .bind(&current.id)
+            .fetch_one(&self.pool)
+            .await
+            .map_err(db_error)?;
+        if uses > 0 {
+            return Err(AppError::conflict(format!(
+                "'{}' is used by {} projects and experiences; merge it into another technology instead",
+                current.name, uses
+            )));
+        }
+
+        let mut tx = self.pool.begin().await.map_err(db_error)?;
+        sqlx::query("DELETE FROM technology_aliases WHERE technology_key = $1")
+            .bind(&current.id)
+            .execute(&mut *tx)
+            .await
+            .map_err(db_error)?;
+        sqlx::query("DELETE FROM technologies WHERE key = $1")
+            .bind(&current.id)
+            .execute(&mut *tx)
+            .await
+            .map_err(db_error)?;
+        tx.commit().await.map_err(db_error)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,38 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// A refresh token as stored in the `refresh_tokens` table.
+///
+/// Only the SHA-256 hash of the token is persisted; the plain value is handed
+/// to the client once and never stored.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RefreshToken {
+    /// Unique identifier for the token
+    pub id: String,
+    /// Account the token was issued to
+    pub user_id: String,
+    /// Hex encoded SHA-256 of the token value
+    pub token_hash: String,
+    /// Identifier shared by every token rotated from the same login
+    pub family_id: String,
+    /// Expiration time (as UTC timestamp)
+    pub expires_at: i64,
+    /// When the token was revoked or rotated (as UTC timestamp)
+    pub revoked_at: Option<i64>,
+    /// Token issued in exchange for this one
+    pub replaced_by: Option<String>,
+}
+
+impl RefreshToken {
+    pub fn new(user_id: String, token_hash: String, family_id: String, expires_at: i64) -> Self {
+        Self {
+            id: Uuid::new_v4().to_string(),
+            user_id,
+            token_hash,
+            family_id,
+            expires_at,
+            revoked_at: None,
+            replaced_by: None,
+        }
+    }
+}
@@ -0,0 +1,167 @@
+use anyhow::Result;
+use sqlx::FromRow;
+use serde::{Serialize, Deserialize};
+
+use crate::config::database::DbPool;
+use crate::models::token::RefreshToken;
+
+// Stores refresh tokens and the access token denylist. Tokens are never
+// updated in place, so this does not implement the generic Repository trait.
+pub struct TokenRepository {
+    pool: DbPool,
+}
+
+#[derive(Debug, FromRow, Serialize, Deserialize)]
+struct RefreshTokenRow {
+    id: String,
+    user_id: String,
+    token_hash: String,
+    family_id: String,
+    expires_at: i64,
+    revoked_at: Option<i64>,
+    replaced_by: Option<String>,
+}
+
+impl From<RefreshTokenRow> for RefreshToken {
+    fn from(row: RefreshTokenRow) -> Self {
+        RefreshToken {
+            id: row.id,
+            user_id: row.user_id,
+            token_hash: row.token_hash,
+            family_id: row.family_id,
+            expires_at: row.expires_at,
+            revoked_at: row.revoked_at,
+            replaced_by: row.replaced_by,
+        }
+    }
+}
+
+impl TokenRepository {
+    pub fn new(pool: DbPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn create_refresh_token(&self, token: RefreshToken) -> Result<RefreshToken> {
+        let query = "
+            INSERT INTO refresh_tokens (id, user_id, token_hash, family_id, expires_at)
+            VALUES ($1, $2, $3, $4, $5)
+        ";
+
+        sqlx::query(query)
+            .bind(&token.id)
+            .bind(&token.user_id)
+            .bind(&token.token_hash)
+            .bind(&token.family_id)
+            .bind(token.expires_at)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(token)
+    }
+
+    pub async fn find_refresh_token_by_hash(&self, token_hash: &str) -> Result<Option<RefreshToken>> {
+        let query = "
+            SELECT id, user_id, token_hash, family_id, expires_at, revoked_at, replaced_by
+            FROM refresh_tokens
+            WHERE token_hash = $1
+        ";
+
+        let row = sqlx::query_as::<_, RefreshTokenRow>(query)
+            .bind(token_hash)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(row.map(RefreshToken::from))
+    }
+
+    /// Mark a token as used, recording its replacement. Returns false if the
+    /// token had already been revoked, which means a concurrent refresh won.
+    pub async fn rotate_refresh_token(&self, id: &str, replaced_by: &str, now: i64) -> Result<bool> {
+        let query = "
+            UPDATE refresh_tokens
+            SET revoked_at = $1, replaced_by = $2
+            WHERE id = $3 AND revoked_at IS NULL
+        ";
+
+        let result = sqlx::query(query)
+            .bind(now)
+            .bind(replaced_by)
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn revoke_refresh_token_family(&self, family_id: &str, now: i64) -> Result<u64> {
+        let query = "
+            UPDATE refresh_tokens
+            SET revoked_at = $1
+            WHERE family_id = $2 AND revoked_at IS NULL
+        ";
+
+        let result = sqlx::query(query)
+            .bind(now)
+            .bind(family_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    pub async fn revoke_refresh_tokens_for_user(&self, user_id: &str, now: i64) -> Result<u64> {
+        let query = "
+            UPDATE refresh_tokens
+            SET revoked_at = $1
+            WHERE user_id = $2 AND revoked_at IS NULL
+        ";
+
+        let result = sqlx::query(query)
+            .bind(now)
+            .bind(user_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    pub async fn revoke_access_token(&self, jti: &str, expires_at: i64) -> Result<()> {
+        let query = "
+            INSERT INTO revoked_tokens (jti, expires_at)
+            VALUES ($1, $2)
+            ON CONFLICT (jti) DO NOTHING
+        ";
+
+        sqlx::query(query)
+            .bind(jti)
+            .bind(expires_at)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn is_access_token_revoked(&self, jti: &str) -> Result<bool> {
+        let row = sqlx::query_as::<_, (String,)>("SELECT jti FROM revoked_tokens WHERE jti = $1")
+            .bind(jti)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(row.is_some())
+    }
+
+    /// Delete denylist entries and refresh tokens that can no longer be used anyway
+    pub async fn purge_expired(&self, now: i64) -> Result<()> {
+        sqlx::query("DELETE FROM revoked_tokens WHERE expires_at < $1")
+            .bind(now)
+            .execute(&self.pool)
+            .await?;
+
+        sqlx::query("DELETE FROM refresh_tokens WHERE expires_at < $1")
+            .bind(now)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+}
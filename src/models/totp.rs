@@ -0,0 +1,37 @@
+use serde::{Deserialize, Serialize};
+
+/// TOTP second factor of a user, as stored in the `user_totp` table.
+///
+/// An enrollment only protects logins once it has been confirmed with a
+/// valid code; until then it can be replaced by enrolling again.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TotpEnrollment {
+    /// Account the second factor belongs to
+    pub user_id: String,
+    /// Base32 encoded shared secret
+    #[serde(skip_serializing)]
+    pub secret: String,
+    /// When the enrollment was confirmed (as UTC timestamp)
+    pub confirmed_at: Option<i64>,
+    /// Last accepted TOTP time step; codes from this step or earlier are rejected
+    pub last_used_step: Option<i64>,
+    /// SHA-256 hashes of the recovery codes that have not been used yet
+    #[serde(skip_serializing)]
+    pub recovery_codes: Vec<String>,
+}
+
+impl TotpEnrollment {
+    pub fn new(user_id: String, secret: String) -> Self {
+        Self {
+            user_id,
+            secret,
+            confirmed_at: None,
+            last_used_step: None,
+            recovery_codes: Vec::new(),
+        }
+    }
+
+    pub fn is_confirmed(&self) -> bool {
+        self.confirmed_at.is_some()
+    }
+}
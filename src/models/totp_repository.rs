@@ -0,0 +1,157 @@
+use anyhow::Result;
+use sqlx::FromRow;
+use serde::{Serialize, Deserialize};
+
+use crate::config::database::DbPool;
+use crate::models::repository::{vec_to_json, json_to_vec};
+use crate::models::totp::TotpEnrollment;
+
+// One enrollment per user, keyed by user id, so this does not implement the
+// generic Repository trait
+pub struct TotpRepository {
+    pool: DbPool,
+}
+
+#[derive(Debug, FromRow, Serialize, Deserialize)]
+struct TotpRow {
+    user_id: String,
+    secret: String,
+    confirmed_at: Option<i64>,
+    last_used_step: Option<i64>,
+    recovery_codes: String,
+}
+
+impl From<TotpRow> for TotpEnrollment {
+    fn from(row: TotpRow) -> Self {
+        TotpEnrollment {
+            user_id: row.user_id,
+            secret: row.secret,
+            confirmed_at: row.confirmed_at,
+            last_used_step: row.last_used_step,
+            recovery_codes: json_to_vec(&row.recovery_codes).unwrap_or_default(),
+        }
+    }
+}
+
+impl TotpRepository {
+    pub fn new(pool: DbPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn find_by_user(&self, user_id: &str) -> Result<Option<TotpEnrollment>> {
+        let query = "
+            SELECT user_id, secret, confirmed_at, last_used_step, recovery_codes
+            FROM user_totp
+            WHERE user_id = $1
+        ";
+
+        let row = sqlx::query_as::<_, TotpRow>(query)
+            .bind(user_id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(row.map(TotpEnrollment::from))
+    }
+
+    /// Store a new, unconfirmed enrollment, replacing any previous one
+    pub async fn save_enrollment(&self, enrollment: TotpEnrollment) -> Result<TotpEnrollment> {
+        let recovery_codes = vec_to_json(&enrollment.recovery_codes)?;
+
+        let query = "
+            INSERT INTO user_totp (user_id, secret, confirmed_at, last_used_step, recovery_codes)
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT (user_id) DO UPDATE SET
+                secret = excluded.secret,
+                confirmed_at = excluded.confirmed_at,
+                last_used_step = excluded.last_used_step,
+                recovery_codes = excluded.recovery_codes
+        ";
+
+        sqlx::query(query)
+            .bind(&enrollment.user_id)
+            .bind(&enrollment.secret)
+            .bind(enrollment.confirmed_at)
+            .bind(enrollment.last_used_step)
+            .bind(recovery_codes)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(enrollment)
+    }
+
+    pub async fn confirm(&self, user_id: &str, now: i64, step: i64, recovery_codes: &[String]) -> Result<bool> {
+        let query = "
+            UPDATE user_totp
+            SET confirmed_at = $1, last_used_step = $2, recovery_codes = $3
+            WHERE user_id = $4 AND confirmed_at IS NULL
+        ";
+
+        let result = sqlx::query(query)
+            .bind(now)
+            .bind(step)
+            .bind(vec_to_json(recovery_codes)?)
+            .bind(user_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Record an accepted time step. Returns false if this step or a later one
+    /// was already used, so the same code cannot be replayed.
+    pub async fn use_step(&self, user_id: &str, step: i64) -> Result<bool> {
+        let query = "
+            UPDATE user_totp
+            SET last_used_step = $1
+            WHERE user_id = $2 AND (last_used_step IS NULL OR last_used_step < $3)
+        ";
+
+        let result = sqlx::query(query)
+            .bind(step)
+            .bind(user_id)
+            .bind(step)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Remove a recovery code hash. Returns false if it was not (or no longer) available.
+    pub async fn use_recovery_code(&self, user_id: &str, code_hash: &str) -> Result<bool> {
+        let mut tx = self.pool.begin().await?;
+
+        let row = sqlx::query_as::<_, (String,)>("SELECT recovery_codes FROM user_totp WHERE user_id = $1")
+            .bind(user_id)
+            .fetch_optional(&mut *tx)
+            .await?;
+
+        let mut codes: Vec<String> = match row {
+            Some((codes,)) => json_to_vec(&codes)?,
+            None => return Ok(false),
+        };
+
+        let before = codes.len();
+        codes.retain(|code| code != code_hash);
+        if codes.len() == before {
+            return Ok(false);
+        }
+
+        sqlx::query("UPDATE user_totp SET recovery_codes = $1 WHERE user_id = $2")
+            .bind(vec_to_json(&codes)?)
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(true)
+    }
+
+    pub async fn delete(&self, user_id: &str) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM user_totp WHERE user_id = $1")
+            .bind(user_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
@@ -0,0 +1,31 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+// Tables whose deleted rows go to the trash; also the `kind` of trashed items
+pub const TRASH_KINDS: &[&str] = &["posts", "projects", "experiences", "skills", "contacts"];
+
+/// A deleted post, project, experience, skill category or contact message
+/// waiting in the trash
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+#[schema(example = json!({
+    "id": "8b0a6b55-3c52-4d47-9a8e-0f1f3c2b5a10",
+    "kind": "projects",
+    "title": "Retro Portfolio",
+    "deleted_at": 1792137600
+}))]
+pub struct TrashItem {
+    pub id: String,
+    /// posts, projects, experiences, skills or contacts
+    pub kind: String,
+    /// Title of the item: position and company of an experience, category of
+    /// a skill, sender of a message
+    pub title: String,
+    /// When the item was deleted (as UTC timestamp)
+    pub deleted_at: i64,
+}
+
+/// Number of items removed for good
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct PurgeResult {
+    pub purged: u64,
+}
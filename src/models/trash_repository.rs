@@ -0,0 +1,162 @@
+use std::collections::HashSet;
+
+use anyhow::{bail, Result};
+use sqlx::{Any, AnyConnection, Encode, FromRow, Type};
+
+use crate::config::database::DbPool;
+use crate::models::repository::{fetch_page, Filter, ListQuery, ListSpec, Page, SortDirection};
+use crate::models::trash::{TrashItem, TRASH_KINDS};
+
+// Deleted rows of every kind. Repositories only mark rows as deleted; rows
+// leave the database when purged from here, with their slug aliases and
+// revisions.
+pub struct TrashRepository {
+    pool: DbPool,
+}
+
+#[derive(Debug, FromRow)]
+struct TrashRow {
+    id: String,
+    kind: String,
+    title: String,
+    deleted_at: i64,
+}
+
+impl From<TrashRow> for TrashItem {
+    fn from(row: TrashRow) -> Self {
+        TrashItem {
+            id: row.id,
+            kind: row.kind,
+            title: row.title,
+            deleted_at: row.deleted_at,
+        }
+    }
+}
+
+fn check_kind(kind: &str) -> Result<()> {
+    if !TRASH_KINDS.contains(&kind) {
+        bail!("{} has no trash", kind);
+    }
+    Ok(())
+}
+
+// Remove the trashed rows of a kind selected by `condition`, which binds $1,
+// along with what refers to them
+async fn purge_rows<V>(conn: &mut AnyConnection, kind: &'static str, condition: &str, value: V) -> Result<u64>
+where
+    V: for<'q> Encode<'q, Any> + Type<Any> + Send + Clone + 'static,
+{
+    check_kind(kind)?;
+
+    // The table name comes from TRASH_KINDS, never from the request
+    let ids = format!("SELECT id FROM {} WHERE deleted_at IS NOT NULL AND {}", kind, condition);
+
+    if matches!(kind, "posts" | "projects") {
+        sqlx::query(&format!("DELETE FROM slug_aliases WHERE target = '{}' AND ref_id IN ({})", kind, ids))
+            .bind(value.clone())
+            .execute(&mut *conn)
+            .await?;
+    }
+
+    if matches!(kind, "posts" | "projects" | "experiences") {
+        sqlx::query(&format!("DELETE FROM revisions WHERE target = '{}' AND ref_id IN ({})", kind, ids))
+            .bind(value.clone())
+            .execute(&mut *conn)
+            .await?;
+    }
+
+    let result = sqlx::query(&format!("DELETE FROM {} WHERE deleted_at IS NOT NULL AND {}", kind, condition))
+        .bind(value)
+        .execute(&mut *conn)
+        .await?;
+
+    Ok(result.rows_affected())
+}
+
+impl TrashRepository {
+    pub const LIST: ListSpec = ListSpec {
+        table: "(
+            SELECT id, 'posts' AS kind, title, deleted_at FROM posts WHERE deleted_at IS NOT NULL
+            UNION ALL
+            SELECT id, 'projects' AS kind, title, deleted_at FROM projects WHERE deleted_at IS NOT NULL
+            UNION ALL
+            SELECT id, 'experiences' AS kind, position || ' at ' || company AS title, deleted_at
+            FROM experiences WHERE deleted_at IS NOT NULL
+            UNION ALL
+            SELECT id, 'skills' AS kind, category AS title, deleted_at FROM skills WHERE deleted_at IS NOT NULL
+            UNION ALL
+            SELECT id, 'contacts' AS kind, name || ' <' || email || '>' AS title, deleted_at
+            FROM contacts WHERE deleted_at IS NOT NULL
+        ) AS trash",
+        columns: "id, kind, title, deleted_at",
+        sort_fields: &[("deleted_at", "deleted_at"), ("kind", "kind"), ("title", "title")],
+        default_sort: ("deleted_at", SortDirection::Desc),
+        filters: &[("kind", Filter::Equals("kind"))],
+    };
+
+    pub fn new(pool: DbPool) -> Self {
+        Self { pool }
+    }
+
+    /// One page of trashed items, most recently deleted first by default
+    pub async fn find_page(&self, query: &ListQuery) -> Result<Page<TrashItem>> {
+        let page = fetch_page::<TrashRow>(&self.pool, query).await?;
+        Ok(page.map(TrashItem::from))
+    }
+
+    /// Take an item out of the trash, returning false if it is not there
+    pub async fn restore(&self, kind: &'static str, id: &str) -> Result<bool> {
+        Self::untrash(&mut *self.pool.acquire().await?, kind, id).await
+    }
+
+    /// `restore` on a connection, for writes made within a transaction
+    pub async fn untrash(conn: &mut AnyConnection, kind: &'static str, id: &str) -> Result<bool> {
+        check_kind(kind)?;
+
+        // Coming back counts as a write for Last-Modified; contact messages
+        // have no updated_at
+        let touch = if kind == "contacts" { "" } else { ", updated_at = CURRENT_TIMESTAMP" };
+        let result = sqlx::query(&format!(
+            "UPDATE {} SET deleted_at = NULL{} WHERE id = $1 AND deleted_at IS NOT NULL",
+            kind, touch
+        ))
+            .bind(id)
+            .execute(&mut *conn)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Ids of the items of a kind in the trash
+    pub async fn trashed_ids(conn: &mut AnyConnection, kind: &'static str) -> Result<HashSet<String>> {
+        check_kind(kind)?;
+
+        let ids = sqlx::query_scalar::<_, String>(&format!("SELECT id FROM {} WHERE deleted_at IS NOT NULL", kind))
+            .fetch_all(&mut *conn)
+            .await?;
+
+        Ok(ids.into_iter().collect())
+    }
+
+    /// Remove an item in the trash for good, returning false if it is not there
+    pub async fn purge(&self, kind: &'static str, id: &str) -> Result<bool> {
+        let mut tx = self.pool.begin().await?;
+        let purged = purge_rows(&mut tx, kind, "id = $1", id.to_string()).await?;
+        tx.commit().await?;
+
+        Ok(purged > 0)
+    }
+
+    /// Remove every item deleted at or before a UTC timestamp, returning how many
+    pub async fn purge_deleted_before(&self, before: i64) -> Result<u64> {
+        let mut tx = self.pool.begin().await?;
+        let mut purged = 0;
+
+        for kind in TRASH_KINDS.iter().copied() {
+            purged += purge_rows(&mut tx, kind, "deleted_at <= $1", before).await?;
+        }
+
+        tx.commit().await?;
+        Ok(purged)
+    }
+}
@@ -0,0 +1,34 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// A user account stored in the `users` table.
+///
+/// This is the persisted account, including the password hash. It is never
+/// serialized in API responses; routes convert it to `auth::User` or a
+/// dedicated response type instead.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct UserAccount {
+    /// Unique identifier for the account
+    pub id: String,
+    /// Login name, unique across all accounts
+    pub username: String,
+    /// Display name
+    pub name: String,
+    /// Argon2id hash of the password in PHC string format
+    #[serde(skip_serializing)]
+    pub password_hash: String,
+    /// Account role ("admin", "editor" or "viewer")
+    pub role: String,
+}
+
+impl UserAccount {
+    pub fn new(username: String, name: String, password_hash: String, role: String) -> Self {
+        Self {
+            id: Uuid::new_v4().to_string(),
+            username,
+            name,
+            password_hash,
+            role,
+        }
+    }
+}
@@ -0,0 +1,158 @@
+use anyhow::Result;
+use sqlx::FromRow;
+use serde::{Serialize, Deserialize};
+
+use crate::config::database::DbPool;
+use crate::models::user::UserAccount;
+use crate::models::repository::{Repository, fetch_page, Filter, ListQuery, ListSpec, Page, SortDirection};
+
+pub struct UserRepository {
+    pool: DbPool,
+}
+
+#[derive(Debug, FromRow, Serialize, Deserialize)]
+struct UserRow {
+    id: String,
+    username: String,
+    name: String,
+    password_hash: String,
+    role: String,
+}
+
+impl From<UserRow> for UserAccount {
+    fn from(row: UserRow) -> Self {
+        UserAccount {
+            id: row.id,
+            username: row.username,
+            name: row.name,
+            password_hash: row.password_hash,
+            role: row.role,
+        }
+    }
+}
+
+impl UserRepository {
+    /// Look up an account by its login name
+    pub async fn find_by_username(&self, username: &str) -> Result<Option<UserAccount>> {
+        let query = "
+            SELECT id, username, name, password_hash, role
+            FROM users
+            WHERE username = $1
+        ";
+
+        let row = sqlx::query_as::<_, UserRow>(query)
+            .bind(username)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(row.map(UserAccount::from))
+    }
+
+    /// Count all accounts, used to decide whether the initial admin must be created
+    pub async fn count(&self) -> Result<i64> {
+        let (count,) = sqlx::query_as::<_, (i64,)>("SELECT COUNT(*) FROM users")
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(count)
+    }
+}
+
+impl Repository<UserAccount> for UserRepository {
+    const LIST: ListSpec = ListSpec {
+        table: "users",
+        columns: "id, username, name, password_hash, role",
+        sort_fields: &[("username", "username"), ("name", "name"), ("role", "role")],
+        default_sort: ("username", SortDirection::Asc),
+        filters: &[("role", Filter::Equals("role"))],
+    };
+
+    fn new(pool: DbPool) -> Self {
+        Self { pool }
+    }
+
+    async fn find_all(&self) -> Result<Vec<UserAccount>> {
+        let query = "
+            SELECT id, username, name, password_hash, role
+            FROM users
+            ORDER BY username ASC
+        ";
+
+        let rows = sqlx::query_as::<_, UserRow>(query)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(rows.into_iter().map(UserAccount::from).collect())
+    }
+
+    async fn find_page(&self, query: &ListQuery) -> Result<Page<UserAccount>> {
+        let page = fetch_page::<UserRow>(&self.pool, query).await?;
+        Ok(page.map(UserAccount::from))
+    }
+
+    async fn find_by_id(&self, id: &str) -> Result<Option<UserAccount>> {
+        let query = "
+            SELECT id, username, name, password_hash, role
+            FROM users
+            WHERE id = $1
+        ";
+
+        let row = sqlx::query_as::<_, UserRow>(query)
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(row.map(UserAccount::from))
+    }
+
+    async fn create(&self, item: UserAccount) -> Result<UserAccount> {
+        let query = "
+            INSERT INTO users (id, username, name, password_hash, role)
+            VALUES ($1, $2, $3, $4, $5)
+        ";
+
+        sqlx::query(query)
+            .bind(&item.id)
+            .bind(&item.username)
+            .bind(&item.name)
+            .bind(&item.password_hash)
+            .bind(&item.role)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(item)
+    }
+
+    async fn update(&self, id: &str, item: UserAccount) -> Result<UserAccount> {
+        let query = "
+            UPDATE users
+            SET username = $1, name = $2, password_hash = $3, role = $4, updated_at = CURRENT_TIMESTAMP
+            WHERE id = $5
+        ";
+
+        sqlx::query(query)
+            .bind(&item.username)
+            .bind(&item.name)
+            .bind(&item.password_hash)
+            .bind(&item.role)
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(item)
+    }
+
+    async fn delete(&self, id: &str) -> Result<bool> {
+        let query = "
+            DELETE FROM users
+            WHERE id = $1
+        ";
+
+        let result = sqlx::query(query)
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
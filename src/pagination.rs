@@ -0,0 +1,60 @@
+use actix_web::{http::header, HttpRequest, HttpResponse};
+use serde::{Deserialize, Serialize};
+use utoipa::IntoParams;
+
+use crate::error::AppResult;
+use crate::models::repository::{ListQuery, ListSpec, Page};
+
+// Response header carrying the number of items across all pages
+pub const TOTAL_COUNT_HEADER: &str = "X-Total-Count";
+
+// Page and sort parameters shared by every list endpoint
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct ListParams {
+    /// Page number, starting at 1 (default 1)
+    page: Option<i64>,
+    /// Items per page (default 20, at most 100)
+    per_page: Option<i64>,
+    /// Field to sort by, prefixed with "-" for descending order, e.g. "-year"
+    sort: Option<String>,
+}
+
+impl ListParams {
+    /// Validate the parameters against the fields a repository can sort by
+    pub fn query(&self, spec: &'static ListSpec) -> AppResult<ListQuery> {
+        ListQuery::new(spec, self.page, self.per_page, self.sort.as_deref())
+    }
+}
+
+// Link to another page of the same request, keeping every other parameter
+fn page_link(req: &HttpRequest, page: i64, rel: &str) -> String {
+    let page_param = format!("page={}", page);
+    let mut params: Vec<&str> = req.query_string()
+        .split('&')
+        .filter(|param| !param.is_empty() && !param.starts_with("page="))
+        .collect();
+    params.push(&page_param);
+
+    format!("<{}?{}>; rel=\"{}\"", req.path(), params.join("&"), rel)
+}
+
+/// Respond with the items of a page as a JSON array. The total count goes in
+/// X-Total-Count and the first, previous, next and last pages in a Link
+/// header (RFC 8288).
+pub fn paginated<T: Serialize>(req: &HttpRequest, page: Page<T>) -> HttpResponse {
+    let last = page.last_page();
+
+    let mut links = vec![page_link(req, 1, "first")];
+    if page.page > 1 {
+        links.push(page_link(req, (page.page - 1).min(last), "prev"));
+    }
+    if page.page < last {
+        links.push(page_link(req, page.page + 1, "next"));
+    }
+    links.push(page_link(req, last, "last"));
+
+    HttpResponse::Ok()
+        .insert_header((TOTAL_COUNT_HEADER, page.total.to_string()))
+        .insert_header((header::LINK, links.join(", ")))
+        .json(page.items)
+}
@@ -0,0 +1,209 @@
+use actix_web::{http::header, HttpRequest};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::error::{AppError, AppResult};
+
+// Partial updates with PATCH, in either of two formats: a JSON Merge Patch
+// (RFC 7396) lists the members to change and removes those set to null; a
+// JSON Patch (RFC 6902) is a list of operations at JSON Pointers (RFC 6901).
+// Both apply to the item as it is returned by GET.
+
+/// Media type of a JSON Merge Patch
+pub const MERGE_PATCH: &str = "application/merge-patch+json";
+/// Media type of a JSON Patch
+pub const JSON_PATCH: &str = "application/json-patch+json";
+/// Patch formats accepted, as sent in Accept-Patch
+pub const ACCEPT_PATCH: &str = "application/merge-patch+json, application/json-patch+json";
+
+/// One operation of a JSON Patch
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "op", rename_all = "lowercase")]
+pub enum PatchOperation {
+    Add { path: String, value: Value },
+    Remove { path: String },
+    Replace { path: String, value: Value },
+    Move { from: String, path: String },
+    Copy { from: String, path: String },
+    Test { path: String, value: Value },
+}
+
+/// Apply a JSON Merge Patch to a document
+pub fn merge_patch(target: &mut Value, patch: &Value) {
+    let Value::Object(members) = patch else {
+        *target = patch.clone();
+        return;
+    };
+
+    if !target.is_object() {
+        *target = Value::Object(Default::default());
+    }
+
+    if let Value::Object(target) = target {
+        for (key, value) in members {
+            if value.is_null() {
+                target.remove(key);
+            } else {
+                merge_patch(target.entry(key.clone()).or_insert(Value::Null), value);
+            }
+        }
+    }
+}
+
+/// Apply the operations of a JSON Patch to a document, in order. A malformed
+/// operation is a 400 Bad Request, and one that does not fit the document (a
+/// missing path, a failed test) a 409 Conflict; the document may then be
+/// partly patched, so apply it to a copy.
+pub fn json_patch(target: &mut Value, operations: &[PatchOperation]) -> AppResult<()> {
+    for operation in operations {
+        match operation {
+            PatchOperation::Add { path, value } => add(target, path, value.clone())?,
+            PatchOperation::Remove { path } => {
+                remove(target, path)?;
+            }
+            PatchOperation::Replace { path, value } => {
+                check_pointer(path)?;
+                *target.pointer_mut(path).ok_or_else(|| missing(path))? = value.clone();
+            }
+            PatchOperation::Move { from, path } => {
+                if path.starts_with(&format!("{}/", from)) {
+                    return Err(AppError::bad_request(format!("Cannot move {} into itself at {}", from, path)));
+                }
+                let value = remove(target, from)?;
+                add(target, path, value)?;
+            }
+            PatchOperation::Copy { from, path } => {
+                check_pointer(from)?;
+                let value = target.pointer(from).cloned().ok_or_else(|| missing(from))?;
+                add(target, path, value)?;
+            }
+            PatchOperation::Test { path, value } => {
+                check_pointer(path)?;
+                if target.pointer(path) != Some(value) {
+                    return Err(AppError::conflict(format!("Test failed: {} does not have the expected value", path)));
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Apply the body of a PATCH request to the current state of an item, in the
+/// format named by its Content-Type, and read back the patched item. The
+/// result must still be a valid item: required fields present with the right
+/// types, no unknown fields and the same id.
+pub fn apply_patch<T: Serialize + DeserializeOwned>(req: &HttpRequest, body: &[u8], current: &T) -> AppResult<T> {
+    let original = serde_json::to_value(current)
+        .map_err(|e| AppError::internal_error(format!("Failed to read the item: {}", e)))?;
+    let mut document = original.clone();
+
+    let media_type = req.headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.split(';').next())
+        .map(|value| value.trim().to_ascii_lowercase());
+
+    match media_type.as_deref() {
+        Some(MERGE_PATCH) => merge_patch(&mut document, &parse::<Value>(body)?),
+        Some(JSON_PATCH) => json_patch(&mut document, &parse::<Vec<PatchOperation>>(body)?)?,
+        _ => {
+            return Err(AppError::unsupported_media_type(
+                format!("Send the patch as {} or {}", MERGE_PATCH, JSON_PATCH),
+                Some(ACCEPT_PATCH),
+            ));
+        }
+    }
+
+    if document.get("id") != original.get("id") {
+        return Err(AppError::bad_request("The id of an item cannot be changed"));
+    }
+
+    let item: T = serde_json::from_value(document.clone())
+        .map_err(|e| AppError::bad_request(format!("Invalid patched item: {}", e)))?;
+
+    // Members the item does not have would otherwise be dropped silently
+    let known = serde_json::to_value(&item)
+        .map_err(|e| AppError::internal_error(format!("Failed to read the item: {}", e)))?;
+    if let (Some(members), Some(known)) = (document.as_object(), known.as_object()) {
+        if let Some(key) = members.keys().find(|key| !known.contains_key(*key)) {
+            return Err(AppError::bad_request(format!("Invalid patched item: unknown field '{}'", key)));
+        }
+    }
+
+    Ok(item)
+}
+
+fn parse<T: DeserializeOwned>(body: &[u8]) -> AppResult<T> {
+    serde_json::from_slice(body).map_err(|e| AppError::bad_request(format!("Invalid patch: {}", e)))
+}
+
+fn missing(pointer: &str) -> AppError {
+    AppError::conflict(format!("Nothing at {} to patch", pointer))
+}
+
+// A JSON Pointer is empty or made of "/"-prefixed tokens, where "~" only
+// starts the escapes "~0" and "~1"
+fn check_pointer(pointer: &str) -> AppResult<()> {
+    let escapes_valid = pointer.split('~').skip(1).all(|rest| rest.starts_with(['0', '1']));
+    if (pointer.is_empty() || pointer.starts_with('/')) && escapes_valid {
+        return Ok(());
+    }
+
+    Err(AppError::bad_request(format!("Invalid JSON Pointer '{}'", pointer)))
+}
+
+// Parent pointer and last token, unescaped, of a pointer to a member
+fn split_pointer(pointer: &str) -> AppResult<(&str, String)> {
+    check_pointer(pointer)?;
+    let (parent, last) = pointer.rsplit_once('/')
+        .ok_or_else(|| AppError::bad_request("The whole item cannot be added or removed"))?;
+
+    Ok((parent, last.replace("~1", "/").replace("~0", "~")))
+}
+
+// Array index of a token: digits without leading zeros, at most `max`
+fn array_index(token: &str, max: usize, pointer: &str) -> AppResult<usize> {
+    let well_formed = !token.is_empty()
+        && token.bytes().all(|byte| byte.is_ascii_digit())
+        && (token == "0" || !token.starts_with('0'));
+    if !well_formed {
+        return Err(AppError::bad_request(format!("Invalid array index in {}", pointer)));
+    }
+
+    token.parse::<usize>()
+        .ok()
+        .filter(|index| *index <= max)
+        .ok_or_else(|| missing(pointer))
+}
+
+fn add(target: &mut Value, pointer: &str, value: Value) -> AppResult<()> {
+    let (parent, token) = split_pointer(pointer)?;
+
+    match target.pointer_mut(parent).ok_or_else(|| missing(pointer))? {
+        Value::Object(members) => {
+            members.insert(token, value);
+        }
+        Value::Array(items) if token == "-" => items.push(value),
+        Value::Array(items) => {
+            let index = array_index(&token, items.len(), pointer)?;
+            items.insert(index, value);
+        }
+        _ => return Err(missing(pointer)),
+    }
+
+    Ok(())
+}
+
+fn remove(target: &mut Value, pointer: &str) -> AppResult<Value> {
+    let (parent, token) = split_pointer(pointer)?;
+
+    match target.pointer_mut(parent).ok_or_else(|| missing(pointer))? {
+        Value::Object(members) => members.remove(&token).ok_or_else(|| missing(pointer)),
+        Value::Array(items) if !items.is_empty() => {
+            let index = array_index(&token, items.len() - 1, pointer)?;
+            Ok(items.remove(index))
+        }
+        _ => Err(missing(pointer)),
+    }
+}
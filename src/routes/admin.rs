@@ -1,10 +1,13 @@
-use actix_web::{get, web, HttpResponse, Responder};
-use log::info;
-use serde::Serialize;
-use utoipa::ToSchema;
+use actix_web::{get, web, HttpRequest, HttpResponse, Responder};
+use log::{error, info};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
 
-use crate::auth::AuthenticatedUser;
-use crate::error::AppResult;
+use crate::auth::{AuthenticatedUser, Permission};
+use crate::config::database::DbPool;
+use crate::error::{AppError, AppResult};
+use crate::models::login_attempt_repository::LoginAttemptRepository;
+use crate::pagination::{paginated, ListParams};
 
 #[derive(Serialize, ToSchema)]
 struct AdminResponse {
@@ -33,6 +36,8 @@ struct AdminResponse {
 )]
 #[get("/admin/dashboard")]
 pub async fn admin_dashboard(user: AuthenticatedUser) -> AppResult<impl Responder> {
+    user.require(Permission::DashboardRead)?;
+    
     info!("Admin dashboard accessed by: {}", user.0.name);
     
     Ok(HttpResponse::Ok().json(AdminResponse {
@@ -41,6 +46,62 @@ pub async fn admin_dashboard(user: AuthenticatedUser) -> AppResult<impl Responde
     }))
 }
 
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct LoginAttemptQuery {
+    /// Only attempts for this username
+    username: Option<String>,
+    /// Only attempts from this client address
+    ip_address: Option<String>,
+    /// Only attempts with this outcome ("success", "mfa_required", "invalid_credentials", "invalid_code" or "locked")
+    outcome: Option<String>,
+}
+
+/// Login audit log
+///
+/// Lists one page of recorded login attempts, most recent first. Sortable by
+/// `attempted_at` and `username`. Requires admin privileges.
+#[utoipa::path(
+    get,
+    path = "/admin/login-attempts",
+    tag = "admin",
+    security(
+        ("jwt_auth" = [])
+    ),
+    params(ListParams, LoginAttemptQuery),
+    responses(
+        (status = 200, description = "Page of recorded login attempts", body = Vec<crate::models::login_attempt::LoginAttempt>),
+        (status = 400, description = "Invalid page, sort field or filter"),
+        (status = 401, description = "Unauthorized - Invalid or missing authentication token"),
+        (status = 403, description = "Forbidden - Role does not grant this permission"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[get("/admin/login-attempts")]
+pub async fn get_login_attempts(
+    req: HttpRequest,
+    params: web::Query<ListParams>,
+    filters: web::Query<LoginAttemptQuery>,
+    db: web::Data<DbPool>,
+    user: AuthenticatedUser,
+) -> AppResult<impl Responder> {
+    user.require(Permission::AuditRead)?;
+    let filters = filters.into_inner();
+    let query = params.query(&LoginAttemptRepository::LIST)?
+        .filter("username", filters.username)?
+        .filter("ip_address", filters.ip_address)?
+        .filter("outcome", filters.outcome)?;
+    let repo = LoginAttemptRepository::new(db.get_ref().clone());
+
+    let attempts = repo.find_page(&query).await
+        .map_err(|e| {
+            error!("Failed to fetch login attempts: {}", e);
+            AppError::internal_error(format!("Failed to fetch login attempts: {}", e))
+        })?;
+
+    Ok(paginated(&req, attempts))
+}
+
 pub fn config(cfg: &mut web::ServiceConfig) {
-    cfg.service(admin_dashboard);
+    cfg.service(admin_dashboard)
+       .service(get_login_attempts);
 }
@@ -0,0 +1,198 @@
+use actix_web::{delete, get, post, web, HttpRequest, HttpResponse, Responder};
+use log::{error, info};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+use validator::Validate;
+
+use crate::auth::api_keys::{generate_api_key, parse_scopes};
+use crate::auth::{AuthenticatedUser, Permission};
+use crate::config::database::DbPool;
+use crate::error::{AppError, AppResult};
+use crate::models::api_key::ApiKey;
+use crate::models::api_key_repository::ApiKeyRepository;
+use crate::pagination::{paginated, ListParams};
+use crate::validation::validate_json;
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+#[schema(example = json!({
+    "name": "CI publisher",
+    "scopes": ["posts:write", "projects:write"],
+    "expires_in_days": 90
+}))]
+pub struct CreateApiKeyRequest {
+    /// Label to recognise the key by
+    #[validate(length(min = 1, max = 100, message = "Name must be between 1 and 100 characters"))]
+    pub name: String,
+    /// Permissions granted to the key, e.g. "posts:write" or "github:refresh"
+    pub scopes: Vec<String>,
+    /// Days until the key expires; omit for a key that never expires
+    #[validate(range(min = 1, max = 3650, message = "Expiry must be between 1 and 3650 days"))]
+    pub expires_in_days: Option<i64>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CreateApiKeyResponse {
+    /// The full key. It is shown only once and cannot be retrieved later.
+    pub key: String,
+    /// Stored details of the key
+    pub api_key: ApiKey,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct ApiKeyFilters {
+    /// Only keys created by this user ID
+    created_by: Option<String>,
+}
+
+/// List API keys
+///
+/// Returns one page of API keys, newest first, including revoked and expired ones.
+/// Sortable by `created_at`, `name` and `last_used_at`. Key values are never returned.
+#[utoipa::path(
+    get,
+    path = "/admin/api-keys",
+    tag = "api-keys",
+    security(
+        ("jwt_auth" = [])
+    ),
+    params(ListParams, ApiKeyFilters),
+    responses(
+        (status = 200, description = "Page of API keys", body = Vec<ApiKey>),
+        (status = 400, description = "Invalid page, sort field or filter"),
+        (status = 401, description = "Unauthorized - Invalid or missing authentication token"),
+        (status = 403, description = "Forbidden - Role does not grant this permission"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[get("/admin/api-keys")]
+pub async fn get_all_api_keys(
+    req: HttpRequest,
+    params: web::Query<ListParams>,
+    filters: web::Query<ApiKeyFilters>,
+    db: web::Data<DbPool>,
+    user: AuthenticatedUser,
+) -> AppResult<impl Responder> {
+    user.require(Permission::ApiKeysManage)?;
+    let query = params.query(&ApiKeyRepository::LIST)?
+        .filter("created_by", filters.into_inner().created_by)?;
+    let repo = ApiKeyRepository::new(db.get_ref().clone());
+
+    let keys = repo.find_page(&query).await
+        .map_err(|e| {
+            error!("Failed to fetch API keys: {}", e);
+            AppError::internal_error(format!("Failed to fetch API keys: {}", e))
+        })?;
+
+    Ok(paginated(&req, keys))
+}
+
+/// Create an API key
+///
+/// Creates a key acting on behalf of the calling user, limited to the given scopes.
+/// Scopes must be granted by the caller's role. The key is returned only in this response.
+#[utoipa::path(
+    post,
+    path = "/admin/api-keys",
+    tag = "api-keys",
+    security(
+        ("jwt_auth" = [])
+    ),
+    request_body = CreateApiKeyRequest,
+    responses(
+        (status = 201, description = "API key created successfully", body = CreateApiKeyResponse),
+        (status = 400, description = "Invalid name, scopes or expiry"),
+        (status = 401, description = "Unauthorized - Invalid or missing authentication token"),
+        (status = 403, description = "Forbidden - Role does not grant this permission or a requested scope"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[post("/admin/api-keys")]
+pub async fn create_api_key(
+    key_req: web::Json<CreateApiKeyRequest>,
+    db: web::Data<DbPool>,
+    user: AuthenticatedUser,
+) -> AppResult<impl Responder> {
+    user.require(Permission::ApiKeysManage)?;
+    let key_req = validate_json(key_req)?;
+    let scopes: Vec<String> = parse_scopes(&key_req.scopes, &user.0.role)?
+        .iter()
+        .map(|scope| scope.as_str().to_string())
+        .collect();
+
+    let expires_at = key_req.expires_in_days
+        .map(|days| chrono::Utc::now().timestamp() + chrono::Duration::days(days).num_seconds());
+
+    let generated = generate_api_key();
+    let api_key = ApiKey::new(
+        key_req.name,
+        generated.prefix,
+        generated.key_hash,
+        scopes,
+        user.0.id.clone(),
+        expires_at,
+    );
+
+    let repo = ApiKeyRepository::new(db.get_ref().clone());
+    let created = repo.create(api_key).await
+        .map_err(|e| {
+            error!("Failed to create API key: {}", e);
+            AppError::internal_error(format!("Failed to create API key: {}", e))
+        })?;
+
+    info!("User {} created API key {} ({})", user.0.name, created.prefix, created.name);
+    Ok(HttpResponse::Created().json(CreateApiKeyResponse {
+        key: generated.key,
+        api_key: created,
+    }))
+}
+
+/// Revoke an API key
+///
+/// Revokes the key with the specified ID. Revoked keys stay listed but can no longer authenticate.
+#[utoipa::path(
+    delete,
+    path = "/admin/api-keys/{id}",
+    tag = "api-keys",
+    security(
+        ("jwt_auth" = [])
+    ),
+    params(
+        ("id" = String, Path, description = "API key unique identifier")
+    ),
+    responses(
+        (status = 204, description = "API key revoked successfully"),
+        (status = 401, description = "Unauthorized - Invalid or missing authentication token"),
+        (status = 403, description = "Forbidden - Role does not grant this permission"),
+        (status = 404, description = "API key not found or already revoked"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[delete("/admin/api-keys/{id}")]
+pub async fn revoke_api_key(
+    path: web::Path<String>,
+    db: web::Data<DbPool>,
+    user: AuthenticatedUser,
+) -> AppResult<impl Responder> {
+    user.require(Permission::ApiKeysManage)?;
+    let id = path.into_inner();
+    let repo = ApiKeyRepository::new(db.get_ref().clone());
+
+    let revoked = repo.revoke(&id, chrono::Utc::now().timestamp()).await
+        .map_err(|e| {
+            error!("Failed to revoke API key {}: {}", id, e);
+            AppError::internal_error(format!("Failed to revoke API key: {}", e))
+        })?;
+
+    if !revoked {
+        return Err(AppError::not_found(format!("Active API key with ID {} not found", id)));
+    }
+
+    info!("User {} revoked API key {}", user.0.name, id);
+    Ok(HttpResponse::NoContent().finish())
+}
+
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_all_api_keys)
+       .service(create_api_key)
+       .service(revoke_api_key);
+}
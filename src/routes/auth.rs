@@ -1,10 +1,23 @@
-use actix_web::{post, web, HttpResponse, Responder};
+use actix_web::{get, post, put, web, HttpRequest, HttpResponse, Responder};
 use log::{error, info};
 use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
+use validator::Validate;
 
-use crate::auth::{authenticate_user, AuthMiddleware, User};
+use crate::auth::session::{issue_session, refresh_session, revoke_session, revoke_user_sessions, Session};
+use crate::auth::throttle::{
+    check_lockout, record_attempt, record_failure, record_success, LoginContext,
+    OUTCOME_INVALID_CODE, OUTCOME_INVALID_CREDENTIALS, OUTCOME_MFA_REQUIRED,
+};
+use crate::auth::totp::{hash_recovery_code, issue_challenge, verify_challenge, verify_code};
+use crate::auth::{authenticate_user, AuthMiddleware, hash_password, verify_password, AuthenticatedUser, User};
+use crate::config::database::DbPool;
 use crate::error::{AppError, AppResult};
+use crate::models::repository::Repository;
+use crate::models::token_repository::TokenRepository;
+use crate::models::totp_repository::TotpRepository;
+use crate::models::user_repository::UserRepository;
+use crate::validation::validate_json;
 
 #[derive(Debug, Deserialize, ToSchema)]
 pub struct LoginRequest {
@@ -18,30 +31,89 @@ pub struct LoginRequest {
 pub struct LoginResponse {
     /// Indicates if login was successful
     success: bool,
-    /// JWT token for authenticated requests
+    /// Short-lived JWT access token for authenticated requests
     token: String,
+    /// Single-use token for POST /auth/refresh
+    refresh_token: String,
+    /// Access token lifetime in seconds
+    expires_in: i64,
     /// User information
     user: User,
 }
 
+impl LoginResponse {
+    fn new(session: Session, user: User) -> Self {
+        Self {
+            success: true,
+            token: session.access_token,
+            refresh_token: session.refresh_token,
+            expires_in: session.expires_in,
+            user,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct MfaChallengeResponse {
+    /// Indicates that the password was correct
+    success: bool,
+    /// Always true; the login must be completed at POST /auth/login/totp
+    mfa_required: bool,
+    /// Interim token to submit together with the authentication code
+    challenge_token: String,
+    /// Challenge token lifetime in seconds
+    expires_in: i64,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct TotpLoginRequest {
+    /// Challenge token returned by POST /auth/login
+    challenge_token: String,
+    /// Six digit code from the authenticator app, or an unused recovery code
+    code: String,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct RefreshRequest {
+    /// Refresh token from a previous login or refresh
+    refresh_token: String,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct LogoutRequest {
+    /// Refresh token of the session to end; its whole rotation chain is revoked
+    refresh_token: Option<String>,
+}
+
 /// Authenticate user and get JWT token
 ///
-/// Authenticates a user with username and password, and returns a JWT token
-/// that can be used for authorized API requests.
+/// Authenticates a user with username and password, and returns a short-lived
+/// JWT access token together with a refresh token. Users with two-factor
+/// authentication enabled get an `MfaChallengeResponse` instead, to be
+/// completed at POST /auth/login/totp.
 #[utoipa::path(
     post,
     path = "/auth/login",
     tag = "auth",
     request_body = LoginRequest,
     responses(
-        (status = 200, description = "Authentication successful", body = LoginResponse),
+        (status = 200, description = "Authentication successful, or a two-factor challenge (MfaChallengeResponse)", body = LoginResponse),
         (status = 401, description = "Invalid credentials"),
+        (status = 429, description = "Too many failed attempts; retry after the number of seconds in the Retry-After header"),
         (status = 500, description = "Internal server error")
     )
 )]
 #[post("/auth/login")]
-pub async fn login(login_req: web::Json<LoginRequest>) -> AppResult<impl Responder> {
-    let user = authenticate_user(&login_req.username, &login_req.password).await
+pub async fn login(
+    req: HttpRequest,
+    login_req: web::Json<LoginRequest>,
+    db: web::Data<DbPool>,
+) -> AppResult<impl Responder> {
+    let now = chrono::Utc::now().timestamp();
+    let attempt = LoginContext::from_request(&req, &login_req.username);
+    check_lockout(db.get_ref(), &attempt, now).await?;
+    
+    let user = authenticate_user(db.get_ref(), &login_req.username, &login_req.password).await
         .map_err(|e| {
             error!("Authentication error: {}", e);
             AppError::internal_error("Authentication failed")
@@ -49,27 +121,289 @@ pub async fn login(login_req: web::Json<LoginRequest>) -> AppResult<impl Respond
     
     match user {
         Some(user) => {
-            let token = AuthMiddleware::generate_token(&user)
+            let enrollment = TotpRepository::new(db.get_ref().clone())
+                .find_by_user(&user.id).await
+                .map_err(|e| {
+                    error!("Failed to fetch two-factor settings for user {}: {}", user.id, e);
+                    AppError::internal_error("Authentication failed")
+                })?;
+            
+            if enrollment.is_some_and(|enrollment| enrollment.is_confirmed()) {
+                let (challenge_token, expires_in) = issue_challenge(&user.id, now)?;
+                record_attempt(db.get_ref(), &attempt, OUTCOME_MFA_REQUIRED, now).await?;
+                
+                info!("User {} passed the password check, awaiting second factor", user.name);
+                
+                return Ok(HttpResponse::Ok().json(MfaChallengeResponse {
+                    success: true,
+                    mfa_required: true,
+                    challenge_token,
+                    expires_in,
+                }));
+            }
+            
+            let session = issue_session(db.get_ref(), &user).await
                 .map_err(|e| {
                     error!("Token generation error: {}", e);
                     AppError::internal_error("Failed to generate token")
                 })?;
             
+            record_success(db.get_ref(), &attempt, now).await?;
             info!("User {} logged in successfully", user.name);
             
-            Ok(HttpResponse::Ok().json(LoginResponse {
-                success: true,
-                token,
-                user,
-            }))
+            Ok(HttpResponse::Ok().json(LoginResponse::new(session, user)))
         },
         None => {
             info!("Failed login attempt for user: {}", login_req.username);
+            record_failure(db.get_ref(), &attempt, OUTCOME_INVALID_CREDENTIALS, now).await?;
             Err(AppError::unauthorized("Invalid username or password"))
         }
     }
 }
 
+/// Complete a two-factor login
+///
+/// Exchanges the challenge token from POST /auth/login and a code from the
+/// authenticator app (or a one-time recovery code) for a session.
+/// Each code and each challenge token can be used only once.
+#[utoipa::path(
+    post,
+    path = "/auth/login/totp",
+    tag = "auth",
+    request_body = TotpLoginRequest,
+    responses(
+        (status = 200, description = "Authentication successful", body = LoginResponse),
+        (status = 401, description = "Invalid or expired challenge token, or invalid code"),
+        (status = 429, description = "Too many failed attempts; retry after the number of seconds in the Retry-After header"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[post("/auth/login/totp")]
+pub async fn login_totp(
+    req: HttpRequest,
+    totp_req: web::Json<TotpLoginRequest>,
+    db: web::Data<DbPool>,
+) -> AppResult<impl Responder> {
+    let claims = verify_challenge(&totp_req.challenge_token)?;
+    let tokens = TokenRepository::new(db.get_ref().clone());
+    
+    let used = tokens.is_access_token_revoked(&claims.jti).await
+        .map_err(|e| {
+            error!("Failed to check challenge token: {}", e);
+            AppError::internal_error("Authentication failed")
+        })?;
+    
+    if used {
+        return Err(AppError::unauthorized("Invalid or expired challenge token"));
+    }
+    
+    let account = UserRepository::new(db.get_ref().clone())
+        .find_by_id(&claims.sub).await
+        .map_err(|e| {
+            error!("Failed to fetch user {}: {}", claims.sub, e);
+            AppError::internal_error("Authentication failed")
+        })?
+        .ok_or_else(|| AppError::unauthorized("User no longer exists"))?;
+    
+    // Code guesses count towards the same lockout as password guesses
+    let now = chrono::Utc::now().timestamp();
+    let attempt = LoginContext::from_request(&req, &account.username);
+    check_lockout(db.get_ref(), &attempt, now).await?;
+    
+    let repo = TotpRepository::new(db.get_ref().clone());
+    let enrollment = repo.find_by_user(&account.id).await
+        .map_err(|e| {
+            error!("Failed to fetch two-factor settings for user {}: {}", account.id, e);
+            AppError::internal_error("Authentication failed")
+        })?
+        .filter(|enrollment| enrollment.is_confirmed())
+        .ok_or_else(|| AppError::unauthorized("Two-factor authentication is not enabled"))?;
+    
+    let accepted = match verify_code(&enrollment.secret, &totp_req.code, now as u64) {
+        Some(step) => repo.use_step(&account.id, step).await,
+        None => repo.use_recovery_code(&account.id, &hash_recovery_code(&totp_req.code)).await,
+    }
+    .map_err(|e| {
+        error!("Failed to record two-factor code for user {}: {}", account.id, e);
+        AppError::internal_error("Authentication failed")
+    })?;
+    
+    if !accepted {
+        info!("Invalid two-factor code for user: {}", account.username);
+        record_failure(db.get_ref(), &attempt, OUTCOME_INVALID_CODE, now).await?;
+        return Err(AppError::unauthorized("Invalid authentication code"));
+    }
+    
+    // The challenge token is single-use, like the code
+    tokens.revoke_access_token(&claims.jti, claims.exp as i64).await
+        .map_err(|e| {
+            error!("Failed to revoke challenge token: {}", e);
+            AppError::internal_error("Authentication failed")
+        })?;
+    
+    let user = User {
+        id: account.id,
+        name: account.name,
+        role: account.role,
+    };
+    
+    let session = issue_session(db.get_ref(), &user).await?;
+    record_success(db.get_ref(), &attempt, now).await?;
+    
+    info!("User {} logged in successfully with two-factor authentication", user.name);
+    Ok(HttpResponse::Ok().json(LoginResponse::new(session, user)))
+}
+
+/// Refresh an access token
+///
+/// Exchanges a refresh token for a new access token and a new refresh token.
+/// Each refresh token can be used only once; reusing one revokes the session.
+#[utoipa::path(
+    post,
+    path = "/auth/refresh",
+    tag = "auth",
+    request_body = RefreshRequest,
+    responses(
+        (status = 200, description = "Tokens refreshed successfully", body = LoginResponse),
+        (status = 401, description = "Invalid, expired or revoked refresh token"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[post("/auth/refresh")]
+pub async fn refresh(
+    refresh_req: web::Json<RefreshRequest>,
+    db: web::Data<DbPool>,
+) -> AppResult<impl Responder> {
+    let (user, session) = refresh_session(db.get_ref(), &refresh_req.refresh_token).await?;
+    
+    info!("Refreshed session for user {}", user.name);
+    Ok(HttpResponse::Ok().json(LoginResponse::new(session, user)))
+}
+
+/// Log out
+///
+/// Revokes the access token used for this request and, if supplied,
+/// the refresh token of the session.
+#[utoipa::path(
+    post,
+    path = "/auth/logout",
+    tag = "auth",
+    security(
+        ("jwt_auth" = [])
+    ),
+    request_body = LogoutRequest,
+    responses(
+        (status = 204, description = "Logged out successfully"),
+        (status = 401, description = "Unauthorized - Invalid or missing authentication token"),
+        (status = 403, description = "Forbidden - Called with an API key"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[post("/auth/logout")]
+pub async fn logout(
+    logout_req: Option<web::Json<LogoutRequest>>,
+    db: web::Data<DbPool>,
+    user: AuthenticatedUser,
+) -> AppResult<impl Responder> {
+    let claims = user.access_token()?;
+    let refresh_token = logout_req.as_ref().and_then(|req| req.refresh_token.as_deref());
+    
+    revoke_session(db.get_ref(), claims, refresh_token).await?;
+    
+    Ok(HttpResponse::NoContent().finish())
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct ChangePasswordRequest {
+    /// Current password of the authenticated user
+    current_password: String,
+    /// New password (at least 8 characters)
+    #[validate(length(min = 8, message = "Password must be at least 8 characters"))]
+    new_password: String,
+}
+
+/// Change own password
+///
+/// Changes the password of the authenticated user.
+/// The current password must be supplied.
+#[utoipa::path(
+    put,
+    path = "/auth/password",
+    tag = "auth",
+    security(
+        ("jwt_auth" = [])
+    ),
+    request_body = ChangePasswordRequest,
+    responses(
+        (status = 204, description = "Password changed successfully"),
+        (status = 400, description = "Validation error"),
+        (status = 401, description = "Unauthorized - Invalid token or wrong current password"),
+        (status = 403, description = "Forbidden - Called with an API key"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[put("/auth/password")]
+pub async fn change_password(
+    password_req: web::Json<ChangePasswordRequest>,
+    db: web::Data<DbPool>,
+    user: AuthenticatedUser,
+) -> AppResult<impl Responder> {
+    user.access_token()?;
+    let password_req = validate_json(password_req)?;
+    let repo = UserRepository::new(db.get_ref().clone());
+
+    let mut account = repo.find_by_id(&user.0.id).await
+        .map_err(|e| {
+            error!("Failed to fetch user {}: {}", user.0.id, e);
+            AppError::internal_error(format!("Failed to fetch user: {}", e))
+        })?
+        .ok_or_else(|| AppError::unauthorized("User no longer exists"))?;
+
+    if !verify_password(&password_req.current_password, &account.password_hash) {
+        info!("Wrong current password supplied by user {}", account.username);
+        return Err(AppError::unauthorized("Current password is incorrect"));
+    }
+
+    account.password_hash = hash_password(&password_req.new_password)?;
+
+    repo.update(&account.id.clone(), account).await
+        .map_err(|e| {
+            error!("Failed to update password for user {}: {}", user.0.id, e);
+            AppError::internal_error(format!("Failed to update password: {}", e))
+        })?;
+
+    // Other sessions may have been opened with the old password
+    revoke_user_sessions(db.get_ref(), &user.0.id).await?;
+
+    info!("User {} changed their password", user.0.name);
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// JSON Web Key Set
+///
+/// Publishes the public keys used to sign access tokens, so other services
+/// can verify them. HMAC keys are never included.
+#[utoipa::path(
+    get,
+    path = "/.well-known/jwks.json",
+    tag = "auth",
+    responses(
+        (status = 200, description = "Public signing keys in JWK Set format", body = Object),
+        (status = 500, description = "Invalid key configuration")
+    )
+)]
+#[get("/.well-known/jwks.json")]
+pub async fn jwks() -> AppResult<impl Responder> {
+    Ok(HttpResponse::Ok()
+        .insert_header(("Cache-Control", "public, max-age=300"))
+        .json(AuthMiddleware::keys()?.jwks()))
+}
+
 pub fn config(cfg: &mut web::ServiceConfig) {
-    cfg.service(login);
+    cfg.service(login)
+       .service(login_totp)
+       .service(refresh)
+       .service(logout)
+       .service(change_password)
+       .service(jwks);
 }
@@ -0,0 +1,180 @@
+use actix_web::{get, http::header, web, HttpRequest, HttpResponse, Responder};
+use log::{error, info};
+use serde::Deserialize;
+use utoipa::IntoParams;
+
+use crate::auth::{AuthenticatedUser, Permission};
+use crate::config::database::DbPool;
+use crate::error::{AppError, AppResult};
+use crate::models::bundle::{Bundle, ImportMode};
+use crate::models::bundle_repository::BundleRepository;
+use crate::services::yaml::{from_yaml, to_yaml};
+
+/// Largest bundle accepted by an import, in bytes
+pub const MAX_BUNDLE_SIZE: usize = 16 * 1024 * 1024;
+
+const YAML_TYPES: &[&str] = &["application/yaml", "application/x-yaml", "text/yaml", "text/x-yaml"];
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct ExportParams {
+    /// "json" (default) or "yaml"
+    format: Option<String>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct ImportParams {
+    /// "merge" (default) to keep the items missing from the bundle, or
+    /// "replace" to delete them
+    mode: Option<String>,
+    /// Only report the changes the import would make
+    dry_run: Option<bool>,
+}
+
+// Whether the bundle is wanted in YAML, rejecting unknown formats
+fn yaml_requested(params: &ExportParams) -> AppResult<bool> {
+    match params.format.as_deref() {
+        None | Some("json") => Ok(false),
+        Some("yaml") => Ok(true),
+        Some(format) => Err(AppError::bad_request(format!("Invalid format '{}'; use json or yaml", format))),
+    }
+}
+
+fn import_mode(params: &ImportParams) -> AppResult<ImportMode> {
+    match params.mode.as_deref() {
+        None | Some("merge") => Ok(ImportMode::Merge),
+        Some("replace") => Ok(ImportMode::Replace),
+        Some(mode) => Err(AppError::bad_request(format!("Invalid mode '{}'; use merge or replace", mode))),
+    }
+}
+
+// The bundle in a request body, as JSON or YAML according to its Content-Type
+fn parse_bundle(req: &HttpRequest, body: &[u8]) -> AppResult<Bundle> {
+    let media_type = req.headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.split(';').next())
+        .map(|value| value.trim().to_ascii_lowercase());
+    let invalid = |e: &dyn std::fmt::Display| AppError::bad_request(format!("Invalid bundle: {}", e));
+
+    match media_type.as_deref() {
+        Some("application/json") => serde_json::from_slice(body).map_err(|e| invalid(&e)),
+        Some(media_type) if YAML_TYPES.contains(&media_type) => {
+            let text = std::str::from_utf8(body).map_err(|e| invalid(&e))?;
+            let value = from_yaml(text).map_err(|e| invalid(&e))?;
+            serde_json::from_value(value).map_err(|e| invalid(&e))
+        }
+        _ => Err(AppError::unsupported_media_type("Send the bundle as application/json or application/yaml", None)),
+    }
+}
+
+/// Export the portfolio
+///
+/// Returns the profile, projects, experiences, skills, posts (drafts
+/// included) and GitHub stats in one versioned document, as JSON or YAML.
+/// Deleted items are left out. Requires the portfolio:export permission.
+#[utoipa::path(
+    get,
+    path = "/admin/export",
+    tag = "admin",
+    security(("jwt_auth" = [])),
+    params(ExportParams),
+    responses(
+        (status = 200, description = "The whole portfolio", body = Bundle),
+        (status = 400, description = "Unknown format"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Role does not grant this permission"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[get("/admin/export")]
+pub async fn export_bundle(
+    params: web::Query<ExportParams>,
+    user: AuthenticatedUser,
+    db: web::Data<DbPool>,
+) -> AppResult<impl Responder> {
+    user.require(Permission::PortfolioExport)?;
+    let yaml = yaml_requested(&params)?;
+
+    let bundle = BundleRepository::new(db.get_ref().clone())
+        .export()
+        .await
+        .map_err(|e| {
+            error!("Failed to export the portfolio: {}", e);
+            AppError::internal_error(format!("Failed to export the portfolio: {}", e))
+        })?;
+
+    let filename = format!("portfolio-{}", chrono::Utc::now().format("%Y-%m-%d"));
+    let mut response = HttpResponse::Ok();
+    if !yaml {
+        response.insert_header((header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}.json\"", filename)));
+        return Ok(response.json(bundle));
+    }
+
+    let body = serde_json::to_value(&bundle)
+        .map_err(anyhow::Error::from)
+        .and_then(|value| to_yaml(&value))
+        .map_err(|e| AppError::internal_error(format!("Failed to export the portfolio: {}", e)))?;
+    response.insert_header((header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}.yaml\"", filename)));
+    Ok(response.content_type("application/yaml; charset=utf-8").body(body))
+}
+
+/// Import the portfolio
+///
+/// Loads a bundle made by the export, sent as JSON or YAML. Items are matched
+/// by id, skill categories by name. In merge mode, items missing from the
+/// bundle are kept; in replace mode they are deleted, posts, projects,
+/// experiences and skills going to the trash. The whole import runs in one
+/// transaction: on any error nothing is saved. With `dry_run=true` the
+/// changes are only reported. Requires the portfolio:import permission.
+#[utoipa::path(
+    post,
+    path = "/admin/import",
+    tag = "admin",
+    security(("jwt_auth" = [])),
+    params(ImportParams),
+    request_body(content = Bundle, description = "Bundle as JSON, or as YAML with Content-Type application/yaml", content_type = "application/json"),
+    responses(
+        (status = 200, description = "Changes made, or that would be made on a dry run", body = ImportReport),
+        (status = 400, description = "Invalid bundle, unsupported format version or unknown mode"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Role does not grant this permission"),
+        (status = 409, description = "Slug already in use by an item outside the bundle, or given to two items of the bundle"),
+        (status = 413, description = "Bundle larger than 16 MiB"),
+        (status = 415, description = "Body neither JSON nor YAML"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+pub async fn import_bundle(
+    req: HttpRequest,
+    params: web::Query<ImportParams>,
+    body: web::Bytes,
+    user: AuthenticatedUser,
+    db: web::Data<DbPool>,
+) -> AppResult<impl Responder> {
+    user.require(Permission::PortfolioImport)?;
+    let mode = import_mode(&params)?;
+    let dry_run = params.dry_run.unwrap_or(false);
+    let bundle = parse_bundle(&req, &body)?;
+
+    let report = BundleRepository::new(db.get_ref().clone())
+        .import(bundle, mode, dry_run, &user.0)
+        .await?;
+
+    if !dry_run {
+        info!(
+            "{} imported a bundle ({:?}): {} created, {} updated, {} deleted",
+            user.0.name, mode, report.created, report.updated, report.deleted
+        );
+    }
+
+    Ok(HttpResponse::Ok().json(report))
+}
+
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(export_bundle)
+       .service(
+           web::resource("/admin/import")
+               .app_data(web::PayloadConfig::new(MAX_BUNDLE_SIZE))
+               .route(web::post().to(import_bundle)),
+       );
+}
@@ -1,12 +1,13 @@
-use actix_web::{post, get, delete, web, HttpResponse, Responder};
+use actix_web::{post, get, delete, web, HttpRequest, HttpResponse, Responder};
 use log::info;
 use validator::Validate;
-use utoipa::ToSchema;
+use utoipa::{IntoParams, ToSchema};
 
-use crate::auth::AuthenticatedUser;
+use crate::auth::{AuthenticatedUser, Permission};
 use crate::config::database::DbPool;
 use crate::error::AppResult;
 use crate::models::contact::{ContactMessage, ContactResponse, ContactRepository};
+use crate::pagination::{paginated, ListParams};
 use crate::validation::validate_json;
 
 #[derive(Debug, Validate, serde::Deserialize, ToSchema)]
@@ -90,30 +91,46 @@ pub async fn submit_contact_form(
     }
 }
 
+#[derive(Debug, serde::Deserialize, IntoParams)]
+pub struct MessageFilters {
+    /// Only messages sent from this email address
+    email: Option<String>,
+}
+
 /// Get all contact messages
 ///
-/// Returns a list of all contact form submissions.
-/// Requires authentication.
+/// Returns one page of contact form submissions, newest first. Sortable by
+/// `created_at`, `name` and `email`. Requires authentication.
 #[utoipa::path(
     get,
     path = "/admin/messages",
     tag = "contact",
     security(("jwt" = [])),
+    params(ListParams, MessageFilters),
     responses(
-        (status = 200, description = "List of all contact messages", body = Vec<ContactMessage>),
+        (status = 200, description = "Page of contact messages", body = Vec<ContactMessage>),
+        (status = 400, description = "Invalid page, sort field or filter"),
         (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Role does not grant this permission"),
         (status = 500, description = "Internal server error")
     )
 )]
 #[get("/admin/messages")]
 pub async fn get_all_messages(
-    _user: AuthenticatedUser,
+    req: HttpRequest,
+    params: web::Query<ListParams>,
+    filters: web::Query<MessageFilters>,
+    user: AuthenticatedUser,
     db: web::Data<DbPool>,
 ) -> AppResult<impl Responder> {
+    user.require(Permission::MessagesRead)?;
+    let query = params.query(&ContactRepository::LIST)?
+        .filter("email", filters.into_inner().email)?;
+    
     let repo = ContactRepository::new(db.get_ref().clone());
-    let messages = repo.get_all().await?;
+    let messages = repo.get_page(&query).await?;
     
-    Ok(HttpResponse::Ok().json(messages))
+    Ok(paginated(&req, messages))
 }
 
 /// Get contact message by ID
@@ -131,16 +148,19 @@ pub async fn get_all_messages(
     responses(
         (status = 200, description = "Message found", body = ContactMessage),
         (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Role does not grant this permission"),
         (status = 404, description = "Message not found"),
         (status = 500, description = "Internal server error")
     )
 )]
 #[get("/admin/messages/{id}")]
 pub async fn get_message_by_id(
-    _user: AuthenticatedUser,
+    user: AuthenticatedUser,
     path: web::Path<String>,
     db: web::Data<DbPool>,
 ) -> AppResult<impl Responder> {
+    user.require(Permission::MessagesRead)?;
+    
     let id = path.into_inner();
     let repo = ContactRepository::new(db.get_ref().clone());
     let message = repo.get_by_id(&id).await?;
@@ -150,7 +170,7 @@ pub async fn get_message_by_id(
 
 /// Delete a contact message
 ///
-/// Deletes a contact message with the specified ID.
+/// Moves a contact message with the specified ID to the trash.
 /// Requires authentication.
 #[utoipa::path(
     delete,
@@ -163,16 +183,19 @@ pub async fn get_message_by_id(
     responses(
         (status = 200, description = "Message deleted successfully"),
         (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Role does not grant this permission"),
         (status = 404, description = "Message not found"),
         (status = 500, description = "Internal server error")
     )
 )]
 #[delete("/admin/messages/{id}")]
 pub async fn delete_message(
-    _user: AuthenticatedUser,
+    user: AuthenticatedUser,
     path: web::Path<String>,
     db: web::Data<DbPool>,
 ) -> AppResult<impl Responder> {
+    user.require(Permission::MessagesDelete)?;
+    
     let id = path.into_inner();
     let repo = ContactRepository::new(db.get_ref().clone());
     repo.delete(&id).await?;
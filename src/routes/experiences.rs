@@ -1,44 +1,70 @@
-use actix_web::{get, post, put, delete, web, HttpResponse, Responder};
+use actix_web::{get, post, put, patch, delete, web, HttpRequest, HttpResponse, Responder};
 use log::{error, info};
 use serde::Deserialize;
-use utoipa::ToSchema;
+use utoipa::{IntoParams, ToSchema};
 
-use crate::auth::AuthenticatedUser;
+use crate::auth::{AuthenticatedUser, Permission};
+use crate::conditional::{changed_since_read, if_match, item_version, versioned_json};
 use crate::config::database::DbPool;
 use crate::error::{AppError, AppResult};
 use crate::models::experience::Experience;
 use crate::models::experience_repository::ExperienceRepository;
-use crate::models::repository::Repository;
+use crate::models::repository::{Repository, Versioned};
+use crate::pagination::{paginated, ListParams};
+use crate::patch::apply_patch;
+use crate::routes::revisions::{self, RevisionDiffQuery};
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct ExperienceFilters {
+    /// Only experiences using this technology (case-insensitive)
+    technology: Option<String>,
+    /// Only experiences that started in this year
+    year: Option<i32>,
+}
 
 /// Get all experiences
 ///
-/// Returns a list of all work experiences sorted by start date (newest first).
+/// Returns one page of work experiences sorted by start date (newest first).
+/// Sortable by `start_date` and `company`. The total count is returned in the
+/// X-Total-Count header and links to the other pages in the Link header.
 #[utoipa::path(
     get,
     path = "/experiences",
     tag = "experiences",
+    params(ListParams, ExperienceFilters),
     responses(
-        (status = 200, description = "List of all experiences retrieved successfully", body = Vec<Experience>),
+        (status = 200, description = "Page of experiences retrieved successfully", body = Vec<Experience>),
+        (status = 400, description = "Invalid page, sort field or filter"),
         (status = 500, description = "Internal server error")
     )
 )]
 #[get("/experiences")]
-pub async fn get_all_experiences(db: web::Data<DbPool>) -> AppResult<impl Responder> {
+pub async fn get_all_experiences(
+    req: HttpRequest,
+    params: web::Query<ListParams>,
+    filters: web::Query<ExperienceFilters>,
+    db: web::Data<DbPool>,
+) -> AppResult<impl Responder> {
+    let filters = filters.into_inner();
+    let query = params.query(&ExperienceRepository::LIST)?
+        .filter("technology", filters.technology)?
+        .filter("year", filters.year.map(|year| format!("{:04}", year)))?;
     let repo = ExperienceRepository::new(db.get_ref().clone());
     
-    let experiences = repo.find_all().await
+    let experiences = repo.find_page(&query).await
         .map_err(|e| {
             error!("Failed to fetch experiences: {}", e);
             AppError::internal_error(format!("Failed to fetch experiences: {}", e))
         })?;
     
-    info!("Retrieved {} experiences", experiences.len());
-    Ok(HttpResponse::Ok().json(experiences))
+    info!("Retrieved {} of {} experiences", experiences.items.len(), experiences.total);
+    Ok(paginated(&req, experiences))
 }
 
 /// Get experience by ID
 ///
-/// Returns a single experience with the specified ID.
+/// Returns a single experience with the specified ID, with its version in the
+/// ETag header.
 #[utoipa::path(
     get,
     path = "/experiences/{id}",
@@ -56,6 +82,7 @@ pub async fn get_all_experiences(db: web::Data<DbPool>) -> AppResult<impl Respon
 pub async fn get_experience_by_id(path: web::Path<String>, db: web::Data<DbPool>) -> AppResult<impl Responder> {
     let id = path.into_inner();
     let repo = ExperienceRepository::new(db.get_ref().clone());
+    let version = item_version(&repo, &id, &format!("Experience with ID {}", id)).await?;
     
     let experience = repo.find_by_id(&id).await
         .map_err(|e| {
@@ -66,7 +93,7 @@ pub async fn get_experience_by_id(path: web::Path<String>, db: web::Data<DbPool>
     match experience {
         Some(experience) => {
             info!("Retrieved experience with ID: {}", id);
-            Ok(HttpResponse::Ok().json(experience))
+            Ok(versioned_json(version, &experience))
         },
         None => {
             info!("Experience with ID {} not found", id);
@@ -127,6 +154,7 @@ pub struct UpdateExperienceRequest {
         (status = 201, description = "Experience created successfully", body = Experience),
         (status = 400, description = "Invalid experience data"),
         (status = 401, description = "Unauthorized - Invalid or missing authentication token"),
+        (status = 403, description = "Forbidden - Role does not grant this permission"),
         (status = 500, description = "Internal server error")
     )
 )]
@@ -134,9 +162,9 @@ pub struct UpdateExperienceRequest {
 pub async fn create_experience(
     experience_req: web::Json<CreateExperienceRequest>,
     db: web::Data<DbPool>,
-    _user: AuthenticatedUser, // Require authentication
+    user: AuthenticatedUser,
 ) -> AppResult<impl Responder> {
-    let repo = ExperienceRepository::new(db.get_ref().clone());
+    user.require(Permission::ExperiencesWrite)?;
     
     let experience = Experience::new(
         experience_req.company.clone(),
@@ -148,12 +176,16 @@ pub async fn create_experience(
         experience_req.highlights.clone(),
     );
     
-    let created_experience = repo.create(experience).await
+    let mut tx = revisions::begin(db.get_ref()).await?;
+    let created_experience = ExperienceRepository::insert(&mut tx, experience).await
         .map_err(|e| {
             error!("Failed to create experience: {}", e);
             AppError::internal_error(format!("Failed to create experience: {}", e))
         })?;
     
+    revisions::record(&mut tx, "experiences", &created_experience.id, None, &created_experience, &user.0).await?;
+    revisions::commit(tx).await?;
+    
     info!("Created new experience with ID: {}", created_experience.id);
     Ok(HttpResponse::Created().json(created_experience))
 }
@@ -170,26 +202,34 @@ pub async fn create_experience(
         ("jwt_auth" = [])
     ),
     params(
-        ("id" = String, Path, description = "Experience unique identifier")
+        ("id" = String, Path, description = "Experience unique identifier"),
+        ("If-Match" = String, Header, description = "ETag of the experience as last read, or *")
     ),
     request_body = UpdateExperienceRequest,
     responses(
         (status = 200, description = "Experience updated successfully", body = Experience),
         (status = 400, description = "Invalid experience data"),
         (status = 401, description = "Unauthorized - Invalid or missing authentication token"),
+        (status = 403, description = "Forbidden - Role does not grant this permission"),
         (status = 404, description = "Experience not found"),
+        (status = 412, description = "The experience changed since it was read"),
+        (status = 428, description = "If-Match header missing"),
         (status = 500, description = "Internal server error")
     )
 )]
 #[put("/experiences/{id}")]
 pub async fn update_experience(
+    req: HttpRequest,
     path: web::Path<String>,
     experience_req: web::Json<UpdateExperienceRequest>,
     db: web::Data<DbPool>,
-    _user: AuthenticatedUser, // Require authentication
+    user: AuthenticatedUser,
 ) -> AppResult<impl Responder> {
+    user.require(Permission::ExperiencesWrite)?;
+    
     let id = path.into_inner();
     let repo = ExperienceRepository::new(db.get_ref().clone());
+    let version = if_match(&req, item_version(&repo, &id, &format!("Experience with ID {}", id)).await?)?;
     
     // First, get the existing experience
     let existing_experience = repo.find_by_id(&id).await
@@ -202,6 +242,8 @@ pub async fn update_experience(
             AppError::not_found(format!("Experience with ID {} not found", id))
         })?;
     
+    let before = existing_experience.clone();
+    
     // Update the experience with new values, keeping existing values if not provided
     let updated_experience = Experience {
         id: existing_experience.id,
@@ -214,20 +256,103 @@ pub async fn update_experience(
         highlights: experience_req.highlights.clone().unwrap_or(existing_experience.highlights),
     };
     
-    // Save the updated experience
-    let result = repo.update(&id, updated_experience.clone()).await
+    save_experience(&db, &user, before, updated_experience, version).await
+}
+
+/// Patch an experience
+///
+/// Changes part of the experience with the specified ID, with a JSON Merge
+/// Patch (`application/merge-patch+json`, where null clears an optional field)
+/// or a JSON Patch (`application/json-patch+json`) of the experience as
+/// returned by GET. The patched experience is validated as a whole before it
+/// is saved.
+/// Requires authentication.
+#[utoipa::path(
+    patch,
+    path = "/experiences/{id}",
+    tag = "experiences",
+    security(
+        ("jwt_auth" = [])
+    ),
+    params(
+        ("id" = String, Path, description = "Experience unique identifier"),
+        ("If-Match" = String, Header, description = "ETag of the experience as last read, or *")
+    ),
+    request_body(content = Object, content_type = "application/merge-patch+json", description = "JSON Merge Patch, or a JSON Patch sent as application/json-patch+json"),
+    responses(
+        (status = 200, description = "Experience patched successfully", body = Experience),
+        (status = 400, description = "Invalid patch, or invalid experience once patched"),
+        (status = 401, description = "Unauthorized - Invalid or missing authentication token"),
+        (status = 403, description = "Forbidden - Role does not grant this permission"),
+        (status = 404, description = "Experience not found"),
+        (status = 409, description = "The patch does not apply to the experience"),
+        (status = 412, description = "The experience changed since it was read"),
+        (status = 415, description = "Patch format not supported"),
+        (status = 428, description = "If-Match header missing"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[patch("/experiences/{id}")]
+pub async fn patch_experience(
+    req: HttpRequest,
+    path: web::Path<String>,
+    body: web::Bytes,
+    db: web::Data<DbPool>,
+    user: AuthenticatedUser,
+) -> AppResult<impl Responder> {
+    user.require(Permission::ExperiencesWrite)?;
+    
+    let id = path.into_inner();
+    let repo = ExperienceRepository::new(db.get_ref().clone());
+    let version = if_match(&req, item_version(&repo, &id, &format!("Experience with ID {}", id)).await?)?;
+    
+    let existing_experience = repo.find_by_id(&id).await
+        .map_err(|e| {
+            error!("Failed to fetch experience {}: {}", id, e);
+            AppError::internal_error(format!("Failed to fetch experience: {}", e))
+        })?
+        .ok_or_else(|| {
+            info!("Experience with ID {} not found for patch", id);
+            AppError::not_found(format!("Experience with ID {} not found", id))
+        })?;
+    
+    let patched_experience: Experience = apply_patch(&req, &body, &existing_experience)?;
+    
+    save_experience(&db, &user, existing_experience, patched_experience, version).await
+}
+
+// Save a new state of an experience read at `version`, recording the change
+// as a revision
+async fn save_experience(
+    db: &DbPool,
+    user: &AuthenticatedUser,
+    before: Experience,
+    experience: Experience,
+    version: i64,
+) -> AppResult<HttpResponse> {
+    let id = before.id.clone();
+    
+    // Save the updated experience, unless it changed since the client read it
+    let mut tx = revisions::begin(db).await?;
+    let (result, saved) = ExperienceRepository::write(&mut tx, &id, experience, Some(version)).await
         .map_err(|e| {
             error!("Failed to update experience {}: {}", id, e);
             AppError::internal_error(format!("Failed to update experience: {}", e))
         })?;
+    if !saved {
+        return Err(changed_since_read());
+    }
+    
+    revisions::record(&mut tx, "experiences", &id, Some(&before), &result, &user.0).await?;
+    revisions::commit(tx).await?;
     
     info!("Updated experience with ID: {}", id);
-    Ok(HttpResponse::Ok().json(result))
+    Ok(versioned_json(version + 1, &result))
 }
 
 /// Delete an experience
 ///
-/// Deletes the work experience entry with the specified ID.
+/// Moves the work experience entry with the specified ID to the trash.
 /// Requires authentication.
 #[utoipa::path(
     delete,
@@ -237,51 +362,220 @@ pub async fn update_experience(
         ("jwt_auth" = [])
     ),
     params(
-        ("id" = String, Path, description = "Experience unique identifier")
+        ("id" = String, Path, description = "Experience unique identifier"),
+        ("If-Match" = String, Header, description = "ETag of the experience as last read, or *")
     ),
     responses(
         (status = 204, description = "Experience deleted successfully"),
         (status = 401, description = "Unauthorized - Invalid or missing authentication token"),
+        (status = 403, description = "Forbidden - Role does not grant this permission"),
         (status = 404, description = "Experience not found"),
+        (status = 412, description = "The experience changed since it was read"),
+        (status = 428, description = "If-Match header missing"),
         (status = 500, description = "Internal server error")
     )
 )]
 #[delete("/experiences/{id}")]
 pub async fn delete_experience(
+    req: HttpRequest,
     path: web::Path<String>,
     db: web::Data<DbPool>,
-    _user: AuthenticatedUser, // Require authentication
+    user: AuthenticatedUser,
 ) -> AppResult<impl Responder> {
+    user.require(Permission::ExperiencesWrite)?;
+    
     let id = path.into_inner();
     let repo = ExperienceRepository::new(db.get_ref().clone());
     
-    // Check if the experience exists
-    let experience_exists = repo.find_by_id(&id).await
-        .map_err(|e| {
-            error!("Failed to fetch experience {}: {}", id, e);
-            AppError::internal_error(format!("Failed to fetch experience: {}", e))
-        })?
-        .is_some();
+    let version = if_match(&req, item_version(&repo, &id, &format!("Experience with ID {}", id)).await?)?;
     
-    if !experience_exists {
-        info!("Experience with ID {} not found for deletion", id);
-        return Err(AppError::not_found(format!("Experience with ID {} not found", id)));
-    }
-    
-    // Delete the experience
-    let deleted = repo.delete(&id).await
+    // Delete the experience, unless it changed since the client read it
+    let deleted = repo.delete_at(&id, version).await
         .map_err(|e| {
             error!("Failed to delete experience {}: {}", id, e);
             AppError::internal_error(format!("Failed to delete experience: {}", e))
         })?;
     
-    if deleted {
-        info!("Deleted experience with ID: {}", id);
-        Ok(HttpResponse::NoContent().finish())
-    } else {
-        error!("Failed to delete experience with ID: {}", id);
-        Err(AppError::internal_error("Failed to delete experience"))
+    if !deleted {
+        return Err(changed_since_read());
+    }
+    
+    info!("Deleted experience with ID: {}", id);
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// List the revisions of an experience
+///
+/// Returns one page of the saved versions of an experience, newest first, with
+/// who saved each and when. Sortable by `number` and `created_at`.
+#[utoipa::path(
+    get,
+    path = "/experiences/{id}/revisions",
+    tag = "experiences",
+    security(
+        ("jwt_auth" = [])
+    ),
+    params(
+        ("id" = String, Path, description = "Experience unique identifier"),
+        ListParams
+    ),
+    responses(
+        (status = 200, description = "Page of revisions", body = Vec<RevisionSummary>),
+        (status = 400, description = "Invalid page or sort field"),
+        (status = 401, description = "Unauthorized - Invalid or missing authentication token"),
+        (status = 403, description = "Forbidden - Role does not grant this permission"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[get("/experiences/{id}/revisions")]
+pub async fn get_experience_revisions(
+    req: HttpRequest,
+    path: web::Path<String>,
+    params: web::Query<ListParams>,
+    db: web::Data<DbPool>,
+    user: AuthenticatedUser,
+) -> AppResult<impl Responder> {
+    user.require(Permission::ExperiencesWrite)?;
+    revisions::list(&req, db.get_ref(), "experiences", &path.into_inner(), &params).await
+}
+
+/// Get a revision of an experience
+///
+/// Returns a saved version of an experience with its full snapshot.
+#[utoipa::path(
+    get,
+    path = "/experiences/{id}/revisions/{number}",
+    tag = "experiences",
+    security(
+        ("jwt_auth" = [])
+    ),
+    params(
+        ("id" = String, Path, description = "Experience unique identifier"),
+        ("number" = i64, Path, description = "Revision number")
+    ),
+    responses(
+        (status = 200, description = "Revision found", body = Revision),
+        (status = 401, description = "Unauthorized - Invalid or missing authentication token"),
+        (status = 403, description = "Forbidden - Role does not grant this permission"),
+        (status = 404, description = "Revision not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[get("/experiences/{id}/revisions/{number:\\d+}")]
+pub async fn get_experience_revision(
+    path: web::Path<(String, i64)>,
+    db: web::Data<DbPool>,
+    user: AuthenticatedUser,
+) -> AppResult<impl Responder> {
+    user.require(Permission::ExperiencesWrite)?;
+    let (id, number) = path.into_inner();
+
+    let revision = revisions::find(db.get_ref(), "experiences", &id, number).await?;
+    Ok(HttpResponse::Ok().json(revision))
+}
+
+/// Compare two revisions of an experience
+///
+/// Returns the values that differ between two saved versions, each with its
+/// JSON Pointer path in the snapshot.
+#[utoipa::path(
+    get,
+    path = "/experiences/{id}/revisions/diff",
+    tag = "experiences",
+    security(
+        ("jwt_auth" = [])
+    ),
+    params(
+        ("id" = String, Path, description = "Experience unique identifier"),
+        RevisionDiffQuery
+    ),
+    responses(
+        (status = 200, description = "Changes between the revisions", body = RevisionDiff),
+        (status = 400, description = "Missing or invalid revision numbers"),
+        (status = 401, description = "Unauthorized - Invalid or missing authentication token"),
+        (status = 403, description = "Forbidden - Role does not grant this permission"),
+        (status = 404, description = "Revision not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[get("/experiences/{id}/revisions/diff")]
+pub async fn diff_experience_revisions(
+    path: web::Path<String>,
+    query: web::Query<RevisionDiffQuery>,
+    db: web::Data<DbPool>,
+    user: AuthenticatedUser,
+) -> AppResult<impl Responder> {
+    user.require(Permission::ExperiencesWrite)?;
+
+    let diff = revisions::diff(db.get_ref(), "experiences", &path.into_inner(), &query).await?;
+    Ok(HttpResponse::Ok().json(diff))
+}
+
+/// Restore a revision of an experience
+///
+/// Writes a saved version back. The restore is saved as a new
+/// revision, so it can be undone in turn.
+#[utoipa::path(
+    post,
+    path = "/experiences/{id}/revisions/{number}/restore",
+    tag = "experiences",
+    security(
+        ("jwt_auth" = [])
+    ),
+    params(
+        ("id" = String, Path, description = "Experience unique identifier"),
+        ("number" = i64, Path, description = "Revision number"),
+        ("If-Match" = String, Header, description = "ETag of the experience as last read, or *")
+    ),
+    responses(
+        (status = 200, description = "Experience restored", body = Experience),
+        (status = 401, description = "Unauthorized - Invalid or missing authentication token"),
+        (status = 403, description = "Forbidden - Role does not grant this permission"),
+        (status = 404, description = "Experience or revision not found"),
+        (status = 409, description = "The revision can no longer be restored"),
+        (status = 412, description = "The experience changed since it was read"),
+        (status = 428, description = "If-Match header missing"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[post("/experiences/{id}/revisions/{number}/restore")]
+pub async fn restore_experience_revision(
+    req: HttpRequest,
+    path: web::Path<(String, i64)>,
+    db: web::Data<DbPool>,
+    user: AuthenticatedUser,
+) -> AppResult<impl Responder> {
+    user.require(Permission::ExperiencesWrite)?;
+
+    let (id, number) = path.into_inner();
+    let repo = ExperienceRepository::new(db.get_ref().clone());
+    let version = if_match(&req, item_version(&repo, &id, &format!("Experience with ID {}", id)).await?)?;
+
+    let existing = repo.find_by_id(&id).await
+        .map_err(|e| {
+            error!("Failed to fetch experience {}: {}", id, e);
+            AppError::internal_error(format!("Failed to fetch experience: {}", e))
+        })?
+        .ok_or_else(|| AppError::not_found(format!("Experience with ID {} not found", id)))?;
+
+    let snapshot: Experience = revisions::snapshot(db.get_ref(), "experiences", &id, number).await?;
+    let restored = Experience { id: existing.id, ..snapshot };
+
+    let mut tx = revisions::begin(db.get_ref()).await?;
+    let (result, saved) = ExperienceRepository::write(&mut tx, &id, restored, Some(version)).await
+        .map_err(|e| {
+            error!("Failed to restore experience {}: {}", id, e);
+            AppError::internal_error(format!("Failed to restore experience: {}", e))
+        })?;
+    if !saved {
+        return Err(changed_since_read());
     }
+
+    revisions::record_restore(&mut tx, "experiences", &id, &result, &user.0, number).await?;
+    revisions::commit(tx).await?;
+
+    info!("Restored experience {} to revision {}", id, number);
+    Ok(versioned_json(version + 1, &result))
 }
 
 pub fn config(cfg: &mut web::ServiceConfig) {
@@ -289,5 +583,10 @@ pub fn config(cfg: &mut web::ServiceConfig) {
        .service(get_experience_by_id)
        .service(create_experience)
        .service(update_experience)
-       .service(delete_experience);
+       .service(patch_experience)
+       .service(delete_experience)
+       .service(get_experience_revisions)
+       .service(diff_experience_revisions)
+       .service(get_experience_revision)
+       .service(restore_experience_revision);
 }
\ No newline at end of file
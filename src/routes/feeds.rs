@@ -0,0 +1,276 @@
+use std::env;
+
+use actix_web::{get, web, HttpRequest, HttpResponse, Responder};
+use log::{error, info};
+
+use crate::conditional::{cache_control, cacheable};
+use crate::config::database::DbPool;
+use crate::error::{AppError, AppResult};
+use crate::models::post::{Post, PostStatus};
+use crate::models::post_repository::PostRepository;
+use crate::models::repository::Repository;
+use crate::services::feed::{Feed, FeedItem};
+use crate::services::markdown::render_cached;
+
+// Most recent posts included in a feed
+const FEED_LENGTH: usize = 20;
+
+#[derive(Debug, Clone, Copy)]
+enum FeedFormat {
+    Rss,
+    Atom,
+    Json,
+}
+
+impl FeedFormat {
+    fn content_type(&self) -> &'static str {
+        match self {
+            FeedFormat::Rss => "application/rss+xml; charset=utf-8",
+            FeedFormat::Atom => "application/atom+xml; charset=utf-8",
+            FeedFormat::Json => "application/feed+json; charset=utf-8",
+        }
+    }
+
+    fn write(&self, feed: &Feed) -> String {
+        match self {
+            FeedFormat::Rss => feed.to_rss(),
+            FeedFormat::Atom => feed.to_atom(),
+            FeedFormat::Json => feed.to_json(),
+        }
+    }
+}
+
+// Public address of the site, where post permalinks live: SITE_URL, else
+// FRONTEND_URL, else this server
+fn site_url(req: &HttpRequest) -> String {
+    env::var("SITE_URL")
+        .or_else(|_| env::var("FRONTEND_URL"))
+        .unwrap_or_else(|_| {
+            let info = req.connection_info();
+            format!("{}://{}", info.scheme(), info.host())
+        })
+        .trim_end_matches('/')
+        .to_string()
+}
+
+// When a post went out: its publication time, else its date, else its last change
+fn published_at(post: &Post, updated: i64) -> i64 {
+    post.publish_at
+        .or_else(|| {
+            chrono::NaiveDate::parse_from_str(&post.date, "%Y-%m-%d")
+                .ok()
+                .and_then(|date| date.and_hms_opt(0, 0, 0))
+                .map(|date| date.and_utc().timestamp())
+        })
+        .unwrap_or(updated)
+}
+
+// The latest published posts, optionally only those with a tag
+async fn build_feed(req: &HttpRequest, db: &DbPool, tag: Option<&str>) -> AppResult<Feed> {
+    let repo = PostRepository::new(db.clone());
+    let db_error = |e: anyhow::Error| {
+        error!("Failed to fetch posts for feed: {}", e);
+        AppError::internal_error(format!("Failed to fetch posts: {}", e))
+    };
+
+    let posts = repo.find_all().await.map_err(db_error)?;
+    let updated_at = repo.find_updated_at().await.map_err(db_error)?;
+
+    let mut posts: Vec<(Post, i64, i64)> = posts
+        .into_iter()
+        .filter(|post| post.status == PostStatus::Published)
+        .filter(|post| tag.is_none_or(|tag| post.tags.iter().any(|t| t.eq_ignore_ascii_case(tag))))
+        .map(|post| {
+            let updated = updated_at.get(&post.id).copied().unwrap_or_default();
+            let published = published_at(&post, updated);
+            (post, published, updated.max(published))
+        })
+        .collect();
+
+    if let Some(tag) = tag {
+        if posts.is_empty() {
+            return Err(AppError::not_found(format!("No published posts with tag {}", tag)));
+        }
+    }
+
+    posts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.id.cmp(&b.0.id)));
+    posts.truncate(FEED_LENGTH);
+
+    let site = site_url(req);
+    let info = req.connection_info().clone();
+    let title = env::var("FEED_TITLE").unwrap_or_else(|_| "Retro Quewui".to_string());
+    let author = env::var("FEED_AUTHOR").unwrap_or_else(|_| title.clone());
+
+    let items: Vec<FeedItem> = posts
+        .into_iter()
+        .map(|(post, published, updated)| {
+            let slug = if post.slug.is_empty() { &post.id } else { &post.slug };
+            FeedItem {
+                id: format!("{}/posts/{}", site, post.id),
+                url: format!("{}/posts/{}", site, slug),
+                content_html: render_cached(&post.content).html.clone(),
+                title: post.title,
+                summary: post.excerpt,
+                tags: post.tags,
+                published,
+                updated,
+            }
+        })
+        .collect();
+
+    Ok(Feed {
+        title: match tag {
+            Some(tag) => format!("{}: {}", title, tag),
+            None => title.clone(),
+        },
+        description: match tag {
+            Some(tag) => format!("Posts tagged {} on {}", tag, title),
+            None => format!("Posts on {}", title),
+        },
+        author,
+        home_url: site,
+        feed_url: format!("{}://{}{}", info.scheme(), info.host(), req.path()),
+        updated: items.iter().map(|item| item.updated).max().unwrap_or_default(),
+        items,
+    })
+}
+
+// The feed with an ETag and Last-Modified, or 304 Not Modified when the
+// client's copy is current
+fn feed_response(req: &HttpRequest, feed: &Feed, format: FeedFormat) -> HttpResponse {
+    info!("Serving {:?} feed with {} posts", format, feed.items.len());
+    let response = HttpResponse::Ok().content_type(format.content_type()).body(format.write(feed));
+    cacheable(req, response, Some(feed.updated), &cache_control("feeds", "public, max-age=300"))
+}
+
+/// RSS feed of posts
+///
+/// Returns the latest published posts as RSS 2.0, with their excerpt, full
+/// content rendered to HTML and tags. Supports If-None-Match and
+/// If-Modified-Since.
+#[utoipa::path(
+    get,
+    path = "/feed.xml",
+    tag = "feeds",
+    responses(
+        (status = 200, description = "RSS 2.0 feed", content_type = "application/rss+xml", body = String),
+        (status = 304, description = "Feed unchanged since the given ETag or date"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[get("/feed.xml")]
+pub async fn rss_feed(req: HttpRequest, db: web::Data<DbPool>) -> AppResult<impl Responder> {
+    let feed = build_feed(&req, db.get_ref(), None).await?;
+    Ok(feed_response(&req, &feed, FeedFormat::Rss))
+}
+
+/// Atom feed of posts
+///
+/// Returns the latest published posts as an Atom feed.
+#[utoipa::path(
+    get,
+    path = "/atom.xml",
+    tag = "feeds",
+    responses(
+        (status = 200, description = "Atom feed", content_type = "application/atom+xml", body = String),
+        (status = 304, description = "Feed unchanged since the given ETag or date"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[get("/atom.xml")]
+pub async fn atom_feed(req: HttpRequest, db: web::Data<DbPool>) -> AppResult<impl Responder> {
+    let feed = build_feed(&req, db.get_ref(), None).await?;
+    Ok(feed_response(&req, &feed, FeedFormat::Atom))
+}
+
+/// JSON Feed of posts
+///
+/// Returns the latest published posts as a JSON Feed 1.1 document.
+#[utoipa::path(
+    get,
+    path = "/feed.json",
+    tag = "feeds",
+    responses(
+        (status = 200, description = "JSON Feed 1.1", content_type = "application/feed+json", body = String),
+        (status = 304, description = "Feed unchanged since the given ETag or date"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[get("/feed.json")]
+pub async fn json_feed(req: HttpRequest, db: web::Data<DbPool>) -> AppResult<impl Responder> {
+    let feed = build_feed(&req, db.get_ref(), None).await?;
+    Ok(feed_response(&req, &feed, FeedFormat::Json))
+}
+
+/// RSS feed of posts with a tag
+///
+/// Returns the latest published posts with the tag (case-insensitive) as RSS 2.0.
+#[utoipa::path(
+    get,
+    path = "/tags/{tag}/feed.xml",
+    tag = "feeds",
+    params(("tag" = String, Path, description = "Post tag")),
+    responses(
+        (status = 200, description = "RSS 2.0 feed", content_type = "application/rss+xml", body = String),
+        (status = 304, description = "Feed unchanged since the given ETag or date"),
+        (status = 404, description = "No published post has this tag"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[get("/tags/{tag}/feed.xml")]
+pub async fn tag_rss_feed(req: HttpRequest, path: web::Path<String>, db: web::Data<DbPool>) -> AppResult<impl Responder> {
+    let feed = build_feed(&req, db.get_ref(), Some(&path.into_inner())).await?;
+    Ok(feed_response(&req, &feed, FeedFormat::Rss))
+}
+
+/// Atom feed of posts with a tag
+///
+/// Returns the latest published posts with the tag (case-insensitive) as an Atom feed.
+#[utoipa::path(
+    get,
+    path = "/tags/{tag}/atom.xml",
+    tag = "feeds",
+    params(("tag" = String, Path, description = "Post tag")),
+    responses(
+        (status = 200, description = "Atom feed", content_type = "application/atom+xml", body = String),
+        (status = 304, description = "Feed unchanged since the given ETag or date"),
+        (status = 404, description = "No published post has this tag"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[get("/tags/{tag}/atom.xml")]
+pub async fn tag_atom_feed(req: HttpRequest, path: web::Path<String>, db: web::Data<DbPool>) -> AppResult<impl Responder> {
+    let feed = build_feed(&req, db.get_ref(), Some(&path.into_inner())).await?;
+    Ok(feed_response(&req, &feed, FeedFormat::Atom))
+}
+
+/// JSON Feed of posts with a tag
+///
+/// Returns the latest published posts with the tag (case-insensitive) as a
+/// JSON Feed 1.1 document.
+#[utoipa::path(
+    get,
+    path = "/tags/{tag}/feed.json",
+    tag = "feeds",
+    params(("tag" = String, Path, description = "Post tag")),
+    responses(
+        (status = 200, description = "JSON Feed 1.1", content_type = "application/feed+json", body = String),
+        (status = 304, description = "Feed unchanged since the given ETag or date"),
+        (status = 404, description = "No published post has this tag"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[get("/tags/{tag}/feed.json")]
+pub async fn tag_json_feed(req: HttpRequest, path: web::Path<String>, db: web::Data<DbPool>) -> AppResult<impl Responder> {
+    let feed = build_feed(&req, db.get_ref(), Some(&path.into_inner())).await?;
+    Ok(feed_response(&req, &feed, FeedFormat::Json))
+}
+
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(rss_feed)
+       .service(atom_feed)
+       .service(json_feed)
+       .service(tag_rss_feed)
+       .service(tag_atom_feed)
+       .service(tag_json_feed);
+}
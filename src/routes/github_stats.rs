@@ -1,11 +1,12 @@
-use actix_web::{get, put, web, HttpResponse, Responder};
+use actix_web::{get, put, web, HttpRequest, HttpResponse, Responder};
 use log::{error, info};
 use serde::Deserialize;
 use std::sync::Mutex;
 use std::time::{Duration, Instant};
 use once_cell::sync::Lazy;
 
-use crate::auth::AuthenticatedUser;
+use crate::auth::{AuthenticatedUser, Permission};
+use crate::conditional::{cache_control, cacheable, last_modified};
 use crate::config::database::DbPool;
 use crate::error::{AppError, AppResult};
 use crate::models::github_stats::{GithubStats, TopLanguage, RecentActivity};
@@ -35,21 +36,26 @@ pub struct UpdateGithubStatsRequest {
 
 /// Get GitHub statistics
 ///
-/// Returns the GitHub statistics for the portfolio owner.
+/// Returns the GitHub statistics for the portfolio owner. Supports
+/// If-None-Match and If-Modified-Since.
 #[utoipa::path(
     get,
     path = "/github-stats",
     tag = "github-stats",
     responses(
         (status = 200, description = "GitHub statistics retrieved successfully", body = GithubStats),
+        (status = 304, description = "Statistics unchanged since the given ETag or date"),
         (status = 500, description = "Internal server error")
     )
 )]
 #[get("/github-stats")]
 pub async fn get_github_stats(
+    req: HttpRequest,
     db: web::Data<DbPool>,
 ) -> AppResult<impl Responder> {
     let repo = GithubStatsRepository::new(db.get_ref().clone());
+    let last_modified = last_modified(db.get_ref(), &["github_stats"]).await?;
+    let cache_control = cache_control("github_stats", "public, max-age=300");
     
     // Check if we have a valid cache
     {
@@ -59,7 +65,7 @@ pub async fn get_github_stats(
                 // Valid cache, use it
                 info!("Using cached GitHub stats (expires in {} seconds)", 
                       CACHE_EXPIRATION.as_secs() - timestamp.elapsed().as_secs());
-                return Ok(HttpResponse::Ok().json(cached_stats.clone()));
+                return Ok(cacheable(&req, HttpResponse::Ok().json(cached_stats), last_modified, &cache_control));
             }
             info!("Cache expired, fetching new data");
         } else {
@@ -114,7 +120,7 @@ pub async fn get_github_stats(
         info!("GitHub stats successfully updated in background");
     });
     
-    Ok(HttpResponse::Ok().json(stats))
+    Ok(cacheable(&req, HttpResponse::Ok().json(stats), last_modified, &cache_control))
 }
 
 /// Force refresh GitHub statistics
@@ -131,14 +137,17 @@ pub async fn get_github_stats(
     responses(
         (status = 200, description = "GitHub statistics refreshed successfully", body = GithubStats),
         (status = 401, description = "Unauthorized - Invalid or missing authentication token"),
+        (status = 403, description = "Forbidden - Role does not grant this permission"),
         (status = 500, description = "Internal server error")
     )
 )]
 #[get("/github-stats/refresh")]
 pub async fn refresh_github_stats(
     db: web::Data<DbPool>,
-    _user: AuthenticatedUser, // Require authentication
+    user: AuthenticatedUser,
 ) -> AppResult<impl Responder> {
+    user.require(Permission::GithubRefresh)?;
+    
     let repo = GithubStatsRepository::new(db.get_ref().clone());
     
     // Get environment variables for GitHub
@@ -241,6 +250,7 @@ async fn update_stats_from_github(
     responses(
         (status = 200, description = "GitHub statistics updated successfully", body = GithubStats),
         (status = 401, description = "Unauthorized - Invalid or missing authentication token"),
+        (status = 403, description = "Forbidden - Role does not grant this permission"),
         (status = 500, description = "Internal server error")
     )
 )]
@@ -248,8 +258,10 @@ async fn update_stats_from_github(
 pub async fn update_github_stats(
     stats_req: web::Json<UpdateGithubStatsRequest>,
     db: web::Data<DbPool>,
-    _user: AuthenticatedUser, // Require authentication
+    user: AuthenticatedUser,
 ) -> AppResult<impl Responder> {
+    user.require(Permission::GithubWrite)?;
+    
     let repo = GithubStatsRepository::new(db.get_ref().clone());
     
     // Get all stats
@@ -1,11 +1,21 @@
 pub mod admin;
+pub mod api_keys;
 pub mod auth;
+pub mod bundle;
 pub mod contact;
 pub mod experiences;
+pub mod feeds;
 pub mod github_stats;
 pub mod github_profile;
 pub mod health;
 pub mod posts;
 pub mod profile;
 pub mod projects;
+pub mod revisions;
+pub mod search;
 pub mod skills;
+pub mod tags;
+pub mod technologies;
+pub mod trash;
+pub mod totp;
+pub mod users;
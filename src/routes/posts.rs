@@ -1,71 +1,202 @@
-use actix_web::{get, post, put, delete, web, HttpResponse, Responder};
+use actix_web::{get, post, put, patch, delete, http::header, web, HttpRequest, HttpResponse, Responder};
 use log::{error, info};
 use serde::Deserialize;
+use utoipa::IntoParams;
 
-use crate::auth::AuthenticatedUser;
+use crate::auth::{AuthenticatedUser, Permission};
+use crate::conditional::{cache_control, cacheable, changed_since_read, if_match, item_version, last_modified, version_etag, PUBLIC_CACHE_CONTROL};
 use crate::config::database::DbPool;
 use crate::error::{AppError, AppResult};
-use crate::models::post::Post;
+use crate::models::post::{resolve_status, Post, PostStatus, RenderedPost};
 use crate::models::post_repository::PostRepository;
-use crate::models::repository::Repository;
+use crate::models::repository::{Repository, Versioned};
+use crate::models::slug_repository::{slug_write_error, SlugRepository};
+use crate::pagination::{paginated, ListParams};
+use crate::patch::apply_patch;
+use crate::routes::revisions::{self, RevisionDiffQuery};
+use crate::services::markdown::render_cached;
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct PostFilters {
+    /// Only posts with this tag (case-insensitive)
+    tag: Option<String>,
+    /// Only posts published in this year
+    year: Option<i32>,
+    /// Only posts in this state: draft, scheduled, published or archived. States
+    /// other than published require preview.
+    status: Option<String>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct PreviewParams {
+    /// Include posts that are not published; requires authentication with the
+    /// posts:write permission
+    preview: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct FormatParams {
+    /// "markdown" (default) for the Markdown source in `content`, or "html"
+    /// for `content_html` with a table of contents, word count and reading time
+    format: Option<String>,
+}
+
+// Whether the content is to be rendered to HTML, rejecting unknown formats
+fn html_requested(params: &FormatParams) -> AppResult<bool> {
+    match params.format.as_deref() {
+        None | Some("markdown") => Ok(false),
+        Some("html") => Ok(true),
+        Some(format) => Err(AppError::bad_request(format!("Invalid format '{}'; use markdown or html", format))),
+    }
+}
+
+fn post_response(post: Post, html: bool, version: Option<i64>) -> HttpResponse {
+    let mut response = HttpResponse::Ok();
+    if let Some(version) = version {
+        // The rendered post is another representation of the version, so it
+        // needs an ETag of its own
+        let etag = if html { format!("\"{}-html\"", version) } else { version_etag(version) };
+        response.insert_header((header::ETAG, etag));
+    }
+
+    if !html {
+        return response.json(post);
+    }
+
+    let rendered = render_cached(&post.content);
+    response.json(RenderedPost::new(post, &rendered))
+}
+
+// Whether unpublished posts may be shown: only on request, and only to users
+// allowed to write posts
+fn preview_allowed(params: &PreviewParams, user: Option<&AuthenticatedUser>) -> AppResult<bool> {
+    if params.preview != Some(true) {
+        return Ok(false);
+    }
+
+    let user = user.ok_or_else(|| AppError::unauthorized("Authentication required to preview posts"))?;
+    user.require(Permission::PostsWrite)?;
+
+    Ok(true)
+}
 
 /// Get all blog posts
 ///
-/// Returns a list of all blog posts.
+/// Returns one page of published blog posts, newest first. Sortable by `date`,
+/// `title` and `publish_at`. With `preview=true`, editors also see drafts,
+/// scheduled and archived posts. The total count is returned in the
+/// X-Total-Count header and links to the other pages in the Link header.
+/// Supports If-None-Match and If-Modified-Since; previews are only cached
+/// privately.
 #[utoipa::path(
     get,
     path = "/posts",
     tag = "posts",
+    params(ListParams, PostFilters, PreviewParams),
     responses(
-        (status = 200, description = "List of all blog posts retrieved successfully", body = Vec<Post>),
+        (status = 200, description = "Page of blog posts retrieved successfully", body = Vec<Post>),
+        (status = 304, description = "Page unchanged since the given ETag or date"),
+        (status = 400, description = "Invalid page, sort field or filter"),
+        (status = 401, description = "Preview requested without authentication"),
+        (status = 403, description = "Preview requested by a role without the posts:write permission"),
         (status = 500, description = "Internal server error")
     )
 )]
 #[get("/posts")]
-pub async fn get_all_posts(db: web::Data<DbPool>) -> AppResult<impl Responder> {
+pub async fn get_all_posts(
+    req: HttpRequest,
+    params: web::Query<ListParams>,
+    filters: web::Query<PostFilters>,
+    preview: web::Query<PreviewParams>,
+    user: Option<AuthenticatedUser>,
+    db: web::Data<DbPool>,
+) -> AppResult<impl Responder> {
+    let filters = filters.into_inner();
+    let preview = preview_allowed(&preview, user.as_ref())?;
+
+    let status = match filters.status.as_deref() {
+        Some(status) => Some(PostStatus::parse(status).ok_or_else(|| {
+            AppError::bad_request(format!("Invalid value '{}' for status", status))
+        })?),
+        None if preview => None,
+        None => Some(PostStatus::Published),
+    };
+    if !preview && status != Some(PostStatus::Published) {
+        return Err(AppError::bad_request("Filtering by a status other than published requires preview=true"));
+    }
+
+    let query = params.query(&PostRepository::LIST)?
+        .filter("tag", filters.tag)?
+        .filter("year", filters.year.map(|year| format!("{:04}", year)))?
+        .filter("status", status.map(|status| status.as_str().to_string()))?;
     let repo = PostRepository::new(db.get_ref().clone());
+    let last_modified = last_modified(db.get_ref(), &["posts"]).await?;
     
-    let posts = repo.find_all().await
+    let posts = repo.find_page(&query).await
         .map_err(|e| {
             error!("Failed to fetch posts: {}", e);
             AppError::internal_error(format!("Failed to fetch posts: {}", e))
         })?;
     
-    info!("Retrieved {} posts", posts.len());
-    Ok(HttpResponse::Ok().json(posts))
+    info!("Retrieved {} of {} posts", posts.items.len(), posts.total);
+    let cache_control = if preview {
+        "private, no-cache".to_string()
+    } else {
+        cache_control("posts", PUBLIC_CACHE_CONTROL)
+    };
+    Ok(cacheable(&req, paginated(&req, posts), last_modified, &cache_control))
 }
 
 /// Get blog post by ID
 ///
-/// Returns a single blog post with the specified ID.
+/// Returns a single published blog post with the specified ID. With
+/// `format=html`, the content is rendered to sanitised HTML and returned as a
+/// RenderedPost. With `preview=true`, editors can also read posts that are not
+/// published. The post's version is sent in the ETag header, followed by
+/// `-html` for the rendered post.
 #[utoipa::path(
     get,
     path = "/posts/{id}",
     tag = "posts",
     params(
-        ("id" = String, Path, description = "Post unique identifier")
+        ("id" = String, Path, description = "Post unique identifier"),
+        FormatParams,
+        PreviewParams
     ),
     responses(
-        (status = 200, description = "Post found", body = Post),
+        (status = 200, description = "Post found; a RenderedPost with format=html", body = Post),
+        (status = 400, description = "Invalid format"),
+        (status = 401, description = "Preview requested without authentication"),
+        (status = 403, description = "Preview requested by a role without the posts:write permission"),
         (status = 404, description = "Post not found"),
         (status = 500, description = "Internal server error")
     )
 )]
 #[get("/posts/{id}")]
-pub async fn get_post_by_id(path: web::Path<String>, db: web::Data<DbPool>) -> AppResult<impl Responder> {
+pub async fn get_post_by_id(
+    path: web::Path<String>,
+    format: web::Query<FormatParams>,
+    preview: web::Query<PreviewParams>,
+    user: Option<AuthenticatedUser>,
+    db: web::Data<DbPool>,
+) -> AppResult<impl Responder> {
     let id = path.into_inner();
+    let html = html_requested(&format)?;
+    let preview = preview_allowed(&preview, user.as_ref())?;
     let repo = PostRepository::new(db.get_ref().clone());
+    let version = item_version(&repo, &id, &format!("Post with ID {}", id)).await?;
     
     let post = repo.find_by_id(&id).await
         .map_err(|e| {
             error!("Failed to fetch post {}: {}", id, e);
             AppError::internal_error(format!("Failed to fetch post: {}", e))
-        })?;
+        })?
+        .filter(|post| preview || post.status == PostStatus::Published);
     
     match post {
         Some(post) => {
             info!("Retrieved post with ID: {}", id);
-            Ok(HttpResponse::Ok().json(post))
+            Ok(post_response(post, html, Some(version)))
         },
         None => {
             info!("Post with ID {} not found", id);
@@ -74,10 +205,96 @@ pub async fn get_post_by_id(path: web::Path<String>, db: web::Data<DbPool>) -> A
     }
 }
 
+/// Get blog post by slug
+///
+/// Returns the published blog post with the specified slug. A slug the post
+/// had before is redirected to its current permalink with 301 Moved
+/// Permanently. `format=html` and `preview=true` work as for posts by ID.
+#[utoipa::path(
+    get,
+    path = "/posts/by-slug/{slug}",
+    tag = "posts",
+    params(
+        ("slug" = String, Path, description = "Current or previous post slug"),
+        FormatParams,
+        PreviewParams
+    ),
+    responses(
+        (status = 200, description = "Post found; a RenderedPost with format=html", body = Post),
+        (status = 301, description = "Former slug; Location holds the current permalink"),
+        (status = 400, description = "Invalid format"),
+        (status = 401, description = "Preview requested without authentication"),
+        (status = 403, description = "Preview requested by a role without the posts:write permission"),
+        (status = 404, description = "Post not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[get("/posts/by-slug/{slug}")]
+pub async fn get_post_by_slug(
+    req: HttpRequest,
+    path: web::Path<String>,
+    format: web::Query<FormatParams>,
+    preview: web::Query<PreviewParams>,
+    user: Option<AuthenticatedUser>,
+    db: web::Data<DbPool>,
+) -> AppResult<impl Responder> {
+    let slug = path.into_inner();
+    let html = html_requested(&format)?;
+    let preview = preview_allowed(&preview, user.as_ref())?;
+    let repo = PostRepository::new(db.get_ref().clone());
+    
+    let post = repo.find_by_slug(&slug).await
+        .map_err(|e| {
+            error!("Failed to fetch post {}: {}", slug, e);
+            AppError::internal_error(format!("Failed to fetch post: {}", e))
+        })?
+        .filter(|post| preview || post.status == PostStatus::Published);
+    
+    if let Some(post) = post {
+        info!("Retrieved post with slug: {}", slug);
+        return Ok(post_response(post, html, None));
+    }
+    
+    let alias = SlugRepository::new(db.get_ref().clone()).find_alias("posts", &slug).await
+        .map_err(|e| {
+            error!("Failed to resolve post slug {}: {}", slug, e);
+            AppError::internal_error(format!("Failed to fetch post: {}", e))
+        })?;
+    
+    let post = match alias {
+        Some(id) => repo.find_by_id(&id).await
+            .map_err(|e| {
+                error!("Failed to fetch post {}: {}", id, e);
+                AppError::internal_error(format!("Failed to fetch post: {}", e))
+            })?
+            .filter(|post| preview || post.status == PostStatus::Published),
+        None => None,
+    };
+    
+    match post {
+        Some(post) => {
+            info!("Redirecting post slug {} to {}", slug, post.slug);
+            let mut location = format!("/posts/by-slug/{}", post.slug);
+            if !req.query_string().is_empty() {
+                location = format!("{}?{}", location, req.query_string());
+            }
+            Ok(HttpResponse::MovedPermanently()
+                .insert_header((header::LOCATION, location))
+                .finish())
+        },
+        None => {
+            info!("Post with slug {} not found", slug);
+            Err(AppError::not_found(format!("Post with slug {} not found", slug)))
+        }
+    }
+}
+
 #[derive(Debug, Deserialize, utoipa::ToSchema)]
 pub struct CreatePostRequest {
     /// Post title
     pub title: String,
+    /// Permalink; generated from the title if not given
+    pub slug: Option<String>,
     /// Publication date (format: YYYY-MM-DD)
     pub date: String,
     /// List of tags for the post
@@ -86,12 +303,20 @@ pub struct CreatePostRequest {
     pub excerpt: String,
     /// Full content of the post (Markdown format)
     pub content: String,
+    /// Publication state (default draft)
+    pub status: Option<PostStatus>,
+    /// Publication time (as UTC timestamp); required for scheduled posts,
+    /// defaults to now for published ones
+    pub publish_at: Option<i64>,
 }
 
 #[derive(Debug, Deserialize, utoipa::ToSchema)]
 pub struct UpdatePostRequest {
     /// Post title
     pub title: Option<String>,
+    /// New permalink; the previous one keeps redirecting to the post. The
+    /// slug does not follow title changes.
+    pub slug: Option<String>,
     /// Publication date (format: YYYY-MM-DD)
     pub date: Option<String>,
     /// List of tags for the post
@@ -100,6 +325,10 @@ pub struct UpdatePostRequest {
     pub excerpt: Option<String>,
     /// Full content of the post (Markdown format)
     pub content: Option<String>,
+    /// Publication state
+    pub status: Option<PostStatus>,
+    /// Publication time (as UTC timestamp)
+    pub publish_at: Option<i64>,
 }
 
 /// Create a new blog post
@@ -118,31 +347,45 @@ pub struct UpdatePostRequest {
         (status = 201, description = "Blog post created successfully", body = Post),
         (status = 400, description = "Invalid post data"),
         (status = 401, description = "Unauthorized - Invalid or missing authentication token"),
+        (status = 403, description = "Forbidden - Role does not grant this permission"),
+        (status = 409, description = "Slug already in use"),
         (status = 500, description = "Internal server error")
     )
 )]
 #[post("/posts")]
 pub async fn create_post(
     post_req: web::Json<CreatePostRequest>,
-    _user: AuthenticatedUser, // Require authentication
+    user: AuthenticatedUser,
     db: web::Data<DbPool>,
 ) -> AppResult<impl Responder> {
-    let repo = PostRepository::new(db.get_ref().clone());
+    user.require(Permission::PostsWrite)?;
     
-    let post = Post::new(
+    let mut post = Post::new(
         post_req.title.clone(),
         post_req.date.clone(),
         post_req.tags.clone(),
         post_req.excerpt.clone(),
         post_req.content.clone(),
     );
+    (post.status, post.publish_at) = resolve_status(
+        post_req.status.unwrap_or(PostStatus::Draft),
+        post_req.publish_at,
+        chrono::Utc::now().timestamp(),
+    )?;
+    post.slug = SlugRepository::new(db.get_ref().clone())
+        .choose("posts", post_req.slug.as_deref(), "", &post.title, &post.id)
+        .await?;
     
-    let created_post = repo.create(post).await
+    let mut tx = revisions::begin(db.get_ref()).await?;
+    let created_post = PostRepository::insert(&mut tx, post).await
         .map_err(|e| {
             error!("Failed to create post: {}", e);
-            AppError::internal_error(format!("Failed to create post: {}", e))
+            slug_write_error(e, "Failed to create post")
         })?;
     
+    revisions::record(&mut tx, "posts", &created_post.id, None, &created_post, &user.0).await?;
+    revisions::commit(tx).await?;
+    
     info!("Created new post: {}", created_post.title);
     
     Ok(HttpResponse::Created().json(created_post))
@@ -160,26 +403,35 @@ pub async fn create_post(
         ("jwt_auth" = [])
     ),
     params(
-        ("id" = String, Path, description = "Post unique identifier")
+        ("id" = String, Path, description = "Post unique identifier"),
+        ("If-Match" = String, Header, description = "ETag of the post as last read, or *")
     ),
     request_body = UpdatePostRequest,
     responses(
         (status = 200, description = "Blog post updated successfully", body = Post),
         (status = 400, description = "Invalid post data"),
         (status = 401, description = "Unauthorized - Invalid or missing authentication token"),
+        (status = 403, description = "Forbidden - Role does not grant this permission"),
         (status = 404, description = "Post not found"),
+        (status = 409, description = "Slug already in use"),
+        (status = 412, description = "The post changed since it was read"),
+        (status = 428, description = "If-Match header missing"),
         (status = 500, description = "Internal server error")
     )
 )]
 #[put("/posts/{id}")]
 pub async fn update_post(
+    req: HttpRequest,
     path: web::Path<String>,
     post_req: web::Json<UpdatePostRequest>,
-    _user: AuthenticatedUser, // Require authentication
+    user: AuthenticatedUser,
     db: web::Data<DbPool>,
 ) -> AppResult<impl Responder> {
+    user.require(Permission::PostsWrite)?;
+    
     let id = path.into_inner();
     let repo = PostRepository::new(db.get_ref().clone());
+    let version = if_match(&req, item_version(&repo, &id, &format!("Post with ID {}", id)).await?)?;
     
     // Find the post to update
     let existing_post = repo.find_by_id(&id).await
@@ -195,27 +447,127 @@ pub async fn update_post(
     // Create updated post
     let updated_post = Post {
         id: existing_post.id.clone(),
+        slug: existing_post.slug.clone(),
         title: post_req.title.clone().unwrap_or_else(|| existing_post.title.clone()),
         date: post_req.date.clone().unwrap_or_else(|| existing_post.date.clone()),
         tags: post_req.tags.clone().unwrap_or_else(|| existing_post.tags.clone()),
         excerpt: post_req.excerpt.clone().unwrap_or_else(|| existing_post.excerpt.clone()),
         content: post_req.content.clone().unwrap_or_else(|| existing_post.content.clone()),
+        status: post_req.status.unwrap_or(existing_post.status),
+        publish_at: post_req.publish_at.or(existing_post.publish_at),
     };
     
-    let result = repo.update(&id, updated_post.clone()).await
+    save_post(&db, &user, existing_post, post_req.slug.as_deref(), updated_post, version).await
+}
+
+/// Patch a blog post
+///
+/// Changes part of the blog post with the specified ID, with a JSON Merge
+/// Patch (`application/merge-patch+json`, where null clears an optional field)
+/// or a JSON Patch (`application/json-patch+json`) of the post as returned by
+/// GET. The patched post is validated as a whole before it is saved, and its
+/// status and publish_at as on update.
+/// Requires authentication.
+#[utoipa::path(
+    patch,
+    path = "/posts/{id}",
+    tag = "posts",
+    security(
+        ("jwt_auth" = [])
+    ),
+    params(
+        ("id" = String, Path, description = "Post unique identifier"),
+        ("If-Match" = String, Header, description = "ETag of the post as last read, or *")
+    ),
+    request_body(content = Object, content_type = "application/merge-patch+json", description = "JSON Merge Patch, or a JSON Patch sent as application/json-patch+json"),
+    responses(
+        (status = 200, description = "Blog post patched successfully", body = Post),
+        (status = 400, description = "Invalid patch, or invalid post once patched"),
+        (status = 401, description = "Unauthorized - Invalid or missing authentication token"),
+        (status = 403, description = "Forbidden - Role does not grant this permission"),
+        (status = 404, description = "Post not found"),
+        (status = 409, description = "Slug already in use, or the patch does not apply to the post"),
+        (status = 412, description = "The post changed since it was read"),
+        (status = 415, description = "Patch format not supported"),
+        (status = 428, description = "If-Match header missing"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[patch("/posts/{id}")]
+pub async fn patch_post(
+    req: HttpRequest,
+    path: web::Path<String>,
+    body: web::Bytes,
+    user: AuthenticatedUser,
+    db: web::Data<DbPool>,
+) -> AppResult<impl Responder> {
+    user.require(Permission::PostsWrite)?;
+    
+    let id = path.into_inner();
+    let repo = PostRepository::new(db.get_ref().clone());
+    let version = if_match(&req, item_version(&repo, &id, &format!("Post with ID {}", id)).await?)?;
+    
+    let existing_post = repo.find_by_id(&id).await
+        .map_err(|e| {
+            error!("Failed to fetch post for patch: {}", e);
+            AppError::internal_error(format!("Failed to fetch post: {}", e))
+        })?
+        .ok_or_else(|| {
+            info!("Post with ID {} not found for patch", id);
+            AppError::not_found(format!("Post with ID {} not found", id))
+        })?;
+    
+    let patched_post: Post = apply_patch(&req, &body, &existing_post)?;
+    let slug = patched_post.slug.clone();
+    
+    save_post(&db, &user, existing_post, Some(&slug), patched_post, version).await
+}
+
+// Save a new state of a post read at `version`, with the requested slug if
+// any: the status and publication time are checked, the slug too, the
+// previous slug is kept as an alias and the change recorded as a revision
+async fn save_post(
+    db: &DbPool,
+    user: &AuthenticatedUser,
+    before: Post,
+    slug: Option<&str>,
+    mut post: Post,
+    version: i64,
+) -> AppResult<HttpResponse> {
+    let id = before.id.clone();
+    (post.status, post.publish_at) = resolve_status(post.status, post.publish_at, chrono::Utc::now().timestamp())?;
+    
+    let slugs = SlugRepository::new(db.clone());
+    post.slug = slugs.choose("posts", slug, &before.slug, &post.title, &id).await?;
+    
+    // Save the updated post, unless it changed since the client read it
+    let mut tx = revisions::begin(db).await?;
+    let (result, saved) = PostRepository::write(&mut tx, &id, post, Some(version)).await
         .map_err(|e| {
             error!("Failed to update post: {}", e);
+            slug_write_error(e, "Failed to update post")
+        })?;
+    if !saved {
+        return Err(changed_since_read());
+    }
+    
+    SlugRepository::write_change(&mut tx, "posts", &id, &before.slug, &result.slug).await
+        .map_err(|e| {
+            error!("Failed to keep the previous slug of post {}: {}", id, e);
             AppError::internal_error(format!("Failed to update post: {}", e))
         })?;
     
+    revisions::record(&mut tx, "posts", &id, Some(&before), &result, &user.0).await?;
+    revisions::commit(tx).await?;
+    
     info!("Updated post with ID: {}", id);
     
-    Ok(HttpResponse::Ok().json(result))
+    Ok(post_response(result, false, Some(version + 1)))
 }
 
 /// Delete a blog post
 ///
-/// Deletes the blog post with the specified ID.
+/// Moves the blog post with the specified ID to the trash.
 /// Requires authentication.
 #[utoipa::path(
     delete,
@@ -225,52 +577,233 @@ pub async fn update_post(
         ("jwt_auth" = [])
     ),
     params(
-        ("id" = String, Path, description = "Post unique identifier")
+        ("id" = String, Path, description = "Post unique identifier"),
+        ("If-Match" = String, Header, description = "ETag of the post as last read, or *")
     ),
     responses(
         (status = 204, description = "Blog post deleted successfully"),
         (status = 401, description = "Unauthorized - Invalid or missing authentication token"),
+        (status = 403, description = "Forbidden - Role does not grant this permission"),
         (status = 404, description = "Post not found"),
+        (status = 412, description = "The post changed since it was read"),
+        (status = 428, description = "If-Match header missing"),
         (status = 500, description = "Internal server error")
     )
 )]
 #[delete("/posts/{id}")]
 pub async fn delete_post(
+    req: HttpRequest,
     path: web::Path<String>,
-    _user: AuthenticatedUser, // Require authentication
+    user: AuthenticatedUser,
     db: web::Data<DbPool>,
 ) -> AppResult<impl Responder> {
+    user.require(Permission::PostsWrite)?;
+    
     let id = path.into_inner();
     let repo = PostRepository::new(db.get_ref().clone());
     
-    // Check if the post exists
-    let post_exists = repo.find_by_id(&id).await
-        .map_err(|e| {
-            error!("Failed to check if post exists: {}", e);
-            AppError::internal_error(format!("Failed to check if post exists: {}", e))
-        })?
-        .is_some();
+    let version = if_match(&req, item_version(&repo, &id, &format!("Post with ID {}", id)).await?)?;
     
-    if !post_exists {
-        info!("Post with ID {} not found for deletion", id);
-        return Err(AppError::not_found(format!("Post with ID {} not found", id)));
-    }
-    
-    repo.delete(&id).await
+    // Delete the post, unless it changed since the client read it
+    let deleted = repo.delete_at(&id, version).await
         .map_err(|e| {
             error!("Failed to delete post: {}", e);
             AppError::internal_error(format!("Failed to delete post: {}", e))
         })?;
     
+    if !deleted {
+        return Err(changed_since_read());
+    }
+    
     info!("Deleted post with ID: {}", id);
     
     Ok(HttpResponse::NoContent().finish())
 }
 
+/// List the revisions of a post
+///
+/// Returns one page of the saved versions of a post, newest first, with
+/// who saved each and when. Sortable by `number` and `created_at`.
+#[utoipa::path(
+    get,
+    path = "/posts/{id}/revisions",
+    tag = "posts",
+    security(
+        ("jwt_auth" = [])
+    ),
+    params(
+        ("id" = String, Path, description = "Post unique identifier"),
+        ListParams
+    ),
+    responses(
+        (status = 200, description = "Page of revisions", body = Vec<RevisionSummary>),
+        (status = 400, description = "Invalid page or sort field"),
+        (status = 401, description = "Unauthorized - Invalid or missing authentication token"),
+        (status = 403, description = "Forbidden - Role does not grant this permission"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[get("/posts/{id}/revisions")]
+pub async fn get_post_revisions(
+    req: HttpRequest,
+    path: web::Path<String>,
+    params: web::Query<ListParams>,
+    db: web::Data<DbPool>,
+    user: AuthenticatedUser,
+) -> AppResult<impl Responder> {
+    user.require(Permission::PostsWrite)?;
+    revisions::list(&req, db.get_ref(), "posts", &path.into_inner(), &params).await
+}
+
+/// Get a revision of a post
+///
+/// Returns a saved version of a post with its full snapshot.
+#[utoipa::path(
+    get,
+    path = "/posts/{id}/revisions/{number}",
+    tag = "posts",
+    security(
+        ("jwt_auth" = [])
+    ),
+    params(
+        ("id" = String, Path, description = "Post unique identifier"),
+        ("number" = i64, Path, description = "Revision number")
+    ),
+    responses(
+        (status = 200, description = "Revision found", body = Revision),
+        (status = 401, description = "Unauthorized - Invalid or missing authentication token"),
+        (status = 403, description = "Forbidden - Role does not grant this permission"),
+        (status = 404, description = "Revision not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[get("/posts/{id}/revisions/{number:\\d+}")]
+pub async fn get_post_revision(
+    path: web::Path<(String, i64)>,
+    db: web::Data<DbPool>,
+    user: AuthenticatedUser,
+) -> AppResult<impl Responder> {
+    user.require(Permission::PostsWrite)?;
+    let (id, number) = path.into_inner();
+
+    let revision = revisions::find(db.get_ref(), "posts", &id, number).await?;
+    Ok(HttpResponse::Ok().json(revision))
+}
+
+/// Compare two revisions of a post
+///
+/// Returns the values that differ between two saved versions, each with its
+/// JSON Pointer path in the snapshot.
+#[utoipa::path(
+    get,
+    path = "/posts/{id}/revisions/diff",
+    tag = "posts",
+    security(
+        ("jwt_auth" = [])
+    ),
+    params(
+        ("id" = String, Path, description = "Post unique identifier"),
+        RevisionDiffQuery
+    ),
+    responses(
+        (status = 200, description = "Changes between the revisions", body = RevisionDiff),
+        (status = 400, description = "Missing or invalid revision numbers"),
+        (status = 401, description = "Unauthorized - Invalid or missing authentication token"),
+        (status = 403, description = "Forbidden - Role does not grant this permission"),
+        (status = 404, description = "Revision not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[get("/posts/{id}/revisions/diff")]
+pub async fn diff_post_revisions(
+    path: web::Path<String>,
+    query: web::Query<RevisionDiffQuery>,
+    db: web::Data<DbPool>,
+    user: AuthenticatedUser,
+) -> AppResult<impl Responder> {
+    user.require(Permission::PostsWrite)?;
+
+    let diff = revisions::diff(db.get_ref(), "posts", &path.into_inner(), &query).await?;
+    Ok(HttpResponse::Ok().json(diff))
+}
+
+/// Restore a revision of a post
+///
+/// Writes a saved version back, keeping the post's current slug, status and publication time. The restore is saved as a new
+/// revision, so it can be undone in turn.
+#[utoipa::path(
+    post,
+    path = "/posts/{id}/revisions/{number}/restore",
+    tag = "posts",
+    security(
+        ("jwt_auth" = [])
+    ),
+    params(
+        ("id" = String, Path, description = "Post unique identifier"),
+        ("number" = i64, Path, description = "Revision number"),
+        ("If-Match" = String, Header, description = "ETag of the post as last read, or *")
+    ),
+    responses(
+        (status = 200, description = "Post restored", body = Post),
+        (status = 401, description = "Unauthorized - Invalid or missing authentication token"),
+        (status = 403, description = "Forbidden - Role does not grant this permission"),
+        (status = 404, description = "Post or revision not found"),
+        (status = 409, description = "The revision can no longer be restored"),
+        (status = 412, description = "The post changed since it was read"),
+        (status = 428, description = "If-Match header missing"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[post("/posts/{id}/revisions/{number}/restore")]
+pub async fn restore_post_revision(
+    req: HttpRequest,
+    path: web::Path<(String, i64)>,
+    db: web::Data<DbPool>,
+    user: AuthenticatedUser,
+) -> AppResult<impl Responder> {
+    user.require(Permission::PostsWrite)?;
+
+    let (id, number) = path.into_inner();
+    let repo = PostRepository::new(db.get_ref().clone());
+    let version = if_match(&req, item_version(&repo, &id, &format!("Post with ID {}", id)).await?)?;
+
+    let existing = repo.find_by_id(&id).await
+        .map_err(|e| {
+            error!("Failed to fetch post {}: {}", id, e);
+            AppError::internal_error(format!("Failed to fetch post: {}", e))
+        })?
+        .ok_or_else(|| AppError::not_found(format!("Post with ID {} not found", id)))?;
+
+    let snapshot: Post = revisions::snapshot(db.get_ref(), "posts", &id, number).await?;
+    let restored = Post { id: existing.id, slug: existing.slug, status: existing.status, publish_at: existing.publish_at, ..snapshot };
+
+    let mut tx = revisions::begin(db.get_ref()).await?;
+    let (result, saved) = PostRepository::write(&mut tx, &id, restored, Some(version)).await
+        .map_err(|e| {
+            error!("Failed to restore post {}: {}", id, e);
+            AppError::internal_error(format!("Failed to restore post: {}", e))
+        })?;
+    if !saved {
+        return Err(changed_since_read());
+    }
+
+    revisions::record_restore(&mut tx, "posts", &id, &result, &user.0, number).await?;
+    revisions::commit(tx).await?;
+
+    info!("Restored post {} to revision {}", id, number);
+    Ok(post_response(result, false, Some(version + 1)))
+}
+
 pub fn config(cfg: &mut web::ServiceConfig) {
     cfg.service(get_all_posts)
        .service(get_post_by_id)
+       .service(get_post_by_slug)
        .service(create_post)
        .service(update_post)
-       .service(delete_post);
+       .service(patch_post)
+       .service(delete_post)
+       .service(get_post_revisions)
+       .service(diff_post_revisions)
+       .service(get_post_revision)
+       .service(restore_post_revision);
 }
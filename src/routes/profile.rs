@@ -1,46 +1,49 @@
-use actix_web::{get, put, web, HttpResponse, Responder};
+use actix_web::{get, post, put, patch, web, HttpRequest, HttpResponse, Responder};
 use log::{error, info};
 use serde::Deserialize;
 
-use crate::auth::AuthenticatedUser;
+use crate::auth::{AuthenticatedUser, Permission};
+use crate::conditional::{cache_control, cacheable, changed_since_read, if_match, item_version, last_modified, versioned_json, PUBLIC_CACHE_CONTROL};
 use crate::config::database::DbPool;
 use crate::error::{AppError, AppResult};
 use crate::models::profile::{Profile, SocialLink, Education, Language};
 use crate::models::profile_repository::ProfileRepository;
 use crate::models::repository::Repository;
+use crate::pagination::ListParams;
+use crate::patch::apply_patch;
+use crate::routes::revisions::{self, RevisionDiffQuery};
 
 /// Get user profile
 ///
-/// Returns the user profile information including bio, social links, education, and languages.
+/// Returns the user profile information including bio, social links, education, and languages,
+/// with its version in the ETag header. Supports If-None-Match and If-Modified-Since.
 #[utoipa::path(
     get,
     path = "/profile",
     tag = "profile",
     responses(
         (status = 200, description = "Profile retrieved successfully", body = Profile),
+        (status = 304, description = "Profile unchanged since the given ETag or date"),
         (status = 500, description = "Internal server error")
     )
 )]
 #[get("/profile")]
-pub async fn get_profile(db: web::Data<DbPool>) -> AppResult<impl Responder> {
+pub async fn get_profile(req: HttpRequest, db: web::Data<DbPool>) -> AppResult<impl Responder> {
     info!("Profile data requested");
     
     let repo = ProfileRepository::new(db.get_ref().clone());
+    let id = profile_id(db.get_ref()).await?;
+    let last_modified = last_modified(db.get_ref(), &["profiles"]).await?;
+    let version = item_version(&repo, &id, "Profile").await?;
     
-    let profiles = repo.find_all().await
+    let profile = repo.find_by_id(&id).await
         .map_err(|e| {
             error!("Failed to fetch profile: {}", e);
             AppError::internal_error(format!("Failed to fetch profile: {}", e))
-        })?;
-    
-    if profiles.is_empty() {
-        return Err(AppError::not_found("Profile not found"));
-    }
+        })?
+        .ok_or_else(|| AppError::not_found("Profile not found"))?;
     
-    // Return the first profile (there should only be one)
-    let profile = &profiles[0];
-    
-    Ok(HttpResponse::Ok().json(profile))
+    Ok(cacheable(&req, versioned_json(version, &profile), last_modified, &cache_control("profile", PUBLIC_CACHE_CONTROL)))
 }
 
 #[derive(Debug, Deserialize, utoipa::ToSchema)]
@@ -66,20 +69,29 @@ pub struct UpdateProfileRequest {
     security(
         ("jwt_auth" = [])
     ),
+    params(
+        ("If-Match" = String, Header, description = "ETag of the profile as last read, or *; not needed before the profile is first saved")
+    ),
     request_body = UpdateProfileRequest,
     responses(
         (status = 200, description = "Profile updated successfully", body = Profile),
         (status = 400, description = "Invalid profile data"),
         (status = 401, description = "Unauthorized - Invalid or missing authentication token"),
+        (status = 403, description = "Forbidden - Role does not grant this permission"),
+        (status = 412, description = "The profile changed since it was read"),
+        (status = 428, description = "If-Match header missing"),
         (status = 500, description = "Internal server error")
     )
 )]
 #[put("/profile")]
 pub async fn update_profile(
+    req: HttpRequest,
     profile_req: web::Json<UpdateProfileRequest>,
     db: web::Data<DbPool>,
-    _user: AuthenticatedUser, // Require authentication
+    user: AuthenticatedUser,
 ) -> AppResult<impl Responder> {
+    user.require(Permission::ProfileWrite)?;
+    
     let repo = ProfileRepository::new(db.get_ref().clone());
     
     // Get all profiles
@@ -89,7 +101,7 @@ pub async fn update_profile(
             AppError::internal_error(format!("Failed to fetch profile: {}", e))
         })?;
     
-    let (profile_id, existing_profile) = if profiles.is_empty() {
+    let (profile_id, existing_profile, version) = if profiles.is_empty() {
         // Create a new profile if none exists
         let new_profile = Profile {
             bio: Vec::new(),
@@ -104,11 +116,10 @@ pub async fn update_profile(
                 AppError::internal_error(format!("Failed to create profile: {}", e))
             })?;
         
-        ("profile-1".to_string(), profile)
+        let profile_id = profile_id(db.get_ref()).await?;
+        let version = item_version(&repo, &profile_id, "Profile").await?;
+        (profile_id, profile, version)
     } else {
-        // Use the first profile from the actual database
-        let profile = &profiles[0];
-        
         // Query to get the ID of the first profile
         let query = "SELECT id FROM profiles LIMIT 1";
         let row = sqlx::query_as::<_, (String,)>(query)
@@ -130,7 +141,17 @@ pub async fn update_profile(
             }
         };
         
-        (profile_id, profile.clone())
+        // Read after the version, so that the changes are applied to the copy
+        // the ETag names
+        let version = if_match(&req, item_version(&repo, &profile_id, "Profile").await?)?;
+        let profile = repo.find_by_id(&profile_id).await
+            .map_err(|e| {
+                error!("Failed to fetch profile: {}", e);
+                AppError::internal_error(format!("Failed to fetch profile: {}", e))
+            })?
+            .ok_or_else(|| AppError::not_found("Profile not found"))?;
+        
+        (profile_id, profile, version)
     };
     
     // Create updated profile
@@ -141,18 +162,94 @@ pub async fn update_profile(
         languages: profile_req.languages.clone().unwrap_or_else(|| existing_profile.languages.clone()),
     };
     
-    // Save updated profile
+    save_profile(&db, &repo, &user, &profile_id, existing_profile, updated_profile, version).await
+}
+
+/// Patch user profile
+///
+/// Changes part of the user profile with a JSON Merge Patch
+/// (`application/merge-patch+json`) or a JSON Patch
+/// (`application/json-patch+json`) of the profile as returned by GET. The
+/// patched profile is validated as a whole before it is saved.
+/// Requires authentication.
+#[utoipa::path(
+    patch,
+    path = "/profile",
+    tag = "profile",
+    security(
+        ("jwt_auth" = [])
+    ),
+    params(
+        ("If-Match" = String, Header, description = "ETag of the profile as last read, or *")
+    ),
+    request_body(content = Object, content_type = "application/merge-patch+json", description = "JSON Merge Patch, or a JSON Patch sent as application/json-patch+json"),
+    responses(
+        (status = 200, description = "Profile patched successfully", body = Profile),
+        (status = 400, description = "Invalid patch, or invalid profile once patched"),
+        (status = 401, description = "Unauthorized - Invalid or missing authentication token"),
+        (status = 403, description = "Forbidden - Role does not grant this permission"),
+        (status = 404, description = "Profile not saved yet"),
+        (status = 409, description = "The patch does not apply to the profile"),
+        (status = 412, description = "The profile changed since it was read"),
+        (status = 415, description = "Patch format not supported"),
+        (status = 428, description = "If-Match header missing"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[patch("/profile")]
+pub async fn patch_profile(
+    req: HttpRequest,
+    body: web::Bytes,
+    db: web::Data<DbPool>,
+    user: AuthenticatedUser,
+) -> AppResult<impl Responder> {
+    user.require(Permission::ProfileWrite)?;
+    
+    let repo = ProfileRepository::new(db.get_ref().clone());
+    let profile_id = profile_id(db.get_ref()).await?;
+    let version = if_match(&req, item_version(&repo, &profile_id, "Profile").await?)?;
+    
+    let existing_profile = repo.find_by_id(&profile_id).await
+        .map_err(|e| {
+            error!("Failed to fetch profile: {}", e);
+            AppError::internal_error(format!("Failed to fetch profile: {}", e))
+        })?
+        .ok_or_else(|| AppError::not_found("Profile not found"))?;
+    
+    let patched_profile: Profile = apply_patch(&req, &body, &existing_profile)?;
+    
+    save_profile(&db, &repo, &user, &profile_id, existing_profile, patched_profile, version).await
+}
+
+// Save a new state of the profile read at `version`, recording the change as
+// a revision
+async fn save_profile(
+    db: &DbPool,
+    repo: &ProfileRepository,
+    user: &AuthenticatedUser,
+    profile_id: &str,
+    before: Profile,
+    profile: Profile,
+    version: i64,
+) -> AppResult<HttpResponse> {
     info!("Updating profile with ID: {}", profile_id);
-    info!("Updated profile data: {:?}", updated_profile);
+    info!("Updated profile data: {:?}", profile);
     
-    let result = repo.update(&profile_id, updated_profile.clone()).await
+    let mut tx = revisions::begin(db).await?;
+    let (result, saved) = ProfileRepository::write(&mut tx, profile_id, profile, Some(version)).await
         .map_err(|e| {
             error!("Failed to update profile: {}", e);
             AppError::internal_error(format!("Failed to update profile: {}", e))
         })?;
+    if !saved {
+        return Err(changed_since_read());
+    }
+    
+    revisions::record(&mut tx, "profiles", profile_id, Some(&before), &result, &user.0).await?;
+    revisions::commit(tx).await?;
     
     // Verify the update by fetching the profile again
-    let updated = repo.find_by_id(&profile_id).await
+    let updated = repo.find_by_id(profile_id).await
         .map_err(|e| {
             error!("Failed to fetch updated profile: {}", e);
             AppError::internal_error(format!("Failed to fetch updated profile: {}", e))
@@ -162,10 +259,185 @@ pub async fn update_profile(
     
     info!("Updated user profile");
     
-    Ok(HttpResponse::Ok().json(result))
+    Ok(versioned_json(version + 1, &result))
+}
+
+// Id of the profile row; 404 Not Found before the profile is first saved
+async fn profile_id(db: &DbPool) -> AppResult<String> {
+    sqlx::query_scalar::<_, String>("SELECT id FROM profiles LIMIT 1")
+        .fetch_optional(db)
+        .await
+        .map_err(|e| {
+            error!("Failed to fetch profile ID: {}", e);
+            AppError::internal_error(format!("Failed to fetch profile ID: {}", e))
+        })?
+        .ok_or_else(|| AppError::not_found("Profile not found"))
+}
+
+/// List the revisions of the profile
+///
+/// Returns one page of the saved versions of the profile, newest first, with
+/// who saved each and when. Sortable by `number` and `created_at`.
+#[utoipa::path(
+    get,
+    path = "/profile/revisions",
+    tag = "profile",
+    security(
+        ("jwt_auth" = [])
+    ),
+    params(ListParams),
+    responses(
+        (status = 200, description = "Page of revisions", body = Vec<RevisionSummary>),
+        (status = 400, description = "Invalid page or sort field"),
+        (status = 401, description = "Unauthorized - Invalid or missing authentication token"),
+        (status = 403, description = "Forbidden - Role does not grant this permission"),
+        (status = 404, description = "Profile not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[get("/profile/revisions")]
+pub async fn get_profile_revisions(
+    req: HttpRequest,
+    params: web::Query<ListParams>,
+    db: web::Data<DbPool>,
+    user: AuthenticatedUser,
+) -> AppResult<impl Responder> {
+    user.require(Permission::ProfileWrite)?;
+    let id = profile_id(db.get_ref()).await?;
+    revisions::list(&req, db.get_ref(), "profiles", &id, &params).await
+}
+
+/// Get a revision of the profile
+///
+/// Returns a saved version of the profile with its full snapshot.
+#[utoipa::path(
+    get,
+    path = "/profile/revisions/{number}",
+    tag = "profile",
+    security(
+        ("jwt_auth" = [])
+    ),
+    params(
+        ("number" = i64, Path, description = "Revision number")
+    ),
+    responses(
+        (status = 200, description = "Revision found", body = Revision),
+        (status = 401, description = "Unauthorized - Invalid or missing authentication token"),
+        (status = 403, description = "Forbidden - Role does not grant this permission"),
+        (status = 404, description = "Profile or revision not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[get("/profile/revisions/{number:\\d+}")]
+pub async fn get_profile_revision(
+    path: web::Path<i64>,
+    db: web::Data<DbPool>,
+    user: AuthenticatedUser,
+) -> AppResult<impl Responder> {
+    user.require(Permission::ProfileWrite)?;
+    let id = profile_id(db.get_ref()).await?;
+
+    let revision = revisions::find(db.get_ref(), "profiles", &id, path.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(revision))
+}
+
+/// Compare two revisions of the profile
+///
+/// Returns the values that differ between two saved versions, each with its
+/// JSON Pointer path in the snapshot.
+#[utoipa::path(
+    get,
+    path = "/profile/revisions/diff",
+    tag = "profile",
+    security(
+        ("jwt_auth" = [])
+    ),
+    params(RevisionDiffQuery),
+    responses(
+        (status = 200, description = "Changes between the revisions", body = RevisionDiff),
+        (status = 400, description = "Missing or invalid revision numbers"),
+        (status = 401, description = "Unauthorized - Invalid or missing authentication token"),
+        (status = 403, description = "Forbidden - Role does not grant this permission"),
+        (status = 404, description = "Profile or revision not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[get("/profile/revisions/diff")]
+pub async fn diff_profile_revisions(
+    query: web::Query<RevisionDiffQuery>,
+    db: web::Data<DbPool>,
+    user: AuthenticatedUser,
+) -> AppResult<impl Responder> {
+    user.require(Permission::ProfileWrite)?;
+    let id = profile_id(db.get_ref()).await?;
+
+    let diff = revisions::diff(db.get_ref(), "profiles", &id, &query).await?;
+    Ok(HttpResponse::Ok().json(diff))
+}
+
+/// Restore a revision of the profile
+///
+/// Writes a saved version of the profile back. The restore is saved as a new
+/// revision, so it can be undone in turn.
+#[utoipa::path(
+    post,
+    path = "/profile/revisions/{number}/restore",
+    tag = "profile",
+    security(
+        ("jwt_auth" = [])
+    ),
+    params(
+        ("number" = i64, Path, description = "Revision number"),
+        ("If-Match" = String, Header, description = "ETag of the profile as last read, or *")
+    ),
+    responses(
+        (status = 200, description = "Profile restored", body = Profile),
+        (status = 401, description = "Unauthorized - Invalid or missing authentication token"),
+        (status = 403, description = "Forbidden - Role does not grant this permission"),
+        (status = 404, description = "Profile or revision not found"),
+        (status = 409, description = "The revision can no longer be restored"),
+        (status = 412, description = "The profile changed since it was read"),
+        (status = 428, description = "If-Match header missing"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[post("/profile/revisions/{number}/restore")]
+pub async fn restore_profile_revision(
+    req: HttpRequest,
+    path: web::Path<i64>,
+    db: web::Data<DbPool>,
+    user: AuthenticatedUser,
+) -> AppResult<impl Responder> {
+    user.require(Permission::ProfileWrite)?;
+    let number = path.into_inner();
+    let id = profile_id(db.get_ref()).await?;
+    let repo = ProfileRepository::new(db.get_ref().clone());
+    let version = if_match(&req, item_version(&repo, &id, "Profile").await?)?;
+
+    let restored: Profile = revisions::snapshot(db.get_ref(), "profiles", &id, number).await?;
+    let mut tx = revisions::begin(db.get_ref()).await?;
+    let (result, saved) = ProfileRepository::write(&mut tx, &id, restored, Some(version)).await
+        .map_err(|e| {
+            error!("Failed to restore profile: {}", e);
+            AppError::internal_error(format!("Failed to restore profile: {}", e))
+        })?;
+    if !saved {
+        return Err(changed_since_read());
+    }
+
+    revisions::record_restore(&mut tx, "profiles", &id, &result, &user.0, number).await?;
+    revisions::commit(tx).await?;
+
+    info!("Restored profile to revision {}", number);
+    Ok(versioned_json(version + 1, &result))
 }
 
 pub fn config(cfg: &mut web::ServiceConfig) {
     cfg.service(get_profile)
-       .service(update_profile);
+       .service(update_profile)
+       .service(patch_profile)
+       .service(get_profile_revisions)
+       .service(diff_profile_revisions)
+       .service(get_profile_revision)
+       .service(restore_profile_revision);
 }
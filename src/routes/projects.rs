@@ -1,44 +1,73 @@
-use actix_web::{get, post, put, delete, web, HttpResponse, Responder};
+use actix_web::{get, post, put, patch, delete, http::header, web, HttpRequest, HttpResponse, Responder};
 use log::{error, info};
 use serde::Deserialize;
-use utoipa::ToSchema;
+use utoipa::{IntoParams, ToSchema};
 
-use crate::auth::AuthenticatedUser;
+use crate::auth::{AuthenticatedUser, Permission};
+use crate::conditional::{cache_control, cacheable, changed_since_read, if_match, item_version, last_modified, versioned_json, PUBLIC_CACHE_CONTROL};
 use crate::config::database::DbPool;
 use crate::error::{AppError, AppResult};
 use crate::models::project::Project;
 use crate::models::project_repository::ProjectRepository;
-use crate::models::repository::Repository;
+use crate::models::repository::{Repository, Versioned};
+use crate::models::slug_repository::{slug_write_error, SlugRepository};
+use crate::pagination::{paginated, ListParams};
+use crate::patch::apply_patch;
+use crate::routes::revisions::{self, RevisionDiffQuery};
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct ProjectFilters {
+    /// Only projects using this technology (case-insensitive)
+    technology: Option<String>,
+    /// Only projects from this year
+    year: Option<i32>,
+}
 
 /// Get all projects
 ///
-/// Returns a list of all projects in the system.
+/// Returns one page of projects, most recent first. Sortable by `year` and `title`.
+/// The total count is returned in the X-Total-Count header and links to the
+/// other pages in the Link header. Supports If-None-Match and If-Modified-Since.
 #[utoipa::path(
     get,
     path = "/projects",
     tag = "projects",
+    params(ListParams, ProjectFilters),
     responses(
-        (status = 200, description = "List of all projects retrieved successfully", body = Vec<Project>),
+        (status = 200, description = "Page of projects retrieved successfully", body = Vec<Project>),
+        (status = 304, description = "Page unchanged since the given ETag or date"),
+        (status = 400, description = "Invalid page, sort field or filter"),
         (status = 500, description = "Internal server error")
     )
 )]
 #[get("/projects")]
-pub async fn get_all_projects(db: web::Data<DbPool>) -> AppResult<impl Responder> {
+pub async fn get_all_projects(
+    req: HttpRequest,
+    params: web::Query<ListParams>,
+    filters: web::Query<ProjectFilters>,
+    db: web::Data<DbPool>,
+) -> AppResult<impl Responder> {
+    let filters = filters.into_inner();
+    let query = params.query(&ProjectRepository::LIST)?
+        .filter("technology", filters.technology)?
+        .filter("year", filters.year.map(|year| year.to_string()))?;
     let repo = ProjectRepository::new(db.get_ref().clone());
+    let last_modified = last_modified(db.get_ref(), &["projects"]).await?;
     
-    let projects = repo.find_all().await
+    let projects = repo.find_page(&query).await
         .map_err(|e| {
             error!("Failed to fetch projects: {}", e);
             AppError::internal_error(format!("Failed to fetch projects: {}", e))
         })?;
     
-    info!("Retrieved {} projects", projects.len());
-    Ok(HttpResponse::Ok().json(projects))
+    info!("Retrieved {} of {} projects", projects.items.len(), projects.total);
+    Ok(cacheable(&req, paginated(&req, projects), last_modified, &cache_control("projects", PUBLIC_CACHE_CONTROL)))
 }
 
 /// Get project by ID
 ///
-/// Returns a single project with the specified ID.
+/// Returns a single project with the specified ID, with its version in the
+/// ETag header.
 #[utoipa::path(
     get,
     path = "/projects/{id}",
@@ -56,6 +85,7 @@ pub async fn get_all_projects(db: web::Data<DbPool>) -> AppResult<impl Responder
 pub async fn get_project_by_id(path: web::Path<String>, db: web::Data<DbPool>) -> AppResult<impl Responder> {
     let id = path.into_inner();
     let repo = ProjectRepository::new(db.get_ref().clone());
+    let version = item_version(&repo, &id, &format!("Project with ID {}", id)).await?;
     
     let project = repo.find_by_id(&id).await
         .map_err(|e| {
@@ -66,7 +96,7 @@ pub async fn get_project_by_id(path: web::Path<String>, db: web::Data<DbPool>) -
     match project {
         Some(project) => {
             info!("Retrieved project with ID: {}", id);
-            Ok(HttpResponse::Ok().json(project))
+            Ok(versioned_json(version, &project))
         },
         None => {
             info!("Project with ID {} not found", id);
@@ -75,10 +105,75 @@ pub async fn get_project_by_id(path: web::Path<String>, db: web::Data<DbPool>) -
     }
 }
 
+/// Get project by slug
+///
+/// Returns the project with the specified slug. A slug the project had before
+/// is redirected to its current permalink with 301 Moved Permanently.
+#[utoipa::path(
+    get,
+    path = "/projects/by-slug/{slug}",
+    tag = "projects",
+    params(
+        ("slug" = String, Path, description = "Current or previous project slug")
+    ),
+    responses(
+        (status = 200, description = "Project found", body = Project),
+        (status = 301, description = "Former slug; Location holds the current permalink"),
+        (status = 404, description = "Project not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[get("/projects/by-slug/{slug}")]
+pub async fn get_project_by_slug(path: web::Path<String>, db: web::Data<DbPool>) -> AppResult<impl Responder> {
+    let slug = path.into_inner();
+    let repo = ProjectRepository::new(db.get_ref().clone());
+    
+    let project = repo.find_by_slug(&slug).await
+        .map_err(|e| {
+            error!("Failed to fetch project {}: {}", slug, e);
+            AppError::internal_error(format!("Failed to fetch project: {}", e))
+        })?;
+    
+    if let Some(project) = project {
+        info!("Retrieved project with slug: {}", slug);
+        return Ok(HttpResponse::Ok().json(project));
+    }
+    
+    let alias = SlugRepository::new(db.get_ref().clone()).find_alias("projects", &slug).await
+        .map_err(|e| {
+            error!("Failed to resolve project slug {}: {}", slug, e);
+            AppError::internal_error(format!("Failed to fetch project: {}", e))
+        })?;
+    
+    let project = match alias {
+        Some(id) => repo.find_by_id(&id).await
+            .map_err(|e| {
+                error!("Failed to fetch project {}: {}", id, e);
+                AppError::internal_error(format!("Failed to fetch project: {}", e))
+            })?,
+        None => None,
+    };
+    
+    match project {
+        Some(project) => {
+            info!("Redirecting project slug {} to {}", slug, project.slug);
+            Ok(HttpResponse::MovedPermanently()
+                .insert_header((header::LOCATION, format!("/projects/by-slug/{}", project.slug)))
+                .finish())
+        },
+        None => {
+            info!("Project with slug {} not found", slug);
+            Err(AppError::not_found(format!("Project with slug {} not found", slug)))
+        }
+    }
+}
+
 #[derive(Debug, Deserialize, ToSchema)]
 pub struct CreateProjectRequest {
     /// Project title
     pub title: String,
+    /// Permalink; generated from the title if not given
+    pub slug: Option<String>,
     /// Detailed description of the project
     pub description: String,
     /// List of technologies used in the project
@@ -101,6 +196,9 @@ pub struct CreateProjectRequest {
 pub struct UpdateProjectRequest {
     /// Project title
     pub title: Option<String>,
+    /// New permalink; the previous one keeps redirecting to the project. The
+    /// slug does not follow title changes.
+    pub slug: Option<String>,
     /// Detailed description of the project
     pub description: Option<String>,
     /// List of technologies used in the project
@@ -135,6 +233,8 @@ pub struct UpdateProjectRequest {
         (status = 201, description = "Project created successfully", body = Project),
         (status = 400, description = "Invalid project data"),
         (status = 401, description = "Unauthorized - Invalid or missing authentication token"),
+        (status = 403, description = "Forbidden - Role does not grant this permission"),
+        (status = 409, description = "Slug already in use"),
         (status = 500, description = "Internal server error")
     )
 )]
@@ -142,11 +242,11 @@ pub struct UpdateProjectRequest {
 pub async fn create_project(
     project_req: web::Json<CreateProjectRequest>,
     db: web::Data<DbPool>,
-    _user: AuthenticatedUser, // Require authentication
+    user: AuthenticatedUser,
 ) -> AppResult<impl Responder> {
-    let repo = ProjectRepository::new(db.get_ref().clone());
+    user.require(Permission::ProjectsWrite)?;
     
-    let project = Project::new(
+    let mut project = Project::new(
         project_req.title.clone(),
         project_req.description.clone(),
         project_req.technologies.clone(),
@@ -157,13 +257,20 @@ pub async fn create_project(
         project_req.year,
         project_req.highlights.clone(),
     );
+    project.slug = SlugRepository::new(db.get_ref().clone())
+        .choose("projects", project_req.slug.as_deref(), "", &project.title, &project.id)
+        .await?;
     
-    let created_project = repo.create(project).await
+    let mut tx = revisions::begin(db.get_ref()).await?;
+    let created_project = ProjectRepository::insert(&mut tx, project).await
         .map_err(|e| {
             error!("Failed to create project: {}", e);
-            AppError::internal_error(format!("Failed to create project: {}", e))
+            slug_write_error(e, "Failed to create project")
         })?;
     
+    revisions::record(&mut tx, "projects", &created_project.id, None, &created_project, &user.0).await?;
+    revisions::commit(tx).await?;
+    
     info!("Created new project with ID: {}", created_project.id);
     Ok(HttpResponse::Created().json(created_project))
 }
@@ -180,26 +287,35 @@ pub async fn create_project(
         ("jwt_auth" = [])
     ),
     params(
-        ("id" = String, Path, description = "Project unique identifier")
+        ("id" = String, Path, description = "Project unique identifier"),
+        ("If-Match" = String, Header, description = "ETag of the project as last read, or *")
     ),
     request_body = UpdateProjectRequest,
     responses(
         (status = 200, description = "Project updated successfully", body = Project),
         (status = 400, description = "Invalid project data"),
         (status = 401, description = "Unauthorized - Invalid or missing authentication token"),
+        (status = 403, description = "Forbidden - Role does not grant this permission"),
         (status = 404, description = "Project not found"),
+        (status = 409, description = "Slug already in use"),
+        (status = 412, description = "The project changed since it was read"),
+        (status = 428, description = "If-Match header missing"),
         (status = 500, description = "Internal server error")
     )
 )]
 #[put("/projects/{id}")]
 pub async fn update_project(
+    req: HttpRequest,
     path: web::Path<String>,
     project_req: web::Json<UpdateProjectRequest>,
     db: web::Data<DbPool>,
-    _user: AuthenticatedUser, // Require authentication
+    user: AuthenticatedUser,
 ) -> AppResult<impl Responder> {
+    user.require(Permission::ProjectsWrite)?;
+    
     let id = path.into_inner();
     let repo = ProjectRepository::new(db.get_ref().clone());
+    let version = if_match(&req, item_version(&repo, &id, &format!("Project with ID {}", id)).await?)?;
     
     // First, get the existing project
     let existing_project = repo.find_by_id(&id).await
@@ -212,9 +328,12 @@ pub async fn update_project(
             AppError::not_found(format!("Project with ID {} not found", id))
         })?;
     
+    let before = existing_project.clone();
+    
     // Update the project with new values, keeping existing values if not provided
     let updated_project = Project {
         id: existing_project.id,
+        slug: existing_project.slug,
         title: project_req.title.clone().unwrap_or(existing_project.title),
         description: project_req.description.clone().unwrap_or(existing_project.description),
         technologies: project_req.technologies.clone().unwrap_or(existing_project.technologies),
@@ -226,20 +345,113 @@ pub async fn update_project(
         highlights: project_req.highlights.clone().unwrap_or(existing_project.highlights),
     };
     
-    // Save the updated project
-    let result = repo.update(&id, updated_project.clone()).await
+    save_project(&db, &user, before, project_req.slug.as_deref(), updated_project, version).await
+}
+
+/// Patch a project
+///
+/// Changes part of the project with the specified ID, with a JSON Merge Patch
+/// (`application/merge-patch+json`, where null clears an optional field) or a
+/// JSON Patch (`application/json-patch+json`) of the project as returned by
+/// GET. The patched project is validated as a whole before it is saved.
+/// Requires authentication.
+#[utoipa::path(
+    patch,
+    path = "/projects/{id}",
+    tag = "projects",
+    security(
+        ("jwt_auth" = [])
+    ),
+    params(
+        ("id" = String, Path, description = "Project unique identifier"),
+        ("If-Match" = String, Header, description = "ETag of the project as last read, or *")
+    ),
+    request_body(content = Object, content_type = "application/merge-patch+json", description = "JSON Merge Patch, or a JSON Patch sent as application/json-patch+json"),
+    responses(
+        (status = 200, description = "Project patched successfully", body = Project),
+        (status = 400, description = "Invalid patch, or invalid project once patched"),
+        (status = 401, description = "Unauthorized - Invalid or missing authentication token"),
+        (status = 403, description = "Forbidden - Role does not grant this permission"),
+        (status = 404, description = "Project not found"),
+        (status = 409, description = "Slug already in use, or the patch does not apply to the project"),
+        (status = 412, description = "The project changed since it was read"),
+        (status = 415, description = "Patch format not supported"),
+        (status = 428, description = "If-Match header missing"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[patch("/projects/{id}")]
+pub async fn patch_project(
+    req: HttpRequest,
+    path: web::Path<String>,
+    body: web::Bytes,
+    db: web::Data<DbPool>,
+    user: AuthenticatedUser,
+) -> AppResult<impl Responder> {
+    user.require(Permission::ProjectsWrite)?;
+    
+    let id = path.into_inner();
+    let repo = ProjectRepository::new(db.get_ref().clone());
+    let version = if_match(&req, item_version(&repo, &id, &format!("Project with ID {}", id)).await?)?;
+    
+    let existing_project = repo.find_by_id(&id).await
+        .map_err(|e| {
+            error!("Failed to fetch project {}: {}", id, e);
+            AppError::internal_error(format!("Failed to fetch project: {}", e))
+        })?
+        .ok_or_else(|| {
+            info!("Project with ID {} not found for patch", id);
+            AppError::not_found(format!("Project with ID {} not found", id))
+        })?;
+    
+    let patched_project: Project = apply_patch(&req, &body, &existing_project)?;
+    let slug = patched_project.slug.clone();
+    
+    save_project(&db, &user, existing_project, Some(&slug), patched_project, version).await
+}
+
+// Save a new state of a project read at `version`, with the requested slug if
+// any: the slug is checked, the previous one kept as an alias and the change
+// recorded as a revision
+async fn save_project(
+    db: &DbPool,
+    user: &AuthenticatedUser,
+    before: Project,
+    slug: Option<&str>,
+    mut project: Project,
+    version: i64,
+) -> AppResult<HttpResponse> {
+    let id = before.id.clone();
+    let slugs = SlugRepository::new(db.clone());
+    project.slug = slugs.choose("projects", slug, &before.slug, &project.title, &id).await?;
+    
+    // Save the updated project, unless it changed since the client read it
+    let mut tx = revisions::begin(db).await?;
+    let (result, saved) = ProjectRepository::write(&mut tx, &id, project, Some(version)).await
         .map_err(|e| {
             error!("Failed to update project {}: {}", id, e);
+            slug_write_error(e, "Failed to update project")
+        })?;
+    if !saved {
+        return Err(changed_since_read());
+    }
+    
+    SlugRepository::write_change(&mut tx, "projects", &id, &before.slug, &result.slug).await
+        .map_err(|e| {
+            error!("Failed to keep the previous slug of project {}: {}", id, e);
             AppError::internal_error(format!("Failed to update project: {}", e))
         })?;
     
+    revisions::record(&mut tx, "projects", &id, Some(&before), &result, &user.0).await?;
+    revisions::commit(tx).await?;
+    
     info!("Updated project with ID: {}", id);
-    Ok(HttpResponse::Ok().json(result))
+    Ok(versioned_json(version + 1, &result))
 }
 
 /// Delete a project
 ///
-/// Deletes the project with the specified ID.
+/// Moves the project with the specified ID to the trash.
 /// Requires authentication.
 #[utoipa::path(
     delete,
@@ -249,57 +461,232 @@ pub async fn update_project(
         ("jwt_auth" = [])
     ),
     params(
-        ("id" = String, Path, description = "Project unique identifier")
+        ("id" = String, Path, description = "Project unique identifier"),
+        ("If-Match" = String, Header, description = "ETag of the project as last read, or *")
     ),
     responses(
         (status = 204, description = "Project deleted successfully"),
         (status = 401, description = "Unauthorized - Invalid or missing authentication token"),
+        (status = 403, description = "Forbidden - Role does not grant this permission"),
         (status = 404, description = "Project not found"),
+        (status = 412, description = "The project changed since it was read"),
+        (status = 428, description = "If-Match header missing"),
         (status = 500, description = "Internal server error")
     )
 )]
 #[delete("/projects/{id}")]
 pub async fn delete_project(
+    req: HttpRequest,
     path: web::Path<String>,
     db: web::Data<DbPool>,
-    _user: AuthenticatedUser, // Require authentication
+    user: AuthenticatedUser,
 ) -> AppResult<impl Responder> {
+    user.require(Permission::ProjectsWrite)?;
+    
     let id = path.into_inner();
     let repo = ProjectRepository::new(db.get_ref().clone());
     
-    // Check if the project exists
-    let project_exists = repo.find_by_id(&id).await
-        .map_err(|e| {
-            error!("Failed to fetch project {}: {}", id, e);
-            AppError::internal_error(format!("Failed to fetch project: {}", e))
-        })?
-        .is_some();
+    let version = if_match(&req, item_version(&repo, &id, &format!("Project with ID {}", id)).await?)?;
     
-    if !project_exists {
-        info!("Project with ID {} not found for deletion", id);
-        return Err(AppError::not_found(format!("Project with ID {} not found", id)));
-    }
-    
-    // Delete the project
-    let deleted = repo.delete(&id).await
+    // Delete the project, unless it changed since the client read it
+    let deleted = repo.delete_at(&id, version).await
         .map_err(|e| {
             error!("Failed to delete project {}: {}", id, e);
             AppError::internal_error(format!("Failed to delete project: {}", e))
         })?;
     
-    if deleted {
-        info!("Deleted project with ID: {}", id);
-        Ok(HttpResponse::NoContent().finish())
-    } else {
-        error!("Failed to delete project with ID: {}", id);
-        Err(AppError::internal_error("Failed to delete project"))
+    if !deleted {
+        return Err(changed_since_read());
+    }
+    
+    info!("Deleted project with ID: {}", id);
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// List the revisions of a project
+///
+/// Returns one page of the saved versions of a project, newest first, with
+/// who saved each and when. Sortable by `number` and `created_at`.
+#[utoipa::path(
+    get,
+    path = "/projects/{id}/revisions",
+    tag = "projects",
+    security(
+        ("jwt_auth" = [])
+    ),
+    params(
+        ("id" = String, Path, description = "Project unique identifier"),
+        ListParams
+    ),
+    responses(
+        (status = 200, description = "Page of revisions", body = Vec<RevisionSummary>),
+        (status = 400, description = "Invalid page or sort field"),
+        (status = 401, description = "Unauthorized - Invalid or missing authentication token"),
+        (status = 403, description = "Forbidden - Role does not grant this permission"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[get("/projects/{id}/revisions")]
+pub async fn get_project_revisions(
+    req: HttpRequest,
+    path: web::Path<String>,
+    params: web::Query<ListParams>,
+    db: web::Data<DbPool>,
+    user: AuthenticatedUser,
+) -> AppResult<impl Responder> {
+    user.require(Permission::ProjectsWrite)?;
+    revisions::list(&req, db.get_ref(), "projects", &path.into_inner(), &params).await
+}
+
+/// Get a revision of a project
+///
+/// Returns a saved version of a project with its full snapshot.
+#[utoipa::path(
+    get,
+    path = "/projects/{id}/revisions/{number}",
+    tag = "projects",
+    security(
+        ("jwt_auth" = [])
+    ),
+    params(
+        ("id" = String, Path, description = "Project unique identifier"),
+        ("number" = i64, Path, description = "Revision number")
+    ),
+    responses(
+        (status = 200, description = "Revision found", body = Revision),
+        (status = 401, description = "Unauthorized - Invalid or missing authentication token"),
+        (status = 403, description = "Forbidden - Role does not grant this permission"),
+        (status = 404, description = "Revision not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[get("/projects/{id}/revisions/{number:\\d+}")]
+pub async fn get_project_revision(
+    path: web::Path<(String, i64)>,
+    db: web::Data<DbPool>,
+    user: AuthenticatedUser,
+) -> AppResult<impl Responder> {
+    user.require(Permission::ProjectsWrite)?;
+    let (id, number) = path.into_inner();
+
+    let revision = revisions::find(db.get_ref(), "projects", &id, number).await?;
+    Ok(HttpResponse::Ok().json(revision))
+}
+
+/// Compare two revisions of a project
+///
+/// Returns the values that differ between two saved versions, each with its
+/// JSON Pointer path in the snapshot.
+#[utoipa::path(
+    get,
+    path = "/projects/{id}/revisions/diff",
+    tag = "projects",
+    security(
+        ("jwt_auth" = [])
+    ),
+    params(
+        ("id" = String, Path, description = "Project unique identifier"),
+        RevisionDiffQuery
+    ),
+    responses(
+        (status = 200, description = "Changes between the revisions", body = RevisionDiff),
+        (status = 400, description = "Missing or invalid revision numbers"),
+        (status = 401, description = "Unauthorized - Invalid or missing authentication token"),
+        (status = 403, description = "Forbidden - Role does not grant this permission"),
+        (status = 404, description = "Revision not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[get("/projects/{id}/revisions/diff")]
+pub async fn diff_project_revisions(
+    path: web::Path<String>,
+    query: web::Query<RevisionDiffQuery>,
+    db: web::Data<DbPool>,
+    user: AuthenticatedUser,
+) -> AppResult<impl Responder> {
+    user.require(Permission::ProjectsWrite)?;
+
+    let diff = revisions::diff(db.get_ref(), "projects", &path.into_inner(), &query).await?;
+    Ok(HttpResponse::Ok().json(diff))
+}
+
+/// Restore a revision of a project
+///
+/// Writes a saved version back, keeping the project's current slug. The restore is saved as a new
+/// revision, so it can be undone in turn.
+#[utoipa::path(
+    post,
+    path = "/projects/{id}/revisions/{number}/restore",
+    tag = "projects",
+    security(
+        ("jwt_auth" = [])
+    ),
+    params(
+        ("id" = String, Path, description = "Project unique identifier"),
+        ("number" = i64, Path, description = "Revision number"),
+        ("If-Match" = String, Header, description = "ETag of the project as last read, or *")
+    ),
+    responses(
+        (status = 200, description = "Project restored", body = Project),
+        (status = 401, description = "Unauthorized - Invalid or missing authentication token"),
+        (status = 403, description = "Forbidden - Role does not grant this permission"),
+        (status = 404, description = "Project or revision not found"),
+        (status = 409, description = "The revision can no longer be restored"),
+        (status = 412, description = "The project changed since it was read"),
+        (status = 428, description = "If-Match header missing"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[post("/projects/{id}/revisions/{number}/restore")]
+pub async fn restore_project_revision(
+    req: HttpRequest,
+    path: web::Path<(String, i64)>,
+    db: web::Data<DbPool>,
+    user: AuthenticatedUser,
+) -> AppResult<impl Responder> {
+    user.require(Permission::ProjectsWrite)?;
+
+    let (id, number) = path.into_inner();
+    let repo = ProjectRepository::new(db.get_ref().clone());
+    let version = if_match(&req, item_version(&repo, &id, &format!("Project with ID {}", id)).await?)?;
+
+    let existing = repo.find_by_id(&id).await
+        .map_err(|e| {
+            error!("Failed to fetch project {}: {}", id, e);
+            AppError::internal_error(format!("Failed to fetch project: {}", e))
+        })?
+        .ok_or_else(|| AppError::not_found(format!("Project with ID {} not found", id)))?;
+
+    let snapshot: Project = revisions::snapshot(db.get_ref(), "projects", &id, number).await?;
+    let restored = Project { id: existing.id, slug: existing.slug, ..snapshot };
+
+    let mut tx = revisions::begin(db.get_ref()).await?;
+    let (result, saved) = ProjectRepository::write(&mut tx, &id, restored, Some(version)).await
+        .map_err(|e| {
+            error!("Failed to restore project {}: {}", id, e);
+            AppError::internal_error(format!("Failed to restore project: {}", e))
+        })?;
+    if !saved {
+        return Err(changed_since_read());
     }
+
+    revisions::record_restore(&mut tx, "projects", &id, &result, &user.0, number).await?;
+    revisions::commit(tx).await?;
+
+    info!("Restored project {} to revision {}", id, number);
+    Ok(versioned_json(version + 1, &result))
 }
 
 pub fn config(cfg: &mut web::ServiceConfig) {
     cfg.service(get_all_projects)
        .service(get_project_by_id)
+       .service(get_project_by_slug)
        .service(create_project)
        .service(update_project)
-       .service(delete_project);
+       .service(patch_project)
+       .service(delete_project)
+       .service(get_project_revisions)
+       .service(diff_project_revisions)
+       .service(get_project_revision)
+       .service(restore_project_revision);
 }
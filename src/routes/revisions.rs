@@ -0,0 +1,131 @@
+use actix_web::{HttpRequest, HttpResponse};
+use log::error;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use sqlx::{Any, AnyConnection, Transaction};
+use utoipa::IntoParams;
+
+use crate::auth::User;
+use crate::config::database::DbPool;
+use crate::error::{AppError, AppResult};
+use crate::models::revision::{diff_snapshots, Revision, RevisionDiff};
+use crate::models::revision_repository::RevisionRepository;
+use crate::pagination::{paginated, ListParams};
+
+// Revision history handlers shared by posts, projects, experiences and the
+// profile. Each resource declares its own routes and checks its own write
+// permission before calling these. Items are written in a transaction from
+// `begin`, and their revision recorded on it before the `commit`, so that a
+// write is never saved without its revision.
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct RevisionDiffQuery {
+    /// Number of the older revision
+    from: i64,
+    /// Number of the newer revision
+    to: i64,
+}
+
+fn db_error(e: anyhow::Error) -> AppError {
+    error!("Failed to fetch revisions: {}", e);
+    AppError::internal_error(format!("Failed to fetch revisions: {}", e))
+}
+
+/// Start the transaction an item and its revision are written in
+pub async fn begin(db: &DbPool) -> AppResult<Transaction<'static, Any>> {
+    db.begin().await.map_err(|e| {
+        error!("Failed to start a transaction: {}", e);
+        AppError::internal_error(format!("Failed to start a transaction: {}", e))
+    })
+}
+
+/// Save the item written and its revision
+pub async fn commit(tx: Transaction<'static, Any>) -> AppResult<()> {
+    tx.commit().await.map_err(|e| {
+        error!("Failed to commit a write: {}", e);
+        AppError::internal_error(format!("Failed to commit a write: {}", e))
+    })
+}
+
+/// Save an item as written by a user; `before` is the item as it was, saved
+/// first when the item has no history yet
+pub async fn record<T: serde::Serialize>(
+    conn: &mut AnyConnection,
+    target: &'static str,
+    ref_id: &str,
+    before: Option<&T>,
+    after: &T,
+    author: &User,
+) -> AppResult<Option<i64>> {
+    RevisionRepository::append(conn, target, ref_id, before, after, author, None)
+        .await
+        .map_err(|e| {
+            error!("Failed to save revision of {} {}: {}", target, ref_id, e);
+            AppError::internal_error(format!("Failed to save revision: {}", e))
+        })
+}
+
+/// One page of an item's revisions, newest first
+pub async fn list(
+    req: &HttpRequest,
+    db: &DbPool,
+    target: &'static str,
+    ref_id: &str,
+    params: &ListParams,
+) -> AppResult<HttpResponse> {
+    let query = params.query(&RevisionRepository::LIST)?;
+
+    let revisions = RevisionRepository::new(db.clone())
+        .find_page(target, ref_id, query)
+        .await
+        .map_err(db_error)?;
+
+    Ok(paginated(req, revisions))
+}
+
+/// A revision of an item, or 404 Not Found
+pub async fn find(db: &DbPool, target: &'static str, ref_id: &str, number: i64) -> AppResult<Revision> {
+    RevisionRepository::new(db.clone())
+        .find(target, ref_id, number)
+        .await
+        .map_err(db_error)?
+        .ok_or_else(|| AppError::not_found(format!("Revision {} not found", number)))
+}
+
+/// The changes between two revisions of an item
+pub async fn diff(db: &DbPool, target: &'static str, ref_id: &str, query: &RevisionDiffQuery) -> AppResult<RevisionDiff> {
+    let from = find(db, target, ref_id, query.from).await?;
+    let to = find(db, target, ref_id, query.to).await?;
+
+    Ok(RevisionDiff {
+        from: from.number,
+        to: to.number,
+        changes: diff_snapshots(&from.snapshot, &to.snapshot),
+    })
+}
+
+/// The item saved in a revision, to write back
+pub async fn snapshot<T: DeserializeOwned>(db: &DbPool, target: &'static str, ref_id: &str, number: i64) -> AppResult<T> {
+    let revision = find(db, target, ref_id, number).await?;
+
+    serde_json::from_value(revision.snapshot).map_err(|e| {
+        AppError::conflict(format!("Revision {} no longer matches the current format: {}", number, e))
+    })
+}
+
+/// Save an item written back from an older revision
+pub async fn record_restore<T: serde::Serialize>(
+    conn: &mut AnyConnection,
+    target: &'static str,
+    ref_id: &str,
+    restored: &T,
+    author: &User,
+    number: i64,
+) -> AppResult<Option<i64>> {
+    RevisionRepository::append(conn, target, ref_id, None, restored, author, Some(number))
+        .await
+        .map_err(|e| {
+            error!("Failed to save revision of {} {}: {}", target, ref_id, e);
+            AppError::internal_error(format!("Failed to save revision: {}", e))
+        })
+}
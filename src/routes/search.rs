@@ -0,0 +1,92 @@
+use actix_web::{get, web, HttpRequest, Responder};
+use log::{error, info};
+use serde::Deserialize;
+use utoipa::IntoParams;
+
+use crate::config::database::DbPool;
+use crate::error::{AppError, AppResult};
+use crate::models::repository::page_bounds;
+use crate::models::search::{parse_search_terms, SEARCH_KINDS};
+use crate::models::search_repository::SearchRepository;
+use crate::pagination::paginated;
+
+// Longest accepted query, in characters
+const MAX_QUERY_LENGTH: usize = 200;
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct SearchParams {
+    /// Words to search for, all of which must match. Words are stemmed, so
+    /// "deploying" also finds "deployed"; a trailing `*` matches any word
+    /// starting with the given letters, e.g. "kube*".
+    q: String,
+    /// Only hits of this type: "post", "project" or "experience"
+    #[serde(rename = "type")]
+    #[param(rename = "type")]
+    kind: Option<String>,
+    /// Page number, starting at 1 (default 1)
+    page: Option<i64>,
+    /// Hits per page (default 20, at most 100)
+    per_page: Option<i64>,
+}
+
+/// Search posts, projects and experiences
+///
+/// Returns one page of hits across all content types, most relevant first,
+/// with the matched words of the title and snippet wrapped in `<mark>` tags.
+/// The total count is returned in the X-Total-Count header and links to the
+/// other pages in the Link header.
+#[utoipa::path(
+    get,
+    path = "/search",
+    tag = "search",
+    params(SearchParams),
+    responses(
+        (status = 200, description = "Page of search hits", body = Vec<SearchHit>),
+        (status = 400, description = "Empty or invalid query, type or page"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[get("/search")]
+pub async fn search(
+    req: HttpRequest,
+    params: web::Query<SearchParams>,
+    db: web::Data<DbPool>,
+) -> AppResult<impl Responder> {
+    let params = params.into_inner();
+    let (page, per_page) = page_bounds(params.page, params.per_page)?;
+
+    if params.q.chars().count() > MAX_QUERY_LENGTH {
+        return Err(AppError::bad_request(format!("q must be at most {} characters", MAX_QUERY_LENGTH)));
+    }
+
+    let terms = parse_search_terms(&params.q);
+    if terms.is_empty() {
+        return Err(AppError::bad_request("q must contain at least one word"));
+    }
+
+    let kind = params.kind.filter(|kind| !kind.is_empty());
+    if let Some(kind) = &kind {
+        if !SEARCH_KINDS.contains(&kind.as_str()) {
+            return Err(AppError::bad_request(format!(
+                "Invalid type '{}'; use one of: {}",
+                kind,
+                SEARCH_KINDS.join(", ")
+            )));
+        }
+    }
+
+    let repo = SearchRepository::new(db.get_ref().clone());
+
+    let hits = repo.search(&terms, kind.as_deref(), page, per_page).await
+        .map_err(|e| {
+            error!("Failed to search: {}", e);
+            AppError::internal_error(format!("Failed to search: {}", e))
+        })?;
+
+    info!("Search for {:?} returned {} of {} hits", params.q, hits.items.len(), hits.total);
+    Ok(paginated(&req, hits))
+}
+
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(search);
+}
@@ -1,54 +1,114 @@
-use actix_web::{get, post, put, delete, HttpResponse, Responder, web};
+use actix_web::{get, post, put, patch, delete, HttpRequest, HttpResponse, Responder, web};
 use log::{error, info};
 use serde::Deserialize;
+use utoipa::IntoParams;
 
-use crate::auth::AuthenticatedUser;
+use crate::auth::{AuthenticatedUser, Permission};
+use crate::conditional::{cache_control, cacheable, changed_since_read, if_match, item_version, last_modified, versioned_json, PUBLIC_CACHE_CONTROL};
 use crate::config::database::DbPool;
 use crate::error::{AppError, AppResult};
-use crate::models::skill::Skill;
+use crate::models::skill::{EnrichedSkill, Skill, SkillItem};
 use crate::models::skill_repository::SkillRepository;
-use crate::models::repository::Repository;
+use crate::models::repository::{Repository, Versioned};
+use crate::pagination::{paginated, ListParams};
+use crate::patch::apply_patch;
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct SkillQuery {
+    /// List with each skill the projects and experiences whose technologies
+    /// mention it
+    evidence: Option<bool>,
+}
 
 /// Get all skills
 ///
-/// Returns a list of all skills grouped by category.
+/// Returns one page of skills grouped by category, each category's skills in
+/// display order. Sortable by `category`. The total count is returned in the
+/// X-Total-Count header and links to the other pages in the Link header.
+/// Supports If-None-Match and If-Modified-Since.
+///
+/// With `evidence=true`, every skill also lists the projects (newest first)
+/// and experiences (most recent first) using it, matched through the
+/// technology registry so that aliases count.
 #[utoipa::path(
     get,
     path = "/skills",
     tag = "skills",
+    params(ListParams, SkillQuery),
     responses(
-        (status = 200, description = "List of all skills retrieved successfully", body = Vec<Skill>),
+        (status = 200, description = "Page of skills retrieved successfully; of EnrichedSkill with evidence=true", body = Vec<Skill>),
+        (status = 304, description = "Page unchanged since the given ETag or date"),
+        (status = 400, description = "Invalid page or sort field"),
         (status = 500, description = "Internal server error")
     )
 )]
 #[get("/skills")]
-pub async fn get_all_skills(db: web::Data<DbPool>) -> AppResult<impl Responder> {
+pub async fn get_all_skills(
+    req: HttpRequest,
+    params: web::Query<ListParams>,
+    skill_query: web::Query<SkillQuery>,
+    db: web::Data<DbPool>,
+) -> AppResult<impl Responder> {
+    let query = params.query(&SkillRepository::LIST)?;
     let repo = SkillRepository::new(db.get_ref().clone());
-    
-    let skills = repo.find_all().await
-        .map_err(|e| {
-            error!("Failed to fetch skills: {}", e);
-            AppError::internal_error(format!("Failed to fetch skills: {}", e))
-        })?;
-    
-    info!("Retrieved {} skill categories", skills.len());
-    Ok(HttpResponse::Ok().json(skills))
+    let evidence = skill_query.evidence.unwrap_or(false);
+    let tables: &[&str] = if evidence { &["skills", "projects", "experiences"] } else { &["skills"] };
+    let last_modified = last_modified(db.get_ref(), tables).await?;
+    let db_error = |e: anyhow::Error| {
+        error!("Failed to fetch skills: {}", e);
+        AppError::internal_error(format!("Failed to fetch skills: {}", e))
+    };
+
+    let skills = repo.find_page(&query).await.map_err(db_error)?;
+    info!("Retrieved {} of {} skill categories", skills.items.len(), skills.total);
+
+    let response = if evidence {
+        let evidence = repo.find_evidence().await.map_err(db_error)?;
+        paginated(&req, skills.map(|skill| EnrichedSkill::new(skill, &evidence)))
+    } else {
+        paginated(&req, skills)
+    };
+
+    Ok(cacheable(&req, response, last_modified, &cache_control("skills", PUBLIC_CACHE_CONTROL)))
 }
 
 #[derive(Debug, Deserialize, utoipa::ToSchema)]
 pub struct CreateSkillRequest {
     /// Skill category (e.g., "Languages", "Frontend", "Backend")
     pub category: String,
-    /// List of skills in this category
-    pub items: Vec<String>,
+    /// Skills in this category, as names or objects with a proficiency,
+    /// years of experience and display order
+    pub items: Vec<SkillItem>,
 }
 
 #[derive(Debug, Deserialize, utoipa::ToSchema)]
 pub struct UpdateSkillRequest {
     /// Skill category (e.g., "Languages", "Frontend", "Backend")
     pub category: Option<String>,
-    /// List of skills in this category
-    pub items: Option<Vec<String>>,
+    /// Skills in this category, as names or objects with a proficiency,
+    /// years of experience and display order
+    pub items: Option<Vec<SkillItem>>,
+}
+
+// Id of the skill category with a name, or 404 Not Found
+async fn skill_id(repo: &SkillRepository, category: &str) -> AppResult<String> {
+    repo.find_id_by_category(category).await
+        .map_err(|e| {
+            error!("Failed to fetch skills: {}", e);
+            AppError::internal_error(format!("Failed to fetch skills: {}", e))
+        })?
+        .ok_or_else(|| {
+            info!("Skill category '{}' not found", category);
+            AppError::not_found(format!("Skill category '{}' not found", category))
+        })
+}
+
+// Id and current version of the skill category with a name, or 404 Not Found
+async fn skill_version(repo: &SkillRepository, category: &str) -> AppResult<(String, i64)> {
+    let id = skill_id(repo, category).await?;
+    let version = item_version(repo, &id, &format!("Skill category '{}'", category)).await?;
+
+    Ok((id, version))
 }
 
 /// Create a new skill category
@@ -67,15 +127,18 @@ pub struct UpdateSkillRequest {
         (status = 201, description = "Skill category created successfully", body = Skill),
         (status = 400, description = "Invalid skill data"),
         (status = 401, description = "Unauthorized - Invalid or missing authentication token"),
+        (status = 403, description = "Forbidden - Role does not grant this permission"),
         (status = 500, description = "Internal server error")
     )
 )]
 #[post("/skills")]
 pub async fn create_skill(
     skill_req: web::Json<CreateSkillRequest>,
-    _user: AuthenticatedUser, // Require authentication
+    user: AuthenticatedUser,
     db: web::Data<DbPool>,
 ) -> AppResult<impl Responder> {
+    user.require(Permission::SkillsWrite)?;
+    
     let repo = SkillRepository::new(db.get_ref().clone());
     
     let skill = Skill {
@@ -96,7 +159,8 @@ pub async fn create_skill(
 
 /// Get a skill category by name
 ///
-/// Returns a single skill category with the specified name.
+/// Returns a single skill category with the specified name, with its version
+/// in the ETag header.
 #[utoipa::path(
     get,
     path = "/skills/{category}",
@@ -117,6 +181,7 @@ pub async fn get_skill_by_category(
 ) -> AppResult<impl Responder> {
     let category = path.into_inner();
     let repo = SkillRepository::new(db.get_ref().clone());
+    let (_, version) = skill_version(&repo, &category).await?;
     
     // Find the skill with the matching category
     let skills = repo.find_all().await
@@ -128,7 +193,7 @@ pub async fn get_skill_by_category(
     match skills.iter().find(|s| s.category == category) {
         Some(skill) => {
             info!("Retrieved skill category: {}", category);
-            Ok(HttpResponse::Ok().json(skill))
+            Ok(versioned_json(version, skill))
         },
         None => {
             info!("Skill category '{}' not found", category);
@@ -149,26 +214,35 @@ pub async fn get_skill_by_category(
         ("jwt_auth" = [])
     ),
     params(
-        ("category" = String, Path, description = "Skill category name")
+        ("category" = String, Path, description = "Skill category name"),
+        ("If-Match" = String, Header, description = "ETag of the skill category as last read, or *")
     ),
     request_body = UpdateSkillRequest,
     responses(
         (status = 200, description = "Skill category updated successfully", body = Skill),
         (status = 400, description = "Invalid skill data"),
         (status = 401, description = "Unauthorized - Invalid or missing authentication token"),
+        (status = 403, description = "Forbidden - Role does not grant this permission"),
         (status = 404, description = "Skill category not found"),
+        (status = 412, description = "The skill category changed since it was read"),
+        (status = 428, description = "If-Match header missing"),
         (status = 500, description = "Internal server error")
     )
 )]
 #[put("/skills/{category}")]
 pub async fn update_skill(
+    req: HttpRequest,
     path: web::Path<String>,
     skill_req: web::Json<UpdateSkillRequest>,
-    _user: AuthenticatedUser, // Require authentication
+    user: AuthenticatedUser,
     db: web::Data<DbPool>,
 ) -> AppResult<impl Responder> {
+    user.require(Permission::SkillsWrite)?;
+    
     let category = path.into_inner();
     let repo = SkillRepository::new(db.get_ref().clone());
+    let (id, version) = skill_version(&repo, &category).await?;
+    let version = if_match(&req, version)?;
     
     // Find the skill to update
     let skills = repo.find_all().await
@@ -177,38 +251,102 @@ pub async fn update_skill(
             AppError::internal_error(format!("Failed to fetch skills: {}", e))
         })?;
     
-    let skill_entry = skills.iter().enumerate()
-        .find(|(_, s)| s.category == category)
+    let existing_skill = skills.iter()
+        .find(|s| s.category == category)
         .ok_or_else(|| {
             info!("Skill category '{}' not found for update", category);
             AppError::not_found(format!("Skill category '{}' not found", category))
         })?;
     
-    let (index, existing_skill) = skill_entry;
-    
     // Create updated skill
     let updated_skill = Skill {
         category: skill_req.category.clone().unwrap_or_else(|| existing_skill.category.clone()),
         items: skill_req.items.clone().unwrap_or_else(|| existing_skill.items.clone()),
     };
     
-    // Get the ID based on index (simplified approach)
-    let id = format!("skill-{}", index + 1);
+    save_skill(&repo, &id, &category, updated_skill, version).await
+}
+
+/// Patch a skill category
+///
+/// Changes part of the skill category with the specified name, with a JSON
+/// Merge Patch (`application/merge-patch+json`) or a JSON Patch
+/// (`application/json-patch+json`) of the category as returned by GET. The
+/// patched category is validated as a whole before it is saved.
+/// Requires authentication.
+#[utoipa::path(
+    patch,
+    path = "/skills/{category}",
+    tag = "skills",
+    security(
+        ("jwt_auth" = [])
+    ),
+    params(
+        ("category" = String, Path, description = "Skill category name"),
+        ("If-Match" = String, Header, description = "ETag of the skill category as last read, or *")
+    ),
+    request_body(content = Object, content_type = "application/merge-patch+json", description = "JSON Merge Patch, or a JSON Patch sent as application/json-patch+json"),
+    responses(
+        (status = 200, description = "Skill category patched successfully", body = Skill),
+        (status = 400, description = "Invalid patch, or invalid skill category once patched"),
+        (status = 401, description = "Unauthorized - Invalid or missing authentication token"),
+        (status = 403, description = "Forbidden - Role does not grant this permission"),
+        (status = 404, description = "Skill category not found"),
+        (status = 409, description = "The patch does not apply to the skill category"),
+        (status = 412, description = "The skill category changed since it was read"),
+        (status = 415, description = "Patch format not supported"),
+        (status = 428, description = "If-Match header missing"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[patch("/skills/{category}")]
+pub async fn patch_skill(
+    req: HttpRequest,
+    path: web::Path<String>,
+    body: web::Bytes,
+    user: AuthenticatedUser,
+    db: web::Data<DbPool>,
+) -> AppResult<impl Responder> {
+    user.require(Permission::SkillsWrite)?;
+    
+    let category = path.into_inner();
+    let repo = SkillRepository::new(db.get_ref().clone());
+    let (id, version) = skill_version(&repo, &category).await?;
+    let version = if_match(&req, version)?;
     
-    let result = repo.update(&id, updated_skill.clone()).await
+    let existing_skill = repo.find_by_id(&id).await
+        .map_err(|e| {
+            error!("Failed to fetch skills: {}", e);
+            AppError::internal_error(format!("Failed to fetch skills: {}", e))
+        })?
+        .ok_or_else(|| {
+            info!("Skill category '{}' not found for patch", category);
+            AppError::not_found(format!("Skill category '{}' not found", category))
+        })?;
+    
+    let patched_skill: Skill = apply_patch(&req, &body, &existing_skill)?;
+    
+    save_skill(&repo, &id, &category, patched_skill, version).await
+}
+
+// Save a new state of a skill category read at `version`
+async fn save_skill(repo: &SkillRepository, id: &str, category: &str, skill: Skill, version: i64) -> AppResult<HttpResponse> {
+    // Save the updated skill, unless it changed since the client read it
+    let result = repo.update_at(id, skill, version).await
         .map_err(|e| {
             error!("Failed to update skill category: {}", e);
             AppError::internal_error(format!("Failed to update skill category: {}", e))
-        })?;
+        })?
+        .ok_or_else(changed_since_read)?;
     
     info!("Updated skill category: {}", category);
     
-    Ok(HttpResponse::Ok().json(result))
+    Ok(versioned_json(version + 1, &result))
 }
 
 /// Delete a skill category
 ///
-/// Deletes the skill category with the specified name.
+/// Moves the skill category with the specified name to the trash.
 /// Requires authentication.
 #[utoipa::path(
     delete,
@@ -218,49 +356,45 @@ pub async fn update_skill(
         ("jwt_auth" = [])
     ),
     params(
-        ("category" = String, Path, description = "Skill category name")
+        ("category" = String, Path, description = "Skill category name"),
+        ("If-Match" = String, Header, description = "ETag of the skill category as last read, or *")
     ),
     responses(
         (status = 204, description = "Skill category deleted successfully"),
         (status = 401, description = "Unauthorized - Invalid or missing authentication token"),
+        (status = 403, description = "Forbidden - Role does not grant this permission"),
         (status = 404, description = "Skill category not found"),
+        (status = 412, description = "The skill category changed since it was read"),
+        (status = 428, description = "If-Match header missing"),
         (status = 500, description = "Internal server error")
     )
 )]
 #[delete("/skills/{category}")]
 pub async fn delete_skill(
+    req: HttpRequest,
     path: web::Path<String>,
-    _user: AuthenticatedUser, // Require authentication
+    user: AuthenticatedUser,
     db: web::Data<DbPool>,
 ) -> AppResult<impl Responder> {
+    user.require(Permission::SkillsWrite)?;
+    
     let category = path.into_inner();
     let repo = SkillRepository::new(db.get_ref().clone());
     
-    // Find the skill to delete
-    let skills = repo.find_all().await
-        .map_err(|e| {
-            error!("Failed to fetch skills: {}", e);
-            AppError::internal_error(format!("Failed to fetch skills: {}", e))
-        })?;
-    
-    let skill_entry = skills.iter().enumerate()
-        .find(|(_, s)| s.category == category)
-        .ok_or_else(|| {
-            info!("Skill category '{}' not found for deletion", category);
-            AppError::not_found(format!("Skill category '{}' not found", category))
-        })?;
-    
-    let (index, _) = skill_entry;
-    
-    // Get the ID based on index (simplified approach)
-    let id = format!("skill-{}", index + 1);
+    let (id, version) = skill_version(&repo, &category).await?;
+    let version = if_match(&req, version)?;
     
-    repo.delete(&id).await
+    // Delete the skill, unless it changed since the client read it
+    let deleted = repo.delete_at(&id, version).await
         .map_err(|e| {
             error!("Failed to delete skill category: {}", e);
             AppError::internal_error(format!("Failed to delete skill category: {}", e))
         })?;
     
+    if !deleted {
+        return Err(changed_since_read());
+    }
+    
     info!("Deleted skill category: {}", category);
     
     Ok(HttpResponse::NoContent().finish())
@@ -271,5 +405,6 @@ pub fn config(cfg: &mut web::ServiceConfig) {
        .service(get_skill_by_category)
        .service(create_skill)
        .service(update_skill)
+       .service(patch_skill)
        .service(delete_skill);
 }
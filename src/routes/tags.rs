@@ -0,0 +1,185 @@
+use actix_web::{get, web, HttpRequest, HttpResponse, Responder};
+use log::{error, info};
+use serde::Deserialize;
+use utoipa::IntoParams;
+
+use crate::config::database::DbPool;
+use crate::error::{AppError, AppResult};
+use crate::models::experience_repository::ExperienceRepository;
+use crate::models::post::PostStatus;
+use crate::models::post_repository::PostRepository;
+use crate::models::project_repository::ProjectRepository;
+use crate::models::repository::Repository;
+use crate::models::tag::{TagDetail, TechnologyDetail};
+use crate::models::tag_repository::TagRepository;
+use crate::pagination::{paginated, ListParams};
+
+/// Get all tags
+///
+/// Returns one page of the tags of published posts with how many posts use
+/// each, alphabetically by default. Sort by `count` for the most used first
+/// with `sort=-count`.
+#[utoipa::path(
+    get,
+    path = "/tags",
+    tag = "tags",
+    params(ListParams),
+    responses(
+        (status = 200, description = "Page of tags retrieved successfully", body = Vec<Tag>),
+        (status = 400, description = "Invalid page or sort field"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[get("/tags")]
+pub async fn get_all_tags(
+    req: HttpRequest,
+    params: web::Query<ListParams>,
+    db: web::Data<DbPool>,
+) -> AppResult<impl Responder> {
+    let query = params.query(&TagRepository::TAG_LIST)?;
+    let repo = TagRepository::new(db.get_ref().clone());
+
+    let tags = repo.find_tags(&query).await
+        .map_err(|e| {
+            error!("Failed to fetch tags: {}", e);
+            AppError::internal_error(format!("Failed to fetch tags: {}", e))
+        })?;
+
+    info!("Retrieved {} of {} tags", tags.items.len(), tags.total);
+    Ok(paginated(&req, tags))
+}
+
+/// Get tag by name
+///
+/// Returns a tag, matched ignoring case, with the published posts using it.
+#[utoipa::path(
+    get,
+    path = "/tags/{tag}",
+    tag = "tags",
+    params(
+        ("tag" = String, Path, description = "Tag name, in any case")
+    ),
+    responses(
+        (status = 200, description = "Tag found", body = TagDetail),
+        (status = 404, description = "No published post has this tag"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[get("/tags/{tag}")]
+pub async fn get_tag(path: web::Path<String>, db: web::Data<DbPool>) -> AppResult<impl Responder> {
+    let name = path.into_inner();
+
+    let tag = TagRepository::new(db.get_ref().clone()).find_tag(&name).await
+        .map_err(|e| {
+            error!("Failed to fetch tag {}: {}", name, e);
+            AppError::internal_error(format!("Failed to fetch tag: {}", e))
+        })?
+        .ok_or_else(|| {
+            info!("Tag {} not found", name);
+            AppError::not_found(format!("Tag {} not found", name))
+        })?;
+
+    let posts = PostRepository::new(db.get_ref().clone()).find_by_tag(&name).await
+        .map_err(|e| {
+            error!("Failed to fetch posts tagged {}: {}", name, e);
+            AppError::internal_error(format!("Failed to fetch tag: {}", e))
+        })?
+        .into_iter()
+        .filter(|post| post.status == PostStatus::Published)
+        .collect();
+
+    info!("Retrieved tag {}", name);
+    Ok(HttpResponse::Ok().json(TagDetail { tag, posts }))
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct TechnologyFilters {
+    /// Only technologies of this registry category, e.g. "language"
+    category: Option<String>,
+}
+
+/// Get all technologies
+///
+/// Returns one page of the technologies of projects and experiences with how
+/// many of each use them, alphabetically by default. Sort by `count`,
+/// `project_count` or `experience_count` for the most used first with
+/// `sort=-count`.
+#[utoipa::path(
+    get,
+    path = "/technologies",
+    tag = "tags",
+    params(ListParams, TechnologyFilters),
+    responses(
+        (status = 200, description = "Page of technologies retrieved successfully", body = Vec<Technology>),
+        (status = 400, description = "Invalid page or sort field"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[get("/technologies")]
+pub async fn get_all_technologies(
+    req: HttpRequest,
+    params: web::Query<ListParams>,
+    filters: web::Query<TechnologyFilters>,
+    db: web::Data<DbPool>,
+) -> AppResult<impl Responder> {
+    let query = params.query(&TagRepository::TECHNOLOGY_LIST)?
+        .filter("category", filters.into_inner().category)?;
+    let repo = TagRepository::new(db.get_ref().clone());
+
+    let technologies = repo.find_technologies(&query).await
+        .map_err(|e| {
+            error!("Failed to fetch technologies: {}", e);
+            AppError::internal_error(format!("Failed to fetch technologies: {}", e))
+        })?;
+
+    info!("Retrieved {} of {} technologies", technologies.items.len(), technologies.total);
+    Ok(paginated(&req, technologies))
+}
+
+/// Get technology by name
+///
+/// Returns a technology, matched by name or alias ignoring case, with the
+/// projects and experiences using it.
+#[utoipa::path(
+    get,
+    path = "/technologies/{technology}",
+    tag = "tags",
+    params(
+        ("technology" = String, Path, description = "Technology name or alias, in any case")
+    ),
+    responses(
+        (status = 200, description = "Technology found", body = TechnologyDetail),
+        (status = 404, description = "No project or experience uses this technology"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[get("/technologies/{technology}")]
+pub async fn get_technology(path: web::Path<String>, db: web::Data<DbPool>) -> AppResult<impl Responder> {
+    let name = path.into_inner();
+    let db_error = |e: anyhow::Error| {
+        error!("Failed to fetch technology {}: {}", name, e);
+        AppError::internal_error(format!("Failed to fetch technology: {}", e))
+    };
+
+    let technology = TagRepository::new(db.get_ref().clone()).find_technology(&name).await
+        .map_err(db_error)?
+        .ok_or_else(|| {
+            info!("Technology {} not found", name);
+            AppError::not_found(format!("Technology {} not found", name))
+        })?;
+
+    let projects = ProjectRepository::new(db.get_ref().clone()).find_by_technology(&technology.name).await
+        .map_err(db_error)?;
+    let experiences = ExperienceRepository::new(db.get_ref().clone()).find_by_technology(&technology.name).await
+        .map_err(db_error)?;
+
+    info!("Retrieved technology {}", name);
+    Ok(HttpResponse::Ok().json(TechnologyDetail { technology, projects, experiences }))
+}
+
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_all_tags)
+       .service(get_tag)
+       .service(get_all_technologies)
+       .service(get_technology);
+}
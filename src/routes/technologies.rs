@@ -0,0 +1,327 @@
+use actix_web::{delete, get, post, put, web, HttpRequest, HttpResponse, Responder};
+use log::{error, info};
+use serde::Deserialize;
+use utoipa::{IntoParams, ToSchema};
+
+use crate::auth::{AuthenticatedUser, Permission};
+use crate::config::database::DbPool;
+use crate::error::{AppError, AppResult};
+use crate::models::technology::TechnologyEntry;
+use crate::models::technology_repository::TechnologyRepository;
+use crate::pagination::{paginated, ListParams};
+
+#[derive(Debug, Deserialize, ToSchema)]
+#[schema(example = json!({
+    "name": "TypeScript",
+    "category": "language",
+    "icon": "simple-icons:typescript",
+    "aliases": ["TS"]
+}))]
+pub struct CreateTechnologyRequest {
+    /// Canonical name, at most 100 characters
+    pub name: String,
+    /// Kind of technology, e.g. "language", "framework" or "database"
+    pub category: Option<String>,
+    /// Icon identifier for the frontend
+    pub icon: Option<String>,
+    /// Other spellings to normalise to this name
+    #[serde(default)]
+    pub aliases: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct UpdateTechnologyRequest {
+    /// New canonical name; the previous one becomes an alias
+    pub name: Option<String>,
+    /// Kind of technology; an empty string clears it
+    pub category: Option<String>,
+    /// Icon identifier; an empty string clears it
+    pub icon: Option<String>,
+    /// Replaces every alias of the technology
+    pub aliases: Option<Vec<String>>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+#[schema(example = json!({ "into": "TypeScript" }))]
+pub struct MergeTechnologyRequest {
+    /// Name or alias of the technology to keep
+    pub into: String,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct RegistryFilters {
+    /// Only technologies of this category
+    category: Option<String>,
+}
+
+/// List the technology registry
+///
+/// Returns one page of registered technologies with their category, icon and
+/// aliases, including ones nothing uses yet. Sortable by `name` and `category`.
+#[utoipa::path(
+    get,
+    path = "/admin/technologies",
+    tag = "technologies",
+    security(
+        ("jwt_auth" = [])
+    ),
+    params(ListParams, RegistryFilters),
+    responses(
+        (status = 200, description = "Page of registered technologies", body = Vec<TechnologyEntry>),
+        (status = 400, description = "Invalid page, sort field or filter"),
+        (status = 401, description = "Unauthorized - Invalid or missing authentication token"),
+        (status = 403, description = "Forbidden - Role does not grant this permission"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[get("/admin/technologies")]
+pub async fn get_registry(
+    req: HttpRequest,
+    params: web::Query<ListParams>,
+    filters: web::Query<RegistryFilters>,
+    db: web::Data<DbPool>,
+    user: AuthenticatedUser,
+) -> AppResult<impl Responder> {
+    user.require(Permission::TechnologiesManage)?;
+    let query = params.query(&TechnologyRepository::LIST)?
+        .filter("category", filters.into_inner().category)?;
+    let repo = TechnologyRepository::new(db.get_ref().clone());
+
+    let technologies = repo.find_page(&query).await
+        .map_err(|e| {
+            error!("Failed to fetch technology registry: {}", e);
+            AppError::internal_error(format!("Failed to fetch technology registry: {}", e))
+        })?;
+
+    Ok(paginated(&req, technologies))
+}
+
+/// Get a registered technology
+///
+/// Returns a technology by its name or one of its aliases, ignoring case.
+#[utoipa::path(
+    get,
+    path = "/admin/technologies/{technology}",
+    tag = "technologies",
+    security(
+        ("jwt_auth" = [])
+    ),
+    params(
+        ("technology" = String, Path, description = "Technology name or alias, in any case")
+    ),
+    responses(
+        (status = 200, description = "Technology found", body = TechnologyEntry),
+        (status = 401, description = "Unauthorized - Invalid or missing authentication token"),
+        (status = 403, description = "Forbidden - Role does not grant this permission"),
+        (status = 404, description = "Technology not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[get("/admin/technologies/{technology}")]
+pub async fn get_registered_technology(
+    path: web::Path<String>,
+    db: web::Data<DbPool>,
+    user: AuthenticatedUser,
+) -> AppResult<impl Responder> {
+    user.require(Permission::TechnologiesManage)?;
+    let name = path.into_inner();
+
+    let technology = TechnologyRepository::new(db.get_ref().clone()).find(&name).await
+        .map_err(|e| {
+            error!("Failed to fetch technology {}: {}", name, e);
+            AppError::internal_error(format!("Failed to fetch technology: {}", e))
+        })?
+        .ok_or_else(|| AppError::not_found(format!("Technology '{}' not found", name)))?;
+
+    Ok(HttpResponse::Ok().json(technology))
+}
+
+/// Register a technology
+///
+/// Adds a technology with its aliases. Projects, experiences, skills and
+/// GitHub languages using one of the aliases are rewritten to the new name.
+#[utoipa::path(
+    post,
+    path = "/admin/technologies",
+    tag = "technologies",
+    security(
+        ("jwt_auth" = [])
+    ),
+    request_body = CreateTechnologyRequest,
+    responses(
+        (status = 201, description = "Technology registered", body = TechnologyEntry),
+        (status = 400, description = "Blank or too long name, alias, category or icon"),
+        (status = 401, description = "Unauthorized - Invalid or missing authentication token"),
+        (status = 403, description = "Forbidden - Role does not grant this permission"),
+        (status = 409, description = "The name or an alias already belongs to a technology"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[post("/admin/technologies")]
+pub async fn create_technology(
+    technology_req: web::Json<CreateTechnologyRequest>,
+    db: web::Data<DbPool>,
+    user: AuthenticatedUser,
+) -> AppResult<impl Responder> {
+    user.require(Permission::TechnologiesManage)?;
+    let technology_req = technology_req.into_inner();
+
+    let created = TechnologyRepository::new(db.get_ref().clone())
+        .create(TechnologyEntry {
+            name: technology_req.name,
+            category: technology_req.category,
+            icon: technology_req.icon,
+            aliases: technology_req.aliases,
+        })
+        .await?;
+
+    info!("Registered technology {}", created.name);
+    Ok(HttpResponse::Created().json(created))
+}
+
+/// Update a registered technology
+///
+/// Changes the name, category, icon or aliases of a technology, keeping the
+/// values that are not given. Stored names are rewritten to match.
+#[utoipa::path(
+    put,
+    path = "/admin/technologies/{technology}",
+    tag = "technologies",
+    security(
+        ("jwt_auth" = [])
+    ),
+    params(
+        ("technology" = String, Path, description = "Technology name or alias, in any case")
+    ),
+    request_body = UpdateTechnologyRequest,
+    responses(
+        (status = 200, description = "Technology updated", body = TechnologyEntry),
+        (status = 400, description = "Blank or too long name, alias, category or icon"),
+        (status = 401, description = "Unauthorized - Invalid or missing authentication token"),
+        (status = 403, description = "Forbidden - Role does not grant this permission"),
+        (status = 404, description = "Technology not found"),
+        (status = 409, description = "The name or an alias belongs to another technology"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[put("/admin/technologies/{technology}")]
+pub async fn update_technology(
+    path: web::Path<String>,
+    technology_req: web::Json<UpdateTechnologyRequest>,
+    db: web::Data<DbPool>,
+    user: AuthenticatedUser,
+) -> AppResult<impl Responder> {
+    user.require(Permission::TechnologiesManage)?;
+    let name = path.into_inner();
+    let technology_req = technology_req.into_inner();
+    let repo = TechnologyRepository::new(db.get_ref().clone());
+
+    let existing = repo.find(&name).await
+        .map_err(|e| {
+            error!("Failed to fetch technology {}: {}", name, e);
+            AppError::internal_error(format!("Failed to fetch technology: {}", e))
+        })?
+        .ok_or_else(|| AppError::not_found(format!("Technology '{}' not found", name)))?;
+
+    let updated = repo
+        .update(&name, TechnologyEntry {
+            name: technology_req.name.unwrap_or(existing.name),
+            category: technology_req.category.or(existing.category),
+            icon: technology_req.icon.or(existing.icon),
+            aliases: technology_req.aliases.unwrap_or(existing.aliases),
+        })
+        .await?;
+
+    info!("Updated technology {}", updated.name);
+    Ok(HttpResponse::Ok().json(updated))
+}
+
+/// Merge a technology into another
+///
+/// Makes the technology and its aliases aliases of the target, which keeps
+/// its own category and icon unless it has none. Every project, experience,
+/// skill and GitHub language using it is rewritten to the target's name.
+#[utoipa::path(
+    post,
+    path = "/admin/technologies/{technology}/merge",
+    tag = "technologies",
+    security(
+        ("jwt_auth" = [])
+    ),
+    params(
+        ("technology" = String, Path, description = "Name or alias of the technology to merge away")
+    ),
+    request_body = MergeTechnologyRequest,
+    responses(
+        (status = 200, description = "Technology merged; returns the target", body = TechnologyEntry),
+        (status = 400, description = "Both names are the same technology"),
+        (status = 401, description = "Unauthorized - Invalid or missing authentication token"),
+        (status = 403, description = "Forbidden - Role does not grant this permission"),
+        (status = 404, description = "Either technology not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[post("/admin/technologies/{technology}/merge")]
+pub async fn merge_technology(
+    path: web::Path<String>,
+    merge_req: web::Json<MergeTechnologyRequest>,
+    db: web::Data<DbPool>,
+    user: AuthenticatedUser,
+) -> AppResult<impl Responder> {
+    user.require(Permission::TechnologiesManage)?;
+    let name = path.into_inner();
+
+    let merged = TechnologyRepository::new(db.get_ref().clone())
+        .merge(&name, &merge_req.into)
+        .await?;
+
+    info!("Merged technology {} into {}", name, merged.name);
+    Ok(HttpResponse::Ok().json(merged))
+}
+
+/// Remove a technology from the registry
+///
+/// Deletes an unused technology and its aliases. Technologies still used by
+/// projects or experiences must be merged into another one instead.
+#[utoipa::path(
+    delete,
+    path = "/admin/technologies/{technology}",
+    tag = "technologies",
+    security(
+        ("jwt_auth" = [])
+    ),
+    params(
+        ("technology" = String, Path, description = "Technology name or alias, in any case")
+    ),
+    responses(
+        (status = 204, description = "Technology removed"),
+        (status = 401, description = "Unauthorized - Invalid or missing authentication token"),
+        (status = 403, description = "Forbidden - Role does not grant this permission"),
+        (status = 404, description = "Technology not found"),
+        (status = 409, description = "Technology still in use"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[delete("/admin/technologies/{technology}")]
+pub async fn delete_technology(
+    path: web::Path<String>,
+    db: web::Data<DbPool>,
+    user: AuthenticatedUser,
+) -> AppResult<impl Responder> {
+    user.require(Permission::TechnologiesManage)?;
+    let name = path.into_inner();
+
+    TechnologyRepository::new(db.get_ref().clone()).delete(&name).await?;
+
+    info!("Removed technology {}", name);
+    Ok(HttpResponse::NoContent().finish())
+}
+
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_registry)
+       .service(create_technology)
+       .service(get_registered_technology)
+       .service(update_technology)
+       .service(merge_technology)
+       .service(delete_technology);
+}
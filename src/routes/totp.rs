@@ -0,0 +1,216 @@
+use actix_web::{delete, post, web, HttpResponse, Responder};
+use log::{error, info};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::auth::totp::{generate_recovery_codes, generate_secret, provisioning_uri, verify_code};
+use crate::auth::{verify_password, AuthenticatedUser};
+use crate::config::database::DbPool;
+use crate::error::{AppError, AppResult};
+use crate::models::repository::Repository;
+use crate::models::totp::TotpEnrollment;
+use crate::models::totp_repository::TotpRepository;
+use crate::models::user::UserAccount;
+use crate::models::user_repository::UserRepository;
+
+#[derive(Debug, Serialize, ToSchema)]
+#[schema(example = json!({
+    "secret": "JBSWY3DPEHPK3PXPJBSWY3DPEHPK3PXP",
+    "provisioning_uri": "otpauth://totp/Retro%20Quewui:admin?secret=JBSWY3DPEHPK3PXPJBSWY3DPEHPK3PXP&issuer=Retro%20Quewui&algorithm=SHA1&digits=6&period=30"
+}))]
+pub struct TotpEnrollResponse {
+    /// Base32 shared secret, for manual entry in an authenticator app
+    pub secret: String,
+    /// otpauth:// URI, usually shown as a QR code
+    pub provisioning_uri: String,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct TotpConfirmRequest {
+    /// Current six digit code from the authenticator app
+    pub code: String,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TotpConfirmResponse {
+    /// One-time recovery codes. They are shown only once.
+    pub recovery_codes: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct TotpDisableRequest {
+    /// Current password of the authenticated user
+    pub current_password: String,
+}
+
+async fn load_account(db: &DbPool, user_id: &str) -> AppResult<UserAccount> {
+    UserRepository::new(db.clone())
+        .find_by_id(user_id).await
+        .map_err(|e| {
+            error!("Failed to fetch user {}: {}", user_id, e);
+            AppError::internal_error(format!("Failed to fetch user: {}", e))
+        })?
+        .ok_or_else(|| AppError::unauthorized("User no longer exists"))
+}
+
+async fn load_enrollment(repo: &TotpRepository, user_id: &str) -> AppResult<Option<TotpEnrollment>> {
+    repo.find_by_user(user_id).await
+        .map_err(|e| {
+            error!("Failed to fetch two-factor settings for user {}: {}", user_id, e);
+            AppError::internal_error(format!("Failed to fetch two-factor settings: {}", e))
+        })
+}
+
+/// Start two-factor enrollment
+///
+/// Generates a new TOTP secret for the authenticated user. Two-factor
+/// authentication is only enabled once a code is confirmed at
+/// POST /auth/totp/confirm. Enrolling again replaces an unconfirmed secret.
+#[utoipa::path(
+    post,
+    path = "/auth/totp/enroll",
+    tag = "auth",
+    security(
+        ("jwt_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Secret generated", body = TotpEnrollResponse),
+        (status = 400, description = "Two-factor authentication is already enabled"),
+        (status = 401, description = "Unauthorized - Invalid or missing authentication token"),
+        (status = 403, description = "Forbidden - Called with an API key"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[post("/auth/totp/enroll")]
+pub async fn enroll_totp(
+    db: web::Data<DbPool>,
+    user: AuthenticatedUser,
+) -> AppResult<impl Responder> {
+    user.access_token()?;
+    let account = load_account(db.get_ref(), &user.0.id).await?;
+    let repo = TotpRepository::new(db.get_ref().clone());
+
+    if load_enrollment(&repo, &account.id).await?.is_some_and(|e| e.is_confirmed()) {
+        return Err(AppError::bad_request("Two-factor authentication is already enabled; disable it first"));
+    }
+
+    let enrollment = TotpEnrollment::new(account.id.clone(), generate_secret());
+    let enrollment = repo.save_enrollment(enrollment).await
+        .map_err(|e| {
+            error!("Failed to store two-factor secret for user {}: {}", account.id, e);
+            AppError::internal_error(format!("Failed to store two-factor secret: {}", e))
+        })?;
+
+    info!("User {} started two-factor enrollment", account.username);
+    Ok(HttpResponse::Ok().json(TotpEnrollResponse {
+        provisioning_uri: provisioning_uri(&enrollment.secret, &account.username),
+        secret: enrollment.secret,
+    }))
+}
+
+/// Confirm two-factor enrollment
+///
+/// Enables two-factor authentication once the authenticator app produces a
+/// valid code, and returns one-time recovery codes.
+#[utoipa::path(
+    post,
+    path = "/auth/totp/confirm",
+    tag = "auth",
+    security(
+        ("jwt_auth" = [])
+    ),
+    request_body = TotpConfirmRequest,
+    responses(
+        (status = 200, description = "Two-factor authentication enabled", body = TotpConfirmResponse),
+        (status = 400, description = "Invalid code, no pending enrollment or already enabled"),
+        (status = 401, description = "Unauthorized - Invalid or missing authentication token"),
+        (status = 403, description = "Forbidden - Called with an API key"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[post("/auth/totp/confirm")]
+pub async fn confirm_totp(
+    confirm_req: web::Json<TotpConfirmRequest>,
+    db: web::Data<DbPool>,
+    user: AuthenticatedUser,
+) -> AppResult<impl Responder> {
+    user.access_token()?;
+    let repo = TotpRepository::new(db.get_ref().clone());
+
+    let enrollment = load_enrollment(&repo, &user.0.id).await?
+        .filter(|e| !e.is_confirmed())
+        .ok_or_else(|| AppError::bad_request("No pending two-factor enrollment; start one at /auth/totp/enroll"))?;
+
+    let now = chrono::Utc::now().timestamp();
+    let step = verify_code(&enrollment.secret, &confirm_req.code, now as u64)
+        .ok_or_else(|| AppError::bad_request("Invalid authentication code"))?;
+
+    let (recovery_codes, hashes) = generate_recovery_codes();
+
+    let confirmed = repo.confirm(&user.0.id, now, step, &hashes).await
+        .map_err(|e| {
+            error!("Failed to enable two-factor authentication for user {}: {}", user.0.id, e);
+            AppError::internal_error(format!("Failed to enable two-factor authentication: {}", e))
+        })?;
+
+    if !confirmed {
+        return Err(AppError::bad_request("Two-factor authentication is already enabled"));
+    }
+
+    info!("User {} enabled two-factor authentication", user.0.name);
+    Ok(HttpResponse::Ok().json(TotpConfirmResponse { recovery_codes }))
+}
+
+/// Disable two-factor authentication
+///
+/// Removes the second factor of the authenticated user. The current password must be supplied.
+#[utoipa::path(
+    delete,
+    path = "/auth/totp",
+    tag = "auth",
+    security(
+        ("jwt_auth" = [])
+    ),
+    request_body = TotpDisableRequest,
+    responses(
+        (status = 204, description = "Two-factor authentication disabled"),
+        (status = 401, description = "Unauthorized - Invalid token or wrong current password"),
+        (status = 403, description = "Forbidden - Called with an API key"),
+        (status = 404, description = "Two-factor authentication is not enabled"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[delete("/auth/totp")]
+pub async fn disable_totp(
+    disable_req: web::Json<TotpDisableRequest>,
+    db: web::Data<DbPool>,
+    user: AuthenticatedUser,
+) -> AppResult<impl Responder> {
+    user.access_token()?;
+    let account = load_account(db.get_ref(), &user.0.id).await?;
+
+    if !verify_password(&disable_req.current_password, &account.password_hash) {
+        info!("Wrong current password supplied by user {}", account.username);
+        return Err(AppError::unauthorized("Current password is incorrect"));
+    }
+
+    let deleted = TotpRepository::new(db.get_ref().clone())
+        .delete(&account.id).await
+        .map_err(|e| {
+            error!("Failed to disable two-factor authentication for user {}: {}", account.id, e);
+            AppError::internal_error(format!("Failed to disable two-factor authentication: {}", e))
+        })?;
+
+    if !deleted {
+        return Err(AppError::not_found("Two-factor authentication is not enabled"));
+    }
+
+    info!("User {} disabled two-factor authentication", account.username);
+    Ok(HttpResponse::NoContent().finish())
+}
+
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(enroll_totp)
+       .service(confirm_totp)
+       .service(disable_totp);
+}
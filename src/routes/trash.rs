@@ -0,0 +1,196 @@
+use actix_web::{delete, get, post, web, HttpRequest, HttpResponse, Responder};
+use log::{error, info};
+use serde::Deserialize;
+use utoipa::IntoParams;
+
+use crate::auth::{AuthenticatedUser, Permission};
+use crate::config::database::DbPool;
+use crate::error::{AppError, AppResult};
+use crate::models::trash::{PurgeResult, TRASH_KINDS};
+use crate::models::trash_repository::TrashRepository;
+use crate::pagination::{paginated, ListParams};
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct TrashQuery {
+    /// Only items of this kind ("posts", "projects", "experiences", "skills" or "contacts")
+    kind: Option<String>,
+}
+
+fn db_error(e: anyhow::Error) -> AppError {
+    error!("Failed to access the trash: {}", e);
+    AppError::internal_error(format!("Failed to access the trash: {}", e))
+}
+
+// The kind named in a path, as one of TRASH_KINDS
+fn trash_kind(kind: &str) -> AppResult<&'static str> {
+    TRASH_KINDS
+        .iter()
+        .copied()
+        .find(|k| *k == kind)
+        .ok_or_else(|| AppError::not_found(format!("Unknown kind '{}'; use one of: {}", kind, TRASH_KINDS.join(", "))))
+}
+
+/// List deleted items
+///
+/// Returns one page of deleted posts, projects, experiences, skill categories
+/// and contact messages, most recently deleted first. Sortable by
+/// `deleted_at`, `kind` and `title`. Requires authentication.
+#[utoipa::path(
+    get,
+    path = "/admin/trash",
+    tag = "admin",
+    security(("jwt_auth" = [])),
+    params(ListParams, TrashQuery),
+    responses(
+        (status = 200, description = "Page of deleted items", body = Vec<TrashItem>),
+        (status = 400, description = "Invalid page, sort field or filter"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Role does not grant this permission"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[get("/admin/trash")]
+pub async fn get_trash(
+    req: HttpRequest,
+    params: web::Query<ListParams>,
+    filters: web::Query<TrashQuery>,
+    user: AuthenticatedUser,
+    db: web::Data<DbPool>,
+) -> AppResult<impl Responder> {
+    user.require(Permission::TrashManage)?;
+    let query = params.query(&TrashRepository::LIST)?
+        .filter("kind", filters.into_inner().kind)?;
+
+    let items = TrashRepository::new(db.get_ref().clone())
+        .find_page(&query)
+        .await
+        .map_err(db_error)?;
+
+    Ok(paginated(&req, items))
+}
+
+/// Restore a deleted item
+///
+/// Takes an item out of the trash, as it was when it was deleted.
+/// Requires authentication.
+#[utoipa::path(
+    post,
+    path = "/admin/trash/{kind}/{id}/restore",
+    tag = "admin",
+    security(("jwt_auth" = [])),
+    params(
+        ("kind" = String, Path, description = "posts, projects, experiences, skills or contacts"),
+        ("id" = String, Path, description = "Item unique identifier")
+    ),
+    responses(
+        (status = 204, description = "Item restored"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Role does not grant this permission"),
+        (status = 404, description = "Unknown kind or item not in the trash"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[post("/admin/trash/{kind}/{id}/restore")]
+pub async fn restore_trash_item(
+    user: AuthenticatedUser,
+    path: web::Path<(String, String)>,
+    db: web::Data<DbPool>,
+) -> AppResult<impl Responder> {
+    user.require(Permission::TrashManage)?;
+    let (kind, id) = path.into_inner();
+    let kind = trash_kind(&kind)?;
+
+    let restored = TrashRepository::new(db.get_ref().clone())
+        .restore(kind, &id)
+        .await
+        .map_err(db_error)?;
+
+    if !restored {
+        return Err(AppError::not_found(format!("No {} with id {} in the trash", kind, id)));
+    }
+
+    info!("{} restored {} {} from the trash", user.0.name, kind, id);
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Purge a deleted item
+///
+/// Removes an item in the trash for good, along with its revisions and old
+/// slugs. Requires authentication.
+#[utoipa::path(
+    delete,
+    path = "/admin/trash/{kind}/{id}",
+    tag = "admin",
+    security(("jwt_auth" = [])),
+    params(
+        ("kind" = String, Path, description = "posts, projects, experiences, skills or contacts"),
+        ("id" = String, Path, description = "Item unique identifier")
+    ),
+    responses(
+        (status = 204, description = "Item purged"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Role does not grant this permission"),
+        (status = 404, description = "Unknown kind or item not in the trash"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[delete("/admin/trash/{kind}/{id}")]
+pub async fn purge_trash_item(
+    user: AuthenticatedUser,
+    path: web::Path<(String, String)>,
+    db: web::Data<DbPool>,
+) -> AppResult<impl Responder> {
+    user.require(Permission::TrashManage)?;
+    let (kind, id) = path.into_inner();
+    let kind = trash_kind(&kind)?;
+
+    let purged = TrashRepository::new(db.get_ref().clone())
+        .purge(kind, &id)
+        .await
+        .map_err(db_error)?;
+
+    if !purged {
+        return Err(AppError::not_found(format!("No {} with id {} in the trash", kind, id)));
+    }
+
+    info!("{} purged {} {} from the trash", user.0.name, kind, id);
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Empty the trash
+///
+/// Removes every item in the trash for good. Requires authentication.
+#[utoipa::path(
+    delete,
+    path = "/admin/trash",
+    tag = "admin",
+    security(("jwt_auth" = [])),
+    responses(
+        (status = 200, description = "Number of items purged", body = PurgeResult),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden - Role does not grant this permission"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[delete("/admin/trash")]
+pub async fn empty_trash(
+    user: AuthenticatedUser,
+    db: web::Data<DbPool>,
+) -> AppResult<impl Responder> {
+    user.require(Permission::TrashManage)?;
+
+    let purged = TrashRepository::new(db.get_ref().clone())
+        .purge_deleted_before(chrono::Utc::now().timestamp())
+        .await
+        .map_err(db_error)?;
+
+    info!("{} emptied the trash ({} items)", user.0.name, purged);
+    Ok(HttpResponse::Ok().json(PurgeResult { purged }))
+}
+
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_trash)
+       .service(empty_trash)
+       .service(restore_trash_item)
+       .service(purge_trash_item);
+}
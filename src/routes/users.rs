@@ -0,0 +1,378 @@
+use actix_web::{get, post, put, delete, web, HttpRequest, HttpResponse, Responder};
+use log::{error, info};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+use validator::Validate;
+
+use crate::auth::session::revoke_user_sessions;
+use crate::auth::{hash_password, AuthenticatedUser, Permission, Role};
+use crate::config::database::DbPool;
+use crate::error::{AppError, AppResult};
+use crate::models::repository::Repository;
+use crate::models::totp_repository::TotpRepository;
+use crate::models::user::UserAccount;
+use crate::models::user_repository::UserRepository;
+use crate::pagination::{paginated, ListParams};
+use crate::validation::validate_json;
+
+#[derive(Debug, Serialize, ToSchema)]
+#[schema(example = json!({
+    "id": "3f1c9b1e-0d5a-4c57-9a0e-5b1f0f2b7c11",
+    "username": "jane",
+    "name": "Jane Doe",
+    "role": "admin"
+}))]
+pub struct UserResponse {
+    /// Unique user identifier
+    pub id: String,
+    /// Login name
+    pub username: String,
+    /// Display name
+    pub name: String,
+    /// User's role
+    pub role: String,
+}
+
+impl From<UserAccount> for UserResponse {
+    fn from(account: UserAccount) -> Self {
+        Self {
+            id: account.id,
+            username: account.username,
+            name: account.name,
+            role: account.role,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct CreateUserRequest {
+    /// Login name (3-50 characters)
+    #[validate(length(min = 3, max = 50, message = "Username must be between 3 and 50 characters"))]
+    pub username: String,
+    /// Display name
+    #[validate(length(min = 1, max = 100, message = "Name must be between 1 and 100 characters"))]
+    pub name: String,
+    /// Initial password (at least 8 characters)
+    #[validate(length(min = 8, message = "Password must be at least 8 characters"))]
+    pub password: String,
+    /// User's role ("admin", "editor" or "viewer")
+    pub role: String,
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct UpdateUserRequest {
+    /// Display name
+    #[validate(length(min = 1, max = 100, message = "Name must be between 1 and 100 characters"))]
+    pub name: Option<String>,
+    /// New password (at least 8 characters)
+    #[validate(length(min = 8, message = "Password must be at least 8 characters"))]
+    pub password: Option<String>,
+    /// User's role ("admin", "editor" or "viewer")
+    pub role: Option<String>,
+}
+
+// Reject roles outside the permission model so no account ends up without access by typo
+fn validate_role(role: &str) -> AppResult<()> {
+    match Role::parse(role) {
+        Some(_) => Ok(()),
+        None => {
+            let roles: Vec<&str> = Role::ALL.iter().map(|r| r.as_str()).collect();
+            Err(AppError::validation_error(format!(
+                "Unknown role '{}', expected one of: {}",
+                role,
+                roles.join(", ")
+            )))
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct UserFilters {
+    /// Only accounts with this role ("admin", "editor" or "viewer")
+    role: Option<String>,
+}
+
+/// List user accounts
+///
+/// Returns one page of user accounts. Sortable by `username`, `name` and `role`.
+/// Requires admin privileges.
+#[utoipa::path(
+    get,
+    path = "/admin/users",
+    tag = "users",
+    security(
+        ("jwt_auth" = [])
+    ),
+    params(ListParams, UserFilters),
+    responses(
+        (status = 200, description = "Page of user accounts", body = Vec<UserResponse>),
+        (status = 400, description = "Invalid page, sort field or filter"),
+        (status = 401, description = "Unauthorized - Invalid or missing authentication token"),
+        (status = 403, description = "Forbidden - Role does not grant this permission"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[get("/admin/users")]
+pub async fn get_all_users(
+    req: HttpRequest,
+    params: web::Query<ListParams>,
+    filters: web::Query<UserFilters>,
+    db: web::Data<DbPool>,
+    user: AuthenticatedUser,
+) -> AppResult<impl Responder> {
+    user.require(Permission::UsersManage)?;
+    let query = params.query(&UserRepository::LIST)?
+        .filter("role", filters.into_inner().role)?;
+    let repo = UserRepository::new(db.get_ref().clone());
+
+    let users = repo.find_page(&query).await
+        .map_err(|e| {
+            error!("Failed to fetch users: {}", e);
+            AppError::internal_error(format!("Failed to fetch users: {}", e))
+        })?;
+
+    Ok(paginated(&req, users.map(UserResponse::from)))
+}
+
+/// Create a user account
+///
+/// Creates a new account with an Argon2id-hashed password. Requires admin privileges.
+#[utoipa::path(
+    post,
+    path = "/admin/users",
+    tag = "users",
+    security(
+        ("jwt_auth" = [])
+    ),
+    request_body = CreateUserRequest,
+    responses(
+        (status = 201, description = "User created successfully", body = UserResponse),
+        (status = 400, description = "Invalid user data or username already taken"),
+        (status = 401, description = "Unauthorized - Invalid or missing authentication token"),
+        (status = 403, description = "Forbidden - Role does not grant this permission"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[post("/admin/users")]
+pub async fn create_user(
+    user_req: web::Json<CreateUserRequest>,
+    db: web::Data<DbPool>,
+    user: AuthenticatedUser,
+) -> AppResult<impl Responder> {
+    user.require(Permission::UsersManage)?;
+    let user_req = validate_json(user_req)?;
+    validate_role(&user_req.role)?;
+    let repo = UserRepository::new(db.get_ref().clone());
+
+    let existing = repo.find_by_username(&user_req.username).await
+        .map_err(|e| {
+            error!("Failed to check username {}: {}", user_req.username, e);
+            AppError::internal_error(format!("Failed to check username: {}", e))
+        })?;
+
+    if existing.is_some() {
+        return Err(AppError::bad_request(format!("Username '{}' is already taken", user_req.username)));
+    }
+
+    let account = UserAccount::new(
+        user_req.username,
+        user_req.name,
+        hash_password(&user_req.password)?,
+        user_req.role,
+    );
+
+    let created = repo.create(account).await
+        .map_err(|e| {
+            error!("Failed to create user: {}", e);
+            AppError::internal_error(format!("Failed to create user: {}", e))
+        })?;
+
+    info!("User {} created account {}", user.0.name, created.username);
+    Ok(HttpResponse::Created().json(UserResponse::from(created)))
+}
+
+/// Update a user account
+///
+/// Updates the name, password or role of an account. Requires admin privileges.
+#[utoipa::path(
+    put,
+    path = "/admin/users/{id}",
+    tag = "users",
+    security(
+        ("jwt_auth" = [])
+    ),
+    params(
+        ("id" = String, Path, description = "User unique identifier")
+    ),
+    request_body = UpdateUserRequest,
+    responses(
+        (status = 200, description = "User updated successfully", body = UserResponse),
+        (status = 400, description = "Invalid user data"),
+        (status = 401, description = "Unauthorized - Invalid or missing authentication token"),
+        (status = 403, description = "Forbidden - Role does not grant this permission"),
+        (status = 404, description = "User not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[put("/admin/users/{id}")]
+pub async fn update_user(
+    path: web::Path<String>,
+    user_req: web::Json<UpdateUserRequest>,
+    db: web::Data<DbPool>,
+    user: AuthenticatedUser,
+) -> AppResult<impl Responder> {
+    user.require(Permission::UsersManage)?;
+    let user_req = validate_json(user_req)?;
+    if let Some(role) = &user_req.role {
+        validate_role(role)?;
+    }
+    let id = path.into_inner();
+    let repo = UserRepository::new(db.get_ref().clone());
+
+    let existing = repo.find_by_id(&id).await
+        .map_err(|e| {
+            error!("Failed to fetch user {}: {}", id, e);
+            AppError::internal_error(format!("Failed to fetch user: {}", e))
+        })?
+        .ok_or_else(|| AppError::not_found(format!("User with ID {} not found", id)))?;
+
+    let password_hash = match &user_req.password {
+        Some(password) => hash_password(password)?,
+        None => existing.password_hash,
+    };
+
+    let updated = UserAccount {
+        id: existing.id,
+        username: existing.username,
+        name: user_req.name.unwrap_or(existing.name),
+        password_hash,
+        role: user_req.role.unwrap_or(existing.role),
+    };
+
+    let result = repo.update(&id, updated).await
+        .map_err(|e| {
+            error!("Failed to update user {}: {}", id, e);
+            AppError::internal_error(format!("Failed to update user: {}", e))
+        })?;
+
+    if user_req.password.is_some() {
+        revoke_user_sessions(db.get_ref(), &id).await?;
+    }
+
+    info!("User {} updated account {}", user.0.name, result.username);
+    Ok(HttpResponse::Ok().json(UserResponse::from(result)))
+}
+
+/// Delete a user account
+///
+/// Deletes the account with the specified ID. Admins cannot delete their own account.
+#[utoipa::path(
+    delete,
+    path = "/admin/users/{id}",
+    tag = "users",
+    security(
+        ("jwt_auth" = [])
+    ),
+    params(
+        ("id" = String, Path, description = "User unique identifier")
+    ),
+    responses(
+        (status = 204, description = "User deleted successfully"),
+        (status = 400, description = "Cannot delete own account"),
+        (status = 401, description = "Unauthorized - Invalid or missing authentication token"),
+        (status = 403, description = "Forbidden - Role does not grant this permission"),
+        (status = 404, description = "User not found"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[delete("/admin/users/{id}")]
+pub async fn delete_user(
+    path: web::Path<String>,
+    db: web::Data<DbPool>,
+    user: AuthenticatedUser,
+) -> AppResult<impl Responder> {
+    user.require(Permission::UsersManage)?;
+    let id = path.into_inner();
+
+    if id == user.0.id {
+        return Err(AppError::bad_request("You cannot delete your own account"));
+    }
+
+    let repo = UserRepository::new(db.get_ref().clone());
+
+    let deleted = repo.delete(&id).await
+        .map_err(|e| {
+            error!("Failed to delete user {}: {}", id, e);
+            AppError::internal_error(format!("Failed to delete user: {}", e))
+        })?;
+
+    if !deleted {
+        return Err(AppError::not_found(format!("User with ID {} not found", id)));
+    }
+
+    revoke_user_sessions(db.get_ref(), &id).await?;
+
+    TotpRepository::new(db.get_ref().clone())
+        .delete(&id).await
+        .map_err(|e| {
+            error!("Failed to remove two-factor settings of user {}: {}", id, e);
+            AppError::internal_error(format!("Failed to remove two-factor settings: {}", e))
+        })?;
+
+    info!("User {} deleted account {}", user.0.name, id);
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Reset two-factor authentication of a user
+///
+/// Removes the second factor of an account, e.g. after the user lost both the
+/// authenticator and the recovery codes. Requires admin privileges.
+#[utoipa::path(
+    delete,
+    path = "/admin/users/{id}/totp",
+    tag = "users",
+    security(
+        ("jwt_auth" = [])
+    ),
+    params(
+        ("id" = String, Path, description = "User unique identifier")
+    ),
+    responses(
+        (status = 204, description = "Two-factor authentication reset"),
+        (status = 401, description = "Unauthorized - Invalid or missing authentication token"),
+        (status = 403, description = "Forbidden - Role does not grant this permission"),
+        (status = 404, description = "User has no two-factor authentication"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+#[delete("/admin/users/{id}/totp")]
+pub async fn reset_user_totp(
+    path: web::Path<String>,
+    db: web::Data<DbPool>,
+    user: AuthenticatedUser,
+) -> AppResult<impl Responder> {
+    user.require(Permission::UsersManage)?;
+    let id = path.into_inner();
+
+    let deleted = TotpRepository::new(db.get_ref().clone())
+        .delete(&id).await
+        .map_err(|e| {
+            error!("Failed to reset two-factor authentication of user {}: {}", id, e);
+            AppError::internal_error(format!("Failed to reset two-factor authentication: {}", e))
+        })?;
+
+    if !deleted {
+        return Err(AppError::not_found(format!("User with ID {} has no two-factor authentication", id)));
+    }
+
+    info!("User {} reset two-factor authentication of account {}", user.0.name, id);
+    Ok(HttpResponse::NoContent().finish())
+}
+
+pub fn config(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_all_users)
+       .service(create_user)
+       .service(update_user)
+       .service(delete_user)
+       .service(reset_user_totp);
+}
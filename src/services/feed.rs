@@ -0,0 +1,171 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+/// A feed of posts, written as RSS 2.0, Atom or JSON Feed 1.1
+#[derive(Debug, Clone)]
+pub struct Feed {
+    pub title: String,
+    pub description: String,
+    pub author: String,
+    /// Page of the site the feed is about
+    pub home_url: String,
+    /// URL of the feed itself
+    pub feed_url: String,
+    /// Latest change to any item (as UTC timestamp)
+    pub updated: i64,
+    pub items: Vec<FeedItem>,
+}
+
+#[derive(Debug, Clone)]
+pub struct FeedItem {
+    /// Stable identifier, unaffected by slug changes
+    pub id: String,
+    pub url: String,
+    pub title: String,
+    pub summary: String,
+    pub content_html: String,
+    pub tags: Vec<String>,
+    /// When the post was published (as UTC timestamp)
+    pub published: i64,
+    /// When the post was last changed (as UTC timestamp)
+    pub updated: i64,
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+fn datetime(timestamp: i64) -> DateTime<Utc> {
+    DateTime::from_timestamp(timestamp, 0).unwrap_or_default()
+}
+
+fn rfc3339(timestamp: i64) -> String {
+    datetime(timestamp).to_rfc3339_opts(chrono::SecondsFormat::Secs, true)
+}
+
+fn rfc2822(timestamp: i64) -> String {
+    datetime(timestamp).to_rfc2822()
+}
+
+#[derive(Serialize)]
+struct JsonFeed<'a> {
+    version: &'static str,
+    title: &'a str,
+    home_page_url: &'a str,
+    feed_url: &'a str,
+    description: &'a str,
+    authors: [JsonFeedAuthor<'a>; 1],
+    items: Vec<JsonFeedItem<'a>>,
+}
+
+#[derive(Serialize)]
+struct JsonFeedAuthor<'a> {
+    name: &'a str,
+}
+
+#[derive(Serialize)]
+struct JsonFeedItem<'a> {
+    id: &'a str,
+    url: &'a str,
+    title: &'a str,
+    summary: &'a str,
+    content_html: &'a str,
+    tags: &'a [String],
+    date_published: String,
+    date_modified: String,
+}
+
+impl Feed {
+    /// RSS 2.0, with the full content in content:encoded
+    pub fn to_rss(&self) -> String {
+        let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        xml.push_str("<rss version=\"2.0\" xmlns:atom=\"http://www.w3.org/2005/Atom\" xmlns:content=\"http://purl.org/rss/1.0/modules/content/\">\n");
+        xml.push_str("<channel>\n");
+        xml.push_str(&format!("<title>{}</title>\n", escape_xml(&self.title)));
+        xml.push_str(&format!("<link>{}</link>\n", escape_xml(&self.home_url)));
+        xml.push_str(&format!("<description>{}</description>\n", escape_xml(&self.description)));
+        xml.push_str(&format!("<atom:link href=\"{}\" rel=\"self\" type=\"application/rss+xml\"/>\n", escape_xml(&self.feed_url)));
+        xml.push_str(&format!("<lastBuildDate>{}</lastBuildDate>\n", rfc2822(self.updated)));
+
+        for item in &self.items {
+            xml.push_str("<item>\n");
+            xml.push_str(&format!("<title>{}</title>\n", escape_xml(&item.title)));
+            xml.push_str(&format!("<link>{}</link>\n", escape_xml(&item.url)));
+            xml.push_str(&format!("<guid isPermaLink=\"false\">{}</guid>\n", escape_xml(&item.id)));
+            xml.push_str(&format!("<pubDate>{}</pubDate>\n", rfc2822(item.published)));
+            xml.push_str(&format!("<description>{}</description>\n", escape_xml(&item.summary)));
+            xml.push_str(&format!("<content:encoded>{}</content:encoded>\n", escape_xml(&item.content_html)));
+            for tag in &item.tags {
+                xml.push_str(&format!("<category>{}</category>\n", escape_xml(tag)));
+            }
+            xml.push_str("</item>\n");
+        }
+
+        xml.push_str("</channel>\n</rss>\n");
+        xml
+    }
+
+    /// Atom (RFC 4287)
+    pub fn to_atom(&self) -> String {
+        let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        xml.push_str("<feed xmlns=\"http://www.w3.org/2005/Atom\">\n");
+        xml.push_str(&format!("<title>{}</title>\n", escape_xml(&self.title)));
+        xml.push_str(&format!("<subtitle>{}</subtitle>\n", escape_xml(&self.description)));
+        xml.push_str(&format!("<id>{}</id>\n", escape_xml(&self.feed_url)));
+        xml.push_str(&format!("<link rel=\"self\" type=\"application/atom+xml\" href=\"{}\"/>\n", escape_xml(&self.feed_url)));
+        xml.push_str(&format!("<link rel=\"alternate\" type=\"text/html\" href=\"{}\"/>\n", escape_xml(&self.home_url)));
+        xml.push_str(&format!("<updated>{}</updated>\n", rfc3339(self.updated)));
+        xml.push_str(&format!("<author><name>{}</name></author>\n", escape_xml(&self.author)));
+
+        for item in &self.items {
+            xml.push_str("<entry>\n");
+            xml.push_str(&format!("<title>{}</title>\n", escape_xml(&item.title)));
+            xml.push_str(&format!("<id>{}</id>\n", escape_xml(&item.id)));
+            xml.push_str(&format!("<link rel=\"alternate\" type=\"text/html\" href=\"{}\"/>\n", escape_xml(&item.url)));
+            xml.push_str(&format!("<published>{}</published>\n", rfc3339(item.published)));
+            xml.push_str(&format!("<updated>{}</updated>\n", rfc3339(item.updated)));
+            xml.push_str(&format!("<summary>{}</summary>\n", escape_xml(&item.summary)));
+            xml.push_str(&format!("<content type=\"html\">{}</content>\n", escape_xml(&item.content_html)));
+            for tag in &item.tags {
+                xml.push_str(&format!("<category term=\"{}\"/>\n", escape_xml(tag)));
+            }
+            xml.push_str("</entry>\n");
+        }
+
+        xml.push_str("</feed>\n");
+        xml
+    }
+
+    /// JSON Feed 1.1
+    pub fn to_json(&self) -> String {
+        let feed = JsonFeed {
+            version: "https://jsonfeed.org/version/1.1",
+            title: &self.title,
+            home_page_url: &self.home_url,
+            feed_url: &self.feed_url,
+            description: &self.description,
+            authors: [JsonFeedAuthor { name: &self.author }],
+            items: self
+                .items
+                .iter()
+                .map(|item| JsonFeedItem {
+                    id: &item.id,
+                    url: &item.url,
+                    title: &item.title,
+                    summary: &item.summary,
+                    content_html: &item.content_html,
+                    tags: &item.tags,
+                    date_published: rfc3339(item.published),
+                    date_modified: rfc3339(item.updated),
+                })
+                .collect(),
+        };
+
+        // Serializing plain strings cannot fail
+        serde_json::to_string_pretty(&feed).unwrap_or_default()
+    }
+}
@@ -0,0 +1,60 @@
+use std::env;
+use std::time::Duration;
+
+use crate::auth::throttle::ThrottleConfig;
+use crate::config::database::DbPool;
+use crate::models::login_attempt_repository::LoginAttemptRepository;
+
+// Seconds between two prunes of the login records
+const PRUNE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+// Days login attempts stay in the audit log; None keeps them
+fn retention_days_from_env() -> Option<i64> {
+    let days = env::var("LOGIN_AUDIT_RETENTION_DAYS")
+        .ok()
+        .and_then(|value| value.parse::<i64>().ok())
+        .filter(|value| *value >= 0)
+        .unwrap_or(90);
+
+    Some(days).filter(|days| *days > 0)
+}
+
+/// Delete the login attempts made more than `retention_days` ago, if given,
+/// and the failure counters that no longer count or lock anything, such as
+/// those of mistyped or guessed usernames. Returns how many attempts were
+/// deleted.
+pub async fn prune_login_records(pool: &DbPool, retention_days: Option<i64>, now: i64) -> anyhow::Result<u64> {
+    let forget_before = now - ThrottleConfig::from_env().max_lockout_seconds;
+    let attempted_before = retention_days.map(|days| now - days * 24 * 60 * 60);
+
+    let deleted = LoginAttemptRepository::new(pool.clone())
+        .prune(attempted_before, forget_before, now)
+        .await?;
+
+    if deleted > 0 {
+        log::info!("Deleted {} login attempt(s) from the audit log", deleted);
+    }
+
+    Ok(deleted)
+}
+
+/// Background task pruning the login audit log past LOGIN_AUDIT_RETENTION_DAYS
+/// (90 by default; 0 keeps it) and stale failure counters every hour. Errors
+/// are logged and retried on the next tick.
+pub async fn run(pool: DbPool) {
+    let retention_days = retention_days_from_env();
+    if retention_days.is_none() {
+        log::info!("Login audit retention disabled; attempts are kept");
+    }
+
+    let mut interval = tokio::time::interval(PRUNE_INTERVAL);
+
+    loop {
+        interval.tick().await;
+
+        let now = chrono::Utc::now().timestamp();
+        if let Err(e) = prune_login_records(&pool, retention_days, now).await {
+            log::error!("Failed to prune login records: {}", e);
+        }
+    }
+}
@@ -0,0 +1,220 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+
+use once_cell::sync::Lazy;
+use pulldown_cmark::{html, CodeBlockKind, CowStr, Event, Options, Parser, Tag, TagEnd};
+use sha2::{Digest, Sha256};
+use syntect::html::{ClassStyle, ClassedHTMLGenerator};
+use syntect::parsing::SyntaxSet;
+use syntect::util::LinesWithEndings;
+
+use crate::models::post::TocEntry;
+use crate::models::slug::slugify;
+
+// Reading speed used for reading time estimates
+pub const WORDS_PER_MINUTE: usize = 200;
+
+// Prefix of the syntax highlighting classes, e.g. "hl-keyword"
+pub const HIGHLIGHT_CLASS_PREFIX: &str = "hl-";
+
+// Rendered posts kept in memory, keyed by a hash of their Markdown so that
+// an edited post is rendered again. The cache is emptied when full.
+static RENDER_CACHE: Lazy<Mutex<HashMap<String, Arc<RenderedMarkdown>>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+const MAX_CACHED_RENDERS: usize = 256;
+
+static SYNTAXES: Lazy<SyntaxSet> = Lazy::new(SyntaxSet::load_defaults_newlines);
+
+/// Markdown rendered to sanitised HTML, with what clients need to present it
+#[derive(Debug, Clone, PartialEq)]
+pub struct RenderedMarkdown {
+    pub html: String,
+    pub toc: Vec<TocEntry>,
+    pub word_count: usize,
+    /// Minutes needed to read the text, rounded up
+    pub reading_time_minutes: usize,
+}
+
+fn options() -> Options {
+    Options::ENABLE_TABLES
+        | Options::ENABLE_FOOTNOTES
+        | Options::ENABLE_STRIKETHROUGH
+        | Options::ENABLE_TASKLISTS
+        | Options::ENABLE_HEADING_ATTRIBUTES
+        | Options::ENABLE_GFM
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+// A fenced code block, highlighted with classes when its language is known
+fn highlight_code(code: &str, language: &str) -> String {
+    let language = language
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .next()
+        .unwrap_or_default();
+
+    let Some(syntax) = SYNTAXES.find_syntax_by_token(language).filter(|_| !language.is_empty()) else {
+        return match language {
+            "" => format!("<pre><code>{}</code></pre>\n", escape_html(code)),
+            _ => format!("<pre><code class=\"language-{}\">{}</code></pre>\n", escape_html(language), escape_html(code)),
+        };
+    };
+
+    let mut generator = ClassedHTMLGenerator::new_with_class_style(
+        syntax,
+        &SYNTAXES,
+        ClassStyle::SpacedPrefixed { prefix: HIGHLIGHT_CLASS_PREFIX },
+    );
+    for line in LinesWithEndings::from(code) {
+        if generator.parse_html_for_line_which_includes_newline(line).is_err() {
+            return format!("<pre><code class=\"language-{}\">{}</code></pre>\n", escape_html(language), escape_html(code));
+        }
+    }
+
+    format!("<pre><code class=\"language-{}\">{}</code></pre>\n", escape_html(language), generator.finalize())
+}
+
+// Keeps the formatting Markdown produces and drops everything else, such as
+// scripts, event handlers and javascript: links from raw HTML in the post
+fn sanitize(html: &str) -> String {
+    let mut builder = ammonia::Builder::default();
+    builder
+        .add_tags(["input"])
+        .add_tag_attributes("input", ["type", "checked", "disabled"])
+        .add_tag_attributes("div", ["id"])
+        .add_tag_attributes("th", ["style"])
+        .add_tag_attributes("td", ["style"])
+        .attribute_filter(|element, attribute, value| match (element, attribute) {
+            // Only the checkboxes of task lists, and the column alignment of tables
+            ("input", "type") if value == "checkbox" => Some(value.into()),
+            ("input", "type") => None,
+            (_, "style") if ["text-align: left", "text-align: center", "text-align: right"].contains(&value) => Some(value.into()),
+            (_, "style") => None,
+            _ => Some(value.into()),
+        });
+
+    for heading in ["h1", "h2", "h3", "h4", "h5", "h6"] {
+        builder.add_tag_attributes(heading, ["id"]);
+    }
+    for tag in ["pre", "code", "span", "div", "sup", "blockquote"] {
+        builder.add_tag_attributes(tag, ["class"]);
+    }
+
+    builder.clean(html).to_string()
+}
+
+/// Render Markdown (CommonMark with the GitHub extensions: tables, task
+/// lists, strikethrough, footnotes and alerts) to sanitised HTML. Headings
+/// get unique ids for the table of contents, taken from `{#id}` attributes or
+/// from their text.
+pub fn render_markdown(markdown: &str) -> RenderedMarkdown {
+    let mut events: Vec<Event> = Parser::new_ext(markdown, options()).collect();
+    let mut toc = Vec::new();
+    let mut ids = HashSet::new();
+    let mut text = String::new();
+
+    // Headings: the text is only known once all their events are read
+    let mut i = 0;
+    while i < events.len() {
+        if let Event::Start(Tag::Heading { level, id, .. }) = &events[i] {
+            let level = *level as u8;
+            let explicit = id.as_ref().map(|id| id.to_string());
+
+            let mut heading = String::new();
+            let mut end = i + 1;
+            while !matches!(events[end], Event::End(TagEnd::Heading(_))) {
+                if let Event::Text(t) | Event::Code(t) = &events[end] {
+                    heading.push_str(t);
+                }
+                end += 1;
+            }
+
+            let base = explicit.unwrap_or_else(|| match slugify(&heading) {
+                slug if slug.is_empty() => "section".to_string(),
+                slug => slug,
+            });
+            let mut id = base.clone();
+            let mut n = 1;
+            while !ids.insert(id.clone()) {
+                n += 1;
+                id = format!("{}-{}", base, n);
+            }
+
+            if let Event::Start(Tag::Heading { id: heading_id, .. }) = &mut events[i] {
+                *heading_id = Some(CowStr::from(id.clone()));
+            }
+            toc.push(TocEntry { level, id, text: heading.trim().to_string() });
+            i = end;
+        }
+        i += 1;
+    }
+
+    // Code blocks are highlighted; their content does not count as words
+    let mut output = Vec::with_capacity(events.len());
+    let mut code: Option<(String, String)> = None;
+    for event in events {
+        match (event, &mut code) {
+            (Event::Start(Tag::CodeBlock(kind)), _) => {
+                let language = match kind {
+                    CodeBlockKind::Fenced(language) => language.to_string(),
+                    CodeBlockKind::Indented => String::new(),
+                };
+                code = Some((language, String::new()));
+            }
+            (Event::End(TagEnd::CodeBlock), Some((language, block))) => {
+                output.push(Event::Html(highlight_code(block, language).into()));
+                code = None;
+            }
+            (Event::Text(t), Some((_, block))) => block.push_str(&t),
+            (event, _) => {
+                match &event {
+                    Event::Text(t) | Event::Code(t) => text.push_str(t),
+                    Event::SoftBreak
+                    | Event::HardBreak
+                    | Event::End(TagEnd::Paragraph | TagEnd::Heading(_) | TagEnd::Item | TagEnd::TableCell) => text.push(' '),
+                    _ => {}
+                }
+                output.push(event);
+            }
+        }
+    }
+
+    // Words are runs of text with at least one letter or digit
+    let word_count = text
+        .split_whitespace()
+        .filter(|word| word.chars().any(char::is_alphanumeric))
+        .count();
+
+    let mut unsafe_html = String::new();
+    html::push_html(&mut unsafe_html, output.into_iter());
+
+    RenderedMarkdown {
+        html: sanitize(&unsafe_html),
+        toc,
+        word_count,
+        reading_time_minutes: word_count.div_ceil(WORDS_PER_MINUTE),
+    }
+}
+
+/// Rendered Markdown, from the cache when the same text was rendered before
+pub fn render_cached(markdown: &str) -> Arc<RenderedMarkdown> {
+    let key = format!("{:x}", Sha256::digest(markdown.as_bytes()));
+
+    if let Some(rendered) = RENDER_CACHE.lock().unwrap().get(&key) {
+        return rendered.clone();
+    }
+
+    let rendered = Arc::new(render_markdown(markdown));
+
+    let mut cache = RENDER_CACHE.lock().unwrap();
+    if cache.len() >= MAX_CACHED_RENDERS {
+        cache.clear();
+    }
+    cache.insert(key, rendered.clone());
+
+    rendered
+}
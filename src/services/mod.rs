@@ -1 +1,7 @@
+pub mod feed;
 pub mod github_service;
+pub mod login_pruner;
+pub mod markdown;
+pub mod post_scheduler;
+pub mod trash_purger;
+pub mod yaml;
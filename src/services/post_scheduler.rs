@@ -0,0 +1,45 @@
+use std::env;
+use std::time::Duration;
+
+use crate::config::database::DbPool;
+use crate::models::post_repository::PostRepository;
+use crate::models::repository::Repository;
+
+// Seconds between two checks for scheduled posts that came due
+fn interval_from_env() -> Duration {
+    let seconds = env::var("POST_SCHEDULER_INTERVAL_SECONDS")
+        .ok()
+        .and_then(|value| value.parse::<u64>().ok())
+        .filter(|value| *value > 0)
+        .unwrap_or(60);
+
+    Duration::from_secs(seconds)
+}
+
+/// Publish the scheduled posts that are due, returning how many were published
+pub async fn publish_due_posts(pool: &DbPool) -> anyhow::Result<u64> {
+    let published = PostRepository::new(pool.clone())
+        .publish_due(chrono::Utc::now().timestamp())
+        .await?;
+
+    if published > 0 {
+        log::info!("Published {} scheduled post(s)", published);
+    }
+
+    Ok(published)
+}
+
+/// Background task publishing scheduled posts every
+/// POST_SCHEDULER_INTERVAL_SECONDS (60 by default). Errors are logged and
+/// retried on the next tick.
+pub async fn run(pool: DbPool) {
+    let mut interval = tokio::time::interval(interval_from_env());
+
+    loop {
+        interval.tick().await;
+
+        if let Err(e) = publish_due_posts(&pool).await {
+            log::error!("Failed to publish scheduled posts: {}", e);
+        }
+    }
+}
@@ -0,0 +1,54 @@
+use std::env;
+use std::time::Duration;
+
+use crate::config::database::DbPool;
+use crate::models::trash_repository::TrashRepository;
+
+// Seconds between two purges of old trash
+const PURGE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+// Days deleted items stay in the trash; None keeps them until purged by hand
+fn retention_days_from_env() -> Option<i64> {
+    let days = env::var("TRASH_RETENTION_DAYS")
+        .ok()
+        .and_then(|value| value.parse::<i64>().ok())
+        .filter(|value| *value >= 0)
+        .unwrap_or(30);
+
+    Some(days).filter(|days| *days > 0)
+}
+
+/// Purge the items deleted more than `retention_days` ago, returning how many
+/// were purged
+pub async fn purge_expired_trash(pool: &DbPool, retention_days: i64) -> anyhow::Result<u64> {
+    let before = chrono::Utc::now().timestamp() - retention_days * 24 * 60 * 60;
+    let purged = TrashRepository::new(pool.clone())
+        .purge_deleted_before(before)
+        .await?;
+
+    if purged > 0 {
+        log::info!("Purged {} item(s) from the trash", purged);
+    }
+
+    Ok(purged)
+}
+
+/// Background task purging items kept in the trash longer than
+/// TRASH_RETENTION_DAYS (30 by default; 0 keeps them) every hour. Errors are
+/// logged and retried on the next tick.
+pub async fn run(pool: DbPool) {
+    let Some(retention_days) = retention_days_from_env() else {
+        log::info!("Trash retention disabled; deleted items are kept until purged");
+        return;
+    };
+
+    let mut interval = tokio::time::interval(PURGE_INTERVAL);
+
+    loop {
+        interval.tick().await;
+
+        if let Err(e) = purge_expired_trash(&pool, retention_days).await {
+            log::error!("Failed to purge the trash: {}", e);
+        }
+    }
+}
@@ -0,0 +1,98 @@
+use anyhow::{bail, Context, Result};
+use serde_json::{Map, Number, Value};
+use yaml_rust2::{parser::Parser, yaml::Hash, Event, Yaml, YamlEmitter, YamlLoader};
+
+// YAML as another spelling of JSON: documents are converted to and from
+// serde_json values, so that the models only need their serde derives.
+// Strings that would read back as another type are quoted by the emitter.
+// Anchors and aliases have no JSON equivalent, and the loader would copy the
+// node for every alias, so a small document could expand without bound: they
+// are refused before the document is loaded.
+
+fn to_node(value: &Value) -> Yaml {
+    match value {
+        Value::Null => Yaml::Null,
+        Value::Bool(b) => Yaml::Boolean(*b),
+        Value::Number(n) => match n.as_i64() {
+            Some(i) => Yaml::Integer(i),
+            None => Yaml::Real(n.to_string()),
+        },
+        Value::String(s) => Yaml::String(s.clone()),
+        Value::Array(items) => Yaml::Array(items.iter().map(to_node).collect()),
+        Value::Object(members) => {
+            let mut hash = Hash::new();
+            for (key, value) in members {
+                hash.insert(Yaml::String(key.clone()), to_node(value));
+            }
+            Yaml::Hash(hash)
+        }
+    }
+}
+
+fn from_node(node: Yaml) -> Result<Value> {
+    Ok(match node {
+        Yaml::Null => Value::Null,
+        Yaml::Boolean(b) => Value::Bool(b),
+        Yaml::Integer(i) => Value::from(i),
+        Yaml::Real(real) => {
+            let number = real.parse::<f64>().ok().and_then(Number::from_f64);
+            Value::Number(number.with_context(|| format!("Invalid number '{}'", real))?)
+        }
+        Yaml::String(s) => Value::String(s),
+        Yaml::Array(items) => Value::Array(items.into_iter().map(from_node).collect::<Result<_>>()?),
+        Yaml::Hash(hash) => {
+            let mut members = Map::new();
+            for (key, value) in hash {
+                let key = match key {
+                    Yaml::String(s) | Yaml::Real(s) => s,
+                    Yaml::Integer(i) => i.to_string(),
+                    Yaml::Boolean(b) => b.to_string(),
+                    _ => bail!("Mapping keys must be strings"),
+                };
+                members.insert(key, from_node(value)?);
+            }
+            Value::Object(members)
+        }
+        Yaml::BadValue => bail!("Invalid YAML value"),
+        Yaml::Alias(_) => unreachable!("the loader resolves aliases, which are refused before loading"),
+    })
+}
+
+/// Write a value as a YAML document
+pub fn to_yaml(value: &Value) -> Result<String> {
+    let mut yaml = String::new();
+    YamlEmitter::new(&mut yaml)
+        .dump(&to_node(value))
+        .map_err(|e| anyhow::anyhow!("Failed to write YAML: {:?}", e))?;
+    yaml.push('\n');
+    Ok(yaml)
+}
+
+// Fail on the first anchor or alias in a text
+fn refuse_aliases(text: &str) -> Result<()> {
+    let mut parser = Parser::new_from_str(text);
+    loop {
+        let (event, mark) = parser.next_token()?;
+        let anchored = match event {
+            Event::StreamEnd => return Ok(()),
+            Event::Alias(_) => true,
+            Event::Scalar(_, _, anchor, _) | Event::SequenceStart(anchor, _) | Event::MappingStart(anchor, _) => anchor > 0,
+            _ => false,
+        };
+        if anchored {
+            bail!("YAML anchors and aliases are not supported (line {})", mark.line());
+        }
+    }
+}
+
+/// Read a YAML document as a value; an empty document is null. Anchors and
+/// aliases are refused.
+pub fn from_yaml(text: &str) -> Result<Value> {
+    refuse_aliases(text)?;
+    let mut documents = YamlLoader::load_from_str(text)?;
+    if documents.len() > 1 {
+        bail!("Expected a single YAML document, found {}", documents.len());
+    }
+
+    documents.pop().map_or(Ok(Value::Null), from_node)
+}
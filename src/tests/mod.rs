@@ -1,60 +1,2465 @@
-#[cfg(test)]
-mod tests {
-    use actix_web::{http::StatusCode, test, web, App};
-    use sqlx::sqlite::SqlitePoolOptions;
-    
-    use crate::routes;
-    
-    async fn setup_test_db() -> sqlx::Pool<sqlx::Sqlite> {
-        // Create in-memory database for testing
-        let db_pool = SqlitePoolOptions::new()
-            .max_connections(5)
-            .connect("sqlite::memory:")
-            .await
-            .expect("Failed to create test database");
-        
-        // Run migrations
-        sqlx::query("CREATE TABLE IF NOT EXISTS projects (id TEXT PRIMARY KEY, title TEXT NOT NULL)")
-            .execute(&db_pool)
-            .await
-            .expect("Failed to create test table");
-        
-        db_pool
-    }
-    
-    #[actix_web::test]
-    async fn test_health_endpoint() {
-        // Setup
-        let db_pool = setup_test_db().await;
-        let app = test::init_service(
-            App::new()
-                .app_data(web::Data::new(db_pool.clone()))
-                .configure(routes::health::config)
-        ).await;
-        
-        // Act
-        let req = test::TestRequest::get().uri("/health").to_request();
-        let resp = test::call_service(&app, req).await;
-        
-        // Assert
-        assert_eq!(resp.status(), StatusCode::OK);
+use actix_web::{http::StatusCode, test, web, App};
+
+use crate::config::database::{connect_url, DbBackend, DbPool};
+use crate::config::migrations::{apply_migrations, embedded_migrations, rollback_migrations, run_migrations, Migration};
+use crate::routes;
+
+// Every connection to sqlite::memory: opens its own empty database
+async fn empty_test_db() -> DbPool {
+    connect_url("sqlite::memory:", 1)
+        .await
+        .expect("Failed to create test database")
+}
+
+async fn setup_test_db() -> DbPool {
+    // Create in-memory database for testing
+    let db_pool = empty_test_db().await;
+    
+    // Run migrations
+    run_migrations(&db_pool)
+        .await
+        .expect("Failed to run migrations");
+    
+    db_pool
+}
+
+#[actix_web::test]
+async fn test_health_endpoint() {
+    // Setup
+    let db_pool = setup_test_db().await;
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(db_pool.clone()))
+            .configure(routes::health::config)
+    ).await;
+    
+    // Act
+    let req = test::TestRequest::get().uri("/health").to_request();
+    let resp = test::call_service(&app, req).await;
+    
+    // Assert
+    assert_eq!(resp.status(), StatusCode::OK);
+}
+
+#[actix_web::test]
+async fn test_get_projects() {
+    // Setup
+    let db_pool = setup_test_db().await;
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(db_pool.clone()))
+            .configure(routes::projects::config)
+    ).await;
+    
+    // Act
+    let req = test::TestRequest::get().uri("/projects").to_request();
+    let resp = test::call_service(&app, req).await;
+    
+    // Assert
+    assert_eq!(resp.status(), StatusCode::OK);
+}
+
+#[actix_web::test]
+async fn test_list_query_validation() {
+    use crate::models::project_repository::ProjectRepository;
+    use actix_web::ResponseError;
+    use crate::models::repository::{ListQuery, Repository, SortDirection};
+
+    let spec = &ProjectRepository::LIST;
+    let query = ListQuery::new(spec, Some(3), Some(10), Some("-title")).unwrap();
+    assert_eq!(query.offset(), 20);
+    assert_eq!(query.sort, Some(("title", SortDirection::Desc)));
+
+    for (page, per_page, sort) in [(Some(0), None, None), (None, Some(0), None), (None, Some(101), None), (None, None, Some("secret"))] {
+        let err = ListQuery::new(spec, page, per_page, sort).unwrap_err();
+        assert_eq!(err.status_code(), StatusCode::BAD_REQUEST);
     }
+
+    let query = ListQuery::new(spec, None, None, None).unwrap();
+    assert!(query.clone().filter("year", Some("twenty".to_string())).is_err());
+    assert!(query.clone().filter("owner", Some("me".to_string())).is_err());
+    assert!(query.filter("year", None).is_ok());
+}
+
+#[actix_web::test]
+async fn test_list_projects_paginated() {
+    use crate::models::project::Project;
+    use crate::models::project_repository::ProjectRepository;
+    use crate::models::repository::Repository;
+
+    let db_pool = setup_test_db().await;
+    let projects = ProjectRepository::new(db_pool.clone());
+    for (title, technologies, year) in [("Alpha", vec!["Rust"], 2023), ("Beta", vec!["Rust", "SQL"], 2024), ("Gamma", vec!["Go"], 2025), ("Delta", vec!["rust_lang"], 2025)] {
+        let technologies = technologies.into_iter().map(str::to_string).collect();
+        let project = Project::new(title.to_string(), String::new(), technologies, None, None, None, None, year, vec![]);
+        projects.create(project).await.unwrap();
+    }
+
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(db_pool.clone()))
+            .configure(routes::projects::config)
+    ).await;
+
+    // Matches whole technology names, case-insensitively
+    let req = test::TestRequest::get().uri("/projects?technology=rust&sort=-title&per_page=1").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(resp.headers().get("x-total-count").unwrap(), "2");
+    assert_eq!(
+        resp.headers().get("link").unwrap(),
+        "</projects?technology=rust&sort=-title&per_page=1&page=1>; rel=\"first\", \
+         </projects?technology=rust&sort=-title&per_page=1&page=2>; rel=\"next\", \
+         </projects?technology=rust&sort=-title&per_page=1&page=2>; rel=\"last\""
+    );
+    let body: Vec<Project> = test::read_body_json(resp).await;
+    assert_eq!(body.iter().map(|p| p.title.as_str()).collect::<Vec<_>>(), ["Beta"]);
+
+    // Defaults to the newest first
+    let req = test::TestRequest::get().uri("/projects?year=2025&page=1").to_request();
+    let body: Vec<Project> = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body.len(), 2);
+
+    let req = test::TestRequest::get().uri("/projects?sort=year").to_request();
+    let body: Vec<Project> = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body[0].title, "Alpha");
+
+    let req = test::TestRequest::get().uri("/projects?per_page=500").to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), StatusCode::BAD_REQUEST);
+}
+
+#[actix_web::test]
+async fn test_migrations_apply_once() {
+    let db_pool = empty_test_db().await;
+    let migrations = vec![
+        Migration::new("0001_create_a", "CREATE TABLE a (id INTEGER)".to_string()),
+        Migration::new("0002_create_b", "CREATE TABLE b (id INTEGER)".to_string()),
+    ];
     
-    #[actix_web::test]
-    async fn test_get_projects() {
-        // Setup
-        let db_pool = setup_test_db().await;
-        let app = test::init_service(
-            App::new()
-                .app_data(web::Data::new(db_pool.clone()))
-                .configure(routes::projects::config)
-        ).await;
-        
-        // Act
-        let req = test::TestRequest::get().uri("/projects").to_request();
-        let resp = test::call_service(&app, req).await;
-        
-        // Assert
+    let applied = apply_migrations(&db_pool, &migrations).await.unwrap();
+    assert_eq!(applied, vec!["0001_create_a", "0002_create_b"]);
+    
+    // Without IF NOT EXISTS a second run would fail if anything was re-executed
+    let applied = apply_migrations(&db_pool, &migrations).await.unwrap();
+    assert!(applied.is_empty());
+}
+
+#[actix_web::test]
+async fn test_migration_runs_every_statement() {
+    let db_pool = empty_test_db().await;
+    let migrations = vec![Migration::new(
+        "0001_create_and_alter",
+        "CREATE TABLE a (id INTEGER);\nALTER TABLE a ADD COLUMN name TEXT;\nINSERT INTO a (id, name) VALUES (1, 'x');".to_string(),
+    )];
+    
+    apply_migrations(&db_pool, &migrations).await.unwrap();
+    
+    let (name,): (String,) = sqlx::query_as("SELECT name FROM a WHERE id = 1")
+        .fetch_one(&db_pool)
+        .await
+        .unwrap();
+    assert_eq!(name, "x");
+}
+
+#[actix_web::test]
+async fn test_failed_migration_is_rolled_back() {
+    let db_pool = empty_test_db().await;
+    let migrations = vec![Migration::new(
+        "0001_broken",
+        "CREATE TABLE a (id INTEGER); INSERT INTO missing VALUES (1);".to_string(),
+    )];
+    
+    assert!(apply_migrations(&db_pool, &migrations).await.is_err());
+    
+    let tables: Vec<(String,)> = sqlx::query_as("SELECT name FROM sqlite_master WHERE name = 'a'")
+        .fetch_all(&db_pool)
+        .await
+        .unwrap();
+    assert!(tables.is_empty());
+}
+
+#[actix_web::test]
+async fn test_modified_migration_is_detected() {
+    let db_pool = empty_test_db().await;
+    
+    apply_migrations(&db_pool, &[Migration::new("0001_create_a", "CREATE TABLE a (id INTEGER)".to_string())])
+        .await
+        .unwrap();
+    
+    let modified = [Migration::new("0001_create_a", "CREATE TABLE a (id TEXT)".to_string())];
+    let error = apply_migrations(&db_pool, &modified).await.unwrap_err();
+    assert!(error.to_string().contains("was modified after it was applied"));
+}
+
+async fn table_exists(db_pool: &DbPool, table: &str) -> bool {
+    sqlx::query_as::<_, (String,)>("SELECT name FROM sqlite_master WHERE type = 'table' AND name = $1")
+        .bind(table)
+        .fetch_optional(db_pool)
+        .await
+        .unwrap()
+        .is_some()
+}
+
+#[actix_web::test]
+async fn test_rollback_to_version() {
+    let db_pool = empty_test_db().await;
+    let migrations = vec![
+        Migration::new("0001_create_a", "CREATE TABLE a (id INTEGER)".to_string())
+            .with_down("DROP TABLE a".to_string()),
+        Migration::new("0002_create_b", "CREATE TABLE b (id INTEGER)".to_string())
+            .with_down("DROP TABLE b".to_string()),
+        Migration::new("0003_create_c", "CREATE TABLE c (id INTEGER)".to_string())
+            .with_down("DROP TABLE c".to_string()),
+    ];
+    apply_migrations(&db_pool, &migrations).await.unwrap();
+    
+    let reverted = rollback_migrations(&db_pool, &migrations, "0001_create_a").await.unwrap();
+    assert_eq!(reverted, vec!["0003_create_c", "0002_create_b"]);
+    assert!(table_exists(&db_pool, "a").await);
+    assert!(!table_exists(&db_pool, "b").await);
+    
+    // Reverted migrations are pending again
+    let applied = apply_migrations(&db_pool, &migrations).await.unwrap();
+    assert_eq!(applied, vec!["0002_create_b", "0003_create_c"]);
+}
+
+#[actix_web::test]
+async fn test_rollback_without_down_migration_changes_nothing() {
+    let db_pool = empty_test_db().await;
+    let migrations = vec![
+        Migration::new("0001_create_a", "CREATE TABLE a (id INTEGER)".to_string()),
+        Migration::new("0002_create_b", "CREATE TABLE b (id INTEGER)".to_string())
+            .with_down("DROP TABLE b".to_string()),
+    ];
+    apply_migrations(&db_pool, &migrations).await.unwrap();
+    
+    assert!(rollback_migrations(&db_pool, &migrations, "0").await.is_err());
+    assert!(table_exists(&db_pool, "b").await);
+}
+
+#[actix_web::test]
+async fn test_embedded_migrations_roll_back_cleanly() {
+    let db_pool = setup_test_db().await;
+    let migrations = embedded_migrations(DbBackend::Sqlite);
+    
+    rollback_migrations(&db_pool, &migrations, "0").await.unwrap();
+    assert!(!table_exists(&db_pool, "projects").await);
+    
+    run_migrations(&db_pool).await.unwrap();
+    assert!(table_exists(&db_pool, "projects").await);
+}
+
+#[actix_web::test]
+async fn test_backends_have_the_same_migrations() {
+    let versions = |backend| {
+        embedded_migrations(backend)
+            .into_iter()
+            .map(|m| (m.version, m.down_sql.is_some()))
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(versions(DbBackend::Sqlite), versions(DbBackend::Postgres));
+}
+
+#[actix_web::test]
+async fn test_backend_from_url() {
+    assert_eq!(DbBackend::from_url("sqlite:./data.db").unwrap(), DbBackend::Sqlite);
+    assert_eq!(DbBackend::from_url("postgres://localhost/quewui").unwrap(), DbBackend::Postgres);
+    assert_eq!(DbBackend::from_url("postgresql://localhost/quewui").unwrap(), DbBackend::Postgres);
+    assert!(DbBackend::from_url("mysql://localhost/quewui").is_err());
+}
+
+// Runs only when TEST_POSTGRES_URL points at a scratch database, which is
+// emptied first
+#[actix_web::test]
+async fn test_postgres_migrations_and_repositories() {
+    use crate::models::login_attempt::LoginAttempt;
+    use crate::models::login_attempt_repository::LoginAttemptRepository;
+    use crate::models::project::Project;
+    use crate::models::project_repository::ProjectRepository;
+    use actix_web::ResponseError;
+    use crate::models::repository::{ListQuery, Repository};
+    use crate::models::slug_repository::{slug_write_error, SlugRepository};
+
+    let Ok(url) = std::env::var("TEST_POSTGRES_URL") else {
+        return;
+    };
+
+    let db_pool = connect_url(&url, 2).await.unwrap();
+    assert_eq!(DbBackend::of(&db_pool), DbBackend::Postgres);
+
+    let migrations = embedded_migrations(DbBackend::Postgres);
+    run_migrations(&db_pool).await.unwrap();
+    rollback_migrations(&db_pool, &migrations, "0").await.unwrap();
+    run_migrations(&db_pool).await.unwrap();
+
+    // Optional values are bound as NULL and as values through the same statements
+    let projects = ProjectRepository::new(db_pool.clone());
+    let bare = Project::new("Bare".to_string(), "No links".to_string(), vec![], None, None, None, None, 2024, vec![]);
+    let linked = Project::new(
+        "Linked".to_string(),
+        "With links".to_string(),
+        vec!["Rust".to_string()],
+        Some("https://github.com/example/linked".to_string()),
+        None,
+        Some("https://example.com/linked.png".to_string()),
+        Some(vec!["https://example.com/1.png".to_string()]),
+        2025,
+        vec![],
+    );
+    projects.create(bare.clone()).await.unwrap();
+    projects.create(linked.clone()).await.unwrap();
+
+    let found = projects.find_by_id(&linked.id).await.unwrap().unwrap();
+    assert_eq!(found.github_url, linked.github_url);
+    assert_eq!(found.image_urls, linked.image_urls);
+    assert_eq!(projects.find_by_id(&bare.id).await.unwrap().unwrap().github_url, None);
+
+    // Timestamps are read as unix seconds
+    let posts = crate::models::post_repository::PostRepository::new(db_pool.clone());
+    let post = posts.create(crate::models::post::Post::new("Timed".to_string(), "2025-01-01".to_string(), vec![], String::new(), String::new())).await.unwrap();
+    let updated_at = posts.find_updated_at().await.unwrap()[&post.id];
+    assert!((updated_at - chrono::Utc::now().timestamp()).abs() < 60);
+
+    // Slugs are backfilled, and aliases resolve to their row
+    let slugs = SlugRepository::new(db_pool.clone());
+    assert!(slugs.backfill().await.unwrap() >= 2);
+    assert_eq!(projects.find_by_slug("linked").await.unwrap().unwrap().id, linked.id);
+    SlugRepository::write_change(&mut db_pool.acquire().await.unwrap(), "projects", &linked.id, "linked-old", "linked").await.unwrap();
+    assert_eq!(slugs.find_alias("projects", "linked-old").await.unwrap(), Some(linked.id.clone()));
+    assert!(slugs.is_taken("projects", "linked-old", &bare.id).await.unwrap());
+
+    // A slug taken after it was checked fails the write with a conflict
+    let mut taken = projects.find_by_id(&bare.id).await.unwrap().unwrap();
+    taken.slug = "linked".to_string();
+    let err = ProjectRepository::write(&mut db_pool.acquire().await.unwrap(), &bare.id, taken, None).await.unwrap_err();
+    assert_eq!(slug_write_error(err, "Failed to update project").status_code(), StatusCode::CONFLICT);
+
+    let attempts = LoginAttemptRepository::new(db_pool.clone());
+    attempts.record(LoginAttempt::new("admin".to_string(), None, None, "invalid_credentials".to_string(), 100)).await.unwrap();
+    attempts.record(LoginAttempt::new("admin".to_string(), Some("10.0.0.1".to_string()), None, "success".to_string(), 200)).await.unwrap();
+    let by_user = ListQuery::new(&LoginAttemptRepository::LIST, None, None, None).unwrap()
+        .filter("username", Some("admin".to_string())).unwrap();
+    assert_eq!(attempts.find_page(&by_user).await.unwrap().total, 2);
+    let by_ip = ListQuery::new(&LoginAttemptRepository::LIST, None, None, Some("-attempted_at")).unwrap()
+        .filter("ip_address", Some("10.0.0.1".to_string())).unwrap()
+        .filter("outcome", Some("success".to_string())).unwrap();
+    assert_eq!(attempts.find_page(&by_ip).await.unwrap().items.len(), 1);
+
+    // Tags and technologies are backfilled from the JSON arrays and kept in step
+    let tags = crate::models::tag_repository::TagRepository::new(db_pool.clone());
+    rollback_migrations(&db_pool, &migrations, "20261017160000_add_slugs").await.unwrap();
+    run_migrations(&db_pool).await.unwrap();
+    assert_eq!(tags.find_technology("RUST").await.unwrap().unwrap().project_count, 1);
+    let mut tagged = crate::models::post::Post::new("Tagged".to_string(), "2025-01-01".to_string(), vec!["Rust".to_string(), "rust".to_string()], String::new(), String::new());
+    tagged.status = crate::models::post::PostStatus::Published;
+    tagged = posts.create(tagged).await.unwrap();
+    assert_eq!(tags.find_tag("rust").await.unwrap().unwrap().post_count, 1);
+    tagged.tags = vec!["Go".to_string()];
+    posts.update(&tagged.id, tagged.clone()).await.unwrap();
+    assert!(tags.find_tag("rust").await.unwrap().is_none());
+    assert_eq!(tags.find_tag("go").await.unwrap().unwrap().name, "Go");
+
+    // JSON array filters and paging behave as on SQLite
+    let rust = ListQuery::new(&ProjectRepository::LIST, Some(1), Some(1), Some("title")).unwrap()
+        .filter("technology", Some("rust".to_string())).unwrap();
+    let page = projects.find_page(&rust).await.unwrap();
+    assert_eq!((page.total, page.items[0].title.as_str()), (1, "Linked"));
+
+    // Registry changes rewrite names, and aliases resolve in filters
+    let registry = crate::models::technology_repository::TechnologyRepository::new(db_pool.clone());
+    let typo = Project::new("Typo".to_string(), String::new(), vec!["Rustlang".to_string(), "ts".to_string()], None, None, None, None, 2024, vec![]);
+    let typo = projects.create(typo).await.unwrap();
+    assert_eq!(typo.technologies, ["Rustlang", "TypeScript"]);
+    assert_eq!(registry.merge("rustlang", "RUST").await.unwrap().aliases, ["Rustlang"]);
+    assert_eq!(projects.find_by_id(&typo.id).await.unwrap().unwrap().technologies, ["Rust", "TypeScript"]);
+    let by_alias = ListQuery::new(&ProjectRepository::LIST, None, None, None).unwrap()
+        .filter("technology", Some("RUSTLANG".to_string())).unwrap();
+    assert_eq!(projects.find_page(&by_alias).await.unwrap().total, 2);
+    assert!(registry.delete("rust").await.is_err());
+
+    // Skill names become objects, and evidence follows the join tables
+    let skills = crate::models::skill_repository::SkillRepository::new(db_pool.clone());
+    rollback_migrations(&db_pool, &migrations, "20261017180000_create_technology_registry").await.unwrap();
+    sqlx::query("INSERT INTO skills (id, category, items) VALUES ('old', 'Languages', '[\"Go\", \"Rust\"]')")
+        .execute(&db_pool).await.unwrap();
+    run_migrations(&db_pool).await.unwrap();
+    let old = skills.find_by_id("old").await.unwrap().unwrap();
+    assert_eq!((old.items[1].name.as_str(), old.items[1].order), ("Rust", 1));
+    let evidence = skills.find_evidence().await.unwrap();
+    assert_eq!(evidence.projects["rust"].len(), 2);
+    assert!(!evidence.projects.contains_key("rustlang"));
+
+    // Revisions are numbered per item, after the state found before history
+    let revisions = crate::models::revision_repository::RevisionRepository::new(db_pool.clone());
+    let author = crate::auth::User { id: "user-1".to_string(), name: "Test".to_string(), role: "editor".to_string() };
+    let mut retitled = typo.clone();
+    retitled.title = "Fixed".to_string();
+    assert_eq!(revisions.record("projects", &typo.id, Some(&typo), &retitled, &author, None).await.unwrap(), Some(2));
+    assert_eq!(revisions.record("projects", &typo.id, Some(&retitled), &retitled, &author, None).await.unwrap(), None);
+    let first = revisions.find("projects", &typo.id, 1).await.unwrap().unwrap();
+    assert_eq!((first.author_id, first.snapshot["title"].as_str()), (None, Some("Typo")));
+    let query = ListQuery::new(&crate::models::revision_repository::RevisionRepository::LIST, None, None, None).unwrap();
+    assert_eq!(revisions.find_page("projects", &typo.id, query).await.unwrap().items[0].number, 2);
+
+    // Search starts from empty tables
+    rollback_migrations(&db_pool, &migrations, "0").await.unwrap();
+    run_migrations(&db_pool).await.unwrap();
+    check_search(&db_pool).await;
+
+    rollback_migrations(&db_pool, &migrations, "0").await.unwrap();
+    run_migrations(&db_pool).await.unwrap();
+    check_trash(&db_pool).await;
+
+    rollback_migrations(&db_pool, &migrations, "0").await.unwrap();
+    run_migrations(&db_pool).await.unwrap();
+    check_versions(&db_pool).await;
+
+    rollback_migrations(&db_pool, &migrations, "0").await.unwrap();
+    run_migrations(&db_pool).await.unwrap();
+    check_last_modified(&db_pool).await;
+
+    rollback_migrations(&db_pool, &migrations, "0").await.unwrap();
+    run_migrations(&db_pool).await.unwrap();
+    check_bundle(&db_pool).await;
+
+    rollback_migrations(&db_pool, &migrations, "0").await.unwrap();
+    run_migrations(&db_pool).await.unwrap();
+    check_login_throttle(&db_pool).await;
+
+    rollback_migrations(&db_pool, &migrations, "0").await.unwrap();
+}
+
+// Search behavior shared by both backends, starting from empty content tables
+async fn check_search(db_pool: &DbPool) {
+    use crate::models::experience::Experience;
+    use crate::models::experience_repository::ExperienceRepository;
+    use crate::models::post::{Post, PostStatus};
+    use crate::models::post_repository::PostRepository;
+    use crate::models::project::Project;
+    use crate::models::project_repository::ProjectRepository;
+    use crate::models::repository::Repository;
+    use crate::models::search::{parse_search_terms, SearchHit};
+    use crate::models::search_repository::SearchRepository;
+
+    let posts = PostRepository::new(db_pool.clone());
+    let projects = ProjectRepository::new(db_pool.clone());
+    let experiences = ExperienceRepository::new(db_pool.clone());
+    let search = SearchRepository::new(db_pool.clone());
+
+    let mut post = Post::new(
+        "Deploying Rust services".to_string(),
+        "2025-04-10".to_string(),
+        vec!["Rust".to_string(), "DevOps".to_string()],
+        "Notes on <b>shipping</b> binaries".to_string(),
+        "We automate releases with Kubernetes.".to_string(),
+    );
+    post.status = PostStatus::Published;
+    let mut draft = Post::new(
+        "Deploying drafts".to_string(),
+        "2025-04-11".to_string(),
+        vec![],
+        "Not public yet".to_string(),
+        String::new(),
+    );
+    let project = Project::new(
+        "Cluster dashboard".to_string(),
+        "A dashboard for Kubernetes clusters".to_string(),
+        vec!["TypeScript".to_string()],
+        None, None, None, None, 2024,
+        vec!["Realtime updates".to_string()],
+    );
+    let experience = Experience::new(
+        "Acme".to_string(),
+        "Platform Engineer".to_string(),
+        "2022-01".to_string(),
+        None,
+        "Deployed the release pipeline".to_string(),
+        vec!["Rust".to_string(), "Terraform".to_string()],
+        vec![],
+    );
+    posts.create(post.clone()).await.unwrap();
+    posts.create(draft.clone()).await.unwrap();
+    projects.create(project.clone()).await.unwrap();
+    experiences.create(experience.clone()).await.unwrap();
+
+    let hits = |q: &'static str, kind: Option<&'static str>| {
+        let search = &search;
+        async move {
+            search.search(&parse_search_terms(q), kind, 1, 20).await.unwrap()
+        }
+    };
+    let kinds = |hits: &[SearchHit]| hits.iter().map(|hit| hit.kind.clone()).collect::<Vec<_>>();
+
+    // Stemming: "deploys" also matches "Deploying" and "Deployed", and title matches rank first
+    let page = hits("deploys", None).await;
+    assert_eq!(page.total, 2);
+    assert_eq!(kinds(&page.items), ["post", "experience"]);
+    assert_eq!(page.items[0].id, post.id);
+    assert!(page.items[0].score > page.items[1].score);
+
+    // Prefixes, keywords and type filters
+    assert_eq!(hits("kube*", None).await.total, 2);
+    assert_eq!(kinds(&hits("kube*", Some("project")).await.items), ["project"]);
+    assert_eq!(hits("rust", None).await.total, 2);
+    assert_eq!(hits("rust terraform", None).await.total, 1);
+
+    // Matches are marked and the text around them escaped
+    let page = hits("platform", None).await;
+    assert_eq!(page.items[0].title, "<mark>Platform</mark> Engineer at Acme");
+    let page = hits("shipping", None).await;
+    assert!(page.items[0].snippet.contains("<mark>shipping</mark>"), "{}", page.items[0].snippet);
+    assert!(!page.items[0].snippet.contains("<b>"), "{}", page.items[0].snippet);
+
+    // Triggers follow updates and deletes
+    let mut renamed = project.clone();
+    renamed.description = "A console for clusters".to_string();
+    projects.update(&project.id, renamed).await.unwrap();
+    assert_eq!(kinds(&hits("kube*", None).await.items), ["post"]);
+    assert_eq!(hits("console", None).await.total, 1);
+
+    posts.delete(&post.id).await.unwrap();
+    assert_eq!(kinds(&hits("deploys", None).await.items), ["experience"]);
+
+    // Only published posts are indexed
+    draft.status = PostStatus::Published;
+    posts.update(&draft.id.clone(), draft).await.unwrap();
+    assert_eq!(kinds(&hits("deploys", None).await.items), ["post", "experience"]);
+}
+
+#[actix_web::test]
+async fn test_post_publication() {
+    use crate::auth::{AuthMiddleware, User};
+    use crate::models::post::{Post, PostStatus};
+    use crate::models::post_repository::PostRepository;
+    use crate::models::repository::Repository;
+    use crate::services::post_scheduler::publish_due_posts;
+
+    let db_pool = setup_test_db().await;
+    let posts = PostRepository::new(db_pool.clone());
+    let now = chrono::Utc::now().timestamp();
+
+    let mut ids = Vec::new();
+    for (title, status, publish_at) in [
+        ("Published", PostStatus::Published, Some(now - 60)),
+        ("Draft", PostStatus::Draft, None),
+        ("Due", PostStatus::Scheduled, Some(now - 1)),
+        ("Later", PostStatus::Scheduled, Some(now + 3600)),
+        ("Archived", PostStatus::Archived, Some(now - 7200)),
+    ] {
+        let mut post = Post::new(title.to_string(), "2025-01-01".to_string(), vec![], String::new(), String::new());
+        post.status = status;
+        post.publish_at = publish_at;
+        ids.push(posts.create(post).await.unwrap().id);
+    }
+
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(db_pool.clone()))
+            .configure(routes::posts::config)
+    ).await;
+
+    let titles = |body: Vec<Post>| {
+        let mut titles: Vec<String> = body.into_iter().map(|post| post.title).collect();
+        titles.sort();
+        titles
+    };
+
+    let req = test::TestRequest::get().uri("/posts").to_request();
+    assert_eq!(titles(test::call_and_read_body_json(&app, req).await), ["Published"]);
+
+    // The scheduler publishes due posts only
+    assert_eq!(publish_due_posts(&db_pool).await.unwrap(), 1);
+    let req = test::TestRequest::get().uri("/posts").to_request();
+    assert_eq!(titles(test::call_and_read_body_json(&app, req).await), ["Due", "Published"]);
+
+    let req = test::TestRequest::get().uri(&format!("/posts/{}", ids[1])).to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), StatusCode::NOT_FOUND);
+    let req = test::TestRequest::get().uri("/posts?status=draft").to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), StatusCode::BAD_REQUEST);
+
+    // Preview needs a user allowed to write posts
+    let req = test::TestRequest::get().uri("/posts?preview=true").to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), StatusCode::UNAUTHORIZED);
+
+    let token = |role: &str| {
+        let user = User { id: "user-1".to_string(), name: "Test".to_string(), role: role.to_string() };
+        format!("Bearer {}", AuthMiddleware::generate_token(&user).unwrap())
+    };
+
+    let req = test::TestRequest::get().uri("/posts?preview=true")
+        .insert_header(("Authorization", token("viewer")))
+        .to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), StatusCode::FORBIDDEN);
+
+    let req = test::TestRequest::get().uri("/posts?preview=true")
+        .insert_header(("Authorization", token("editor")))
+        .to_request();
+    assert_eq!(titles(test::call_and_read_body_json(&app, req).await).len(), 5);
+
+    let req = test::TestRequest::get().uri("/posts?preview=true&status=scheduled")
+        .insert_header(("Authorization", token("editor")))
+        .to_request();
+    assert_eq!(titles(test::call_and_read_body_json(&app, req).await), ["Later"]);
+
+    let req = test::TestRequest::get().uri(&format!("/posts/{}?preview=true", ids[1]))
+        .insert_header(("Authorization", token("editor")))
+        .to_request();
+    let post: Post = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(post.status, PostStatus::Draft);
+
+    // New posts are drafts; scheduling needs a time
+    let req = test::TestRequest::post().uri("/posts")
+        .insert_header(("Authorization", token("editor")))
+        .set_json(serde_json::json!({"title": "New", "date": "2025-02-01", "tags": [], "excerpt": "", "content": ""}))
+        .to_request();
+    let post: Post = test::call_and_read_body_json(&app, req).await;
+    assert_eq!((post.status, post.publish_at), (PostStatus::Draft, None));
+
+    let req = test::TestRequest::put().uri(&format!("/posts/{}", post.id))
+        .insert_header(("Authorization", token("editor")))
+        .insert_header(("If-Match", "*"))
+        .set_json(serde_json::json!({"status": "scheduled"}))
+        .to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), StatusCode::BAD_REQUEST);
+
+    let req = test::TestRequest::put().uri(&format!("/posts/{}", post.id))
+        .insert_header(("Authorization", token("editor")))
+        .insert_header(("If-Match", "*"))
+        .set_json(serde_json::json!({"status": "published"}))
+        .to_request();
+    let post: Post = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(post.status, PostStatus::Published);
+    assert!(post.publish_at.is_some_and(|publish_at| publish_at >= now));
+}
+
+#[actix_web::test]
+async fn test_slugify() {
+    use crate::models::slug::{is_valid_slug, slugify, MAX_SLUG_LENGTH};
+
+    assert_eq!(slugify("Crème Brûlée & Ça"), "creme-brulee-ca");
+    assert_eq!(slugify("  Straße, Łódź und Æsir!  "), "strasse-lodz-und-aesir");
+    assert_eq!(slugify("Rust's 2024 edition"), "rust-s-2024-edition");
+    assert_eq!(slugify("日本語"), "");
+
+    let long = slugify(&"word ".repeat(30));
+    assert!(long.len() <= MAX_SLUG_LENGTH && long.ends_with("word"));
+
+    assert!(is_valid_slug("my-post-2"));
+    for slug in ["", "My-Post", "my--post", "-my-post", "my-post-", "crème", &"a".repeat(MAX_SLUG_LENGTH + 1)] {
+        assert!(!is_valid_slug(slug), "{}", slug);
+    }
+}
+
+#[actix_web::test]
+async fn test_slugs() {
+    use crate::auth::{AuthMiddleware, User};
+    use crate::models::post::{Post, PostStatus};
+    use crate::models::post_repository::PostRepository;
+    use crate::models::project::Project;
+    use crate::models::project_repository::ProjectRepository;
+    use actix_web::ResponseError;
+    use crate::models::repository::Repository;
+    use crate::models::slug_repository::{slug_write_error, SlugRepository};
+
+    let db_pool = setup_test_db().await;
+    let slugs = SlugRepository::new(db_pool.clone());
+
+    // Rows from before slugs existed get one at startup, and only once
+    let old = Project::new("Crème Brûlée".to_string(), String::new(), vec![], None, None, None, None, 2020, vec![]);
+    ProjectRepository::new(db_pool.clone()).create(old).await.unwrap();
+    assert_eq!(slugs.backfill().await.unwrap(), 1);
+    assert_eq!(slugs.backfill().await.unwrap(), 0);
+
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(db_pool.clone()))
+            .configure(routes::projects::config)
+            .configure(routes::posts::config)
+    ).await;
+
+    let user = User { id: "user-1".to_string(), name: "Test".to_string(), role: "editor".to_string() };
+    let token = format!("Bearer {}", AuthMiddleware::generate_token(&user).unwrap());
+    let project = |title: &str| serde_json::json!({
+        "title": title, "description": "", "technologies": [], "year": 2025, "highlights": []
+    });
+
+    // Equal titles get distinct slugs
+    let mut created = Vec::new();
+    for _ in 0..2 {
+        let req = test::TestRequest::post().uri("/projects")
+            .insert_header(("Authorization", token.clone()))
+            .set_json(project("Crème Brûlée"))
+            .to_request();
+        created.push(test::call_and_read_body_json::<_, _, Project>(&app, req).await);
+    }
+    assert_eq!(created[0].slug, "creme-brulee-2");
+    assert_eq!(created[1].slug, "creme-brulee-3");
+
+    // Renaming keeps the slug; changing the slug leaves a redirect behind
+    let req = test::TestRequest::put().uri(&format!("/projects/{}", created[0].id))
+        .insert_header(("Authorization", token.clone()))
+        .insert_header(("If-Match", "*"))
+        .set_json(serde_json::json!({"title": "Flan"}))
+        .to_request();
+    assert_eq!(test::call_and_read_body_json::<_, _, Project>(&app, req).await.slug, "creme-brulee-2");
+
+    let req = test::TestRequest::put().uri(&format!("/projects/{}", created[0].id))
+        .insert_header(("Authorization", token.clone()))
+        .insert_header(("If-Match", "*"))
+        .set_json(serde_json::json!({"slug": "flan"}))
+        .to_request();
+    assert_eq!(test::call_and_read_body_json::<_, _, Project>(&app, req).await.slug, "flan");
+
+    let req = test::TestRequest::get().uri("/projects/by-slug/flan").to_request();
+    let found: Project = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(found.id, created[0].id);
+
+    let req = test::TestRequest::get().uri("/projects/by-slug/creme-brulee-2").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::MOVED_PERMANENTLY);
+    assert_eq!(resp.headers().get("Location").unwrap(), "/projects/by-slug/flan");
+
+    // Slugs and aliases of other projects are taken; malformed slugs are rejected
+    for (slug, status) in [("creme-brulee", StatusCode::CONFLICT), ("creme-brulee-2", StatusCode::CONFLICT), ("flan", StatusCode::CONFLICT), ("Flan!", StatusCode::BAD_REQUEST)] {
+        let req = test::TestRequest::put().uri(&format!("/projects/{}", created[1].id))
+            .insert_header(("Authorization", token.clone()))
+            .insert_header(("If-Match", "*"))
+            .set_json(serde_json::json!({"slug": slug}))
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), status, "{}", slug);
+    }
+
+    // A slug taken by another write after it was checked is a conflict too
+    let mut taken = created[1].clone();
+    taken.slug = "flan".to_string();
+    let err = ProjectRepository::write(&mut db_pool.acquire().await.unwrap(), &created[1].id, taken, None).await.unwrap_err();
+    assert_eq!(slug_write_error(err, "Failed to update project").status_code(), StatusCode::CONFLICT);
+
+    // A project can take its old slug back
+    let req = test::TestRequest::put().uri(&format!("/projects/{}", created[0].id))
+        .insert_header(("Authorization", token.clone()))
+        .insert_header(("If-Match", "*"))
+        .set_json(serde_json::json!({"slug": "creme-brulee-2"}))
+        .to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), StatusCode::OK);
+    assert_eq!(slugs.find_alias("projects", "creme-brulee-2").await.unwrap(), None);
+    assert_eq!(slugs.find_alias("projects", "flan").await.unwrap(), Some(created[0].id.clone()));
+
+    // A deleted project keeps its slug and aliases until it is purged
+    let req = test::TestRequest::delete().uri(&format!("/projects/{}", created[0].id))
+        .insert_header(("Authorization", token.clone()))
+        .insert_header(("If-Match", "*"))
+        .to_request();
+    assert!(test::call_service(&app, req).await.status().is_success());
+    let req = test::TestRequest::get().uri("/projects/by-slug/flan").to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), StatusCode::NOT_FOUND);
+    assert!(slugs.is_taken("projects", "flan", &created[1].id).await.unwrap());
+    crate::models::trash_repository::TrashRepository::new(db_pool.clone()).purge("projects", &created[0].id).await.unwrap();
+    assert_eq!(slugs.find_alias("projects", "flan").await.unwrap(), None);
+
+    // Drafts are only found by slug in preview
+    let mut draft = Post::new("Work in progress".to_string(), "2025-01-01".to_string(), vec![], String::new(), String::new());
+    draft.slug = "wip".to_string();
+    PostRepository::new(db_pool.clone()).create(draft).await.unwrap();
+
+    let req = test::TestRequest::get().uri("/posts/by-slug/wip").to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), StatusCode::NOT_FOUND);
+
+    let req = test::TestRequest::get().uri("/posts/by-slug/wip?preview=true")
+        .insert_header(("Authorization", token.clone()))
+        .to_request();
+    let post: Post = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(post.status, PostStatus::Draft);
+}
+
+#[actix_web::test]
+async fn test_render_markdown() {
+    use std::sync::Arc;
+    use crate::models::post::{Post, PostStatus};
+    use crate::models::post_repository::PostRepository;
+    use crate::models::repository::Repository;
+    use crate::services::markdown::{render_cached, render_markdown};
+
+    let markdown = "# Intro\n\nHello *world* and `code`.\n\n## Setup {#install}\n\n## Intro\n\n\
+        ```rust\nfn main() {}\n```\n\n\
+        | a | b |\n|:--|--:|\n| 1 | 2 |\n\n\
+        - [x] done\n- [ ] todo\n\n\
+        <script>alert(1)</script><a href=\"javascript:alert(1)\" onclick=\"x()\">link</a>\n";
+    let rendered = render_markdown(markdown);
+
+    let toc: Vec<(u8, &str, &str)> = rendered.toc.iter().map(|e| (e.level, e.id.as_str(), e.text.as_str())).collect();
+    assert_eq!(toc, [(1, "intro", "Intro"), (2, "install", "Setup"), (2, "intro-2", "Intro")]);
+    assert!(rendered.html.contains("<h2 id=\"install\">Setup</h2>"));
+
+    // Code is highlighted with classes and does not count as words
+    assert!(rendered.html.contains("<pre><code class=\"language-rust\"><span class=\"hl-source hl-rust\">"));
+    assert_eq!(rendered.word_count, 13);
+    assert_eq!(rendered.reading_time_minutes, 1);
+
+    assert!(rendered.html.contains("<th style=\"text-align: left\">a</th>"));
+    assert!(rendered.html.contains("<li><input disabled=\"\" type=\"checkbox\" checked=\"\">"));
+    assert!(rendered.html.contains("<a rel=\"noopener noreferrer\">link</a>"));
+    assert!(!rendered.html.contains("<script") && !rendered.html.contains("alert"));
+
+    assert_eq!(render_markdown("").reading_time_minutes, 0);
+    assert!(Arc::ptr_eq(&render_cached(markdown), &render_cached(markdown)));
+
+    // Posts are rendered on request
+    let db_pool = setup_test_db().await;
+    let mut post = Post::new("Rendered".to_string(), "2025-01-01".to_string(), vec![], String::new(), markdown.to_string());
+    post.status = PostStatus::Published;
+    let post = PostRepository::new(db_pool.clone()).create(post).await.unwrap();
+
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(db_pool.clone()))
+            .configure(routes::posts::config)
+    ).await;
+
+    let req = test::TestRequest::get().uri(&format!("/posts/{}?format=html", post.id)).to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.headers().get("ETag").unwrap(), "\"1-html\"");
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["content_html"], rendered.html.as_str());
+    assert_eq!(body["toc"][1]["id"], "install");
+    assert_eq!(body["word_count"], rendered.word_count);
+    assert!(body.get("content").is_none());
+
+    let req = test::TestRequest::get().uri(&format!("/posts/{}", post.id)).to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.headers().get("ETag").unwrap(), "\"1\"");
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["content"], markdown);
+
+    let req = test::TestRequest::get().uri(&format!("/posts/{}?format=pdf", post.id)).to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), StatusCode::BAD_REQUEST);
+}
+
+#[actix_web::test]
+async fn test_feeds() {
+    use crate::models::post::{Post, PostStatus};
+    use crate::models::post_repository::PostRepository;
+    use crate::models::repository::Repository;
+
+    let db_pool = setup_test_db().await;
+    let posts = PostRepository::new(db_pool.clone());
+    for (title, tags, status, publish_at) in [
+        ("Older", vec!["Rust"], PostStatus::Published, 1_700_000_000),
+        ("Newer & <better>", vec!["rust", "Web"], PostStatus::Published, 1_700_100_000),
+        ("Draft", vec!["Rust"], PostStatus::Draft, 1_700_200_000),
+    ] {
+        let tags = tags.into_iter().map(String::from).collect();
+        let mut post = Post::new(title.to_string(), "2023-11-14".to_string(), tags, "Excerpt".to_string(), "Some **bold** text".to_string());
+        post.status = status;
+        post.publish_at = Some(publish_at);
+        posts.create(post).await.unwrap();
+    }
+
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(db_pool.clone()))
+            .configure(routes::feeds::config)
+    ).await;
+
+    let req = test::TestRequest::get().uri("/feed.xml").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(resp.headers().get("Content-Type").unwrap(), "application/rss+xml; charset=utf-8");
+    let etag = resp.headers().get("ETag").unwrap().clone();
+    let last_modified = resp.headers().get("Last-Modified").unwrap().clone();
+    let rss = String::from_utf8(test::read_body(resp).await.to_vec()).unwrap();
+    assert!(rss.find("Newer &amp; &lt;better&gt;").unwrap() < rss.find("Older").unwrap());
+    assert!(!rss.contains("Draft"));
+    assert!(rss.contains("<content:encoded>&lt;p&gt;Some &lt;strong&gt;bold&lt;/strong&gt; text&lt;/p&gt;"));
+    assert!(rss.contains("<category>Web</category>"));
+
+    // Unchanged feeds are not sent again
+    let req = test::TestRequest::get().uri("/feed.xml").insert_header(("If-None-Match", etag.clone())).to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), StatusCode::NOT_MODIFIED);
+    let req = test::TestRequest::get().uri("/feed.xml").insert_header(("If-Modified-Since", last_modified)).to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), StatusCode::NOT_MODIFIED);
+    let req = test::TestRequest::get().uri("/atom.xml").insert_header(("If-None-Match", etag)).to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), StatusCode::OK);
+
+    let req = test::TestRequest::get().uri("/tags/RUST/feed.json").to_request();
+    let feed: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(feed["version"], "https://jsonfeed.org/version/1.1");
+    assert_eq!(feed["items"].as_array().unwrap().len(), 2);
+    assert_eq!(feed["items"][1]["date_published"], "2023-11-14T22:13:20Z");
+
+    let req = test::TestRequest::get().uri("/tags/web/atom.xml").to_request();
+    let atom = String::from_utf8(test::call_and_read_body(&app, req).await.to_vec()).unwrap();
+    assert_eq!(atom.matches("<entry>").count(), 1);
+    assert!(atom.contains("<published>2023-11-16T02:00:00Z</published>"));
+
+    let req = test::TestRequest::get().uri("/tags/go/feed.xml").to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), StatusCode::NOT_FOUND);
+}
+
+#[actix_web::test]
+async fn test_tags() {
+    use crate::models::experience::Experience;
+    use crate::models::experience_repository::ExperienceRepository;
+    use crate::models::post::{Post, PostStatus};
+    use crate::models::post_repository::PostRepository;
+    use crate::models::project::Project;
+    use crate::models::project_repository::ProjectRepository;
+    use crate::models::repository::{ListQuery, Repository};
+
+    // Existing JSON arrays are copied into the tag tables by the migration
+    let db_pool = setup_test_db().await;
+    let migrations = embedded_migrations(DbBackend::Sqlite);
+    rollback_migrations(&db_pool, &migrations, "20261017160000_add_slugs").await.unwrap();
+    sqlx::query("INSERT INTO posts (id, title, date, tags, excerpt, content) VALUES ('old', 'Old', '2020-01-01', '[\"Rust\", \" rust \", \"Web\"]', '', '')")
+        .execute(&db_pool).await.unwrap();
+    run_migrations(&db_pool).await.unwrap();
+    let tags = crate::models::tag_repository::TagRepository::new(db_pool.clone());
+    let tag = tags.find_tag("RUST").await.unwrap().unwrap();
+    assert_eq!((tag.name.as_str(), tag.post_count), ("Rust", 1));
+
+    // Triggers keep the tag tables in step with later writes
+    let posts = PostRepository::new(db_pool.clone());
+    let new_post = |title: &str, tags: &[&str], status| {
+        let mut post = Post::new(title.to_string(), "2024-01-01".to_string(), tags.iter().map(|t| t.to_string()).collect(), String::new(), String::new());
+        post.status = status;
+        post
+    };
+    let mut newer = posts.create(new_post("Newer", &["rust", "Go"], PostStatus::Published)).await.unwrap();
+    posts.create(new_post("Draft", &["Rust", "Secret"], PostStatus::Draft)).await.unwrap();
+    assert_eq!(tags.find_tag("rust").await.unwrap().unwrap().post_count, 2);
+    assert!(tags.find_tag("secret").await.unwrap().is_none());
+
+    newer.tags = vec!["Go".to_string()];
+    posts.update(&newer.id, newer.clone()).await.unwrap();
+    assert_eq!(tags.find_tag("rust").await.unwrap().unwrap().post_count, 1);
+    posts.delete("old").await.unwrap();
+    assert!(tags.find_tag("rust").await.unwrap().is_none());
+
+    let by_tag = ListQuery::new(&PostRepository::LIST, None, None, None).unwrap()
+        .filter("tag", Some(" GO ".to_string())).unwrap();
+    assert_eq!(posts.find_page(&by_tag).await.unwrap().items[0].id, newer.id);
+    let partial = ListQuery::new(&PostRepository::LIST, None, None, None).unwrap()
+        .filter("tag", Some("G".to_string())).unwrap();
+    assert_eq!(posts.find_page(&partial).await.unwrap().total, 0);
+
+    let projects = ProjectRepository::new(db_pool.clone());
+    let tech = |names: &[&str]| names.iter().map(|t| t.to_string()).collect::<Vec<_>>();
+    projects.create(Project::new("Site".to_string(), String::new(), tech(&["TypeScript", "Rust"]), None, None, None, None, 2024, vec![])).await.unwrap();
+    projects.create(Project::new("Tool".to_string(), String::new(), tech(&["rust"]), None, None, None, None, 2023, vec![])).await.unwrap();
+    ExperienceRepository::new(db_pool.clone())
+        .create(Experience::new("Acme".to_string(), "Developer".to_string(), "2022-01".to_string(), None, String::new(), tech(&["Rust"]), vec![]))
+        .await.unwrap();
+    let by_technology = ListQuery::new(&ProjectRepository::LIST, None, None, None).unwrap()
+        .filter("technology", Some("RUST".to_string())).unwrap();
+    assert_eq!(projects.find_page(&by_technology).await.unwrap().total, 2);
+
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(db_pool.clone()))
+            .configure(routes::tags::config)
+    ).await;
+
+    let req = test::TestRequest::get().uri("/tags").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.headers().get("X-Total-Count").unwrap(), "1");
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body, serde_json::json!([{ "name": "Go", "post_count": 1 }]));
+
+    let req = test::TestRequest::get().uri("/tags/go").to_request();
+    let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!((body["name"].as_str(), body["posts"][0]["title"].as_str()), (Some("Go"), Some("Newer")));
+
+    let req = test::TestRequest::get().uri("/tags/secret").to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), StatusCode::NOT_FOUND);
+
+    let req = test::TestRequest::get().uri("/technologies?sort=-count").to_request();
+    let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!((body[0]["name"].as_str(), body[0]["usage_count"].as_i64()), (Some("Rust"), Some(3)));
+
+    let req = test::TestRequest::get().uri("/technologies/RUST").to_request();
+    let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!((body["project_count"].as_i64(), body["experience_count"].as_i64()), (Some(2), Some(1)));
+    assert_eq!(body["projects"][0]["title"], "Site");
+    assert_eq!(body["experiences"][0]["company"], "Acme");
+
+    let req = test::TestRequest::get().uri("/technologies?sort=popularity").to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), StatusCode::BAD_REQUEST);
+}
+
+#[actix_web::test]
+async fn test_technology_registry() {
+    use std::collections::HashMap;
+    use crate::auth::{AuthMiddleware, User};
+    use crate::models::github_stats::{GithubStats, TopLanguage};
+    use crate::models::github_stats_repository::GithubStatsRepository;
+    use crate::models::project::Project;
+    use crate::models::project_repository::ProjectRepository;
+    use crate::models::repository::{ListQuery, Repository};
+    use crate::models::skill::{Skill, SkillItem};
+    use crate::models::skill_repository::SkillRepository;
+    use crate::models::technology::normalize_technologies;
+    use crate::models::technology_repository::TechnologyRepository;
+
+    let names = |names: &[&str]| names.iter().map(|n| n.to_string()).collect::<Vec<_>>();
+    let canonical = HashMap::from([
+        ("typescript".to_string(), "TypeScript".to_string()),
+        ("ts".to_string(), "TypeScript".to_string()),
+    ]);
+    assert_eq!(normalize_technologies(&names(&["ts", " Rust ", "", "TypeScript", "rust"]), &canonical), ["TypeScript", "Rust"]);
+
+    // Writes are normalised against the aliases registered by the migration
+    let db_pool = setup_test_db().await;
+    let projects = ProjectRepository::new(db_pool.clone());
+    let project = projects.create(Project::new("Site".to_string(), String::new(), names(&["TS", "rust", "golang"]), None, None, None, None, 2024, vec![])).await.unwrap();
+    assert_eq!(project.technologies, ["TypeScript", "rust", "Go"]);
+    let skill = SkillRepository::new(db_pool.clone()).create(Skill { category: "Web".to_string(), items: names(&["js", "Postgres"]).into_iter().map(SkillItem::new).collect() }).await.unwrap();
+    assert_eq!(skill.items, [SkillItem::new("JavaScript".to_string()), SkillItem::new("PostgreSQL".to_string())]);
+    let language = |name: &str, percentage| TopLanguage { name: name.to_string(), percentage };
+    let stats = GithubStatsRepository::new(db_pool.clone()).create(GithubStats {
+        username: "quewui".to_string(),
+        repo_count: 1,
+        followers: 0,
+        contributions: 0,
+        top_languages: vec![language("TypeScript", 40), language("Rust", 50), language("TS", 10)],
+        recent_activity: vec![],
+    }).await.unwrap();
+    assert_eq!(stats.top_languages, [language("TypeScript", 50), language("rust", 50)]);
+
+    let by_alias = ListQuery::new(&ProjectRepository::LIST, None, None, None).unwrap()
+        .filter("technology", Some("ts".to_string())).unwrap();
+    assert_eq!(projects.find_page(&by_alias).await.unwrap().total, 1);
+
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(db_pool.clone()))
+            .configure(routes::tags::config)
+            .configure(routes::technologies::config)
+    ).await;
+    let token = |role: &str| {
+        let user = User { id: "user-1".to_string(), name: "Test".to_string(), role: role.to_string() };
+        format!("Bearer {}", AuthMiddleware::generate_token(&user).unwrap())
+    };
+    let editor = token("editor");
+
+    let req = test::TestRequest::get().uri("/admin/technologies?category=language&sort=name").insert_header(("Authorization", editor.clone())).to_request();
+    let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    let typescript = body.as_array().unwrap().iter().find(|t| t["name"] == "TypeScript").unwrap();
+    assert_eq!(typescript["aliases"], serde_json::json!(["TS"]));
+    let req = test::TestRequest::get().uri("/admin/technologies").insert_header(("Authorization", token("viewer"))).to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), StatusCode::FORBIDDEN);
+
+    // Renaming rewrites the names in use and keeps the old spelling as an alias
+    let req = test::TestRequest::put().uri("/admin/technologies/RUST").insert_header(("Authorization", editor.clone()))
+        .set_json(serde_json::json!({ "name": "Rust", "category": "language", "aliases": ["rust-lang"] })).to_request();
+    let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body, serde_json::json!({ "name": "Rust", "category": "language", "icon": null, "aliases": ["rust-lang"] }));
+    assert_eq!(projects.find_by_id(&project.id).await.unwrap().unwrap().technologies, ["TypeScript", "Rust", "Go"]);
+    let stats = GithubStatsRepository::new(db_pool.clone()).find_all().await.unwrap();
+    assert_eq!(stats[0].top_languages[1], language("Rust", 50));
+
+    let req = test::TestRequest::post().uri("/admin/technologies").insert_header(("Authorization", editor.clone()))
+        .set_json(serde_json::json!({ "name": "Svelte", "aliases": ["ts"] })).to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), StatusCode::CONFLICT);
+    let req = test::TestRequest::post().uri("/admin/technologies").insert_header(("Authorization", editor.clone()))
+        .set_json(serde_json::json!({ "name": " ", "aliases": [] })).to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), StatusCode::BAD_REQUEST);
+    let req = test::TestRequest::post().uri("/admin/technologies").insert_header(("Authorization", editor.clone()))
+        .set_json(serde_json::json!({ "name": "Deno", "category": "runtime", "icon": "simple-icons:deno" })).to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), StatusCode::CREATED);
+
+    // Merging a duplicate moves its uses and spellings to the target
+    let typo = projects.create(Project::new("Db".to_string(), String::new(), names(&["Postgre"]), None, None, None, None, 2023, vec![])).await.unwrap();
+    let req = test::TestRequest::post().uri("/admin/technologies/postgre/merge").insert_header(("Authorization", editor.clone()))
+        .set_json(serde_json::json!({ "into": "postgres" })).to_request();
+    let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!((body["name"].as_str(), body["aliases"].as_array().unwrap().len()), (Some("PostgreSQL"), 2));
+    assert_eq!(projects.find_by_id(&typo.id).await.unwrap().unwrap().technologies, ["PostgreSQL"]);
+
+    let req = test::TestRequest::get().uri("/technologies/postgre").to_request();
+    let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!((body["name"].as_str(), body["category"].as_str(), body["project_count"].as_i64()), (Some("PostgreSQL"), Some("database"), Some(1)));
+
+    let req = test::TestRequest::delete().uri("/admin/technologies/PostgreSQL").insert_header(("Authorization", editor.clone())).to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), StatusCode::CONFLICT);
+    let req = test::TestRequest::delete().uri("/admin/technologies/deno").insert_header(("Authorization", editor.clone())).to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), StatusCode::NO_CONTENT);
+    let req = test::TestRequest::get().uri("/admin/technologies/deno").insert_header(("Authorization", editor)).to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), StatusCode::NOT_FOUND);
+
+    // Names stored without going through the registry are fixed at startup
+    sqlx::query("UPDATE skills SET items = '[\"k8s\", \"JavaScript\"]'").execute(&db_pool).await.unwrap();
+    let registry = TechnologyRepository::new(db_pool.clone());
+    assert_eq!(registry.apply().await.unwrap(), 1);
+    assert_eq!(registry.apply().await.unwrap(), 0);
+    assert_eq!(SkillRepository::new(db_pool.clone()).find_all().await.unwrap()[0].items, [SkillItem::new("Kubernetes".to_string()), SkillItem::new("JavaScript".to_string())]);
+}
+
+#[actix_web::test]
+async fn test_skills() {
+    use crate::auth::{AuthMiddleware, User};
+    use crate::models::experience::Experience;
+    use crate::models::experience_repository::ExperienceRepository;
+    use crate::models::project::Project;
+    use crate::models::project_repository::ProjectRepository;
+    use crate::models::repository::Repository;
+    use crate::models::skill::{Proficiency, SkillItem};
+    use crate::models::skill_repository::SkillRepository;
+
+    // Items stored as plain names are turned into objects by the migration
+    let db_pool = setup_test_db().await;
+    let migrations = embedded_migrations(DbBackend::Sqlite);
+    rollback_migrations(&db_pool, &migrations, "20261017180000_create_technology_registry").await.unwrap();
+    sqlx::query("INSERT INTO skills (id, category, items) VALUES ('old', 'Tools', '[\"Git\", \"Docker\"]')")
+        .execute(&db_pool).await.unwrap();
+    run_migrations(&db_pool).await.unwrap();
+    let stored: (String,) = sqlx::query_as("SELECT items FROM skills WHERE id = 'old'").fetch_one(&db_pool).await.unwrap();
+    assert_eq!(serde_json::from_str::<serde_json::Value>(&stored.0).unwrap(), serde_json::json!([
+        { "name": "Git", "proficiency": null, "years": null, "order": 0 },
+        { "name": "Docker", "proficiency": null, "years": null, "order": 1 }
+    ]));
+    let skills = SkillRepository::new(db_pool.clone());
+    assert_eq!(skills.find_by_id("old").await.unwrap().unwrap().items[1], SkillItem { order: 1, ..SkillItem::new("Docker".to_string()) });
+
+    let tech = |names: &[&str]| names.iter().map(|t| t.to_string()).collect::<Vec<_>>();
+    let projects = ProjectRepository::new(db_pool.clone());
+    projects.create(Project::new("Old Site".to_string(), String::new(), tech(&["TS"]), None, None, None, None, 2021, vec![])).await.unwrap();
+    projects.create(Project::new("New Site".to_string(), String::new(), tech(&["TypeScript", "Rust"]), None, None, None, None, 2024, vec![])).await.unwrap();
+    ExperienceRepository::new(db_pool.clone())
+        .create(Experience::new("Acme".to_string(), "Developer".to_string(), "2022-01".to_string(), None, String::new(), tech(&["ts"]), vec![]))
+        .await.unwrap();
+
+    let user = User { id: "user-1".to_string(), name: "Test".to_string(), role: "editor".to_string() };
+    let token = format!("Bearer {}", AuthMiddleware::generate_token(&user).unwrap());
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(db_pool.clone()))
+            .configure(routes::skills::config)
+    ).await;
+
+    // Names and objects mix; items come back in display order
+    let req = test::TestRequest::post().uri("/skills")
+        .insert_header(("Authorization", token.clone()))
+        .set_json(serde_json::json!({
+            "category": "Languages",
+            "items": [
+                { "name": "Rust", "proficiency": "advanced", "years": 4, "order": 2 },
+                "ts",
+                { "name": "Elixir", "order": 1 }
+            ]
+        }))
+        .to_request();
+    let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body["items"], serde_json::json!([
+        { "name": "TypeScript", "proficiency": null, "years": null, "order": 0 },
+        { "name": "Elixir", "proficiency": null, "years": null, "order": 1 },
+        { "name": "Rust", "proficiency": "advanced", "years": 4, "order": 2 }
+    ]));
+    let languages = skills.find_all().await.unwrap().into_iter().find(|s| s.category == "Languages").unwrap();
+    assert_eq!(languages.items[2].proficiency, Some(Proficiency::Advanced));
+
+    let req = test::TestRequest::post().uri("/skills")
+        .insert_header(("Authorization", token.clone()))
+        .set_json(serde_json::json!({ "category": "Bad", "items": [{ "name": "Go", "proficiency": "guru" }] }))
+        .to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), StatusCode::BAD_REQUEST);
+
+    // Plain listing leaves out the evidence
+    let req = test::TestRequest::get().uri("/skills?sort=category").to_request();
+    let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body[0]["category"], "Languages");
+    assert!(body[0]["items"][0].get("projects").is_none());
+
+    // Evidence matches aliases through the registry, newest first
+    let req = test::TestRequest::get().uri("/skills?evidence=true&sort=category").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.headers().get("X-Total-Count").unwrap(), "2");
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    let typescript = &body[0]["items"][0];
+    assert_eq!(typescript["name"], "TypeScript");
+    let titles: Vec<_> = typescript["projects"].as_array().unwrap().iter().map(|p| p["title"].as_str().unwrap()).collect();
+    assert_eq!(titles, ["New Site", "Old Site"]);
+    assert_eq!(typescript["experiences"][0]["company"], "Acme");
+    assert_eq!(body[0]["items"][1]["projects"], serde_json::json!([]));
+    assert_eq!(body[0]["items"][2]["projects"][0]["year"], 2024);
+    assert_eq!(body[1]["items"][0]["experiences"], serde_json::json!([]));
+}
+
+#[actix_web::test]
+async fn test_revisions() {
+    use crate::auth::{AuthMiddleware, User};
+    use crate::models::project::Project;
+    use crate::models::project_repository::ProjectRepository;
+    use crate::models::repository::Repository;
+    use crate::models::revision::diff_snapshots;
+
+    let changes = diff_snapshots(
+        &serde_json::json!({ "bio": ["a", "b"], "a/b": 1, "same": true }),
+        &serde_json::json!({ "bio": ["a"], "a/b": 2, "same": true, "new": null }),
+    );
+    let paths: Vec<_> = changes.iter().map(|change| change.path.as_str()).collect();
+    assert_eq!(paths, ["/a~1b", "/bio/1", "/new"]);
+    assert_eq!((changes[1].from.clone(), changes[1].to.clone()), (Some(serde_json::json!("b")), None));
+
+    let db_pool = setup_test_db().await;
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(db_pool.clone()))
+            .configure(routes::projects::config)
+            .configure(routes::profile::config)
+    ).await;
+    let token = |role: &str| {
+        let user = User { id: format!("{}-1", role), name: format!("Test {}", role), role: role.to_string() };
+        format!("Bearer {}", AuthMiddleware::generate_token(&user).unwrap())
+    };
+    let editor = token("editor");
+    let call = |method: test::TestRequest, uri: &str, body: Option<serde_json::Value>| {
+        let req = method.uri(uri).insert_header(("Authorization", editor.clone())).insert_header(("If-Match", "*"));
+        match body {
+            Some(body) => req.set_json(body).to_request(),
+            None => req.to_request(),
+        }
+    };
+
+    // Creating and updating save revisions; a write that changes nothing does not
+    let req = call(test::TestRequest::post(), "/projects", Some(serde_json::json!({
+        "title": "Site", "description": "First", "technologies": [], "year": 2024, "highlights": []
+    })));
+    let project: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    let id = project["id"].as_str().unwrap().to_string();
+    for description in ["Second", "Oops", "Oops"] {
+        let req = call(test::TestRequest::put(), &format!("/projects/{}", id), Some(serde_json::json!({ "description": description, "slug": "renamed" })));
+        assert_eq!(test::call_service(&app, req).await.status(), StatusCode::OK);
+    }
+
+    let req = call(test::TestRequest::get(), &format!("/projects/{}/revisions", id), None);
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.headers().get("X-Total-Count").unwrap(), "3");
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body[0]["number"], 3);
+    assert_eq!((body[2]["author_id"].as_str(), body[2]["author_name"].as_str()), (Some("editor-1"), Some("Test editor")));
+    assert!(body[0].get("snapshot").is_none());
+
+    let req = call(test::TestRequest::get(), &format!("/projects/{}/revisions/1", id), None);
+    let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body["snapshot"]["description"], "First");
+
+    let req = call(test::TestRequest::get(), &format!("/projects/{}/revisions/diff?from=1&to=3", id), None);
+    let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body["changes"], serde_json::json!([
+        { "path": "/description", "from": "First", "to": "Oops" },
+        { "path": "/slug", "from": "site", "to": "renamed" }
+    ]));
+
+    // Restoring writes the old version back, keeping the slug, as a new
+    // revision; like any write, it needs the current ETag
+    let restore = format!("/projects/{}/revisions/1/restore", id);
+    let req = test::TestRequest::post().uri(&restore).insert_header(("Authorization", editor.clone())).to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), StatusCode::PRECONDITION_REQUIRED);
+    let req = test::TestRequest::post().uri(&restore).insert_header(("Authorization", editor.clone())).insert_header(("If-Match", "\"3\"")).to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), StatusCode::PRECONDITION_FAILED);
+    let req = test::TestRequest::post().uri(&restore).insert_header(("Authorization", editor.clone())).insert_header(("If-Match", "\"4\"")).to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.headers().get("ETag").unwrap(), "\"5\"");
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!((body["description"].as_str(), body["slug"].as_str()), (Some("First"), Some("renamed")));
+    let req = call(test::TestRequest::get(), &format!("/projects/{}/revisions/4", id), None);
+    let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body["restored_from"], 1);
+
+    for uri in [format!("/projects/{}/revisions/9", id), format!("/projects/{}/revisions/9/restore", id)] {
+        let method = if uri.ends_with("restore") { test::TestRequest::post() } else { test::TestRequest::get() };
+        assert_eq!(test::call_service(&app, call(method, &uri, None)).await.status(), StatusCode::NOT_FOUND);
+    }
+    let req = test::TestRequest::get().uri(&format!("/projects/{}/revisions", id))
+        .insert_header(("Authorization", token("viewer")))
+        .to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), StatusCode::FORBIDDEN);
+
+    // Items written before history was kept get their previous state saved first
+    let projects = ProjectRepository::new(db_pool.clone());
+    let old = projects.create(Project::new("Old".to_string(), "Before".to_string(), vec![], None, None, None, None, 2020, vec![])).await.unwrap();
+    let req = call(test::TestRequest::put(), &format!("/projects/{}", old.id), Some(serde_json::json!({ "description": "After" })));
+    test::call_service(&app, req).await;
+    let req = call(test::TestRequest::get(), &format!("/projects/{}/revisions?sort=number", old.id), None);
+    let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!((body[0]["author_id"].clone(), body[1]["author_id"].as_str()), (serde_json::Value::Null, Some("editor-1")));
+
+    // The profile keeps its history too
+    for bio in ["Developer.", "Oops"] {
+        let req = call(test::TestRequest::put(), "/profile", Some(serde_json::json!({ "bio": [bio] })));
+        assert_eq!(test::call_service(&app, req).await.status(), StatusCode::OK);
+    }
+    let req = call(test::TestRequest::get(), "/profile/revisions/diff?from=2&to=3", None);
+    let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body["changes"], serde_json::json!([{ "path": "/bio/0", "from": "Developer.", "to": "Oops" }]));
+    let req = call(test::TestRequest::post(), "/profile/revisions/2/restore", None);
+    let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(body["bio"], serde_json::json!(["Developer."]));
+
+    // Deleting an item keeps its history for the trash; purging it drops the history
+    let req = call(test::TestRequest::delete(), &format!("/projects/{}", id), None);
+    test::call_service(&app, req).await;
+    let count = "SELECT COUNT(*) FROM revisions WHERE ref_id = $1";
+    let remaining: i64 = sqlx::query_scalar(count).bind(&id).fetch_one(&db_pool).await.unwrap();
+    assert_eq!(remaining, 4);
+    crate::models::trash_repository::TrashRepository::new(db_pool.clone()).purge("projects", &id).await.unwrap();
+    let remaining: i64 = sqlx::query_scalar(count).bind(&id).fetch_one(&db_pool).await.unwrap();
+    assert_eq!(remaining, 0);
+}
+
+// Trash behavior shared by both backends, starting from empty content tables
+async fn check_trash(db_pool: &DbPool) {
+    use crate::models::contact::{ContactMessage, ContactRepository};
+    use crate::models::experience::Experience;
+    use crate::models::experience_repository::ExperienceRepository;
+    use crate::models::post::{Post, PostStatus};
+    use crate::models::post_repository::PostRepository;
+    use crate::models::project::Project;
+    use crate::models::project_repository::ProjectRepository;
+    use crate::models::repository::{ListQuery, Repository};
+    use crate::models::search::parse_search_terms;
+    use crate::models::search_repository::SearchRepository;
+    use crate::models::skill::{Skill, SkillItem};
+    use crate::models::skill_repository::SkillRepository;
+    use crate::models::tag_repository::TagRepository;
+    use crate::models::trash_repository::TrashRepository;
+
+    let posts = PostRepository::new(db_pool.clone());
+    let projects = ProjectRepository::new(db_pool.clone());
+    let experiences = ExperienceRepository::new(db_pool.clone());
+    let skills = SkillRepository::new(db_pool.clone());
+    let contacts = ContactRepository::new(db_pool.clone());
+    let tags = TagRepository::new(db_pool.clone());
+    let search = SearchRepository::new(db_pool.clone());
+    let trash = TrashRepository::new(db_pool.clone());
+
+    let mut post = Post::new("Composting notes".to_string(), "2025-03-01".to_string(), vec!["Gardening".to_string()], String::new(), String::new());
+    post.status = PostStatus::Published;
+    let post = posts.create(post).await.unwrap();
+    let project = projects
+        .create(Project::new("Greenhouse".to_string(), String::new(), vec!["Arduino".to_string()], None, None, None, None, 2024, vec![]))
+        .await.unwrap();
+    skills.create(Skill { category: "Tools".to_string(), items: vec![SkillItem::new("Git".to_string())] }).await.unwrap();
+    let skill_id = skills.find_id_by_category("Tools").await.unwrap().unwrap();
+    let experience = experiences
+        .create(Experience::new("Acme".to_string(), "Gardener".to_string(), "2021-04".to_string(), None, String::new(), vec![], vec![]))
+        .await.unwrap();
+    let message = contacts.create(ContactMessage::new("Ana".to_string(), "ana@example.com".to_string(), "Hello".to_string())).await.unwrap();
+
+    // Deleted rows are hidden everywhere and can only be deleted once
+    assert!(posts.delete(&post.id).await.unwrap());
+    assert!(projects.delete(&project.id).await.unwrap());
+    assert!(skills.delete(&skill_id).await.unwrap());
+    contacts.delete(&message.id).await.unwrap();
+    assert!(experiences.delete(&experience.id).await.unwrap());
+    assert!(!projects.delete(&project.id).await.unwrap());
+    assert!(contacts.delete(&message.id).await.is_err());
+
+    // nor written over until restored
+    let mut conn = db_pool.acquire().await.unwrap();
+    assert!(!PostRepository::write(&mut conn, &post.id, post.clone(), None).await.unwrap().1);
+    assert!(!ProjectRepository::write(&mut conn, &project.id, project.clone(), None).await.unwrap().1);
+    assert!(!ExperienceRepository::write(&mut conn, &experience.id, experience.clone(), None).await.unwrap().1);
+    let tools = Skill { category: "Tools".to_string(), items: vec![SkillItem::new("Jujutsu".to_string())] };
+    assert!(!SkillRepository::write(&mut conn, &skill_id, tools, None).await.unwrap().1);
+    drop(conn);
+    trash.purge("experiences", &experience.id).await.unwrap();
+
+    assert!(posts.find_by_id(&post.id).await.unwrap().is_none());
+    assert!(projects.find_all().await.unwrap().is_empty());
+    assert!(skills.find_id_by_category("Tools").await.unwrap().is_none());
+    assert!(contacts.get_by_id(&message.id).await.is_err());
+    assert!(tags.find_tag("gardening").await.unwrap().is_none());
+    assert!(tags.find_technology("arduino").await.unwrap().is_none());
+    assert_eq!(search.search(&parse_search_terms("greenhouse"), None, 1, 20).await.unwrap().total, 0);
+
+    let page = trash.find_page(&ListQuery::new(&TrashRepository::LIST, None, None, None).unwrap()).await.unwrap();
+    assert_eq!(page.total, 4);
+    let titles: Vec<_> = page.items.iter().map(|item| (item.kind.as_str(), item.title.as_str())).collect();
+    assert!(titles.contains(&("contacts", "Ana <ana@example.com>")), "{:?}", titles);
+    let by_kind = ListQuery::new(&TrashRepository::LIST, None, None, None).unwrap()
+        .filter("kind", Some("projects".to_string())).unwrap();
+    assert_eq!(trash.find_page(&by_kind).await.unwrap().items[0].id, project.id);
+
+    // Restoring puts a row back in the indexes
+    assert!(trash.restore("projects", &project.id).await.unwrap());
+    assert!(!trash.restore("projects", &project.id).await.unwrap());
+    assert_eq!(projects.find_by_id(&project.id).await.unwrap().unwrap().title, "Greenhouse");
+    assert_eq!(tags.find_technology("arduino").await.unwrap().unwrap().project_count, 1);
+    assert_eq!(search.search(&parse_search_terms("greenhouse"), None, 1, 20).await.unwrap().total, 1);
+
+    // Purging removes a row for good
+    assert!(trash.purge("posts", &post.id).await.unwrap());
+    assert!(!trash.restore("posts", &post.id).await.unwrap());
+    assert!(!trash.purge("projects", &project.id).await.unwrap());
+    let rows: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM posts").fetch_one(db_pool).await.unwrap();
+    assert_eq!(rows, 0);
+
+    // Only rows deleted before the cutoff are purged
+    let now = chrono::Utc::now().timestamp();
+    sqlx::query("UPDATE skills SET deleted_at = $1 WHERE id = $2").bind(now - 31 * 24 * 60 * 60).bind(&skill_id).execute(db_pool).await.unwrap();
+    assert_eq!(trash.purge_deleted_before(now - 30 * 24 * 60 * 60).await.unwrap(), 1);
+    assert_eq!(trash.purge_deleted_before(now).await.unwrap(), 1);
+    assert_eq!(trash.purge_deleted_before(now).await.unwrap(), 0);
+}
+
+#[actix_web::test]
+async fn test_trash() {
+    use crate::auth::{AuthMiddleware, User};
+
+    let db_pool = setup_test_db().await;
+    check_trash(&db_pool).await;
+
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(db_pool.clone()))
+            .configure(routes::projects::config)
+            .configure(routes::skills::config)
+            .configure(routes::trash::config)
+    ).await;
+    let token = |role: &str| {
+        let user = User { id: format!("{}-1", role), name: format!("Test {}", role), role: role.to_string() };
+        format!("Bearer {}", AuthMiddleware::generate_token(&user).unwrap())
+    };
+    let editor = token("editor");
+    let call = |method: test::TestRequest, uri: &str| method.uri(uri).insert_header(("Authorization", editor.clone())).insert_header(("If-Match", "*")).to_request();
+
+    let req = test::TestRequest::post().uri("/projects")
+        .insert_header(("Authorization", editor.clone()))
+        .set_json(serde_json::json!({ "title": "Site", "description": "", "technologies": [], "year": 2024, "highlights": [] }))
+        .to_request();
+    let project: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    let id = project["id"].as_str().unwrap().to_string();
+
+    assert!(test::call_service(&app, call(test::TestRequest::delete(), &format!("/projects/{}", id))).await.status().is_success());
+    assert_eq!(test::call_service(&app, call(test::TestRequest::delete(), &format!("/projects/{}", id))).await.status(), StatusCode::NOT_FOUND);
+    assert_eq!(test::call_service(&app, call(test::TestRequest::get(), "/projects/by-slug/site")).await.status(), StatusCode::NOT_FOUND);
+
+    let resp = test::call_service(&app, call(test::TestRequest::get(), "/admin/trash")).await;
+    assert_eq!(resp.headers().get("X-Total-Count").unwrap(), "1");
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!((body[0]["kind"].as_str(), body[0]["title"].as_str()), (Some("projects"), Some("Site")));
+
+    let req = test::TestRequest::get().uri("/admin/trash").insert_header(("Authorization", token("viewer"))).to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), StatusCode::FORBIDDEN);
+
+    // Restoring brings the project back under its slug
+    let restore = format!("/admin/trash/projects/{}/restore", id);
+    assert_eq!(test::call_service(&app, call(test::TestRequest::post(), &restore)).await.status(), StatusCode::NO_CONTENT);
+    assert_eq!(test::call_service(&app, call(test::TestRequest::post(), &restore)).await.status(), StatusCode::NOT_FOUND);
+    assert_eq!(test::call_service(&app, call(test::TestRequest::get(), "/projects/by-slug/site")).await.status(), StatusCode::OK);
+    let req = call(test::TestRequest::post(), &format!("/admin/trash/users/{}/restore", id));
+    assert_eq!(test::call_service(&app, req).await.status(), StatusCode::NOT_FOUND);
+
+    // Purging and emptying the trash
+    test::call_service(&app, call(test::TestRequest::delete(), &format!("/projects/{}", id))).await;
+    let req = call(test::TestRequest::delete(), &format!("/admin/trash/projects/{}", id));
+    assert_eq!(test::call_service(&app, req).await.status(), StatusCode::NO_CONTENT);
+    let req = call(test::TestRequest::delete(), &format!("/admin/trash/projects/{}", id));
+    assert_eq!(test::call_service(&app, req).await.status(), StatusCode::NOT_FOUND);
+
+    let req = test::TestRequest::post().uri("/skills")
+        .insert_header(("Authorization", editor.clone()))
+        .set_json(serde_json::json!({ "category": "Tools", "items": ["Git"] }))
+        .to_request();
+    assert!(test::call_service(&app, req).await.status().is_success());
+    assert!(test::call_service(&app, call(test::TestRequest::delete(), "/skills/Tools")).await.status().is_success());
+    assert_eq!(test::call_service(&app, call(test::TestRequest::get(), "/skills/Tools")).await.status(), StatusCode::NOT_FOUND);
+    let body: serde_json::Value = test::call_and_read_body_json(&app, call(test::TestRequest::delete(), "/admin/trash")).await;
+    assert_eq!(body["purged"], 1);
+}
+
+// Versioned writes shared by both backends, starting from empty content tables
+async fn check_versions(db_pool: &DbPool) {
+    use crate::models::experience::Experience;
+    use crate::models::experience_repository::ExperienceRepository;
+    use crate::models::profile::Profile;
+    use crate::models::profile_repository::ProfileRepository;
+    use crate::models::project::Project;
+    use crate::models::project_repository::ProjectRepository;
+    use crate::models::repository::{Repository, Versioned};
+
+    let projects = ProjectRepository::new(db_pool.clone());
+    let project = projects
+        .create(Project::new("Greenhouse".to_string(), String::new(), vec![], None, None, None, None, 2024, vec![]))
+        .await.unwrap();
+    assert_eq!(projects.find_version(&project.id).await.unwrap(), Some(1));
+
+    // A write at the current version bumps it; one at an older version is refused
+    let mut renamed = project.clone();
+    renamed.title = "Glasshouse".to_string();
+    assert!(projects.update_at(&project.id, renamed.clone(), 1).await.unwrap().is_some());
+    assert!(projects.update_at(&project.id, project.clone(), 1).await.unwrap().is_none());
+    assert_eq!(projects.find_by_id(&project.id).await.unwrap().unwrap().title, "Glasshouse");
+
+    // Unconditional writes bump the version too
+    projects.update(&project.id, renamed).await.unwrap();
+    assert_eq!(projects.find_version(&project.id).await.unwrap(), Some(3));
+    assert!(!projects.delete_at(&project.id, 2).await.unwrap());
+    assert!(projects.delete_at(&project.id, 3).await.unwrap());
+    assert_eq!(projects.find_version(&project.id).await.unwrap(), None);
+
+    let experiences = ExperienceRepository::new(db_pool.clone());
+    let experience = experiences
+        .create(Experience::new("Acme".to_string(), "Engineer".to_string(), "2020-01".to_string(), None, String::new(), vec![], vec![]))
+        .await.unwrap();
+    assert!(experiences.update_at(&experience.id, experience.clone(), 2).await.unwrap().is_none());
+    assert!(experiences.update_at(&experience.id, experience.clone(), 1).await.unwrap().is_some());
+
+    let profiles = ProfileRepository::new(db_pool.clone());
+    profiles.create(Profile { bio: vec![], social_links: vec![], education: vec![], languages: vec![] }).await.unwrap();
+    let id: String = sqlx::query_scalar("SELECT id FROM profiles").fetch_one(db_pool).await.unwrap();
+    let profile = Profile { bio: vec!["Hello".to_string()], social_links: vec![], education: vec![], languages: vec![] };
+    assert_eq!(profiles.update_at(&id, profile, 1).await.unwrap().unwrap().bio, vec!["Hello".to_string()]);
+    assert_eq!(profiles.find_version(&id).await.unwrap(), Some(2));
+}
+
+#[actix_web::test]
+async fn test_versions() {
+    use crate::auth::{AuthMiddleware, User};
+
+    let db_pool = setup_test_db().await;
+    check_versions(&db_pool).await;
+    sqlx::query("DELETE FROM profiles").execute(&db_pool).await.unwrap();
+
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(db_pool.clone()))
+            .configure(routes::profile::config)
+            .configure(routes::projects::config)
+            .configure(routes::skills::config)
+    ).await;
+    let user = User { id: "editor-1".to_string(), name: "Test editor".to_string(), role: "editor".to_string() };
+    let editor = format!("Bearer {}", AuthMiddleware::generate_token(&user).unwrap());
+    let call = |method: test::TestRequest, uri: &str, etag: Option<&str>| {
+        let req = method.uri(uri).insert_header(("Authorization", editor.clone()));
+        let req = match etag {
+            Some(etag) => req.insert_header(("If-Match", etag.to_string())),
+            None => req,
+        };
+        req.set_json(serde_json::json!({ "description": "Changed", "items": ["Git", "Make"] })).to_request()
+    };
+
+    let req = test::TestRequest::post().uri("/projects")
+        .insert_header(("Authorization", editor.clone()))
+        .set_json(serde_json::json!({ "title": "Site", "description": "", "technologies": [], "year": 2024, "highlights": [] }))
+        .to_request();
+    let project: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    let uri = format!("/projects/{}", project["id"].as_str().unwrap());
+
+    let resp = test::call_service(&app, test::TestRequest::get().uri(&uri).to_request()).await;
+    assert_eq!(resp.headers().get("ETag").unwrap(), "\"1\"");
+
+    // Writes need the ETag the client last saw
+    assert_eq!(test::call_service(&app, call(test::TestRequest::put(), &uri, None)).await.status(), StatusCode::PRECONDITION_REQUIRED);
+    assert_eq!(test::call_service(&app, call(test::TestRequest::put(), &uri, Some("\"0\""))).await.status(), StatusCode::PRECONDITION_FAILED);
+    let resp = test::call_service(&app, call(test::TestRequest::put(), &uri, Some("\"1\""))).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(resp.headers().get("ETag").unwrap(), "\"2\"");
+    assert_eq!(test::call_service(&app, call(test::TestRequest::put(), &uri, Some("\"1\""))).await.status(), StatusCode::PRECONDITION_FAILED);
+    assert_eq!(test::call_service(&app, call(test::TestRequest::delete(), &uri, Some("\"1\""))).await.status(), StatusCode::PRECONDITION_FAILED);
+    let resp = test::call_service(&app, call(test::TestRequest::delete(), &uri, Some("\"5\", \"2\""))).await;
+    assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+    assert_eq!(test::call_service(&app, call(test::TestRequest::put(), &uri, Some("*"))).await.status(), StatusCode::NOT_FOUND);
+
+    let req = test::TestRequest::post().uri("/skills")
+        .insert_header(("Authorization", editor.clone()))
+        .set_json(serde_json::json!({ "category": "Tools", "items": ["Git"] }))
+        .to_request();
+    assert!(test::call_service(&app, req).await.status().is_success());
+    let resp = test::call_service(&app, test::TestRequest::get().uri("/skills/Tools").to_request()).await;
+    assert_eq!(resp.headers().get("ETag").unwrap(), "\"1\"");
+    let resp = test::call_service(&app, call(test::TestRequest::put(), "/skills/Tools", Some("*"))).await;
+    assert_eq!(resp.headers().get("ETag").unwrap(), "\"2\"");
+
+    // The first save of the profile has nothing to match
+    let resp = test::call_service(&app, call(test::TestRequest::put(), "/profile", None)).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let resp = test::call_service(&app, test::TestRequest::get().uri("/profile").to_request()).await;
+    assert_eq!(resp.headers().get("ETag").unwrap(), "\"2\"");
+    assert_eq!(test::call_service(&app, call(test::TestRequest::put(), "/profile", None)).await.status(), StatusCode::PRECONDITION_REQUIRED);
+    assert_eq!(test::call_service(&app, call(test::TestRequest::put(), "/profile", Some("\"2\""))).await.status(), StatusCode::OK);
+}
+
+// Last modification times shared by both backends, starting from empty content tables
+async fn check_last_modified(db_pool: &DbPool) {
+    use crate::models::project::Project;
+    use crate::models::project_repository::ProjectRepository;
+    use crate::models::repository::{find_last_modified, Repository};
+    use crate::models::trash_repository::TrashRepository;
+
+    let projects = ProjectRepository::new(db_pool.clone());
+    let now = chrono::Utc::now().timestamp();
+    assert_eq!(find_last_modified(db_pool, &["projects", "profiles"]).await.unwrap(), None);
+
+    let project = projects
+        .create(Project::new("Greenhouse".to_string(), String::new(), vec![], None, None, None, None, 2024, vec![]))
+        .await.unwrap();
+    sqlx::query("UPDATE projects SET updated_at = '2020-01-01 00:00:00'").execute(db_pool).await.unwrap();
+    assert_eq!(find_last_modified(db_pool, &["projects", "profiles"]).await.unwrap(), Some(1577836800));
+
+    // Moving to the trash and back both count as writes
+    projects.delete(&project.id).await.unwrap();
+    assert!(find_last_modified(db_pool, &["projects"]).await.unwrap().is_some_and(|time| time >= now));
+    sqlx::query("UPDATE projects SET deleted_at = 0").execute(db_pool).await.unwrap();
+    assert_eq!(find_last_modified(db_pool, &["projects"]).await.unwrap(), Some(1577836800));
+    TrashRepository::new(db_pool.clone()).restore("projects", &project.id).await.unwrap();
+    assert!(find_last_modified(db_pool, &["projects"]).await.unwrap().is_some_and(|time| time >= now));
+}
+
+#[actix_web::test]
+async fn test_conditional_get() {
+    use crate::auth::{AuthMiddleware, User};
+
+    let db_pool = setup_test_db().await;
+    check_last_modified(&db_pool).await;
+
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(db_pool.clone()))
+            .configure(routes::posts::config)
+            .configure(routes::profile::config)
+            .configure(routes::projects::config)
+    ).await;
+    let editor = User { id: "editor-1".to_string(), name: "Test editor".to_string(), role: "editor".to_string() };
+    let token = format!("Bearer {}", AuthMiddleware::generate_token(&editor).unwrap());
+    let get = |uri: &str, header: Option<(&'static str, String)>| {
+        let req = test::TestRequest::get().uri(uri);
+        match header {
+            Some(header) => req.insert_header(header).to_request(),
+            None => req.to_request(),
+        }
+    };
+
+    let resp = test::call_service(&app, get("/projects", None)).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(resp.headers().get("Cache-Control").unwrap(), "public, max-age=60");
+    let etag = resp.headers().get("ETag").unwrap().to_str().unwrap().to_string();
+    let last_modified = resp.headers().get("Last-Modified").unwrap().to_str().unwrap().to_string();
+
+    // The client's copy is current by ETag or by date
+    let resp = test::call_service(&app, get("/projects", Some(("If-None-Match", etag.clone())))).await;
+    assert_eq!(resp.status(), StatusCode::NOT_MODIFIED);
+    assert_eq!(resp.headers().get("ETag").unwrap(), etag.as_str());
+    assert!(test::read_body(resp).await.is_empty());
+    let resp = test::call_service(&app, get("/projects", Some(("If-Modified-Since", last_modified.clone())))).await;
+    assert_eq!(resp.status(), StatusCode::NOT_MODIFIED);
+    let resp = test::call_service(&app, get("/projects", Some(("If-Modified-Since", "Sat, 01 Jan 2000 00:00:00 GMT".to_string())))).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    // A change gives a new ETag
+    sqlx::query("UPDATE projects SET title = 'Glasshouse', updated_at = CURRENT_TIMESTAMP").execute(&db_pool).await.unwrap();
+    let resp = test::call_service(&app, get("/projects", Some(("If-None-Match", etag.clone())))).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_ne!(resp.headers().get("ETag").unwrap(), etag.as_str());
+
+    // Items with a version keep it as their ETag
+    let req = test::TestRequest::put().uri("/profile")
+        .insert_header(("Authorization", token.clone()))
+        .set_json(serde_json::json!({ "bio": ["Hello"] }))
+        .to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), StatusCode::OK);
+    let resp = test::call_service(&app, get("/profile", Some(("If-None-Match", "\"2\"".to_string())))).await;
+    assert_eq!(resp.status(), StatusCode::NOT_MODIFIED);
+
+    // Previews are only cached by the browser
+    let resp = test::call_service(&app, get("/posts?preview=true", Some(("Authorization", token)))).await;
+    assert_eq!(resp.headers().get("Cache-Control").unwrap(), "private, no-cache");
+}
+
+#[actix_web::test]
+async fn test_json_patch() {
+    use crate::patch::{json_patch, merge_patch, PatchOperation};
+    use actix_web::ResponseError;
+
+    // The example of RFC 7396
+    let mut document = serde_json::json!({ "title": "Goodbye!", "author": { "givenName": "John", "familyName": "Doe" }, "tags": ["example", "sample"], "content": "This will be unchanged" });
+    merge_patch(&mut document, &serde_json::json!({ "title": "Hello!", "phoneNumber": "+01-123-456-7890", "author": { "familyName": null }, "tags": ["example"] }));
+    assert_eq!(document, serde_json::json!({ "title": "Hello!", "author": { "givenName": "John" }, "tags": ["example"], "content": "This will be unchanged", "phoneNumber": "+01-123-456-7890" }));
+
+    let operations = |value: serde_json::Value| serde_json::from_value::<Vec<PatchOperation>>(value).unwrap();
+    let mut document = serde_json::json!({ "foo": ["bar", "baz"], "a/b": 1, "m~n": { "x": 2 } });
+    json_patch(&mut document, &operations(serde_json::json!([
+        { "op": "test", "path": "/a~1b", "value": 1 },
+        { "op": "add", "path": "/foo/1", "value": "qux" },
+        { "op": "add", "path": "/foo/-", "value": "end" },
+        { "op": "remove", "path": "/foo/0" },
+        { "op": "replace", "path": "/m~0n/x", "value": 3 },
+        { "op": "copy", "from": "/m~0n", "path": "/copy" },
+        { "op": "move", "from": "/a~1b", "path": "/moved" }
+    ]))).unwrap();
+    assert_eq!(document, serde_json::json!({ "foo": ["qux", "baz", "end"], "m~n": { "x": 3 }, "copy": { "x": 3 }, "moved": 1 }));
+
+    // Operations that do not fit the document conflict; malformed ones are rejected
+    for (operation, status) in [
+        (serde_json::json!({ "op": "test", "path": "/moved", "value": 2 }), StatusCode::CONFLICT),
+        (serde_json::json!({ "op": "remove", "path": "/missing" }), StatusCode::CONFLICT),
+        (serde_json::json!({ "op": "add", "path": "/foo/4", "value": 0 }), StatusCode::CONFLICT),
+        (serde_json::json!({ "op": "replace", "path": "/foo/3", "value": 0 }), StatusCode::CONFLICT),
+        (serde_json::json!({ "op": "add", "path": "/foo/01", "value": 0 }), StatusCode::BAD_REQUEST),
+        (serde_json::json!({ "op": "add", "path": "foo", "value": 0 }), StatusCode::BAD_REQUEST),
+        (serde_json::json!({ "op": "move", "from": "/m~0n", "path": "/m~0n/y" }), StatusCode::BAD_REQUEST),
+    ] {
+        let error = json_patch(&mut document.clone(), &operations(serde_json::json!([operation.clone()]))).unwrap_err();
+        assert_eq!(error.status_code(), status, "{}", operation);
+    }
+    assert!(serde_json::from_value::<Vec<PatchOperation>>(serde_json::json!([{ "op": "merge", "path": "/foo" }])).is_err());
+}
+
+#[actix_web::test]
+async fn test_patch() {
+    use crate::auth::{AuthMiddleware, User};
+
+    let db_pool = setup_test_db().await;
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(db_pool.clone()))
+            .configure(routes::posts::config)
+            .configure(routes::profile::config)
+            .configure(routes::projects::config)
+    ).await;
+    let user = User { id: "editor-1".to_string(), name: "Test editor".to_string(), role: "editor".to_string() };
+    let editor = format!("Bearer {}", AuthMiddleware::generate_token(&user).unwrap());
+    let patch = |uri: &str, content_type: &str, etag: Option<&str>, body: serde_json::Value| {
+        let req = test::TestRequest::patch().uri(uri)
+            .insert_header(("Authorization", editor.clone()))
+            .insert_header(("Content-Type", content_type.to_string()))
+            .set_payload(body.to_string());
+        match etag {
+            Some(etag) => req.insert_header(("If-Match", etag.to_string())).to_request(),
+            None => req.to_request(),
+        }
+    };
+    let merge = "application/merge-patch+json";
+    let json_patch = "application/json-patch+json";
+
+    let req = test::TestRequest::post().uri("/projects")
+        .insert_header(("Authorization", editor.clone()))
+        .set_json(serde_json::json!({ "title": "Site", "description": "", "technologies": ["Rust"], "github_url": "https://github.com/example/site", "year": 2024, "highlights": [] }))
+        .to_request();
+    let project: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    let uri = format!("/projects/{}", project["id"].as_str().unwrap());
+
+    // null clears an optional field; absent fields are kept
+    let resp = test::call_service(&app, patch(&uri, merge, Some("\"1\""), serde_json::json!({ "github_url": null, "title": "New site" }))).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(resp.headers().get("ETag").unwrap(), "\"2\"");
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!((body["github_url"].clone(), body["title"].as_str(), body["technologies"].clone()), (serde_json::Value::Null, Some("New site"), serde_json::json!(["Rust"])));
+    assert_eq!(body["slug"], "site");
+
+    let resp = test::call_service(&app, patch(&uri, json_patch, Some("*"), serde_json::json!([
+        { "op": "test", "path": "/title", "value": "New site" },
+        { "op": "add", "path": "/technologies/-", "value": "SQLite" },
+        { "op": "replace", "path": "/slug", "value": "new-site" }
+    ]))).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!((body["technologies"].clone(), body["slug"].as_str()), (serde_json::json!(["Rust", "SQLite"]), Some("new-site")));
+    let resp = test::call_service(&app, test::TestRequest::get().uri("/projects/by-slug/site").to_request()).await;
+    assert_eq!(resp.status(), StatusCode::MOVED_PERMANENTLY);
+
+    // The patched project must still be valid, and nothing is saved otherwise
+    for (content_type, body, status) in [
+        (merge, serde_json::json!({ "year": "soon" }), StatusCode::BAD_REQUEST),
+        (merge, serde_json::json!({ "title": null }), StatusCode::BAD_REQUEST),
+        (merge, serde_json::json!({ "githuburl": "https://example.com" }), StatusCode::BAD_REQUEST),
+        (merge, serde_json::json!({ "id": "other" }), StatusCode::BAD_REQUEST),
+        (merge, serde_json::json!({ "slug": "Not a slug" }), StatusCode::BAD_REQUEST),
+        (json_patch, serde_json::json!([{ "op": "test", "path": "/title", "value": "Site" }]), StatusCode::CONFLICT),
+        (json_patch, serde_json::json!({ "op": "remove", "path": "/title" }), StatusCode::BAD_REQUEST),
+    ] {
+        let resp = test::call_service(&app, patch(&uri, content_type, Some("*"), body.clone())).await;
+        assert_eq!(resp.status(), status, "{}", body);
+    }
+    let resp = test::call_service(&app, patch(&uri, "application/json", Some("*"), serde_json::json!({ "title": "Plain" }))).await;
+    assert_eq!(resp.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+    assert_eq!(resp.headers().get("Accept-Patch").unwrap(), "application/merge-patch+json, application/json-patch+json");
+    assert_eq!(test::call_service(&app, patch(&uri, merge, None, serde_json::json!({}))).await.status(), StatusCode::PRECONDITION_REQUIRED);
+    assert_eq!(test::call_service(&app, patch(&uri, merge, Some("\"1\""), serde_json::json!({}))).await.status(), StatusCode::PRECONDITION_FAILED);
+
+    let resp = test::call_service(&app, test::TestRequest::get().uri(&uri).to_request()).await;
+    assert_eq!(resp.headers().get("ETag").unwrap(), "\"3\"");
+    let revisions: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM revisions WHERE target = 'projects'").fetch_one(&db_pool).await.unwrap();
+    assert_eq!(revisions, 3);
+
+    // Posts keep their publication rules
+    let req = test::TestRequest::post().uri("/posts")
+        .insert_header(("Authorization", editor.clone()))
+        .set_json(serde_json::json!({ "title": "Draft", "date": "2025-02-01", "tags": [], "excerpt": "", "content": "", "publish_at": 4102444800i64 }))
+        .to_request();
+    let post: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    let uri = format!("/posts/{}", post["id"].as_str().unwrap());
+    let resp = test::call_service(&app, patch(&uri, merge, Some("*"), serde_json::json!({ "status": "published" }))).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    let resp = test::call_service(&app, patch(&uri, merge, Some("*"), serde_json::json!({ "status": "published", "publish_at": null }))).await;
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["status"], "published");
+    assert!(body["publish_at"].as_i64().is_some_and(|publish_at| publish_at < 4102444800));
+
+    // The profile must exist to be patched
+    assert_eq!(test::call_service(&app, patch("/profile", merge, Some("*"), serde_json::json!({}))).await.status(), StatusCode::NOT_FOUND);
+    let req = test::TestRequest::put().uri("/profile")
+        .insert_header(("Authorization", editor.clone()))
+        .set_json(serde_json::json!({ "bio": ["Second"] }))
+        .to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), StatusCode::OK);
+    let resp = test::call_service(&app, patch("/profile", json_patch, Some("*"), serde_json::json!([{ "op": "add", "path": "/bio/0", "value": "First" }]))).await;
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["bio"], serde_json::json!(["First", "Second"]));
+}
+
+async fn check_bundle(db_pool: &DbPool) {
+    use crate::models::bundle::{ImportAction, ImportMode};
+    use crate::models::bundle_repository::BundleRepository;
+    use crate::models::post::Post;
+    use crate::models::post_repository::PostRepository;
+    use crate::models::project::Project;
+    use crate::models::project_repository::ProjectRepository;
+    use crate::models::repository::{Repository, Versioned};
+
+    let author = crate::auth::User { id: "admin-1".to_string(), name: "Test".to_string(), role: "admin".to_string() };
+    let projects = ProjectRepository::new(db_pool.clone());
+    let mut project = Project::new("Orchard".to_string(), String::new(), vec!["Rust".to_string()], None, None, None, None, 2024, vec![]);
+    project.slug = "orchard".to_string();
+    let project = projects.create(project).await.unwrap();
+    let posts = PostRepository::new(db_pool.clone());
+    let post = |title: &str, slug: &str| {
+        let mut post = Post::new(title.to_string(), "2025-01-01".to_string(), vec![], String::new(), String::new());
+        post.slug = slug.to_string();
+        post
+    };
+    posts.create(post("Draft", "draft")).await.unwrap();
+
+    // An export imports back as it is, drafts included
+    let bundles = BundleRepository::new(db_pool.clone());
+    let mut bundle = bundles.export().await.unwrap();
+    assert_eq!((bundle.projects.len(), bundle.posts.len()), (1, 1));
+    let report = bundles.import(bundle.clone(), ImportMode::Replace, false, &author).await.unwrap();
+    assert_eq!((report.changes.len(), report.unchanged), (0, 2));
+
+    // Changes are rolled back on a dry run and saved otherwise, within one transaction
+    bundle.projects[0].title = "Orchards".to_string();
+    bundle.posts.clear();
+    let report = bundles.import(bundle.clone(), ImportMode::Replace, true, &author).await.unwrap();
+    let actions: Vec<_> = report.changes.iter().map(|change| (change.kind.as_str(), change.action)).collect();
+    assert_eq!(actions, [("projects", ImportAction::Update), ("posts", ImportAction::Delete)]);
+    assert_eq!(projects.find_by_id(&project.id).await.unwrap().unwrap().title, "Orchard");
+    bundles.import(bundle.clone(), ImportMode::Replace, false, &author).await.unwrap();
+    assert_eq!(projects.find_version(&project.id).await.unwrap(), Some(2));
+    assert!(posts.find_all().await.unwrap().is_empty());
+
+    // A failing item leaves the earlier ones untouched
+    bundle.projects[0].title = "Grove".to_string();
+    posts.create(post("Taken", "taken")).await.unwrap();
+    bundle.posts = vec![post("Taken", "taken")];
+    assert!(bundles.import(bundle, ImportMode::Merge, false, &author).await.is_err());
+    assert_eq!(projects.find_by_id(&project.id).await.unwrap().unwrap().title, "Orchards");
+}
+
+#[actix_web::test]
+async fn test_bundle_repository() {
+    let db_pool = setup_test_db().await;
+    check_bundle(&db_pool).await;
+}
+
+#[actix_web::test]
+async fn test_bundle() {
+    use crate::auth::{AuthMiddleware, User};
+    use crate::services::yaml::{from_yaml, to_yaml};
+
+    let db_pool = setup_test_db().await;
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(db_pool.clone()))
+            .configure(routes::bundle::config)
+            .configure(routes::posts::config)
+            .configure(routes::profile::config)
+            .configure(routes::projects::config)
+            .configure(routes::skills::config)
+    ).await;
+    let token = |name: &str, role: &str| {
+        let user = User { id: format!("{}-1", role), name: name.to_string(), role: role.to_string() };
+        format!("Bearer {}", AuthMiddleware::generate_token(&user).unwrap())
+    };
+    let (admin, editor) = (token("Test admin", "admin"), token("Test editor", "editor"));
+    let import = |uri: &str, content_type: &str, body: String| {
+        test::TestRequest::post().uri(uri)
+            .insert_header(("Authorization", admin.clone()))
+            .insert_header(("Content-Type", content_type.to_string()))
+            .set_payload(body)
+            .to_request()
+    };
+
+    for (uri, body) in [
+        ("/projects", serde_json::json!({ "title": "Site", "description": "", "technologies": ["Rust"], "year": 2024, "highlights": [] })),
+        ("/posts", serde_json::json!({ "title": "Hello", "date": "2025-02-01", "tags": ["rust"], "excerpt": "", "content": "Hi" })),
+        ("/skills", serde_json::json!({ "category": "Languages", "items": [{ "name": "Rust", "proficiency": "advanced" }] })),
+    ] {
+        let req = test::TestRequest::post().uri(uri).insert_header(("Authorization", editor.clone())).set_json(body).to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), StatusCode::CREATED);
+    }
+    let req = test::TestRequest::put().uri("/profile").insert_header(("Authorization", editor.clone())).set_json(serde_json::json!({ "bio": ["Hello"] })).to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), StatusCode::OK);
+
+    // Exports as JSON and YAML hold the same bundle
+    let resp = test::call_service(&app, test::TestRequest::get().uri("/admin/export").insert_header(("Authorization", editor.clone())).to_request()).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert!(resp.headers().get("Content-Disposition").unwrap().to_str().unwrap().ends_with(".json\""));
+    let mut bundle: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(bundle["format_version"], 1);
+    assert_eq!((bundle["projects"][0]["title"].as_str(), bundle["posts"][0]["slug"].as_str()), (Some("Site"), Some("hello")));
+    let resp = test::call_service(&app, test::TestRequest::get().uri("/admin/export?format=yaml").insert_header(("Authorization", editor.clone())).to_request()).await;
+    assert_eq!(resp.headers().get("Content-Type").unwrap(), "application/yaml; charset=utf-8");
+    let yaml = String::from_utf8(test::read_body(resp).await.to_vec()).unwrap();
+    let mut from_yaml_bundle = from_yaml(&yaml).unwrap();
+    from_yaml_bundle["exported_at"] = bundle["exported_at"].clone();
+    assert_eq!(from_yaml_bundle, bundle);
+    assert_eq!(test::call_service(&app, test::TestRequest::get().uri("/admin/export?format=xml").insert_header(("Authorization", editor.clone())).to_request()).await.status(), StatusCode::BAD_REQUEST);
+
+    // Editors may export but not import
+    let req = test::TestRequest::post().uri("/admin/import").insert_header(("Authorization", editor.clone())).set_json(&bundle).to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), StatusCode::FORBIDDEN);
+
+    // A dry run reports the changes without saving them
+    let original = bundle.clone();
+    bundle["projects"][0]["title"] = serde_json::json!("New site");
+    bundle["projects"].as_array_mut().unwrap().push(serde_json::json!({ "id": "imported-1", "title": "Imported", "description": "", "technologies": ["rust"], "year": 2025, "highlights": [], "slug": "" }));
+    bundle["posts"] = serde_json::json!([]);
+    let resp = test::call_service(&app, import("/admin/import?mode=replace&dry_run=true", "application/json", bundle.to_string())).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let report: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!((report["dry_run"].as_bool(), report["created"].as_u64(), report["updated"].as_u64(), report["deleted"].as_u64(), report["unchanged"].as_u64()), (Some(true), Some(1), Some(1), Some(1), Some(2)));
+    let update = report["changes"].as_array().unwrap().iter().find(|change| change["action"] == "update").unwrap();
+    assert_eq!(update["changes"], serde_json::json!([{ "path": "/title", "from": "Site", "to": "New site" }]));
+    let title: String = sqlx::query_scalar("SELECT title FROM projects WHERE slug = 'site'").fetch_one(&db_pool).await.unwrap();
+    assert_eq!(title, "Site");
+
+    // Replace deletes what the bundle lacks, normalizing what it brings
+    let resp = test::call_service(&app, import("/admin/import?mode=replace", "application/json", bundle.to_string())).await;
+    let report: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!((report["dry_run"].as_bool(), report["deleted"].as_u64()), (Some(false), Some(1)));
+    let resp = test::call_service(&app, test::TestRequest::get().uri("/projects/imported-1").to_request()).await;
+    let project: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!((project["slug"].as_str(), project["technologies"].clone()), (Some("imported"), serde_json::json!(["Rust"])));
+    let deleted: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM posts WHERE deleted_at IS NOT NULL").fetch_one(&db_pool).await.unwrap();
+    assert_eq!(deleted, 1);
+    let revisions: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM revisions WHERE target = 'projects' AND author_name = 'Test admin'").fetch_one(&db_pool).await.unwrap();
+    assert_eq!(revisions, 2);
+    let resp = test::call_service(&app, import("/admin/import?mode=replace", "application/json", bundle.to_string())).await;
+    let report: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!((report["changes"].as_array().map(Vec::len), report["unchanged"].as_u64()), (Some(0), Some(4)));
+
+    // Merging the YAML export brings the deleted post back and keeps the new project
+    let resp = test::call_service(&app, import("/admin/import", "application/yaml", to_yaml(&original).unwrap())).await;
+    let report: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!((report["mode"].as_str(), report["created"].as_u64(), report["updated"].as_u64(), report["deleted"].as_u64()), (Some("merge"), Some(1), Some(1), Some(0)));
+    let deleted: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM posts WHERE deleted_at IS NOT NULL").fetch_one(&db_pool).await.unwrap();
+    assert_eq!(deleted, 0);
+    let projects: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM projects WHERE deleted_at IS NULL").fetch_one(&db_pool).await.unwrap();
+    assert_eq!(projects, 2);
+
+    // Invalid bundles are refused as a whole
+    let mut duplicate = original.clone();
+    duplicate["projects"][0]["title"] = serde_json::json!("Duplicate");
+    duplicate["projects"].as_array_mut().unwrap().push(original["projects"][0].clone());
+    let mut taken = original.clone();
+    taken["projects"][0]["title"] = serde_json::json!("Taken");
+    taken["projects"][0]["slug"] = serde_json::json!("imported");
+    let mut future = original.clone();
+    future["format_version"] = serde_json::json!(2);
+    let mut unknown = original.clone();
+    unknown["pages"] = serde_json::json!([]);
+    let mut unscheduled = original.clone();
+    unscheduled["posts"][0]["status"] = serde_json::json!("scheduled");
+    unscheduled["posts"][0]["publish_at"] = serde_json::Value::Null;
+    let mut early = original.clone();
+    early["posts"][0]["status"] = serde_json::json!("published");
+    early["posts"][0]["publish_at"] = serde_json::json!(chrono::Utc::now().timestamp() + 3600);
+    for (uri, content_type, body, status) in [
+        ("/admin/import", "application/json", duplicate.to_string(), StatusCode::BAD_REQUEST),
+        ("/admin/import", "application/json", taken.to_string(), StatusCode::CONFLICT),
+        ("/admin/import", "application/json", future.to_string(), StatusCode::BAD_REQUEST),
+        ("/admin/import", "application/json", unknown.to_string(), StatusCode::BAD_REQUEST),
+        ("/admin/import", "application/json", unscheduled.to_string(), StatusCode::BAD_REQUEST),
+        ("/admin/import", "application/json", early.to_string(), StatusCode::BAD_REQUEST),
+        ("/admin/import", "application/yaml", "projects: [".to_string(), StatusCode::BAD_REQUEST),
+        ("/admin/import", "text/plain", original.to_string(), StatusCode::UNSUPPORTED_MEDIA_TYPE),
+        ("/admin/import?mode=overwrite", "application/json", original.to_string(), StatusCode::BAD_REQUEST),
+    ] {
+        let resp = test::call_service(&app, import(uri, content_type, body)).await;
+        assert_eq!(resp.status(), status, "{} {}", uri, content_type);
+    }
+    let title: String = sqlx::query_scalar("SELECT title FROM projects WHERE slug = 'site'").fetch_one(&db_pool).await.unwrap();
+    assert_eq!(title, "Site");
+    let statuses: Vec<String> = sqlx::query_scalar("SELECT status FROM posts").fetch_all(&db_pool).await.unwrap();
+    assert_eq!(statuses, ["draft"]);
+
+    // YAML anchors and aliases are refused, even where the bundle would be valid
+    let aliased = to_yaml(&original).unwrap()
+        .replacen("format_version: 1", "format_version: &version 1", 1)
+        .replacen("year: 2024", "year: *version", 1);
+    assert!(aliased.contains("*version"));
+    let resp = test::call_service(&app, import("/admin/import", "application/yaml", aliased)).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert!(body["message"].as_str().unwrap().contains("anchors and aliases"));
+    let laughs = "a: &a [lol, lol, lol]\nb: &b [*a, *a, *a]\nc: [*b, *b, *b]\n";
+    assert!(from_yaml(laughs).is_err());
+
+    // Items of the bundle may swap slugs, but not both take the same one
+    let resp = test::call_service(&app, test::TestRequest::get().uri("/admin/export").insert_header(("Authorization", admin.clone())).to_request()).await;
+    let mut swapped: serde_json::Value = test::read_body_json(resp).await;
+    let projects = swapped["projects"].as_array_mut().unwrap();
+    let slugs = (projects[0]["slug"].clone(), projects[1]["slug"].clone());
+    (projects[0]["slug"], projects[1]["slug"]) = (slugs.1.clone(), slugs.0.clone());
+    let mut shared = swapped.clone();
+    shared["projects"][1]["slug"] = slugs.1.clone();
+    let resp = test::call_service(&app, import("/admin/import", "application/json", shared.to_string())).await;
+    assert_eq!(resp.status(), StatusCode::CONFLICT);
+    let resp = test::call_service(&app, import("/admin/import", "application/json", swapped.to_string())).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    for project in swapped["projects"].as_array().unwrap() {
+        let uri = format!("/projects/by-slug/{}", project["slug"].as_str().unwrap());
+        let resp = test::call_service(&app, test::TestRequest::get().uri(&uri).to_request()).await;
         assert_eq!(resp.status(), StatusCode::OK);
+        let found: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(found["id"], project["id"]);
+    }
+    let aliases: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM slug_aliases WHERE target = 'projects' AND slug IN (SELECT slug FROM projects)")
+        .fetch_one(&db_pool).await.unwrap();
+    assert_eq!(aliases, 0);
+}
+
+#[actix_web::test]
+async fn test_search() {
+    use crate::models::search::{fts5_query, parse_search_terms, tsquery, SearchTerm};
+
+    let terms = parse_search_terms("Rust's \"web\" kube* OR -x");
+    assert_eq!(terms[3], SearchTerm { word: "kube".to_string(), prefix: true });
+    assert_eq!(fts5_query(&terms), "\"rust\" \"s\" \"web\" \"kube\"* \"or\" \"x\"");
+    assert_eq!(tsquery(&terms), "rust & s & web & kube:* & or & x");
+    assert!(parse_search_terms(" *-\" ").is_empty());
+
+    let db_pool = setup_test_db().await;
+    check_search(&db_pool).await;
+
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(db_pool.clone()))
+            .configure(routes::search::config)
+    ).await;
+
+    let req = test::TestRequest::get().uri("/search?q=Acme&per_page=1").to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(resp.headers().get("x-total-count").unwrap(), "1");
+
+    for uri in ["/search?q=", "/search?q=%2A", "/search?q=rust&type=skill", "/search?q=rust&page=0", "/search"] {
+        let req = test::TestRequest::get().uri(uri).to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), StatusCode::BAD_REQUEST, "{}", uri);
+    }
+}
+
+#[actix_web::test]
+async fn test_accounts() {
+    use crate::auth::{authenticate_user, ensure_admin_user};
+    use crate::models::repository::Repository;
+    use crate::models::user_repository::UserRepository;
+
+    let db_pool = setup_test_db().await;
+
+    // The first admin comes from the environment, once
+    std::env::set_var("ADMIN_USERNAME", "root");
+    std::env::set_var("ADMIN_PASSWORD", "first-password");
+    ensure_admin_user(&db_pool).await.unwrap();
+    std::env::set_var("ADMIN_PASSWORD", "second-password");
+    ensure_admin_user(&db_pool).await.unwrap();
+    std::env::remove_var("ADMIN_USERNAME");
+    std::env::remove_var("ADMIN_PASSWORD");
+    assert_eq!(UserRepository::new(db_pool.clone()).count().await.unwrap(), 1);
+    let root = authenticate_user(&db_pool, "root", "first-password").await.unwrap().unwrap();
+    assert_eq!(root.role, "admin");
+    assert!(authenticate_user(&db_pool, "root", "second-password").await.unwrap().is_none());
+    assert!(authenticate_user(&db_pool, "nobody", "first-password").await.unwrap().is_none());
+
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(db_pool.clone()))
+            .configure(routes::auth::config)
+            .configure(routes::users::config)
+    ).await;
+    let login = |username: &str, password: &str| {
+        test::TestRequest::post().uri("/auth/login")
+            .set_json(serde_json::json!({ "username": username, "password": password }))
+            .to_request()
+    };
+
+    let resp = test::call_service(&app, login("root", "first-password")).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert_eq!(body["user"]["role"], "admin");
+    let admin = format!("Bearer {}", body["token"].as_str().unwrap());
+    assert!(body["refresh_token"].as_str().is_some_and(|token| !token.is_empty()));
+    for (username, password) in [("root", "wrong-password"), ("nobody", "first-password")] {
+        let resp = test::call_service(&app, login(username, password)).await;
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    // Accounts are managed by admins, with known roles and unique usernames
+    let create = |body: serde_json::Value| {
+        test::TestRequest::post().uri("/admin/users")
+            .insert_header(("Authorization", admin.clone()))
+            .set_json(body)
+            .to_request()
+    };
+    let resp = test::call_service(&app, create(serde_json::json!({ "username": "jane", "name": "Jane", "password": "jane-password", "role": "editor" }))).await;
+    assert_eq!(resp.status(), StatusCode::CREATED);
+    let jane: serde_json::Value = test::read_body_json(resp).await;
+    assert!(jane.get("password_hash").is_none());
+    for body in [
+        serde_json::json!({ "username": "jane", "name": "Jane", "password": "jane-password", "role": "editor" }),
+        serde_json::json!({ "username": "joe", "name": "Joe", "password": "joe-password", "role": "owner" }),
+        serde_json::json!({ "username": "joe", "name": "Joe", "password": "short", "role": "viewer" }),
+    ] {
+        assert_eq!(test::call_service(&app, create(body.clone())).await.status(), StatusCode::BAD_REQUEST, "{}", body);
+    }
+    assert_eq!(test::call_service(&app, login("jane", "jane-password")).await.status(), StatusCode::OK);
+
+    let req = test::TestRequest::get().uri("/admin/users?sort=username").insert_header(("Authorization", admin.clone())).to_request();
+    let page: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    let usernames: Vec<&str> = page.as_array().unwrap().iter().map(|user| user["username"].as_str().unwrap()).collect();
+    assert_eq!(usernames, ["jane", "root"]);
+
+    let uri = format!("/admin/users/{}", jane["id"].as_str().unwrap());
+    let req = test::TestRequest::put().uri(&uri)
+        .insert_header(("Authorization", admin.clone()))
+        .set_json(serde_json::json!({ "role": "viewer", "password": "new-jane-password" }))
+        .to_request();
+    let updated: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(updated["role"], "viewer");
+    assert_eq!(test::call_service(&app, login("jane", "jane-password")).await.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(test::call_service(&app, login("jane", "new-jane-password")).await.status(), StatusCode::OK);
+
+    let req = test::TestRequest::delete().uri(&format!("/admin/users/{}", root.id)).insert_header(("Authorization", admin.clone())).to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), StatusCode::BAD_REQUEST);
+    let req = test::TestRequest::delete().uri(&uri).insert_header(("Authorization", admin.clone())).to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), StatusCode::NO_CONTENT);
+    assert_eq!(test::call_service(&app, login("jane", "new-jane-password")).await.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[actix_web::test]
+async fn test_role_permissions() {
+    use crate::auth::{AuthMiddleware, User};
+
+    let db_pool = setup_test_db().await;
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(db_pool.clone()))
+            .configure(routes::admin::config)
+            .configure(routes::projects::config)
+            .configure(routes::users::config)
+    ).await;
+    let project = serde_json::json!({ "title": "Site", "description": "", "technologies": [], "year": 2024, "highlights": [] });
+
+    // Expected status of the dashboard, a content write, and the admin-only
+    // user list and audit log for each role
+    for (role, dashboard, write, users) in [
+        ("admin", StatusCode::OK, StatusCode::CREATED, StatusCode::OK),
+        ("editor", StatusCode::OK, StatusCode::CREATED, StatusCode::FORBIDDEN),
+        ("viewer", StatusCode::OK, StatusCode::FORBIDDEN, StatusCode::FORBIDDEN),
+        ("owner", StatusCode::FORBIDDEN, StatusCode::FORBIDDEN, StatusCode::FORBIDDEN),
+    ] {
+        let user = User { id: format!("{}-1", role), name: format!("Test {}", role), role: role.to_string() };
+        let token = format!("Bearer {}", AuthMiddleware::generate_token(&user).unwrap());
+
+        let req = test::TestRequest::get().uri("/admin/dashboard").insert_header(("Authorization", token.clone())).to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), dashboard, "{} dashboard", role);
+        let req = test::TestRequest::post().uri("/projects").insert_header(("Authorization", token.clone())).set_json(&project).to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), write, "{} write", role);
+        let req = test::TestRequest::get().uri("/admin/users").insert_header(("Authorization", token.clone())).to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), users, "{} users", role);
+        let req = test::TestRequest::get().uri("/admin/login-attempts").insert_header(("Authorization", token.clone())).to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), users, "{} audit", role);
+    }
+
+    // Refused writes leave nothing behind
+    let projects: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM projects").fetch_one(&db_pool).await.unwrap();
+    assert_eq!(projects, 2);
+    let req = test::TestRequest::get().uri("/admin/dashboard").to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), StatusCode::UNAUTHORIZED);
+}
+
+// Store an account with a hashed password, as the admin routes do
+async fn create_account(db_pool: &DbPool, username: &str, password: &str, role: &str) -> crate::models::user::UserAccount {
+    use crate::models::repository::Repository;
+    use crate::models::user::UserAccount;
+    use crate::models::user_repository::UserRepository;
+
+    let hash = crate::auth::hash_password(password).unwrap();
+    let account = UserAccount::new(username.to_string(), format!("{} account", username), hash, role.to_string());
+    UserRepository::new(db_pool.clone()).create(account).await.unwrap()
+}
+
+#[actix_web::test]
+async fn test_sessions() {
+    let db_pool = setup_test_db().await;
+    create_account(&db_pool, "jane", "jane-password", "editor").await;
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(db_pool.clone()))
+            .configure(routes::admin::config)
+            .configure(routes::auth::config)
+    ).await;
+    let login = || {
+        test::TestRequest::post().uri("/auth/login")
+            .set_json(serde_json::json!({ "username": "jane", "password": "jane-password" }))
+            .to_request()
+    };
+    let refresh = |refresh_token: &serde_json::Value| {
+        test::TestRequest::post().uri("/auth/refresh")
+            .set_json(serde_json::json!({ "refresh_token": refresh_token }))
+            .to_request()
+    };
+    let dashboard = |token: &serde_json::Value| {
+        test::TestRequest::get().uri("/admin/dashboard")
+            .insert_header(("Authorization", format!("Bearer {}", token.as_str().unwrap())))
+            .to_request()
+    };
+
+    // Each refresh token is exchanged once for a new pair
+    let first: serde_json::Value = test::call_and_read_body_json(&app, login()).await;
+    assert_eq!(test::call_service(&app, dashboard(&first["token"])).await.status(), StatusCode::OK);
+    let resp = test::call_service(&app, refresh(&first["refresh_token"])).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let second: serde_json::Value = test::read_body_json(resp).await;
+    assert_ne!(second["refresh_token"], first["refresh_token"]);
+    assert_eq!(test::call_service(&app, dashboard(&second["token"])).await.status(), StatusCode::OK);
+
+    // Reusing a rotated token revokes the whole family, the token it was rotated into included
+    assert_eq!(test::call_service(&app, refresh(&first["refresh_token"])).await.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(test::call_service(&app, refresh(&second["refresh_token"])).await.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(test::call_service(&app, refresh(&serde_json::json!("not-a-token"))).await.status(), StatusCode::UNAUTHORIZED);
+
+    // Logging out revokes the access token at once, and the session with it
+    let session: serde_json::Value = test::call_and_read_body_json(&app, login()).await;
+    let req = test::TestRequest::post().uri("/auth/logout")
+        .insert_header(("Authorization", format!("Bearer {}", session["token"].as_str().unwrap())))
+        .set_json(serde_json::json!({ "refresh_token": session["refresh_token"] }))
+        .to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), StatusCode::NO_CONTENT);
+    assert_eq!(test::call_service(&app, dashboard(&session["token"])).await.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(test::call_service(&app, refresh(&session["refresh_token"])).await.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(test::call_service(&app, dashboard(&second["token"])).await.status(), StatusCode::OK);
+}
+
+#[actix_web::test]
+async fn test_api_key_scopes() {
+    use crate::auth::{AuthMiddleware, User};
+
+    let db_pool = setup_test_db().await;
+    let account = create_account(&db_pool, "ci-owner", "owner-password", "admin").await;
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(db_pool.clone()))
+            .configure(routes::api_keys::config)
+            .configure(routes::posts::config)
+            .configure(routes::projects::config)
+    ).await;
+    let user = User { id: account.id.clone(), name: account.name.clone(), role: account.role.clone() };
+    let admin = format!("Bearer {}", AuthMiddleware::generate_token(&user).unwrap());
+    let mut keys = Vec::new();
+    for scopes in [serde_json::json!(["projects:write"]), serde_json::json!(["posts:write"])] {
+        let req = test::TestRequest::post().uri("/admin/api-keys")
+            .insert_header(("Authorization", admin.clone()))
+            .set_json(serde_json::json!({ "name": "CI", "scopes": scopes }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::CREATED);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        keys.push((body["key"].as_str().unwrap().to_string(), body["api_key"]["id"].as_str().unwrap().to_string()));
+    }
+    let (projects_key, posts_key) = (&keys[0], &keys[1]);
+    let project = serde_json::json!({ "title": "Site", "description": "", "technologies": [], "year": 2024, "highlights": [] });
+    let create_project = |header: (&str, String)| {
+        test::TestRequest::post().uri("/projects").insert_header(header).set_json(&project).to_request()
+    };
+
+    // A key acts within its scopes, sent in either header
+    let resp = test::call_service(&app, create_project(("X-API-Key", projects_key.0.clone()))).await;
+    assert_eq!(resp.status(), StatusCode::CREATED);
+    let resp = test::call_service(&app, create_project(("Authorization", format!("Bearer {}", projects_key.0)))).await;
+    assert_eq!(resp.status(), StatusCode::CREATED);
+    let resp = test::call_service(&app, create_project(("X-API-Key", posts_key.0.clone()))).await;
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+    let req = test::TestRequest::post().uri("/posts")
+        .insert_header(("X-API-Key", posts_key.0.clone()))
+        .set_json(serde_json::json!({ "title": "Hello", "date": "2025-02-01", "tags": [], "excerpt": "", "content": "" }))
+        .to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), StatusCode::CREATED);
+    let req = test::TestRequest::get().uri("/admin/api-keys").insert_header(("X-API-Key", projects_key.0.clone())).to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), StatusCode::FORBIDDEN);
+    let projects: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM projects").fetch_one(&db_pool).await.unwrap();
+    assert_eq!(projects, 2);
+
+    // Revoked and unknown keys are refused
+    let req = test::TestRequest::delete().uri(&format!("/admin/api-keys/{}", projects_key.1)).insert_header(("Authorization", admin.clone())).to_request();
+    assert_eq!(test::call_service(&app, req).await.status(), StatusCode::NO_CONTENT);
+    let resp = test::call_service(&app, create_project(("X-API-Key", projects_key.0.clone()))).await;
+    assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+    let mut forged = posts_key.0.clone();
+    let last = forged.pop().unwrap();
+    forged.push(if last == 'x' { 'y' } else { 'x' });
+    let resp = test::call_service(&app, create_project(("X-API-Key", forged))).await;
+    assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[actix_web::test]
+async fn test_two_factor_login() {
+    use crate::auth::totp::code_at;
+
+    let db_pool = setup_test_db().await;
+    create_account(&db_pool, "jane", "jane-password", "editor").await;
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(db_pool.clone()))
+            .configure(routes::auth::config)
+            .configure(routes::totp::config)
+    ).await;
+    let login = || {
+        test::TestRequest::post().uri("/auth/login")
+            .set_json(serde_json::json!({ "username": "jane", "password": "jane-password" }))
+            .to_request()
+    };
+    let login_totp = |challenge: &serde_json::Value, code: &str| {
+        test::TestRequest::post().uri("/auth/login/totp")
+            .set_json(serde_json::json!({ "challenge_token": challenge["challenge_token"], "code": code }))
+            .to_request()
+    };
+
+    // Enrol through the API, confirming with the current code
+    let session: serde_json::Value = test::call_and_read_body_json(&app, login()).await;
+    let token = format!("Bearer {}", session["token"].as_str().unwrap());
+    let req = test::TestRequest::post().uri("/auth/totp/enroll").insert_header(("Authorization", token.clone())).to_request();
+    let enrollment: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    let secret = enrollment["secret"].as_str().unwrap().to_string();
+    let now = chrono::Utc::now().timestamp() as u64;
+    let req = test::TestRequest::post().uri("/auth/totp/confirm")
+        .insert_header(("Authorization", token.clone()))
+        .set_json(serde_json::json!({ "code": code_at(&secret, now).unwrap() }))
+        .to_request();
+    let confirmed: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    let recovery_codes: Vec<String> = serde_json::from_value(confirmed["recovery_codes"].clone()).unwrap();
+
+    // The password alone now only gives a challenge
+    let challenge: serde_json::Value = test::call_and_read_body_json(&app, login()).await;
+    assert_eq!(challenge["mfa_required"], true);
+    assert!(challenge.get("token").is_none() && challenge.get("refresh_token").is_none());
+
+    // A code outside the accepted window is refused
+    let window: Vec<String> = [now - 30, now, now + 30].iter().map(|&time| code_at(&secret, time).unwrap()).collect();
+    let wrong = (0..).map(|n| format!("{:06}", n)).find(|code| !window.contains(code)).unwrap();
+    assert_eq!(test::call_service(&app, login_totp(&challenge, &wrong)).await.status(), StatusCode::UNAUTHORIZED);
+
+    // The next code completes the login, once per challenge
+    let resp = test::call_service(&app, login_totp(&challenge, &code_at(&secret, now + 30).unwrap())).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body: serde_json::Value = test::read_body_json(resp).await;
+    assert!(body["token"].as_str().is_some_and(|token| !token.is_empty()));
+    let challenge_again = login_totp(&challenge, &recovery_codes[0]);
+    assert_eq!(test::call_service(&app, challenge_again).await.status(), StatusCode::UNAUTHORIZED);
+
+    // A recovery code works once
+    let challenge: serde_json::Value = test::call_and_read_body_json(&app, login()).await;
+    assert_eq!(test::call_service(&app, login_totp(&challenge, &recovery_codes[0])).await.status(), StatusCode::OK);
+    let challenge: serde_json::Value = test::call_and_read_body_json(&app, login()).await;
+    assert_eq!(test::call_service(&app, login_totp(&challenge, &recovery_codes[0])).await.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(test::call_service(&app, login_totp(&challenge, &recovery_codes[1])).await.status(), StatusCode::OK);
+}
+
+async fn check_login_throttle(db_pool: &DbPool) {
+    use crate::models::login_attempt::LoginAttempt;
+    use crate::models::login_attempt_repository::LoginAttemptRepository;
+
+    let attempts = LoginAttemptRepository::new(db_pool.clone());
+
+    // Concurrent failures are all counted
+    let failures = futures::future::join_all((0..8).map(|_| attempts.add_failure("user:guessed", 1000, 0))).await;
+    let mut counts: Vec<i64> = failures.into_iter().map(Result::unwrap).collect();
+    counts.sort();
+    assert_eq!(counts, (1..=8).collect::<Vec<_>>());
+
+    // Locks only grow, and go with the failures once these are forgotten
+    attempts.lock_throttle("user:guessed", 1300).await.unwrap();
+    attempts.lock_throttle("user:guessed", 1100).await.unwrap();
+    assert_eq!(attempts.find_throttle("user:guessed").await.unwrap().unwrap().locked_until, Some(1300));
+    assert_eq!(attempts.add_failure("user:guessed", 1200, 900).await.unwrap(), 9);
+    assert_eq!(attempts.find_throttle("user:guessed").await.unwrap().unwrap().locked_until, Some(1300));
+    assert_eq!(attempts.add_failure("user:guessed", 5000, 1200).await.unwrap(), 1);
+    assert_eq!(attempts.find_throttle("user:guessed").await.unwrap().unwrap().locked_until, None);
+
+    // Pruning keeps recent attempts and counters that still count or lock
+    attempts.add_failure("user:locked", 100, 0).await.unwrap();
+    attempts.lock_throttle("user:locked", 9000).await.unwrap();
+    attempts.add_failure("user:stale", 100, 0).await.unwrap();
+    for attempted_at in [100, 4000] {
+        attempts.record(LoginAttempt::new("guessed".to_string(), None, None, "invalid_credentials".to_string(), attempted_at)).await.unwrap();
+    }
+    assert_eq!(attempts.prune(Some(3000), 1000, 5000).await.unwrap(), 1);
+    assert!(attempts.find_throttle("user:stale").await.unwrap().is_none());
+    assert!(attempts.find_throttle("user:locked").await.unwrap().is_some());
+    assert!(attempts.find_throttle("user:guessed").await.unwrap().is_some());
+    assert_eq!(attempts.prune(None, 1000, 5000).await.unwrap(), 0);
+    let remaining: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM login_attempts WHERE username = 'guessed'").fetch_one(db_pool).await.unwrap();
+    assert_eq!(remaining, 1);
+}
+
+#[actix_web::test]
+async fn test_login_throttle_repository() {
+    let db_pool = setup_test_db().await;
+    check_login_throttle(&db_pool).await;
+}
+
+#[actix_web::test]
+async fn test_login_lockout() {
+    use crate::auth::{AuthMiddleware, User};
+
+    let db_pool = setup_test_db().await;
+    create_account(&db_pool, "jane", "jane-password", "editor").await;
+    let app = test::init_service(
+        App::new()
+            .app_data(web::Data::new(db_pool.clone()))
+            .configure(routes::admin::config)
+            .configure(routes::auth::config)
+    ).await;
+    let login = |password: &str| {
+        test::TestRequest::post().uri("/auth/login")
+            .insert_header(("User-Agent", "lockout-test"))
+            .set_json(serde_json::json!({ "username": "jane", "password": password }))
+            .to_request()
+    };
+
+    // The fifth failure locks the username, even against the right password
+    for _ in 0..5 {
+        assert_eq!(test::call_service(&app, login("wrong-password")).await.status(), StatusCode::UNAUTHORIZED);
     }
+    let resp = test::call_service(&app, login("jane-password")).await;
+    assert_eq!(resp.status(), StatusCode::TOO_MANY_REQUESTS);
+    let retry_after: i64 = resp.headers().get("Retry-After").unwrap().to_str().unwrap().parse().unwrap();
+    assert!((1..=30).contains(&retry_after), "{}", retry_after);
+
+    // Every attempt is in the audit log
+    let admin = User { id: "admin-1".to_string(), name: "Test admin".to_string(), role: "admin".to_string() };
+    let admin = format!("Bearer {}", AuthMiddleware::generate_token(&admin).unwrap());
+    let req = test::TestRequest::get().uri("/admin/login-attempts?username=jane").insert_header(("Authorization", admin.clone())).to_request();
+    let resp = test::call_service(&app, req).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(resp.headers().get("X-Total-Count").unwrap(), "6");
+    let req = test::TestRequest::get().uri("/admin/login-attempts?outcome=invalid_credentials").insert_header(("Authorization", admin.clone())).to_request();
+    let page: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!(page.as_array().map(Vec::len), Some(5));
+    let req = test::TestRequest::get().uri("/admin/login-attempts?outcome=locked").insert_header(("Authorization", admin)).to_request();
+    let page: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+    assert_eq!((page.as_array().map(Vec::len), page[0]["user_agent"].as_str()), (Some(1), Some("lockout-test")));
 }